@@ -0,0 +1,37 @@
+#!/bin/sh
+# Runs the tests of the teardown of connections and statements with valgrind
+# and the address sanitizer, failing if either of them reports an error
+#
+# Needs the oracle database of `DB_URL` (c.f. `src/test/mod.rs`), valgrind
+# and a nightly toolchain. Oracle client specific reports can be suppressed
+# with `VALGRIND_SUPPRESSIONS=<file>`.
+set -eu
+
+cd "$(dirname "$0")/.."
+
+TESTS="test::connections_are_dropped_repeatedly
+test::lazy_cursor_outlives_connection
+test::failed_connections_free_their_handles"
+TARGET=${TARGET:-x86_64-unknown-linux-gnu}
+
+# the test binary of the library, which contains the tests of `src/test`
+binary=$(cargo test --lib --no-run --message-format=json |
+    sed -n 's/.*"executable":"\([^"]*\)".*/\1/p' | tail -n 1)
+if [ -z "$binary" ]; then
+    echo "no test binary was built" >&2
+    exit 1
+fi
+
+suppressions=""
+if [ -n "${VALGRIND_SUPPRESSIONS:-}" ]; then
+    suppressions="--suppressions=$VALGRIND_SUPPRESSIONS"
+fi
+# shellcheck disable=SC2086
+valgrind --error-exitcode=1 --leak-check=full --errors-for-leak-kinds=definite \
+    $suppressions "$binary" --test-threads=1 --exact $TESTS
+
+# the client library keeps its global state until the process exits, which
+# would be reported as leaks, those are checked by valgrind above
+# shellcheck disable=SC2086
+ASAN_OPTIONS=${ASAN_OPTIONS:-detect_leaks=0} RUSTFLAGS=-Zsanitizer=address \
+    cargo +nightly test --lib --target "$TARGET" -- --test-threads=1 --exact $TESTS
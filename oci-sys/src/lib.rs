@@ -1299,6 +1299,7 @@ pub const OCI_STMT_ALTER: ::std::os::raw::c_uint = 7;
 pub const OCI_STMT_BEGIN: ::std::os::raw::c_uint = 8;
 pub const OCI_STMT_DECLARE: ::std::os::raw::c_uint = 9;
 pub const OCI_STMT_CALL: ::std::os::raw::c_uint = 10;
+pub const OCI_STMT_MERGE: ::std::os::raw::c_uint = 16;
 pub const OCI_PTYPE_UNK: ::std::os::raw::c_uint = 0;
 pub const OCI_PTYPE_TABLE: ::std::os::raw::c_uint = 1;
 pub const OCI_PTYPE_VIEW: ::std::os::raw::c_uint = 2;
@@ -1,11 +1,30 @@
 #[macro_use]
 extern crate diesel;
+#[cfg(feature = "arrow")]
+extern crate arrow;
 extern crate bigdecimal;
 extern crate byteorder;
+#[cfg(feature = "chrono-time")]
+extern crate chrono;
 extern crate libc;
+#[macro_use]
 extern crate log;
+#[cfg(test)]
+extern crate diesel_migrations;
 extern crate oci_sys;
+#[cfg(feature = "rust-oracle")]
+extern crate oracle as rust_oracle;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "tower")]
+extern crate tokio;
+#[cfg(feature = "tower")]
+extern crate tower;
+#[cfg(feature = "tracing")]
+extern crate tracing;
 pub mod oracle;
 
+pub use oracle::connection::test_transaction;
+
 #[cfg(test)]
 mod test;
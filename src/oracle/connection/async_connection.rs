@@ -0,0 +1,259 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use diesel::result::{ConnectionError, ConnectionResult, DatabaseErrorKind, Error, QueryResult};
+
+#[cfg(feature = "tower")]
+use super::deadline;
+use super::{OciConnection, OciConnectionBuilder};
+
+type Job = Box<dyn FnOnce(&OciConnection) + Send>;
+
+/// A connection usable from async code, which runs all statements on a
+/// dedicated thread
+///
+/// OCI calls block, so every call of `interact` is sent to the thread owning
+/// the `OciConnection` and the returned future completes once it finished.
+/// The futures work with any executor.
+///
+/// Dropping the connection does not block, its thread closes the
+/// `OciConnection` after the pending calls. Use `close` to wait for that.
+///
+/// ```ignore
+/// let conn = AsyncOciConnection::establish(OciConnectionBuilder::from_url(url)?).await?;
+/// let users = conn.interact(|conn| users::table.load::<User>(conn)).await?;
+/// ```
+pub struct AsyncOciConnection {
+    jobs: Option<mpsc::Sender<Job>>,
+    closed: Option<Interact<()>>,
+}
+
+impl AsyncOciConnection {
+    /// Establishes the connection on a new thread
+    pub fn establish(config: OciConnectionBuilder) -> Establish {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let (completer, established) = oneshot();
+        let (closer, closed) = oneshot();
+        thread::spawn(move || {
+            let conn = match config.establish() {
+                Ok(conn) => {
+                    completer.complete(Ok(Ok(())));
+                    conn
+                }
+                Err(e) => return completer.complete(Ok(Err(e))),
+            };
+            for job in receiver {
+                job(&conn);
+            }
+            drop(conn);
+            closer.complete(Ok(()));
+        });
+        Establish {
+            established,
+            conn: Some(AsyncOciConnection {
+                jobs: Some(jobs),
+                closed: Some(closed),
+            }),
+        }
+    }
+
+    /// Runs `f` with the connection on its thread
+    ///
+    /// Calls are executed in order. If `f` panics, the connection is closed
+    /// and this and all later calls fail with
+    /// `DatabaseErrorKind::UnableToSendCommand`.
+    pub fn interact<T, F>(&self, f: F) -> Interact<T>
+    where
+        F: FnOnce(&OciConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (completer, interact) = oneshot();
+        #[cfg(feature = "tower")]
+        let job: Job = {
+            let deadline = deadline::current_deadline();
+            Box::new(move |conn| {
+                completer.complete(deadline::with_job_deadline(deadline, || f(conn)))
+            })
+        };
+        #[cfg(not(feature = "tower"))]
+        let job: Job = Box::new(move |conn| completer.complete(f(conn)));
+        if let Some(ref jobs) = self.jobs {
+            // a failed send drops the completer, which completes the future
+            // with an error
+            let _ = jobs.send(job);
+        }
+        interact
+    }
+
+    /// Closes the connection, the returned future completes once the pending
+    /// calls finished and the `OciConnection` is closed
+    pub fn close(mut self) -> Interact<()> {
+        // closing the channel stops the thread after the pending calls
+        self.jobs.take();
+        self.closed
+            .take()
+            .expect("the connection is closed only once")
+    }
+}
+
+impl Drop for AsyncOciConnection {
+    fn drop(&mut self) {
+        // the thread is detached, blocking here would block the executor
+        self.jobs.take();
+    }
+}
+
+impl fmt::Debug for AsyncOciConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncOciConnection").finish()
+    }
+}
+
+/// The future returned by `AsyncOciConnection::establish`
+#[derive(Debug)]
+pub struct Establish {
+    established: Interact<ConnectionResult<()>>,
+    conn: Option<AsyncOciConnection>,
+}
+
+impl Future for Establish {
+    type Output = ConnectionResult<AsyncOciConnection>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.established).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(Ok(()))) => Poll::Ready(Ok(self
+                .conn
+                .take()
+                .expect("Establish polled after completion"))),
+            Poll::Ready(Ok(Err(e))) => Poll::Ready(Err(e)),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(ConnectionError::BadConnection(e.to_string()))),
+        }
+    }
+}
+
+struct Shared<T> {
+    value: Option<QueryResult<T>>,
+    waker: Option<Waker>,
+}
+
+/// The future returned by `AsyncOciConnection::interact`
+pub struct Interact<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Future for Interact<T> {
+    type Output = QueryResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Interact<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Interact").finish()
+    }
+}
+
+// completes the corresponding `Interact`, with an error if dropped without
+// a value (i.e. the thread of the connection stopped)
+struct Completer<T> {
+    shared: Option<Arc<Mutex<Shared<T>>>>,
+}
+
+impl<T> Completer<T> {
+    fn complete(mut self, value: QueryResult<T>) {
+        self.set(value);
+    }
+
+    fn set(&mut self, value: QueryResult<T>) {
+        if let Some(shared) = self.shared.take() {
+            let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+            shared.value = Some(value);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        self.set(Err(Error::DatabaseError(
+            DatabaseErrorKind::UnableToSendCommand,
+            Box::new(String::from("The thread of the connection stopped")),
+        )));
+    }
+}
+
+fn oneshot<T>() -> (Completer<T>, Interact<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        value: None,
+        waker: None,
+    }));
+    (
+        Completer {
+            shared: Some(shared.clone()),
+        },
+        Interact { shared },
+    )
+}
+
+// a minimal executor for the tests, c.f. `test::async_connection`
+#[cfg(test)]
+struct ThreadWaker(thread::Thread);
+
+#[cfg(test)]
+impl ::std::task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(mut future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        // the future is not moved until it completed
+        let pinned = unsafe { Pin::new_unchecked(&mut future) };
+        match pinned.poll(&mut cx) {
+            Poll::Ready(value) => return value,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_on, oneshot};
+    use diesel::result::{DatabaseErrorKind, Error};
+    use std::thread;
+
+    #[test]
+    fn check_oneshot() {
+        let (completer, interact) = oneshot::<i32>();
+        thread::spawn(move || completer.complete(Ok(42)));
+        assert_eq!(block_on(interact).unwrap(), 42);
+
+        let (completer, interact) = oneshot::<i32>();
+        thread::spawn(move || drop(completer));
+        match block_on(interact) {
+            Err(Error::DatabaseError(DatabaseErrorKind::UnableToSendCommand, _)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
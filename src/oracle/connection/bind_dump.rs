@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use diesel::query_builder::bind_collector::RawBytesBindCollector;
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::result::QueryResult;
+
+use oracle::backend::Oracle;
+use oracle::query_builder::OciQueryBuilder;
+use oracle::types::OCIDataType;
+
+use super::bind_value::BindValue;
+use super::OciConnection;
+
+/// A value bound to a statement, c.f. `Statement::bound_parameters`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundParameter {
+    /// The position of the placeholder, starting at 1
+    pub position: u32,
+    pub data_type: OCIDataType,
+    /// The value as sql literal, e.g. `'text'`, `42` or `NULL`
+    pub value: String,
+}
+
+impl BoundParameter {
+    pub(super) fn new(position: u32, value: &BindValue) -> Self {
+        BoundParameter {
+            position,
+            data_type: value.data_type(),
+            value: literal(value),
+        }
+    }
+
+    // a parameter oracle writes to, which has no literal
+    pub(super) fn output(position: u32, data_type: OCIDataType) -> Self {
+        BoundParameter {
+            position,
+            data_type,
+            value: format!("NULL /* OUT {:?} */", data_type),
+        }
+    }
+}
+
+impl fmt::Display for BoundParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":{} {:?} = ", self.position, self.data_type)?;
+        f.write_str(&self.value)
+    }
+}
+
+impl OciConnection {
+    /// The sql of `query` with its bind values as literals, e.g. to run a
+    /// failing diesel query in SQL*Plus or sqlcl
+    ///
+    /// Unlike `debug_query` the sql is built for the server of this
+    /// connection and the values are rendered the way they are bound, e.g.
+    /// `TO_DATE(...)` for dates or `HEXTORAW(...)` for binary data.
+    pub fn sql_with_binds<Q>(&self, query: &Q) -> QueryResult<String>
+    where
+        Q: QueryFragment<Oracle>,
+    {
+        let mut bind_collector = RawBytesBindCollector::<Oracle>::new();
+        query.collect_binds(&mut bind_collector, &())?;
+        let mut query_builder = OciQueryBuilder::new();
+        self.with_sql_dialect(|| query.to_sql(&mut query_builder))?;
+        let parameters = bind_collector
+            .metadata
+            .into_iter()
+            .zip(bind_collector.binds)
+            .enumerate()
+            .map(|(i, (tpe, value))| {
+                BoundParameter::new(i as u32 + 1, &BindValue::from_sql(tpe, value))
+            })
+            .collect::<Vec<_>>();
+        Ok(inline_binds(&query_builder.finish(), &parameters))
+    }
+}
+
+fn literal(value: &BindValue) -> String {
+    match *value {
+        BindValue::Int(v) => v.to_string(),
+        BindValue::Float(v) if v.is_nan() => "BINARY_FLOAT_NAN".into(),
+        BindValue::Float(v) if v.is_infinite() => {
+            format!("{}BINARY_FLOAT_INFINITY", if v < 0.0 { "-" } else { "" })
+        }
+        BindValue::Float(v) => format!("{:?}f", v),
+        BindValue::Double(v) if v.is_nan() => "BINARY_DOUBLE_NAN".into(),
+        BindValue::Double(v) if v.is_infinite() => {
+            format!("{}BINARY_DOUBLE_INFINITY", if v < 0.0 { "-" } else { "" })
+        }
+        BindValue::Double(v) => format!("{:?}d", v),
+        BindValue::Text(ref v) => format!("'{}'", v.replace('\'', "''")),
+        BindValue::NationalText(ref v) => format!("N'{}'", v.replace('\'', "''")),
+        BindValue::Bytes(ref v) => format!("HEXTORAW('{}')", hex(v)),
+        BindValue::Date(ref d) => format!(
+            "TO_DATE('{:04}-{:02}-{:02} {:02}:{:02}:{:02}', 'YYYY-MM-DD HH24:MI:SS')",
+            (i32::from(d[0]) - 100) * 100 + i32::from(d[1]) - 100,
+            d[2],
+            d[3],
+            d[4].saturating_sub(1),
+            d[5].saturating_sub(1),
+            d[6].saturating_sub(1)
+        ),
+        BindValue::Null(_) => "NULL".into(),
+        BindValue::Lob(ref lob) => format!("NULL /* {:?} */", lob.lob_type()),
+        BindValue::Raw(tpe, ref v) => format!("HEXTORAW('{}') /* {:?} */", hex(v), tpe),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// `sql` with its placeholders replaced by the literals of `parameters`
+///
+/// Numbered placeholders (`:1`) refer to the parameter at that position,
+/// named ones to the parameters in the order of their first occurrence.
+/// Placeholders without a bound parameter are kept.
+pub(super) fn inline_binds(sql: &str, parameters: &[BoundParameter]) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut names = HashMap::new();
+    let mut quote = None;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, ':') => {
+                let mut end = i + 1;
+                while let Some(&(j, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '$' || c == '#') {
+                        break;
+                    }
+                    end = j + c.len_utf8();
+                    chars.next();
+                }
+                let name = &sql[i + 1..end];
+                let position = match name.parse::<u32>() {
+                    Ok(position) => Some(position),
+                    Err(_) if !name.is_empty() => {
+                        let next = names.len() as u32 + 1;
+                        Some(*names.entry(name.to_uppercase()).or_insert(next))
+                    }
+                    Err(_) => None,
+                };
+                match position.and_then(|p| parameters.iter().find(|b| b.position == p)) {
+                    Some(parameter) => out.push_str(&parameter.value),
+                    None => out.push_str(&sql[i..end]),
+                }
+                continue;
+            }
+            (None, _) => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inline_binds, BoundParameter};
+    use oracle::connection::BindValue;
+    use oracle::types::OCIDataType;
+
+    #[test]
+    fn check_bound_parameter_literals() {
+        let literal = |value: BindValue| BoundParameter::new(1, &value).value;
+        assert_eq!(literal(BindValue::Int(-42)), "-42");
+        assert_eq!(literal(BindValue::Double(1.0)), "1.0d");
+        assert_eq!(literal(BindValue::Float(f32::NAN)), "BINARY_FLOAT_NAN");
+        assert_eq!(literal("it's".into()), "'it''s'");
+        assert_eq!(literal(BindValue::NationalText("Ærø".into())), "N'Ærø'");
+        assert_eq!(literal(vec![0x0a, 0xff].into()), "HEXTORAW('0AFF')");
+        assert_eq!(literal(BindValue::Null(OCIDataType::Int)), "NULL");
+        // 2019-03-04 05:06:07
+        assert_eq!(
+            literal(BindValue::Date([120, 119, 3, 4, 6, 7, 8])),
+            "TO_DATE('2019-03-04 05:06:07', 'YYYY-MM-DD HH24:MI:SS')"
+        );
+        assert_eq!(
+            BoundParameter::new(2, &BindValue::Int(7)).to_string(),
+            ":2 Int = 7"
+        );
+    }
+
+    #[test]
+    fn check_inline_binds() {
+        let parameters = vec![
+            BoundParameter::new(1, &"a'b".into()),
+            BoundParameter::new(2, &BindValue::Int(10)),
+        ];
+        assert_eq!(
+            inline_binds(
+                "SELECT ':1', \":2\" FROM t WHERE a = :1 AND b < :2 AND c = :3",
+                &parameters
+            ),
+            "SELECT ':1', \":2\" FROM t WHERE a = 'a''b' AND b < 10 AND c = :3"
+        );
+        assert_eq!(
+            inline_binds("BEGIN p(:name, :id, :NAME); END;", &parameters),
+            "BEGIN p('a''b', 10, 'a''b'); END;"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use byteorder::{ByteOrder, NativeEndian};
+
+use oracle::types::OCIDataType;
+
+use super::lob::{Lob, LobType};
+
+/// A value bound to a placeholder in the representation oracle receives it
+/// in, c.f. `PlSqlBind::value`
+///
+/// Values serialized by diesel are converted with `BindValue::from_sql`.
+#[derive(Debug)]
+pub enum BindValue {
+    /// An integer, all integers are bound with 8 bytes as otherwise oracle
+    /// creates a new child cursor for each combination of bind sizes
+    Int(i64),
+    /// A `BINARY_FLOAT`
+    Float(f32),
+    /// A `BINARY_DOUBLE`
+    Double(f64),
+    /// Text in the character set of the client
+    Text(String),
+    /// Text converted to the national character set, c.f. `NVarchar`
+    NationalText(String),
+    Bytes(Vec<u8>),
+    /// The 7 byte format of `DATE`
+    Date([u8; 7]),
+    /// `NULL` of the given type, which oracle needs e.g. to resolve the
+    /// overload of a function called with it
+    Null(OCIDataType),
+    /// A LOB bound by its locator, a temporary LOB is freed once the value
+    /// is dropped
+    Lob(Lob),
+    /// A value of another type in the representation of `OCIDataType`
+    Raw(OCIDataType, Vec<u8>),
+}
+
+impl BindValue {
+    /// The value diesel serialized for a bind of type `tpe`
+    pub fn from_sql(tpe: OCIDataType, value: Option<Vec<u8>>) -> BindValue {
+        let value = match value {
+            Some(value) => value,
+            None => return BindValue::Null(tpe),
+        };
+        match (tpe, value.len()) {
+            (OCIDataType::Int, 1) => BindValue::Int(i64::from(value[0] as i8)),
+            (OCIDataType::Int, 2) => BindValue::Int(i64::from(NativeEndian::read_i16(&value))),
+            (OCIDataType::Int, 4) => BindValue::Int(i64::from(NativeEndian::read_i32(&value))),
+            (OCIDataType::Int, 8) => BindValue::Int(NativeEndian::read_i64(&value)),
+            (OCIDataType::Float, 4) => BindValue::Float(NativeEndian::read_f32(&value)),
+            (OCIDataType::Float, 8) => BindValue::Double(NativeEndian::read_f64(&value)),
+            (OCIDataType::InternDate, 7) => {
+                let mut date = [0; 7];
+                date.copy_from_slice(&value);
+                BindValue::Date(date)
+            }
+            (OCIDataType::Char, _) | (OCIDataType::NationalChar, _) => {
+                match String::from_utf8(value) {
+                    Ok(text) if tpe == OCIDataType::Char => BindValue::Text(text),
+                    Ok(text) => BindValue::NationalText(text),
+                    Err(e) => BindValue::Raw(tpe, e.into_bytes()),
+                }
+            }
+            (OCIDataType::Binary, _) => BindValue::Bytes(value),
+            _ => BindValue::Raw(tpe, value),
+        }
+    }
+
+    /// The type the value is bound as
+    pub fn data_type(&self) -> OCIDataType {
+        match *self {
+            BindValue::Int(_) => OCIDataType::Int,
+            // c.f. `Statement::bind_buffer`, which binds 4 byte values of
+            // this type as `BINARY_FLOAT`
+            BindValue::Float(_) | BindValue::Double(_) => OCIDataType::Float,
+            BindValue::Text(_) => OCIDataType::Char,
+            BindValue::NationalText(_) => OCIDataType::NationalChar,
+            BindValue::Bytes(_) => OCIDataType::Binary,
+            BindValue::Date(_) => OCIDataType::InternDate,
+            BindValue::Null(tpe) | BindValue::Raw(tpe, _) => tpe,
+            BindValue::Lob(ref lob) => match lob.lob_type() {
+                LobType::Blob => OCIDataType::Blob,
+                _ => OCIDataType::Clob,
+            },
+        }
+    }
+
+    /// The bytes of the value, `None` for `NULL`
+    ///
+    /// # Panics
+    ///
+    /// For `Lob`, which is bound by its locator instead.
+    pub(super) fn into_raw(self) -> (OCIDataType, Option<Vec<u8>>) {
+        let tpe = self.data_type();
+        let value = match self {
+            BindValue::Int(value) => value.to_ne_bytes().to_vec(),
+            BindValue::Float(value) => value.to_ne_bytes().to_vec(),
+            BindValue::Double(value) => value.to_ne_bytes().to_vec(),
+            BindValue::Text(value) | BindValue::NationalText(value) => value.into_bytes(),
+            BindValue::Bytes(value) | BindValue::Raw(_, value) => value,
+            BindValue::Date(value) => value.to_vec(),
+            BindValue::Null(_) => return (tpe, None),
+            BindValue::Lob(_) => panic!("LOBs are bound by their locator"),
+        };
+        (tpe, Some(value))
+    }
+}
+
+/// The internal format of `NUMBER` (`SQLT_NUM`) holding `value`
+///
+/// The first byte holds the sign and the exponent to base 100, the following
+/// ones the digits to base 100 without trailing zeros, which are offset by 1
+/// for positive numbers and subtracted from 101 for negative ones. Negative
+/// numbers with less than 20 digits end with 102.
+pub(super) fn oracle_number(value: i64) -> Vec<u8> {
+    if value == 0 {
+        return vec![0x80];
+    }
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    while magnitude > 0 {
+        digits.push((magnitude % 100) as u8);
+        magnitude /= 100;
+    }
+    digits.reverse();
+    let exponent = digits.len() as u8 - 1;
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    let mut number = Vec::with_capacity(digits.len() + 2);
+    if value > 0 {
+        number.push(0xc1 + exponent);
+        number.extend(digits.iter().map(|d| d + 1));
+    } else {
+        number.push(0x3e - exponent);
+        number.extend(digits.iter().map(|d| 101 - d));
+        if digits.len() < 20 {
+            number.push(102);
+        }
+    }
+    number
+}
+
+impl From<i64> for BindValue {
+    fn from(value: i64) -> Self {
+        BindValue::Int(value)
+    }
+}
+
+impl From<f64> for BindValue {
+    fn from(value: f64) -> Self {
+        BindValue::Double(value)
+    }
+}
+
+impl From<String> for BindValue {
+    fn from(value: String) -> Self {
+        BindValue::Text(value)
+    }
+}
+
+impl<'a> From<&'a str> for BindValue {
+    fn from(value: &'a str) -> Self {
+        BindValue::Text(value.to_owned())
+    }
+}
+
+impl From<Vec<u8>> for BindValue {
+    fn from(value: Vec<u8>) -> Self {
+        BindValue::Bytes(value)
+    }
+}
+
+impl From<Lob> for BindValue {
+    fn from(value: Lob) -> Self {
+        BindValue::Lob(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{oracle_number, BindValue};
+    use oracle::types::OCIDataType;
+
+    fn from_sql(tpe: OCIDataType, value: &[u8]) -> BindValue {
+        BindValue::from_sql(tpe, Some(value.to_vec()))
+    }
+
+    #[test]
+    fn check_bind_value_from_sql() {
+        match from_sql(OCIDataType::Int, &(-3i16).to_ne_bytes()) {
+            BindValue::Int(-3) => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match from_sql(OCIDataType::Int, &7i32.to_ne_bytes()).into_raw() {
+            (OCIDataType::Int, Some(ref v)) if v[..] == 7i64.to_ne_bytes()[..] => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match from_sql(OCIDataType::Float, &0.5f32.to_ne_bytes()) {
+            BindValue::Float(0.5) => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match from_sql(OCIDataType::Char, b"abc") {
+            BindValue::Text(ref v) if v == "abc" => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match from_sql(OCIDataType::NationalChar, "Ærø".as_bytes()) {
+            BindValue::NationalText(ref v) if v == "Ærø" => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match from_sql(OCIDataType::Char, &[0xff]) {
+            BindValue::Raw(OCIDataType::Char, ref v) if v[..] == [0xff] => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        match BindValue::from_sql(OCIDataType::InternDate, None) {
+            BindValue::Null(OCIDataType::InternDate) => {}
+            v => panic!("unexpected {:?}", v),
+        }
+        assert_eq!(
+            BindValue::Null(OCIDataType::Int).into_raw(),
+            (OCIDataType::Int, None)
+        );
+    }
+
+    #[test]
+    fn check_oracle_number() {
+        assert_eq!(oracle_number(0), [0x80]);
+        assert_eq!(oracle_number(1), [0xc1, 2]);
+        assert_eq!(oracle_number(100), [0xc2, 2]);
+        assert_eq!(oracle_number(123), [0xc2, 2, 24]);
+        assert_eq!(oracle_number(-1), [0x3e, 100, 102]);
+        assert_eq!(oracle_number(-123), [0x3d, 100, 78, 102]);
+        assert_eq!(
+            oracle_number(i64::MAX),
+            [0xca, 10, 23, 34, 73, 4, 69, 55, 78, 59, 8]
+        );
+        assert_eq!(
+            oracle_number(i64::MIN),
+            [0x35, 92, 79, 68, 29, 98, 33, 47, 24, 43, 93, 102]
+        );
+    }
+}
@@ -0,0 +1,1257 @@
+use diesel::result::{ConnectionError, ConnectionResult};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::easy_connect::EasyConnect;
+use super::failover::{FailoverAction, FailoverEvent, FailoverHook, FailoverType};
+use super::nls::alter_session_nls;
+use super::raw::{check_charset, RawConnection};
+use super::spans;
+use super::OciConnection;
+use oracle::query_builder::IdentifierQuoting;
+
+/// Configuration of a new `OciConnection`
+///
+/// ```ignore
+/// let conn = OciConnection::builder()
+///     .username("diesel")
+///     .password("secret")
+///     .connect_string("//localhost:1521/orcl")
+///     .prefetch_rows(100)
+///     .establish()?;
+/// ```
+///
+/// `Connection::establish` parses urls of the form
+/// `oci://user/password@connect_string`, c.f. `from_url`.
+///
+/// Neither `Debug` nor `Display` print the password, use
+/// `display_unredacted` for local debugging.
+#[derive(Clone, Debug)]
+pub struct OciConnectionBuilder {
+    pub(super) username: String,
+    pub(super) password: Secret,
+    pub(super) connect_string: String,
+    pub(super) charset: String,
+    pub(super) prefetch_rows: Option<u32>,
+    pub(super) prefetch_memory: Option<u32>,
+    pub(super) statement_cache_size: u32,
+    pub(super) autocommit: bool,
+    pub(super) send_timeout: Option<Duration>,
+    pub(super) receive_timeout: Option<Duration>,
+    pub(super) statement_timeout: Option<Duration>,
+    pub(super) tns_admin: Option<PathBuf>,
+    pub(super) external_auth: bool,
+    pub(super) session_timezone: Option<String>,
+    pub(super) current_schema: Option<String>,
+    pub(super) default_owner: Option<String>,
+    pub(super) nls_parameters: Vec<(String, String)>,
+    pub(super) proxy_client: Option<String>,
+    pub(super) tcps: bool,
+    pub(super) wallet_location: Option<PathBuf>,
+    pub(super) ssl_server_dn_match: bool,
+    pub(super) ssl_server_cert_dn: Option<String>,
+    pub(super) auto_reconnect: bool,
+    pub(super) shared_environment: bool,
+    pub(super) identifier_quoting: IdentifierQuoting,
+    pub(super) failover_hook: Option<FailoverHook>,
+}
+
+impl Default for OciConnectionBuilder {
+    fn default() -> Self {
+        OciConnectionBuilder {
+            username: String::new(),
+            password: Secret(String::new()),
+            connect_string: String::new(),
+            charset: "AL32UTF8".into(),
+            prefetch_rows: None,
+            prefetch_memory: None,
+            statement_cache_size: 0,
+            autocommit: false,
+            send_timeout: None,
+            receive_timeout: None,
+            statement_timeout: None,
+            tns_admin: None,
+            external_auth: false,
+            session_timezone: None,
+            current_schema: None,
+            default_owner: None,
+            nls_parameters: Vec::new(),
+            proxy_client: None,
+            tcps: false,
+            wallet_location: None,
+            ssl_server_dn_match: false,
+            ssl_server_cert_dn: None,
+            auto_reconnect: false,
+            shared_environment: true,
+            identifier_quoting: IdentifierQuoting::default(),
+            failover_hook: None,
+        }
+    }
+}
+
+impl OciConnectionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder from an url of the form
+    /// `oci://user/password@connect_string`
+    ///
+    /// The connect string is anything understood by oracle, c.f.
+    /// `connect_string`. External authentication is used for urls like
+    /// `oci://@//localhost:1521/orcl?auth=external`, proxy authentication
+    /// for `oci://proxy_user[target_user]/password@connect_string`.
+    ///
+    /// Encrypted connections are configured with the parameters
+    /// `protocol=tcps`, `wallet_location`, `ssl_server_dn_match` and
+    /// `ssl_server_cert_dn`, e.g.
+    /// `oci://user/password@//db.example.com:2484/orcl?protocol=tcps&wallet_location=/etc/wallet`.
+    /// NLS parameters, the session time zone, the current schema and the
+    /// character set are set by parameters like `nls_date_format=YYYY-MM-DD`,
+    /// `time_zone=UTC`, `current_schema=APP` and `charset=UTF8`, the
+    /// quoting of identifiers by `identifier_quoting=smart` and the owner
+    /// of tables by `default_owner=APP`.
+    /// Easy connect plus strings like
+    /// `oci://user/password@tcps://db.example.com:1522/orcl?retry_count=3`
+    /// work with all oracle clients.
+    pub fn from_url(database_url: &str) -> ConnectionResult<Self> {
+        let (username, password, connect_string) = parse_db_string(database_url)?;
+        let (username, proxy_client) = split_proxy_user(&username);
+        let mut builder = OciConnectionBuilder {
+            username,
+            password: Secret(password),
+            proxy_client,
+            ..Self::default()
+        };
+        builder.connect_string = take_params(&connect_string, &mut builder)?;
+        if builder.username.is_empty() && !builder.external_auth {
+            return Err(ConnectionError::InvalidConnectionUrl(format!(
+                "Missing username in {}, use ?auth=external for external authentication",
+                redact_url(database_url)
+            )));
+        }
+        Ok(builder)
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = username.into();
+        self
+    }
+
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Secret(password.into());
+        self
+    }
+
+    /// Opens the session as `target_user` through the proxy user given as
+    /// `username`
+    ///
+    /// The target user needs to allow this with
+    /// `ALTER USER target_user GRANT CONNECT THROUGH proxy_user`.
+    pub fn proxy_client(mut self, target_user: &str) -> Self {
+        self.proxy_client = Some(target_user.into());
+        self
+    }
+
+    /// The database to connect to, this is either
+    ///
+    /// * an easy connect string like `//localhost:1521/orcl`
+    /// * an easy connect plus string like
+    ///   `tcps://db.example.com:1522/orcl?retry_count=3`, which is
+    ///   translated into a connect descriptor
+    /// * an alias defined in `tnsnames.ora`, c.f. `tns_admin`
+    /// * a full connect descriptor like
+    ///   `(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=localhost)(PORT=1521))(CONNECT_DATA=(SERVICE_NAME=orcl)))`
+    pub fn connect_string(mut self, connect_string: &str) -> Self {
+        self.connect_string = connect_string.into();
+        self
+    }
+
+    /// The oracle name of the character set (and national character set)
+    /// used for strings exchanged with the database, `AL32UTF8` by default
+    ///
+    /// Strings are read and bound as UTF-8, so only `AL32UTF8` and `UTF8`
+    /// are supported, other names fail when the connection is established.
+    /// Note that oracle's `UTF8` is CESU-8, which encodes characters outside
+    /// of the basic multilingual plane (e.g. emojis) differently than rust.
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = charset.into();
+        self
+    }
+
+    /// The number of rows oracle prefetches for each query, c.f.
+    /// `StatementOptions` for single queries
+    pub fn prefetch_rows(mut self, rows: u32) -> Self {
+        self.prefetch_rows = Some(rows);
+        self
+    }
+
+    /// The maximal number of bytes oracle uses for prefetching the rows of
+    /// each query, c.f. `StatementOptions` for single queries
+    pub fn prefetch_memory(mut self, bytes: u32) -> Self {
+        self.prefetch_memory = Some(bytes);
+        self
+    }
+
+    /// The number of statements kept prepared by oracle's client side
+    /// statement cache, `0` (the default) disables the cache
+    ///
+    /// Statements are looked up by their sql text, so statements prepared
+    /// again (e.g. by `Connection::execute`) are not parsed again. This is
+    /// independent of diesel's statement cache, which only keeps statements
+    /// of queries built by the dsl.
+    pub fn statement_cache_size(mut self, size: u32) -> Self {
+        self.statement_cache_size = size;
+        self
+    }
+
+    /// Commits each statement executed outside of `Connection::transaction`
+    /// when it succeeds (`OCI_COMMIT_ON_SUCCESS`), off by default
+    ///
+    /// Without autocommit the changes of these statements are not committed
+    /// until an explicit `COMMIT`, the next DDL statement or the next
+    /// transaction commits them, c.f. `OciConnection::set_autocommit`.
+    /// Autocommit saves a round trip per transaction, but bulk loads
+    /// committing each statement are slower than a single transaction.
+    pub fn autocommit(mut self, enabled: bool) -> Self {
+        self.autocommit = enabled;
+        self
+    }
+
+    /// The timeout for sending data to the database
+    pub fn send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout for receiving data from the database
+    pub fn receive_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_timeout = Some(timeout);
+        self
+    }
+
+    /// The time after which statements are cancelled, c.f.
+    /// `OciConnection::set_statement_timeout`
+    pub fn statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// The directory containing the `tnsnames.ora` used to resolve aliases
+    ///
+    /// The directory is passed with the alias (`alias?TNS_ADMIN=dir`, which
+    /// needs an oracle client 19c or later), so it only applies to this
+    /// connection. Without this setting oracle uses the `TNS_ADMIN`
+    /// environment variable or the network configuration of the oracle
+    /// client installation. Easy connect strings and connect descriptors
+    /// don't need `tnsnames.ora`.
+    pub fn tns_admin<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.tns_admin = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Authenticates with external credentials (`OCI_CRED_EXT`), e.g. an
+    /// oracle wallet or the operating system user, instead of username and
+    /// password
+    pub fn external_auth(mut self, external_auth: bool) -> Self {
+        self.external_auth = external_auth;
+        self
+    }
+
+    /// Replaces a lost session (e.g. after `ORA-03113`) by a new one and
+    /// retries the failed query, c.f. `OciConnection::reconnect`
+    ///
+    /// Only queries (`load`, `get_result`, ...) are retried, and only if the
+    /// lost session had no uncommitted changes and was not part of a
+    /// transaction or a global transaction branch. Other statements might
+    /// have been committed before the session was lost, their error is
+    /// returned.
+    pub fn auto_reconnect(mut self, auto_reconnect: bool) -> Self {
+        self.auto_reconnect = auto_reconnect;
+        self
+    }
+
+    /// Uses the oci environment shared by all connections with the same
+    /// character set (the default)
+    ///
+    /// Creating an environment is expensive, so it is created by the first
+    /// connection and freed with the last one using it. With `false` the
+    /// connection gets its own environment, which is freed with the
+    /// connection.
+    pub fn shared_environment(mut self, shared: bool) -> Self {
+        self.shared_environment = shared;
+        self
+    }
+
+    /// Writes the identifiers of queries with `quoting`, by default in
+    /// uppercase and quoted (`IdentifierQuoting::Uppercase`)
+    ///
+    /// The quoting only applies to the queries of this connection,
+    /// `debug_query` always uses the default.
+    pub fn identifier_quoting(mut self, quoting: IdentifierQuoting) -> Self {
+        self.identifier_quoting = quoting;
+        self
+    }
+
+    /// Calls `hook` on the events of a transparent application failover
+    /// (TAF), e.g. to log them or to retry after `FailoverEvent::Error`
+    ///
+    /// TAF is configured by the `FAILOVER_MODE` of the service or of the
+    /// connect descriptor. All events are logged, with or without a hook.
+    /// The hook is called on an oci thread and must not use the connection.
+    /// Session state (e.g. `ALTER SESSION` settings) is lost on failover.
+    ///
+    /// ```ignore
+    /// OciConnection::builder().on_failover(|event, _| match event {
+    ///     FailoverEvent::Error => {
+    ///         thread::sleep(Duration::from_secs(1));
+    ///         FailoverAction::Retry
+    ///     }
+    ///     _ => FailoverAction::Continue,
+    /// })
+    /// ```
+    pub fn on_failover<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(FailoverEvent, FailoverType) -> FailoverAction + Send + Sync + 'static,
+    {
+        self.failover_hook = Some(FailoverHook(Arc::new(hook)));
+        self
+    }
+
+    /// Sets the time zone of new sessions, e.g. `UTC`
+    ///
+    /// Otherwise oracle uses `ORA_SDTZ` or the time zone of the operating
+    /// system, c.f. `OciConnection::session_timezone`.
+    pub fn session_timezone(mut self, timezone: &str) -> Self {
+        self.session_timezone = Some(timezone.to_owned());
+        self
+    }
+
+    /// Resolves unqualified names of new sessions in `schema` instead of
+    /// the schema of the user, c.f. `OciConnection::set_current_schema`
+    pub fn current_schema(mut self, schema: &str) -> Self {
+        self.current_schema = Some(schema.to_owned());
+        self
+    }
+
+    /// Qualifies the tables of `table!` definitions without a schema (and
+    /// the sequences of `nextval`) with `owner` in the queries of this
+    /// connection, e.g. `FROM "APP"."USERS"`
+    ///
+    /// Unlike `current_schema` this doesn't change the session, so raw sql
+    /// still resolves unqualified names in the schema of the user. The
+    /// owner is quoted like the other identifiers, c.f.
+    /// `identifier_quoting`.
+    pub fn default_owner(mut self, owner: &str) -> Self {
+        self.default_owner = Some(owner.to_owned());
+        self
+    }
+
+    /// Encrypts the connection with TLS (protocol `TCPS`)
+    ///
+    /// The connect string needs to be an easy connect string like
+    /// `//db.example.com:2484/orcl` (the port defaults to 2484) or a connect
+    /// descriptor which already uses `(PROTOCOL=TCPS)`. Aliases of
+    /// `tnsnames.ora` are configured there instead.
+    pub fn tcps(mut self, tcps: bool) -> Self {
+        self.tcps = tcps;
+        self
+    }
+
+    /// The directory of the oracle wallet containing the certificates
+    /// trusted for (and the client certificate of) `TCPS` connections
+    ///
+    /// Without this setting oracle uses the wallet configured in
+    /// `sqlnet.ora`.
+    pub fn wallet_location<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.wallet_location = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Checks that the distinguished name of the server certificate matches
+    /// the service, or `ssl_server_cert_dn` if set
+    pub fn ssl_server_dn_match(mut self, dn_match: bool) -> Self {
+        self.ssl_server_dn_match = dn_match;
+        self
+    }
+
+    /// The expected distinguished name of the server certificate, e.g.
+    /// `CN=db.example.com,O=Example`, this enables `ssl_server_dn_match`
+    pub fn ssl_server_cert_dn(mut self, dn: &str) -> Self {
+        self.ssl_server_cert_dn = Some(dn.to_owned());
+        self.ssl_server_dn_match = true;
+        self
+    }
+
+    fn uses_tls_options(&self) -> bool {
+        self.tcps
+            || self.wallet_location.is_some()
+            || self.ssl_server_dn_match
+            || self.ssl_server_cert_dn.is_some()
+    }
+
+    /// The connect string passed to oracle
+    ///
+    /// Easy connect plus strings (e.g. `tcps://db:1522/orcl?retry_count=3`)
+    /// are translated into a connect descriptor, as older oracle clients
+    /// don't understand them. The TLS options are added to easy connect
+    /// strings and descriptors, the `tns_admin` directory to aliases.
+    pub(super) fn server_connect_string(&self) -> ConnectionResult<String> {
+        // parameters of a connect string set by `connect_string` configure
+        // the connection like the ones of an url
+        let mut config = self.clone();
+        let connect_string = take_params(self.connect_string.trim(), &mut config)?;
+        let tls = config.uses_tls_options();
+        if connect_string.starts_with('(') {
+            if !tls {
+                return Ok(connect_string);
+            }
+            return add_security_section(&connect_string, &config.security_section()?);
+        }
+        match EasyConnect::parse(&connect_string)? {
+            Some(ref easy_connect) if easy_connect.is_plain() && !tls => Ok(connect_string.clone()),
+            Some(mut easy_connect) => {
+                easy_connect.tcps |= config.tcps;
+                easy_connect.to_descriptor(&config.security_section()?)
+            }
+            None if tls => Err(ConnectionError::InvalidConnectionUrl(format!(
+                "The TLS options need an easy connect string or a connect descriptor, \
+                 configure the alias {} in tnsnames.ora instead",
+                connect_string
+            ))),
+            None => match config.tns_admin {
+                Some(ref dir) => {
+                    let separator = if connect_string.contains('?') {
+                        '&'
+                    } else {
+                        '?'
+                    };
+                    Ok(format!(
+                        "{}{}TNS_ADMIN={}",
+                        connect_string,
+                        separator,
+                        dir.display()
+                    ))
+                }
+                None => Ok(connect_string),
+            },
+        }
+    }
+
+    fn security_section(&self) -> ConnectionResult<String> {
+        // the values are quoted, a quote or parenthesis would end the
+        // value or the section
+        let quoted = |name: &str, value: String| {
+            if value.contains(&['"', ')'][..]) {
+                Err(ConnectionError::InvalidConnectionUrl(format!(
+                    "{} must not contain `\"` or `)`: {}",
+                    name, value
+                )))
+            } else {
+                Ok(format!("({}=\"{}\")", name, value))
+            }
+        };
+        let mut security = String::new();
+        if self.ssl_server_dn_match {
+            security.push_str("(SSL_SERVER_DN_MATCH=YES)");
+        }
+        if let Some(ref dn) = self.ssl_server_cert_dn {
+            security.push_str(&quoted("SSL_SERVER_CERT_DN", dn.clone())?);
+        }
+        if let Some(ref dir) = self.wallet_location {
+            security.push_str(&quoted("MY_WALLET_DIRECTORY", dir.display().to_string())?);
+        }
+        if security.is_empty() {
+            Ok(security)
+        } else {
+            Ok(format!("(SECURITY={})", security))
+        }
+    }
+
+    /// Sets a NLS parameter of new sessions, e.g.
+    /// `nls_parameter("NLS_SORT", "BINARY_CI")`
+    ///
+    /// Otherwise the session uses the NLS settings of the client (e.g.
+    /// `NLS_LANG`) and the database, which may change how dates and numbers
+    /// are converted from and to strings.
+    pub fn nls_parameter(mut self, name: &str, value: &str) -> Self {
+        self.set_nls_parameter(name, value);
+        self
+    }
+
+    fn set_nls_parameter(&mut self, name: &str, value: &str) {
+        let name = name.to_uppercase();
+        self.nls_parameters.retain(|p| p.0 != name);
+        self.nls_parameters.push((name, value.to_owned()));
+    }
+
+    /// The format of dates converted from and to strings, e.g. `YYYY-MM-DD`
+    pub fn nls_date_format(self, format: &str) -> Self {
+        self.nls_parameter("NLS_DATE_FORMAT", format)
+    }
+
+    /// The decimal and group separator of numbers converted from and to
+    /// strings, e.g. `.,`
+    pub fn nls_numeric_characters(self, characters: &str) -> Self {
+        self.nls_parameter("NLS_NUMERIC_CHARACTERS", characters)
+    }
+
+    /// The territory defining the defaults of the other NLS parameters,
+    /// e.g. `AMERICA`
+    pub fn nls_territory(self, territory: &str) -> Self {
+        self.nls_parameter("NLS_TERRITORY", territory)
+    }
+
+    // oracle opens a proxy session for user names like `proxy[target]`
+    pub(super) fn session_username(&self) -> String {
+        match self.proxy_client {
+            Some(ref target) => format!("{}[{}]", self.username, target),
+            None => self.username.clone(),
+        }
+    }
+
+    pub fn establish(&self) -> ConnectionResult<OciConnection> {
+        check_connect_string(&self.connect_string)?;
+        let span = spans::connect(&self.connect_string, &self.session_username());
+        let _entered = span.enter();
+        let raw = RawConnection::establish(self)?;
+        let conn = OciConnection::from_raw(Arc::new(raw), self.clone());
+        if let Some(timeout) = self.statement_timeout {
+            conn.set_statement_timeout(timeout)
+                .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        }
+        // language and territory set the defaults of the other parameters,
+        // so they go first
+        let mut nls_parameters = self.nls_parameters.iter().collect::<Vec<_>>();
+        nls_parameters.sort_by_key(|p| p.0 != "NLS_LANGUAGE" && p.0 != "NLS_TERRITORY");
+        for p in nls_parameters {
+            conn.set_nls_parameter(&p.0, &p.1)
+                .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        }
+        if let Some(ref timezone) = self.session_timezone {
+            conn.set_session_timezone(timezone)
+                .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        }
+        if let Some(ref schema) = self.current_schema {
+            conn.set_current_schema(schema)
+                .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        }
+        Ok(conn)
+    }
+}
+
+impl OciConnectionBuilder {
+    /// Displays the configuration as url including the password
+    pub fn display_unredacted(&self) -> Unredacted<'_> {
+        Unredacted(self)
+    }
+
+    fn fmt_url(&self, f: &mut fmt::Formatter, password: &str) -> fmt::Result {
+        write!(f, "oci://{}", self.username)?;
+        if let Some(ref target) = self.proxy_client {
+            write!(f, "[{}]", target)?;
+        }
+        if !self.external_auth {
+            write!(f, "/{}", password)?;
+        }
+        write!(f, "@{}", self.connect_string)?;
+        let mut params = Vec::new();
+        if self.external_auth {
+            params.push("auth=external".to_owned());
+        }
+        if self.tcps {
+            params.push("protocol=tcps".to_owned());
+        }
+        if let Some(ref dir) = self.wallet_location {
+            params.push(format!("wallet_location={}", dir.display()));
+        }
+        // a certificate dn implies the dn match
+        if self.ssl_server_dn_match && self.ssl_server_cert_dn.is_none() {
+            params.push("ssl_server_dn_match=yes".to_owned());
+        }
+        if let Some(ref dn) = self.ssl_server_cert_dn {
+            params.push(format!("ssl_server_cert_dn={}", dn));
+        }
+        if self.statement_cache_size != 0 {
+            params.push(format!(
+                "statement_cache_size={}",
+                self.statement_cache_size
+            ));
+        }
+        if self.autocommit {
+            params.push("autocommit=yes".to_owned());
+        }
+        if self.charset != Self::default().charset {
+            params.push(format!("charset={}", self.charset));
+        }
+        if !self.shared_environment {
+            params.push("shared_environment=no".to_owned());
+        }
+        if self.identifier_quoting != IdentifierQuoting::default() {
+            params.push(format!(
+                "identifier_quoting={}",
+                self.identifier_quoting.name()
+            ));
+        }
+        for p in &self.nls_parameters {
+            params.push(format!("{}={}", p.0.to_lowercase(), p.1));
+        }
+        if let Some(ref timezone) = self.session_timezone {
+            params.push(format!("time_zone={}", timezone));
+        }
+        if let Some(ref schema) = self.current_schema {
+            params.push(format!("current_schema={}", schema));
+        }
+        if let Some(ref owner) = self.default_owner {
+            params.push(format!("default_owner={}", owner));
+        }
+        for (i, param) in params.iter().enumerate() {
+            let separator = if i == 0 && !self.connect_string.contains('?') {
+                '?'
+            } else {
+                '&'
+            };
+            write!(f, "{}{}", separator, param)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays the configuration as url with the password replaced by `***`
+impl fmt::Display for OciConnectionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_url(f, "***")
+    }
+}
+
+/// The configuration displayed including the password, c.f.
+/// `OciConnectionBuilder::display_unredacted`
+pub struct Unredacted<'a>(&'a OciConnectionBuilder);
+
+impl<'a> fmt::Display for Unredacted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_url(f, self.0.password.expose())
+    }
+}
+
+/// A password, which is overwritten when dropped and never printed
+#[derive(Clone)]
+pub(super) struct Secret(String);
+
+impl Secret {
+    pub(super) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        unsafe {
+            for b in self.0.as_bytes_mut() {
+                ptr::write_volatile(b, 0);
+            }
+        }
+    }
+}
+
+// replaces the password of an `oci://user/password@connect_string` url
+fn redact_url(database_url: &str) -> String {
+    let prefix = "oci://";
+    let at = match database_url.rfind('@') {
+        Some(at) if database_url.starts_with(prefix) && at >= prefix.len() => at,
+        _ => return database_url.to_owned(),
+    };
+    match database_url[prefix.len()..at].find('/') {
+        Some(slash) => format!(
+            "{}/***{}",
+            &database_url[..prefix.len() + slash],
+            &database_url[at..]
+        ),
+        None => database_url.to_owned(),
+    }
+}
+
+fn parse_db_string(database_url: &str) -> ConnectionResult<(String, String, String)> {
+    // example: oci://\"diesel\"/diesel@//192.168.2.81:1521/orcl, c.f. sqplus manual
+    let invalid = || {
+        ConnectionError::InvalidConnectionUrl(format!(
+            "Could not use {} with oci backend, expected oci://user/password@connect_string",
+            redact_url(database_url)
+        ))
+    };
+    if !database_url.starts_with("oci://") {
+        return Err(invalid());
+    }
+    let rest = &database_url["oci://".len()..];
+    // the password may contain an `@`, the connect string usually does not
+    let at = rest.rfind('@').ok_or_else(invalid)?;
+    let (credentials, connect_string) = (&rest[..at], &rest[at + 1..]);
+    // no credentials at all for external authentication
+    let (user, password) = if credentials.is_empty() {
+        ("", "")
+    } else {
+        let slash = credentials.find('/').ok_or_else(invalid)?;
+        let (user, password) = (&credentials[..slash], &credentials[slash + 1..]);
+        if user.is_empty() {
+            return Err(invalid());
+        }
+        (user, password)
+    };
+    let connect_string = connect_string.trim_start_matches("//");
+    if connect_string.is_empty() {
+        return Err(invalid());
+    }
+
+    check_connect_string(connect_string)?;
+
+    Ok((user.into(), password.into(), connect_string.into()))
+}
+
+// splits `proxy_user[target_user]` into both user names
+fn split_proxy_user(username: &str) -> (String, Option<String>) {
+    if username.ends_with(']') {
+        if let Some(open) = username.find('[') {
+            return (
+                username[..open].to_owned(),
+                Some(username[open + 1..username.len() - 1].to_owned()),
+            );
+        }
+    }
+    (username.to_owned(), None)
+}
+
+// removes the parameters configuring the builder (`auth` and the TLS
+// options) from the connect string, other parameters are passed to oracle
+fn take_params(
+    connect_string: &str,
+    builder: &mut OciConnectionBuilder,
+) -> ConnectionResult<String> {
+    let question_mark = match connect_string.rfind('?') {
+        Some(i) => i,
+        None => return Ok(connect_string.into()),
+    };
+    let mut params = Vec::new();
+    for param in connect_string[question_mark + 1..].split('&') {
+        let (key, value) = match param.find('=') {
+            Some(i) => (&param[..i], &param[i + 1..]),
+            None => (param, ""),
+        };
+        match (&*key.to_lowercase(), value) {
+            ("auth", "external") => builder.external_auth = true,
+            ("auth", "password") => builder.external_auth = false,
+            ("auth", other) => {
+                return Err(ConnectionError::InvalidConnectionUrl(format!(
+                    "Unknown authentication {}, expected auth=external or auth=password",
+                    other
+                )));
+            }
+            ("protocol", p) if p.eq_ignore_ascii_case("tcps") => builder.tcps = true,
+            ("protocol", p) if p.eq_ignore_ascii_case("tcp") => builder.tcps = false,
+            ("wallet_location", dir) => builder.wallet_location = Some(dir.into()),
+            ("ssl_server_dn_match", v) => builder.ssl_server_dn_match = parse_flag(key, v)?,
+            ("autocommit", v) => builder.autocommit = parse_flag(key, v)?,
+            ("time_zone", tz) => builder.session_timezone = Some(tz.into()),
+            ("current_schema", schema) => builder.current_schema = Some(schema.into()),
+            ("default_owner", owner) => builder.default_owner = Some(owner.into()),
+            ("charset", charset) => {
+                check_charset(charset).map_err(ConnectionError::InvalidConnectionUrl)?;
+                builder.charset = charset.into();
+            }
+            ("shared_environment", v) => builder.shared_environment = parse_flag(key, v)?,
+            ("identifier_quoting", quoting) => {
+                builder.identifier_quoting =
+                    IdentifierQuoting::from_name(quoting).ok_or_else(|| {
+                        ConnectionError::InvalidConnectionUrl(format!(
+                            "Unknown identifier_quoting {}, expected uppercase, always, \
+                             never or smart",
+                            quoting
+                        ))
+                    })?;
+            }
+            ("statement_cache_size", size) => {
+                builder.statement_cache_size = size.parse().map_err(|_| {
+                    ConnectionError::InvalidConnectionUrl(format!(
+                        "Invalid statement_cache_size {}",
+                        size
+                    ))
+                })?;
+            }
+            (name, value) if name.starts_with("nls_") => {
+                alter_session_nls(name, value).map_err(ConnectionError::InvalidConnectionUrl)?;
+                builder.set_nls_parameter(name, value);
+            }
+            ("ssl_server_cert_dn", dn) => {
+                builder.ssl_server_cert_dn = Some(dn.into());
+                builder.ssl_server_dn_match = true;
+            }
+            _ => params.push(param),
+        }
+    }
+    let mut connect_string = connect_string[..question_mark].to_owned();
+    if !params.is_empty() {
+        connect_string.push('?');
+        connect_string.push_str(&params.join("&"));
+    }
+    Ok(connect_string)
+}
+
+fn parse_flag(key: &str, value: &str) -> ConnectionResult<bool> {
+    match &*value.to_lowercase() {
+        "yes" | "on" | "true" => Ok(true),
+        "no" | "off" | "false" => Ok(false),
+        _ => Err(ConnectionError::InvalidConnectionUrl(format!(
+            "Invalid value {} of {}, expected yes or no",
+            value, key
+        ))),
+    }
+}
+
+// Adds the TLS options to a connect descriptor, the security section
+// belongs into its outermost DESCRIPTION. A DESCRIPTION_LIST would need the
+// section in each of its descriptions, so it is rejected as well as a
+// descriptor with a section of its own.
+fn add_security_section(descriptor: &str, security: &str) -> ConnectionResult<String> {
+    let invalid = |reason: &str| {
+        Err(ConnectionError::InvalidConnectionUrl(format!(
+            "Cannot add the TLS options to the connect descriptor {}: {}",
+            descriptor, reason
+        )))
+    };
+    let normalized = descriptor
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if normalized.starts_with("(DESCRIPTION_LIST=") {
+        return invalid("configure them in each DESCRIPTION of the DESCRIPTION_LIST");
+    }
+    if !normalized.starts_with("(DESCRIPTION=") {
+        return invalid("it is no DESCRIPTION");
+    }
+    if normalized.contains("(SECURITY=") {
+        return invalid("it already has a SECURITY section");
+    }
+    // the parenthesis closing the DESCRIPTION
+    let mut depth = 0;
+    let end = descriptor.char_indices().find(|&(_, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        depth == 0 && c == ')'
+    });
+    match end {
+        Some((i, _)) if i == descriptor.len() - 1 => {
+            Ok(format!("{}{})", &descriptor[..i], security))
+        }
+        _ => invalid("it does not end with the `)` closing the DESCRIPTION"),
+    }
+}
+
+// catches the most common mistake in hand written connect descriptors
+// before oracle reports a generic `ORA-12154`
+fn check_connect_string(connect_string: &str) -> ConnectionResult<()> {
+    if !connect_string.trim_start().starts_with('(') {
+        return Ok(());
+    }
+    let mut depth = 0i32;
+    for c in connect_string.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            break;
+        }
+    }
+    if depth == 0 {
+        Ok(())
+    } else {
+        Err(ConnectionError::InvalidConnectionUrl(format!(
+            "Unbalanced parentheses in connect descriptor {}",
+            connect_string
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_db_string, redact_url, OciConnectionBuilder, Secret};
+
+    #[test]
+    fn check_parse_database_url_1() {
+        let input = "oci://user/password@//localhost:1234/my_database";
+        let output = parse_db_string(input).unwrap();
+        assert_eq!(
+            output,
+            (
+                "user".into(),
+                "password".into(),
+                "localhost:1234/my_database".into()
+            )
+        );
+    }
+
+    #[test]
+    fn check_parse_database_url_2() {
+        let input = "oci://user/password@//localhost/my_database";
+        let output = parse_db_string(input).unwrap();
+        assert_eq!(
+            output,
+            (
+                "user".into(),
+                "password".into(),
+                "localhost/my_database".into()
+            )
+        );
+    }
+
+    #[test]
+    fn check_parse_database_url_special_password() {
+        let input = "oci://\"diesel\"/p@ss/word@orcl_alias";
+        let output = parse_db_string(input).unwrap();
+        assert_eq!(
+            output,
+            ("\"diesel\"".into(), "p@ss/word".into(), "orcl_alias".into())
+        );
+    }
+
+    #[test]
+    fn check_parse_connect_descriptor() {
+        let descriptor = "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=db.example.com)(PORT=1521))\
+                          (CONNECT_DATA=(SERVICE_NAME=orcl)))";
+        let output = parse_db_string(&format!("oci://user/password@{}", descriptor)).unwrap();
+        assert_eq!(
+            output,
+            ("user".into(), "password".into(), descriptor.into())
+        );
+
+        let invalid = "oci://user/password@(DESCRIPTION=(ADDRESS=(HOST=db.example.com)";
+        assert!(parse_db_string(invalid).is_err());
+    }
+
+    #[test]
+    fn check_parse_external_auth() {
+        let builder =
+            OciConnectionBuilder::from_url("oci://@//localhost/orcl?auth=external").unwrap();
+        assert!(builder.external_auth);
+        assert_eq!(builder.connect_string, "localhost/orcl");
+
+        let builder = OciConnectionBuilder::from_url(
+            "oci://@//localhost/orcl?connect_timeout=10&auth=external",
+        )
+        .unwrap();
+        assert!(builder.external_auth);
+        assert_eq!(builder.connect_string, "localhost/orcl?connect_timeout=10");
+
+        let builder = OciConnectionBuilder::from_url("oci://user/password@orcl").unwrap();
+        assert!(!builder.external_auth);
+
+        assert!(OciConnectionBuilder::from_url("oci://@//localhost/orcl").is_err());
+        assert!(OciConnectionBuilder::from_url("oci://@//localhost/orcl?auth=kerberos").is_err());
+    }
+
+    #[test]
+    fn check_parse_proxy_user() {
+        let builder =
+            OciConnectionBuilder::from_url("oci://proxy[scott]/secret@//localhost/orcl").unwrap();
+        assert_eq!(builder.username, "proxy");
+        assert_eq!(builder.proxy_client, Some("scott".to_owned()));
+        assert_eq!(builder.session_username(), "proxy[scott]");
+
+        let builder = OciConnectionBuilder::new()
+            .username("proxy")
+            .proxy_client("scott");
+        assert_eq!(builder.session_username(), "proxy[scott]");
+
+        let builder = OciConnectionBuilder::from_url("oci://scott/secret@orcl").unwrap();
+        assert_eq!(builder.proxy_client, None);
+        assert_eq!(builder.session_username(), "scott");
+    }
+
+    #[test]
+    fn check_parse_tls_options() {
+        let builder = OciConnectionBuilder::from_url(
+            "oci://scott/tiger@//db.example.com:1522/orcl?protocol=tcps\
+             &wallet_location=/etc/wallet&ssl_server_cert_dn=CN=db.example.com,O=Example",
+        )
+        .unwrap();
+        assert!(builder.tcps);
+        assert!(builder.ssl_server_dn_match);
+        assert_eq!(builder.connect_string, "db.example.com:1522/orcl");
+        assert_eq!(
+            builder.server_connect_string().unwrap(),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=db.example.com)(PORT=1522))\
+             (CONNECT_DATA=(SERVICE_NAME=orcl))\
+             (SECURITY=(SSL_SERVER_DN_MATCH=YES)\
+             (SSL_SERVER_CERT_DN=\"CN=db.example.com,O=Example\")\
+             (MY_WALLET_DIRECTORY=\"/etc/wallet\")))"
+        );
+        assert_eq!(
+            builder.to_string(),
+            "oci://scott/***@db.example.com:1522/orcl?protocol=tcps\
+             &wallet_location=/etc/wallet&ssl_server_cert_dn=CN=db.example.com,O=Example"
+        );
+
+        let builder = OciConnectionBuilder::new()
+            .connect_string("[::1]/orcl")
+            .tcps(true);
+        assert_eq!(
+            builder.server_connect_string().unwrap(),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=[::1])(PORT=2484))\
+             (CONNECT_DATA=(SERVICE_NAME=orcl)))"
+        );
+
+        let builder = OciConnectionBuilder::new()
+            .connect_string("(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=db)(PORT=2484)))")
+            .ssl_server_dn_match(true);
+        assert_eq!(
+            builder.server_connect_string().unwrap(),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=db)(PORT=2484))\
+             (SECURITY=(SSL_SERVER_DN_MATCH=YES)))"
+        );
+
+        let builder = OciConnectionBuilder::new()
+            .connect_string("orcl_alias")
+            .tcps(true);
+        assert!(builder.server_connect_string().is_err());
+        let builder = OciConnectionBuilder::new().connect_string("orcl_alias");
+        assert_eq!(builder.server_connect_string().unwrap(), "orcl_alias");
+        let builder = builder.tns_admin("/etc/oracle");
+        assert_eq!(
+            builder.server_connect_string().unwrap(),
+            "orcl_alias?TNS_ADMIN=/etc/oracle"
+        );
+        let builder = builder.connect_string("db:1521/orcl");
+        assert_eq!(builder.server_connect_string().unwrap(), "db:1521/orcl");
+
+        assert!(
+            OciConnectionBuilder::from_url("oci://u/p@db/orcl?ssl_server_dn_match=maybe").is_err()
+        );
+    }
+
+    #[test]
+    fn check_tls_options_in_descriptor() {
+        let with_descriptor = |descriptor: &str| {
+            OciConnectionBuilder::new()
+                .connect_string(descriptor)
+                .ssl_server_dn_match(true)
+                .server_connect_string()
+        };
+        assert_eq!(
+            with_descriptor("( description = (address=(host=dbü)(port=2484)) )").unwrap(),
+            "( description = (address=(host=dbü)(port=2484)) (SECURITY=(SSL_SERVER_DN_MATCH=YES)))"
+        );
+        assert!(with_descriptor(
+            "(DESCRIPTION_LIST=(DESCRIPTION=(ADDRESS=(HOST=a)))(DESCRIPTION=(ADDRESS=(HOST=b))))"
+        )
+        .is_err());
+        assert!(with_descriptor(
+            "(DESCRIPTION=(ADDRESS=(HOST=db))(SECURITY=(SSL_SERVER_DN_MATCH=NO)))"
+        )
+        .is_err());
+        assert!(with_descriptor("(ADDRESS=(HOST=db))").is_err());
+        assert!(with_descriptor("(DESCRIPTION=(ADDRESS=(HOST=db)))(ADDRESS=(HOST=ü))").is_err());
+
+        let builder = OciConnectionBuilder::new().connect_string("db/orcl");
+        assert!(builder
+            .clone()
+            .ssl_server_cert_dn("CN=db\")(SECURITY=(SSL_SERVER_DN_MATCH=NO)")
+            .server_connect_string()
+            .is_err());
+        assert!(builder
+            .wallet_location("/etc/wallet)")
+            .server_connect_string()
+            .is_err());
+    }
+
+    #[test]
+    fn check_parse_easy_connect_plus() {
+        let url = "oci://admin/secret@tcps://adb.example.com:1522/abc_high\
+                   ?wallet_location=/etc/wallet&retry_count=3";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert_eq!(
+            builder.connect_string,
+            "tcps://adb.example.com:1522/abc_high?retry_count=3"
+        );
+        let expected = "(DESCRIPTION=(RETRY_COUNT=3)\
+                        (ADDRESS=(PROTOCOL=TCPS)(HOST=adb.example.com)(PORT=1522))\
+                        (CONNECT_DATA=(SERVICE_NAME=abc_high))\
+                        (SECURITY=(MY_WALLET_DIRECTORY=\"/etc/wallet\")))";
+        assert_eq!(builder.server_connect_string().unwrap(), expected);
+
+        // the same parameters given to the builder
+        let builder = OciConnectionBuilder::new().connect_string(
+            "tcps://adb.example.com:1522/abc_high?wallet_location=/etc/wallet&retry_count=3",
+        );
+        assert_eq!(builder.server_connect_string().unwrap(), expected);
+
+        let builder = OciConnectionBuilder::from_url("oci://u/p@//localhost:1521/orcl").unwrap();
+        assert_eq!(
+            builder.server_connect_string().unwrap(),
+            "localhost:1521/orcl"
+        );
+    }
+
+    #[test]
+    fn check_parse_nls_parameters() {
+        let url = "oci://scott/tiger@//localhost/orcl?nls_date_format=YYYY-MM-DD\
+                   &NLS_NUMERIC_CHARACTERS=.,&time_zone=UTC&current_schema=APP";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert_eq!(builder.connect_string, "localhost/orcl");
+        assert_eq!(
+            builder.nls_parameters,
+            vec![
+                ("NLS_DATE_FORMAT".to_owned(), "YYYY-MM-DD".to_owned()),
+                ("NLS_NUMERIC_CHARACTERS".to_owned(), ".,".to_owned()),
+            ]
+        );
+        assert_eq!(builder.session_timezone, Some("UTC".to_owned()));
+        assert_eq!(builder.current_schema, Some("APP".to_owned()));
+        assert_eq!(
+            builder.to_string(),
+            "oci://scott/***@localhost/orcl?nls_date_format=YYYY-MM-DD\
+             &nls_numeric_characters=.,&time_zone=UTC&current_schema=APP"
+        );
+
+        let builder = OciConnectionBuilder::new()
+            .nls_date_format("YYYY")
+            .nls_parameter("nls_date_format", "YYYY-MM-DD");
+        assert_eq!(
+            builder.nls_parameters,
+            vec![("NLS_DATE_FORMAT".to_owned(), "YYYY-MM-DD".to_owned())]
+        );
+        assert!(OciConnectionBuilder::from_url("oci://u/p@db/orcl?nls_x%27=1").is_err());
+    }
+
+    #[test]
+    fn check_parse_charset() {
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl").unwrap();
+        assert_eq!(builder.charset, "AL32UTF8");
+        assert_eq!(builder.to_string(), "oci://u/***@db/orcl");
+
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl?charset=UTF8").unwrap();
+        assert_eq!(builder.charset, "UTF8");
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(builder.to_string(), "oci://u/***@db/orcl?charset=UTF8");
+
+        let ret = OciConnectionBuilder::from_url("oci://u/p@db/orcl?charset=WE8ISO8859P1");
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn check_parse_shared_environment() {
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl").unwrap();
+        assert!(builder.shared_environment);
+
+        let url = "oci://u/p@db/orcl?shared_environment=no";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert!(!builder.shared_environment);
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(
+            builder.to_string(),
+            "oci://u/***@db/orcl?shared_environment=no"
+        );
+    }
+
+    #[test]
+    fn check_parse_identifier_quoting() {
+        use oracle::query_builder::IdentifierQuoting;
+
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl").unwrap();
+        assert_eq!(builder.identifier_quoting, IdentifierQuoting::Uppercase);
+
+        let url = "oci://u/p@db/orcl?identifier_quoting=smart";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert_eq!(builder.identifier_quoting, IdentifierQuoting::Smart);
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(builder.to_string(), url.replace("/p@", "/***@"));
+
+        let url = "oci://u/p@db/orcl?identifier_quoting=lower";
+        assert!(OciConnectionBuilder::from_url(url).is_err());
+    }
+
+    #[test]
+    fn check_parse_default_owner() {
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl").unwrap();
+        assert_eq!(builder.default_owner, None);
+
+        let url = "oci://u/p@db/orcl?default_owner=APP";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert_eq!(builder.default_owner, Some("APP".to_owned()));
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(builder.to_string(), url.replace("/p@", "/***@"));
+    }
+
+    #[test]
+    fn check_parse_statement_cache_size() {
+        let builder =
+            OciConnectionBuilder::from_url("oci://u/p@db/orcl?statement_cache_size=50").unwrap();
+        assert_eq!(builder.statement_cache_size, 50);
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(
+            builder.to_string(),
+            "oci://u/***@db/orcl?statement_cache_size=50"
+        );
+        assert!(
+            OciConnectionBuilder::from_url("oci://u/p@db/orcl?statement_cache_size=-1").is_err()
+        );
+    }
+
+    #[test]
+    fn check_parse_autocommit() {
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl").unwrap();
+        assert!(!builder.autocommit);
+
+        let builder = OciConnectionBuilder::from_url("oci://u/p@db/orcl?autocommit=on").unwrap();
+        assert!(builder.autocommit);
+        assert_eq!(builder.connect_string, "db/orcl");
+        assert_eq!(builder.to_string(), "oci://u/***@db/orcl?autocommit=yes");
+        assert!(OciConnectionBuilder::from_url("oci://u/p@db/orcl?autocommit=maybe").is_err());
+    }
+
+    #[test]
+    fn check_config_is_redacted() {
+        let url = "oci://scott/tiger@//localhost:1521/orcl";
+        let builder = OciConnectionBuilder::from_url(url).unwrap();
+        assert!(!format!("{:?}", builder).contains("tiger"));
+        assert_eq!(builder.to_string(), "oci://scott/***@localhost:1521/orcl");
+        assert_eq!(
+            builder.display_unredacted().to_string(),
+            "oci://scott/tiger@localhost:1521/orcl"
+        );
+
+        let builder = OciConnectionBuilder::from_url("oci://@orcl?auth=external").unwrap();
+        assert_eq!(builder.to_string(), "oci://@orcl?auth=external");
+
+        let error = OciConnectionBuilder::from_url("oci://scott/tiger@").unwrap_err();
+        assert!(!error.to_string().contains("tiger"));
+        assert_eq!(redact_url(url), "oci://scott/***@//localhost:1521/orcl");
+        assert_eq!(redact_url("oci://@orcl"), "oci://@orcl");
+    }
+
+    #[test]
+    fn check_parse_invalid_database_url() {
+        assert!(parse_db_string("postgres://user/password@localhost").is_err());
+        assert!(parse_db_string("oci://user@localhost").is_err());
+        assert!(parse_db_string("oci://user/password").is_err());
+    }
+
+    #[test]
+    fn check_secret_is_not_printed() {
+        let secret = Secret("password".into());
+        assert_eq!(format!("{:?}", secret), "\"***\"");
+        assert_eq!(secret.expose(), "password");
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use diesel::result::QueryResult;
+use oci_sys as ffi;
+
+use super::stmt::Statement;
+use super::OciConnection;
+
+// The handles needed to call `OCIBreak`, removed when the connection is
+// closed
+pub(super) struct BreakHandles {
+    pub(super) service_handle: *mut ffi::OCISvcCtx,
+    // a separate error handle, as the one of the connection is used by the
+    // statement being cancelled
+    pub(super) error_handle: *mut ffi::OCIError,
+}
+
+// OCI allows to call `OCIBreak` from another thread, as the environment is
+// created with `OCI_THREADED`
+unsafe impl Send for BreakHandles {}
+
+/// Cancels the statement currently executed by a connection, c.f.
+/// `OciConnection::cancel_token`
+///
+/// The token can be sent to and cloned for other threads.
+#[derive(Clone)]
+pub struct CancelToken {
+    handles: Arc<Mutex<Option<BreakHandles>>>,
+}
+
+impl CancelToken {
+    pub(super) fn new(handles: Arc<Mutex<Option<BreakHandles>>>) -> Self {
+        CancelToken { handles }
+    }
+
+    /// Aborts the statement currently executed or fetched from by the
+    /// connection, which then fails with
+    /// `ORA-01013: user requested cancel of current operation`
+    ///
+    /// Returns `false` if the connection was already closed. Cancelling an
+    /// idle connection has no effect.
+    pub fn cancel(&self) -> QueryResult<bool> {
+        // holding the lock keeps the connection from being closed
+        let handles = self.handles.lock().unwrap_or_else(|e| e.into_inner());
+        match *handles {
+            Some(ref h) => {
+                let status = unsafe { ffi::OCIBreak(h.service_handle as *mut _, h.error_handle) };
+                Statement::check_error(h.error_handle, status)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelToken").finish()
+    }
+}
+
+impl OciConnection {
+    /// Returns a token to cancel running statements of this connection from
+    /// another thread
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken::new(self.raw().cancel_handles.clone())
+    }
+}
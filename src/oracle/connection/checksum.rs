@@ -0,0 +1,123 @@
+use diesel::query_builder::{QueryFragment, QueryId};
+use diesel::result::QueryResult;
+
+use super::super::backend::Oracle;
+use super::OciConnection;
+
+/// Row count and checksum of the result set of a query
+///
+/// The checksum is the wrapping sum of all row hashes, so it does not depend
+/// on the order of the returned rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct QueryChecksum {
+    pub row_count: u64,
+    pub checksum: u64,
+}
+
+impl QueryChecksum {
+    fn add_row(&mut self, hash: u64) {
+        self.row_count += 1;
+        self.checksum = self.checksum.wrapping_add(hash);
+    }
+}
+
+// 64 bit FNV-1a, c.f. http://www.isthe.com/chongo/tech/comp/fnv/
+// we cannot use std's DefaultHasher here as its output is not guaranteed
+// to be stable between rust releases
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for b in bytes {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Computes a stable hash over the raw values of a row
+///
+/// Null values and the length of each value are part of the hash, so
+/// `(NULL, 'ab')` and `('a', 'b')` result in different hashes.
+pub fn row_hash(row: &[Option<&[u8]>]) -> u64 {
+    row.iter()
+        .fold(FNV_OFFSET_BASIS, |hash, value| match *value {
+            None => fnv1a(hash, &[0]),
+            Some(v) => {
+                let hash = fnv1a(hash, &[1]);
+                let hash = fnv1a(hash, &(v.len() as u32).to_le_bytes());
+                fnv1a(hash, v)
+            }
+        })
+}
+
+impl OciConnection {
+    /// Computes a hash for each row returned by the given query
+    ///
+    /// The hashes are computed on the client side from the fetched values,
+    /// which makes them comparable between runs of the same query against
+    /// different databases, as long as the column types are the same.
+    pub fn row_hashes<T>(&self, source: &T) -> QueryResult<Vec<u64>>
+    where
+        T: QueryFragment<Oracle> + QueryId,
+    {
+        let mut stmt = self.prepare_query(source)?;
+        let mut cursor = stmt.run_with_raw_cursor()?;
+        let mut ret = Vec::new();
+        while let Some(row) = cursor.next_row()? {
+            ret.push(row_hash(&row));
+        }
+        Ok(ret)
+    }
+
+    /// Computes the row count and an order independent checksum of the
+    /// result set of the given query
+    ///
+    /// This is meant to validate that two tables contain the same data, e.g.
+    /// after a replication job.
+    pub fn checksum<T>(&self, source: &T) -> QueryResult<QueryChecksum>
+    where
+        T: QueryFragment<Oracle> + QueryId,
+    {
+        let mut stmt = self.prepare_query(source)?;
+        let mut cursor = stmt.run_with_raw_cursor()?;
+        let mut ret = QueryChecksum::default();
+        while let Some(row) = cursor.next_row()? {
+            ret.add_row(row_hash(&row));
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{row_hash, QueryChecksum};
+
+    #[test]
+    fn check_row_hash_is_stable() {
+        let row: Vec<Option<&[u8]>> = vec![Some(b"abc"), None];
+        assert_eq!(row_hash(&row), row_hash(&row.clone()));
+        assert_eq!(row_hash(&[]), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn check_row_hash_distinguishes_values() {
+        let a: Vec<Option<&[u8]>> = vec![None, Some(b"ab")];
+        let b: Vec<Option<&[u8]>> = vec![Some(b"a"), Some(b"b")];
+        let c: Vec<Option<&[u8]>> = vec![Some(b""), Some(b"ab")];
+        assert_ne!(row_hash(&a), row_hash(&b));
+        assert_ne!(row_hash(&a), row_hash(&c));
+    }
+
+    #[test]
+    fn check_checksum_is_order_independent() {
+        let mut a = QueryChecksum::default();
+        a.add_row(1);
+        a.add_row(u64::MAX);
+        let mut b = QueryChecksum::default();
+        b.add_row(u64::MAX);
+        b.add_row(1);
+        assert_eq!(a, b);
+        assert_eq!(a.row_count, 2);
+    }
+}
@@ -0,0 +1,28 @@
+use oracle::types::OCIDataType;
+
+/// Metadata of a single column of a result set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// The raw `SQLT_*` type code reported by oracle
+    pub type_code: u16,
+    /// The precision of numeric columns, `0` if not set
+    pub precision: i16,
+    /// The scale of numeric columns, `-127` for floating point numbers
+    pub scale: i8,
+    /// The length of character columns in characters
+    pub char_length: u16,
+    /// The maximal size of the column in bytes
+    pub byte_length: u16,
+    pub nullable: bool,
+    /// Whether this column uses the national character set, i.e. is a
+    /// `NCHAR`, `NVARCHAR2` or `NCLOB` column
+    pub national: bool,
+}
+
+impl ColumnInfo {
+    /// The oracle type of this column, if it is known to diesel-oci
+    pub fn data_type(&self) -> Option<OCIDataType> {
+        OCIDataType::from_raw(u32::from(self.type_code))
+    }
+}
@@ -0,0 +1,73 @@
+use diesel::dsl::sql;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use diesel::sql_types::Text;
+use diesel::{Connection, RunQueryDsl};
+
+use super::OciConnection;
+use oracle::query_builder::is_simple_identifier;
+
+impl OciConnection {
+    /// The schema unqualified names are resolved in
+    /// (`SYS_CONTEXT('USERENV', 'CURRENT_SCHEMA')`)
+    ///
+    /// This is the user of the session unless it was changed with
+    /// `set_current_schema`.
+    pub fn current_schema(&self) -> QueryResult<String> {
+        sql::<Text>("SELECT SYS_CONTEXT('USERENV', 'CURRENT_SCHEMA') FROM dual").get_result(self)
+    }
+
+    /// Resolves unqualified names in `schema` for the rest of the session
+    ///
+    /// This sets the default owner of the tables of `table!` definitions
+    /// without a schema, e.g. to use the same definitions for the schemas of
+    /// several tenants, for raw sql as well (c.f.
+    /// `OciConnectionBuilder::default_owner`). Privileges are still checked
+    /// for the user of the session. A schema name that is not a simple identifier has to be
+    /// quoted, e.g. `"My Schema"`.
+    pub fn set_current_schema(&self, schema: &str) -> QueryResult<()> {
+        if !is_schema_name(schema) {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!("Invalid schema name {}", schema)),
+            ));
+        }
+        Connection::execute(
+            self,
+            &format!("ALTER SESSION SET CURRENT_SCHEMA = {}", schema),
+        )
+        .map(|_| ())
+    }
+
+    /// Resolves unqualified names in the schema of the session user again
+    pub fn reset_current_schema(&self) -> QueryResult<()> {
+        let user = sql::<Text>("SELECT SYS_CONTEXT('USERENV', 'SESSION_USER') FROM dual")
+            .get_result::<String>(self)?;
+        self.set_current_schema(&format!("\"{}\"", user))
+    }
+}
+
+// a simple identifier or one in double quotes, the statement can't bind
+// the name
+fn is_schema_name(schema: &str) -> bool {
+    if schema.len() > 2 && schema.starts_with('"') && schema.ends_with('"') {
+        return !schema[1..schema.len() - 1].contains('"');
+    }
+    is_simple_identifier(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_schema_name;
+
+    #[test]
+    fn check_schema_name() {
+        assert!(is_schema_name("SCOTT"));
+        assert!(is_schema_name("app_owner$1"));
+        assert!(is_schema_name("\"My Schema\""));
+        assert!(!is_schema_name(""));
+        assert!(!is_schema_name("1abc"));
+        assert!(!is_schema_name("scott; DROP TABLE x"));
+        assert!(!is_schema_name("\"\""));
+        assert!(!is_schema_name("\"a\"b\""));
+    }
+}
@@ -1,21 +1,39 @@
-use diesel::deserialize::{FromSqlRow, Queryable};
+use diesel::deserialize::{FromSqlRow, Queryable, QueryableByName};
 use diesel::result::Error::DeserializationError;
-use diesel::result::QueryResult;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
 use diesel::sql_types::HasSqlType;
 use oci_sys as ffi;
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::ptr;
+use std::rc::Rc;
+use std::time::Instant;
 
 use super::super::backend::Oracle;
 use super::super::types::OCIDataType;
-use super::row::OciRow;
+use super::column_info::ColumnInfo;
+use super::long::LongValue;
+use super::row::{self, OciRow};
 use super::stmt::Statement;
 
+// ORA-01406: fetched column value was truncated
+const TRUNCATED: u16 = 1406;
+
 pub struct Field {
     inner: *mut ffi::OCIDefine,
     buffer: Vec<u8>,
     null_indicator: Box<i16>,
-    #[allow(dead_code)]
+    // the return code of the value, `1406` if it was truncated
+    return_code: Box<u16>,
+    // the `OCINumber` a `NUMBER` column is fetched into, which is converted
+    // into `buffer` after each fetch
+    number: Option<Box<ffi::OCINumber>>,
+    // the descriptor a `TIMESTAMP WITH (LOCAL) TIME ZONE` column is fetched
+    // into, which is converted into `buffer` after each fetch as well
+    timestamp: Option<Box<*mut ffi::OCIDateTime>>,
     typ: OCIDataType,
+    info: ColumnInfo,
+    long: Option<Box<LongValue>>,
 }
 
 impl Field {
@@ -23,45 +41,351 @@ impl Field {
         raw: *mut ffi::OCIDefine,
         buffer: Vec<u8>,
         indicator: Box<i16>,
+        return_code: Box<u16>,
         typ: OCIDataType,
+        info: ColumnInfo,
     ) -> Field {
         Field {
             inner: raw,
             buffer,
             null_indicator: indicator,
+            return_code,
+            number: None,
+            timestamp: None,
             typ,
+            info,
+            long: None,
+        }
+    }
+
+    /// A field of a `NUMBER` column fetched into `number`, `buffer` receives
+    /// the value converted to `typ` (`Int`, `BFloat` or `BDouble`)
+    pub(super) fn with_number(mut self, number: Box<ffi::OCINumber>) -> Field {
+        self.number = Some(number);
+        self
+    }
+
+    /// A field of a timestamp column fetched into the descriptor
+    /// `timestamp`, `buffer` receives the value in the format of
+    /// `FetchType::TimestampTz`
+    pub(super) fn with_timestamp(mut self, timestamp: Box<*mut ffi::OCIDateTime>) -> Field {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// A field of a `LONG` (`typ` is `String`) or `LONG RAW` (`typ` is
+    /// `Binary`) column, whose value is fetched piecewise into `long`
+    pub(super) fn new_long(
+        raw: *mut ffi::OCIDefine,
+        long: Box<LongValue>,
+        typ: OCIDataType,
+        info: ColumnInfo,
+    ) -> Field {
+        Field {
+            inner: raw,
+            buffer: Vec::new(),
+            null_indicator: Box::new(-1),
+            return_code: Box::new(0),
+            number: None,
+            timestamp: None,
+            typ,
+            info,
+            long: Some(long),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    pub fn info(&self) -> &ColumnInfo {
+        &self.info
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        if let Some(ref long) = self.long {
+            // only strings keep the terminating null byte
+            return long.value().map(|v| match self.typ {
+                OCIDataType::Binary => &v[..v.len() - 1],
+                _ => v,
+            });
+        }
+        if self.is_null() {
+            None
+        } else {
+            Some(&self.buffer)
         }
     }
 
     pub fn is_null(&self) -> bool {
+        if let Some(ref long) = self.long {
+            return long.is_null();
+        }
         *self.null_indicator == -1
     }
+
+    /// Whether the value of the current row did not fit into the buffer
+    /// (`ORA-01406`)
+    pub(super) fn is_truncated(&self) -> bool {
+        *self.return_code == TRUNCATED
+    }
+
+    /// Converts the `OCINumber` of the current row with `OCINumberToInt` or
+    /// `OCINumberToReal`, values exceeding the size of `buffer` fail with a
+    /// `DeserializationError`
+    fn convert_number(&mut self, stmt: &Statement) -> QueryResult<()> {
+        let number = match self.number {
+            Some(ref number) if *self.null_indicator != -1 => number,
+            _ => return Ok(()),
+        };
+        let error_handle = stmt.error_handle.handle;
+        if self.typ == OCIDataType::Int && self.buffer.len() == 16 {
+            let value = large_number(stmt, number).map_err(|e| self.conversion_error(e))?;
+            self.buffer.copy_from_slice(&value.to_ne_bytes());
+            return Ok(());
+        }
+        let status = unsafe {
+            match self.typ {
+                OCIDataType::Int => ffi::OCINumberToInt(
+                    error_handle,
+                    &**number,
+                    self.buffer.len() as ffi::uword,
+                    ffi::OCI_NUMBER_SIGNED,
+                    self.buffer.as_mut_ptr() as *mut _,
+                ),
+                _ => ffi::OCINumberToReal(
+                    error_handle,
+                    &**number,
+                    self.buffer.len() as ffi::uword,
+                    self.buffer.as_mut_ptr() as *mut _,
+                ),
+            }
+        };
+        stmt.check(status).map_err(|e| self.conversion_error(e))
+    }
+
+    /// Converts the timestamp descriptor of the current row into its local
+    /// time and the offset of its time zone
+    fn convert_timestamp(&mut self, stmt: &Statement) -> QueryResult<()> {
+        let timestamp = match self.timestamp {
+            Some(ref timestamp) if *self.null_indicator != -1 => **timestamp,
+            _ => return Ok(()),
+        };
+        let value = timestamp_tz(stmt, timestamp).map_err(|e| self.conversion_error(e))?;
+        self.buffer.copy_from_slice(&value);
+        Ok(())
+    }
+
+    fn conversion_error(&self, e: Error) -> Error {
+        DeserializationError(
+            format!("Could not convert the value of {}: {}", self.info.name, e).into(),
+        )
+    }
+}
+
+// `OCINumberToInt` converts to at most 8 bytes, so numbers with up to 38
+// digits (c.f. `FetchType::LargeInt`) are converted via text
+fn large_number(stmt: &Statement, number: &ffi::OCINumber) -> QueryResult<i128> {
+    const FORMAT: &[u8] = b"TM9";
+    let mut buffer = [0u8; 64];
+    let mut len = buffer.len() as u32;
+    let status = unsafe {
+        ffi::OCINumberToText(
+            stmt.error_handle.handle,
+            number,
+            FORMAT.as_ptr(),
+            FORMAT.len() as u32,
+            ptr::null(),
+            0,
+            &mut len,
+            buffer.as_mut_ptr(),
+        )
+    };
+    stmt.check(status)?;
+    let text = String::from_utf8_lossy(&buffer[..len as usize]);
+    text.trim().parse::<i128>().map_err(|_| {
+        Error::DatabaseError(
+            DatabaseErrorKind::__Unknown,
+            Box::new(format!("{} is not an integer", text.trim())),
+        )
+    })
+}
+
+// the components of `timestamp` in the format of `FetchType::TimestampTz`
+fn timestamp_tz(stmt: &Statement, timestamp: *mut ffi::OCIDateTime) -> QueryResult<[u8; 13]> {
+    let handle = stmt.connection.datetime_handle();
+    let error_handle = stmt.error_handle.handle;
+    let (mut year, mut month, mut day) = (0i16, 0u8, 0u8);
+    let (mut hour, mut minute, mut second, mut nanos) = (0u8, 0u8, 0u8, 0u32);
+    let (mut tz_hour, mut tz_minute) = (0i8, 0i8);
+    unsafe {
+        stmt.check(ffi::OCIDateTimeGetDate(
+            handle,
+            error_handle,
+            timestamp,
+            &mut year,
+            &mut month,
+            &mut day,
+        ))?;
+        stmt.check(ffi::OCIDateTimeGetTime(
+            handle,
+            error_handle,
+            timestamp,
+            &mut hour,
+            &mut minute,
+            &mut second,
+            &mut nanos,
+        ))?;
+        stmt.check(ffi::OCIDateTimeGetTimeZoneOffset(
+            handle,
+            error_handle,
+            timestamp,
+            &mut tz_hour,
+            &mut tz_minute,
+        ))?;
+    }
+    let (century, year) = if year > 0 {
+        (year / 100 + 100, year % 100 + 100)
+    } else {
+        (year / 100, year % 100)
+    };
+    let mut value = [0u8; 13];
+    value[..7].copy_from_slice(&[
+        century as u8,
+        year as u8,
+        month,
+        day,
+        hour + 1,
+        minute + 1,
+        second + 1,
+    ]);
+    value[7..11].copy_from_slice(&nanos.to_ne_bytes());
+    value[11] = tz_hour as u8;
+    value[12] = tz_minute as u8;
+    Ok(value)
 }
 
 impl Drop for Field {
     fn drop(&mut self) {
         unsafe {
+            if let Some(ref timestamp) = self.timestamp {
+                ffi::OCIDescriptorFree(**timestamp as *mut _, ffi::OCI_DTYPE_TIMESTAMP_TZ);
+            }
             ffi::OCIHandleFree(self.inner as *mut _, ffi::OCI_HTYPE_DEFINE);
         }
     }
 }
 
+/// A cursor over the rows of a result set
+///
+/// Cursors of statements without a result set don't have any fields and
+/// never yield a row.
 pub struct Cursor<'a, ST, T> {
     stmt: &'a Statement,
     _marker: PhantomData<(ST, T)>,
     results: Vec<Field>,
+    column_indices: HashMap<String, usize>,
     current_row: u32,
 }
 
+// the statistics of the statement are recorded once the rows are fetched
+impl<'a, ST, T> Drop for Cursor<'a, ST, T> {
+    fn drop(&mut self) {
+        self.stmt.finish_stats();
+    }
+}
+
 impl<'a, ST, T> Cursor<'a, ST, T> {
     pub fn new(stmt: &'a Statement, binds: Vec<Field>) -> Cursor<'a, ST, T> {
+        let column_indices = row::column_indices(&binds);
         Cursor {
             stmt,
             _marker: PhantomData,
             results: binds,
+            column_indices,
             current_row: 0,
         }
     }
+
+    /// Metadata of the columns returned by this cursor
+    pub fn columns(&self) -> Vec<&ColumnInfo> {
+        self.results.iter().map(Field::info).collect()
+    }
+}
+
+/// The row to move a scrollable cursor to, c.f. `Cursor::seek`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollPosition {
+    First,
+    Last,
+    /// The row with the given number, starting with 1
+    Absolute(u32),
+    /// The row the given number of rows after (or before, if negative) the
+    /// current row
+    Relative(i32),
+}
+
+impl<'a, ST, T> Cursor<'a, ST, T>
+where
+    Oracle: HasSqlType<ST>,
+    T: Queryable<ST, Oracle>,
+{
+    /// Moves the cursor to the given row and returns it, `None` if there is
+    /// no such row
+    ///
+    /// Iterating continues after the returned row. Only cursors of
+    /// `OciConnection::with_scrollable_cursor` can be moved.
+    pub fn seek(&mut self, position: ScrollPosition) -> QueryResult<Option<T>> {
+        if !self.stmt.is_scrollable() {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(String::from("The cursor is not scrollable")),
+            ));
+        }
+        if self.results.is_empty() {
+            return Ok(None);
+        }
+        let (orientation, offset) = match position {
+            ScrollPosition::First => (ffi::OCI_FETCH_FIRST, 0),
+            ScrollPosition::Last => (ffi::OCI_FETCH_LAST, 0),
+            ScrollPosition::Absolute(row) => (ffi::OCI_FETCH_ABSOLUTE, row as i32),
+            ScrollPosition::Relative(rows) => (ffi::OCI_FETCH_RELATIVE, rows),
+        };
+        if !fetch_at(self.stmt, orientation, offset)? {
+            return Ok(None);
+        }
+        finish_row(self.stmt, &mut self.results)?;
+        self.current_row = self.row_number()?;
+        self.build_row().map(Some)
+    }
+
+    /// The number of the current row, starting with 1, `0` before the first
+    /// row was fetched
+    pub fn row_number(&self) -> QueryResult<u32> {
+        if !self.stmt.is_scrollable() {
+            return Ok(self.current_row);
+        }
+        let mut position = 0u32;
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                self.stmt.inner_statement as *const _,
+                ffi::OCI_HTYPE_STMT,
+                &mut position as *mut u32 as *mut _,
+                &mut 0,
+                ffi::OCI_ATTR_CURRENT_POSITION,
+                self.stmt.error_handle.handle,
+            )
+        };
+        self.stmt.check(status)?;
+        Ok(position)
+    }
+
+    fn build_row(&self) -> QueryResult<T> {
+        let mut row = OciRow::new(&self.results, &self.column_indices);
+        T::Row::build_from_row(&mut row)
+            .map(T::build)
+            .map_err(DeserializationError)
+    }
 }
 
 impl<'a, ST, T> Iterator for Cursor<'a, ST, T>
@@ -72,37 +396,257 @@ where
     type Item = QueryResult<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            let status = ffi::OCIStmtFetch2(
-                self.stmt.inner_statement,
-                self.stmt.connection.env.error_handle,
-                1,
-                ffi::OCI_FETCH_NEXT as u16,
-                0,
-                ffi::OCI_DEFAULT,
-            );
-            if let Some(err) =
-                Statement::check_error(self.stmt.connection.env.error_handle, status).err()
-            {
-                return Some(Err(err));
-            }
-            if status as u32 == ffi::OCI_NO_DATA {
-                return None;
-            }
+        if self.results.is_empty() {
+            return None;
+        }
+        match fetch_row(self.stmt, &mut self.results) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(Err(e)),
         }
 
         self.current_row += 1;
-        let null_indicators = self.results.iter().map(|r| r.is_null()).collect();
-        let mut row = OciRow::new(
-            self.results
-                .iter_mut()
-                .map(|r: &mut Field| &r.buffer[..])
-                .collect::<Vec<&[u8]>>(),
-            null_indicators,
-        );
-        let value = T::Row::build_from_row(&mut row)
-            .map(T::build)
-            .map_err(DeserializationError);
-        Some(value)
+        Some(self.build_row())
+    }
+}
+
+/// A cursor owning the statement it fetches from, so it can be returned
+/// from functions or stored in structs, c.f. `Statement::into_cursor`
+///
+/// The statement is freed once the cursor is dropped.
+pub struct OwnedCursor<ST, T> {
+    // dropped before the statement the values are defined for
+    results: Vec<Field>,
+    column_indices: HashMap<String, usize>,
+    stmt: Rc<Statement>,
+    _marker: PhantomData<(ST, T)>,
+}
+
+impl<ST, T> OwnedCursor<ST, T> {
+    pub(super) fn new(stmt: Rc<Statement>, binds: Vec<Field>) -> OwnedCursor<ST, T> {
+        let column_indices = row::column_indices(&binds);
+        OwnedCursor {
+            results: binds,
+            column_indices,
+            stmt,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Metadata of the columns returned by this cursor
+    pub fn columns(&self) -> Vec<&ColumnInfo> {
+        self.results.iter().map(Field::info).collect()
     }
 }
+
+impl<ST, T> Iterator for OwnedCursor<ST, T>
+where
+    Oracle: HasSqlType<ST>,
+    T: Queryable<ST, Oracle>,
+{
+    type Item = QueryResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.results.is_empty() {
+            return None;
+        }
+        match fetch_row(&self.stmt, &mut self.results) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(Err(e)),
+        }
+
+        let mut row = OciRow::new(&self.results, &self.column_indices);
+        Some(
+            T::Row::build_from_row(&mut row)
+                .map(T::build)
+                .map_err(DeserializationError),
+        )
+    }
+}
+
+pub struct NamedCursor<'a, T> {
+    stmt: &'a Statement,
+    _marker: PhantomData<T>,
+    results: Vec<Field>,
+    column_indices: HashMap<String, usize>,
+}
+
+impl<'a, T> Drop for NamedCursor<'a, T> {
+    fn drop(&mut self) {
+        self.stmt.finish_stats();
+    }
+}
+
+impl<'a, T> NamedCursor<'a, T> {
+    pub fn new(stmt: &'a Statement, binds: Vec<Field>) -> NamedCursor<'a, T> {
+        let column_indices = row::column_indices(&binds);
+        NamedCursor {
+            stmt,
+            _marker: PhantomData,
+            results: binds,
+            column_indices,
+        }
+    }
+
+    /// Metadata of the columns returned by this cursor
+    pub fn columns(&self) -> Vec<&ColumnInfo> {
+        self.results.iter().map(Field::info).collect()
+    }
+
+    /// Fetches the next row into the define buffers, the returned row
+    /// borrows them
+    pub(super) fn next_row(&mut self) -> QueryResult<Option<OciRow<'_>>> {
+        if self.results.is_empty() || !fetch_row(self.stmt, &mut self.results)? {
+            return Ok(None);
+        }
+        Ok(Some(OciRow::new(&self.results, &self.column_indices)))
+    }
+}
+
+impl<'a, T> Iterator for NamedCursor<'a, T>
+where
+    T: QueryableByName<Oracle>,
+{
+    type Item = QueryResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.results.is_empty() {
+            return None;
+        }
+        match fetch_row(self.stmt, &mut self.results) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(Err(e)),
+        }
+
+        let row = OciRow::new(&self.results, &self.column_indices);
+        Some(T::build(&row).map_err(DeserializationError))
+    }
+}
+
+/// A cursor handing out the raw column values of each row
+pub struct RawCursor<'a> {
+    stmt: &'a Statement,
+    results: Vec<Field>,
+}
+
+impl<'a> Drop for RawCursor<'a> {
+    fn drop(&mut self) {
+        self.stmt.finish_stats();
+    }
+}
+
+impl<'a> RawCursor<'a> {
+    pub fn new(stmt: &'a Statement, binds: Vec<Field>) -> RawCursor<'a> {
+        RawCursor {
+            stmt,
+            results: binds,
+        }
+    }
+
+    /// Metadata of the columns returned by this cursor
+    pub fn columns(&self) -> Vec<&ColumnInfo> {
+        self.results.iter().map(Field::info).collect()
+    }
+
+    /// Fetches the next row, returns `None` if there are no more rows
+    ///
+    /// Values of string columns are cut at the terminating null byte.
+    pub fn next_row(&mut self) -> QueryResult<Option<Vec<Option<&[u8]>>>> {
+        if self.results.is_empty() || !fetch_row(self.stmt, &mut self.results)? {
+            return Ok(None);
+        }
+        Ok(Some(raw_values(&self.results)))
+    }
+}
+
+/// The values of the current row, string columns are cut at the terminating
+/// null byte
+pub(super) fn raw_values(fields: &[Field]) -> Vec<Option<&[u8]>> {
+    fields
+        .iter()
+        .map(|f| {
+            f.value().map(|v| match f.typ {
+                OCIDataType::String => v.split(|&b| b == 0).next().unwrap_or(v),
+                _ => v,
+            })
+        })
+        .collect()
+}
+
+/// Fails with `ORA-01406` if a value of the current row was truncated,
+/// instead of returning a cut off value
+///
+/// The buffers are sized by the maximal length of each column, so this
+/// only happens if oracle reports a length too small, e.g. for expressions.
+pub(super) fn check_truncation(stmt: &Statement, fields: &[Field]) -> QueryResult<()> {
+    match fields.iter().find(|f| f.is_truncated()) {
+        Some(field) => Err(stmt.truncation_error(field.name())),
+        None => Ok(()),
+    }
+}
+
+/// Checks the values of a fetched row and converts its numbers, c.f.
+/// `Field::convert_number`
+pub(super) fn finish_row(stmt: &Statement, fields: &mut [Field]) -> QueryResult<()> {
+    check_truncation(stmt, fields)?;
+    for field in fields {
+        field.convert_number(stmt)?;
+        field.convert_timestamp(stmt)?;
+    }
+    Ok(())
+}
+
+// fetches the next row into `fields`
+fn fetch_row(stmt: &Statement, fields: &mut [Field]) -> QueryResult<bool> {
+    let fetched = fetch(stmt)?;
+    if fetched {
+        finish_row(stmt, fields)?;
+    }
+    Ok(fetched)
+}
+
+/// Calls `OCIStmtFetch2` for the next row, returning its unchecked status
+pub(super) fn fetch_raw(stmt: &Statement) -> i32 {
+    unsafe {
+        ffi::OCIStmtFetch2(
+            stmt.inner_statement,
+            stmt.error_handle.handle,
+            1,
+            ffi::OCI_FETCH_NEXT as u16,
+            0,
+            ffi::OCI_DEFAULT,
+        )
+    }
+}
+
+pub(super) fn fetch(stmt: &Statement) -> QueryResult<bool> {
+    let start = Instant::now();
+    let status = fetch_raw(stmt);
+    let ret = stmt
+        .check(status)
+        .map(|_| status as u32 != ffi::OCI_NO_DATA);
+    stmt.note_fetch(start.elapsed(), matches!(ret, Ok(true)));
+    ret
+}
+
+// fetches the row at the given position of a scrollable cursor
+fn fetch_at(stmt: &Statement, orientation: u32, offset: i32) -> QueryResult<bool> {
+    let start = Instant::now();
+    let status = unsafe {
+        ffi::OCIStmtFetch2(
+            stmt.inner_statement,
+            stmt.error_handle.handle,
+            1,
+            orientation as u16,
+            offset,
+            ffi::OCI_DEFAULT,
+        )
+    };
+    let ret = stmt
+        .check(status)
+        .map(|_| status as u32 != ffi::OCI_NO_DATA);
+    stmt.note_fetch(start.elapsed(), matches!(ret, Ok(true)));
+    ret
+}
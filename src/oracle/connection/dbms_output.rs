@@ -0,0 +1,52 @@
+use diesel::result::QueryResult;
+use diesel::sql_types::{Integer, Nullable, Text};
+
+use super::plsql::{OutParamType, PlSqlBind};
+use super::OciConnection;
+
+// the number of lines fetched by one call of `DBMS_OUTPUT.GET_LINES`, each
+// line takes a buffer of `Text::capacity()` bytes
+const LINES_PER_CALL: i32 = 100;
+
+impl OciConnection {
+    /// Enables `DBMS_OUTPUT` for the current session, c.f. `get_dbms_output`
+    ///
+    /// `buffer_size` is the size of the output buffer in bytes, `None`
+    /// means unlimited.
+    pub fn enable_dbms_output(&self, buffer_size: Option<i32>) -> QueryResult<()> {
+        let mut binds = [PlSqlBind::input::<Nullable<Integer>, _>(&buffer_size)?];
+        self.execute_plsql("BEGIN DBMS_OUTPUT.ENABLE(:1); END;", &mut binds)
+    }
+
+    /// Disables `DBMS_OUTPUT` for the current session
+    pub fn disable_dbms_output(&self) -> QueryResult<()> {
+        self.execute_plsql("BEGIN DBMS_OUTPUT.DISABLE; END;", &mut [])
+    }
+
+    /// Returns (and removes) the lines written with `DBMS_OUTPUT.PUT_LINE`
+    /// since the last call
+    ///
+    /// This is empty unless `DBMS_OUTPUT` was enabled with
+    /// `enable_dbms_output`.
+    pub fn get_dbms_output(&self) -> QueryResult<Vec<String>> {
+        let mut lines = Vec::new();
+        loop {
+            let mut binds = [
+                PlSqlBind::output_array::<Nullable<Text>>(
+                    Text::capacity(),
+                    LINES_PER_CALL as usize,
+                ),
+                PlSqlBind::in_out::<Integer, _>(&LINES_PER_CALL, Integer::capacity())?,
+            ];
+            self.execute_plsql("BEGIN DBMS_OUTPUT.GET_LINES(:1, :2); END;", &mut binds)?;
+            // empty lines are returned as NULL
+            let batch = binds[0].get_array::<Nullable<Text>, Option<String>>()?;
+            lines.extend(batch.into_iter().map(Option::unwrap_or_default));
+            // fewer lines than requested means there are no more lines
+            if binds[1].get::<Integer, i32>()? < LINES_PER_CALL {
+                break;
+            }
+        }
+        Ok(lines)
+    }
+}
@@ -0,0 +1,196 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use tokio::task::futures::TaskLocalFuture;
+use tower::{Layer, Service};
+
+use super::raw::RawConnection;
+
+::tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+thread_local! {
+    // the deadline of the task which sent the running job to the thread of
+    // an `AsyncOciConnection`
+    static JOB_DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Runs `future` with a deadline, each statement it executes fails with
+/// `ORA-03156: OCI call timed out` if it is still running at `deadline`
+///
+/// The call timeout of the connection is limited to the time left before
+/// each statement is executed and restored afterwards. An earlier deadline
+/// of an enclosing `with_deadline` is kept. Statements executed with
+/// `AsyncOciConnection::interact` use the deadline current when `interact`
+/// is called, so it must be called inside of `future`.
+///
+/// ```ignore
+/// let deadline = Instant::now() + Duration::from_secs(2);
+/// let users = with_deadline(deadline, async {
+///     conn.interact(|conn| users::table.load::<User>(conn)).await
+/// })
+/// .await?;
+/// ```
+pub fn with_deadline<F: Future>(deadline: Instant, future: F) -> TaskLocalFuture<Instant, F> {
+    let deadline = current_deadline().map_or(deadline, |current| current.min(deadline));
+    DEADLINE.scope(deadline, future)
+}
+
+/// The deadline of the current task, c.f. `with_deadline`
+pub fn current_deadline() -> Option<Instant> {
+    DEADLINE
+        .try_with(|deadline| *deadline)
+        .ok()
+        .or_else(|| JOB_DEADLINE.with(Cell::get))
+}
+
+// runs a job of an `AsyncOciConnection` with the deadline of the task which
+// sent it
+pub(super) fn with_job_deadline<T, F>(deadline: Option<Instant>, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let previous = JOB_DEADLINE.with(|d| d.replace(deadline));
+    let ret = f();
+    JOB_DEADLINE.with(|d| d.set(previous));
+    ret
+}
+
+/// A tower `Layer` running each request with a deadline `timeout` after it
+/// arrived, c.f. `with_deadline`
+///
+/// ```ignore
+/// let service = ServiceBuilder::new()
+///     .layer(DeadlineLayer::new(Duration::from_secs(5)))
+///     .service(handler);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DeadlineLayer {
+    timeout: Duration,
+}
+
+impl DeadlineLayer {
+    pub fn new(timeout: Duration) -> Self {
+        DeadlineLayer { timeout }
+    }
+}
+
+impl<S> Layer<S> for DeadlineLayer {
+    type Service = Deadline<S>;
+
+    fn layer(&self, inner: S) -> Deadline<S> {
+        Deadline {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// The service created by `DeadlineLayer`
+#[derive(Debug, Clone)]
+pub struct Deadline<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S, R> Service<R> for Deadline<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TaskLocalFuture<Instant, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let deadline = Instant::now() + self.timeout;
+        with_deadline(deadline, self.inner.call(request))
+    }
+}
+
+/// Restores the call timeout of the connection changed by `apply_deadline`
+pub(super) struct DeadlineTimeout<'a> {
+    raw: &'a RawConnection,
+    previous: Option<Duration>,
+}
+
+impl<'a> Drop for DeadlineTimeout<'a> {
+    fn drop(&mut self) {
+        if let Err(e) = self.raw.set_call_timeout(self.previous) {
+            warn!("Restoring the call timeout failed: {}", e);
+        }
+    }
+}
+
+/// Limits the call timeout of the connection to the time left until the
+/// current deadline, if any, until the returned guard is dropped
+pub(super) fn apply_deadline(raw: &RawConnection) -> QueryResult<Option<DeadlineTimeout<'_>>> {
+    let deadline = match current_deadline() {
+        Some(deadline) => deadline,
+        None => return Ok(None),
+    };
+    let previous = raw.call_timeout();
+    raw.set_call_timeout(Some(call_timeout(Instant::now(), deadline, previous)?))?;
+    Ok(Some(DeadlineTimeout { raw, previous }))
+}
+
+// the time left until `deadline`, a shorter timeout of the connection is
+// kept
+fn call_timeout(
+    now: Instant,
+    deadline: Instant,
+    timeout: Option<Duration>,
+) -> QueryResult<Duration> {
+    let left = deadline
+        .checked_duration_since(now)
+        .unwrap_or_else(|| Duration::from_secs(0));
+    // oracle counts the timeout in milliseconds, 0 disables it
+    if left < Duration::from_millis(1) {
+        return Err(Error::DatabaseError(
+            DatabaseErrorKind::__Unknown,
+            Box::new(String::from("The deadline of the request passed")),
+        ));
+    }
+    Ok(timeout.map_or(left, |timeout| timeout.min(left)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_deadline_call_timeout() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(2);
+        assert_eq!(
+            call_timeout(now, deadline, None).unwrap(),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            call_timeout(now, deadline, Some(Duration::from_secs(1))).unwrap(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            call_timeout(now, deadline, Some(Duration::from_secs(5))).unwrap(),
+            Duration::from_secs(2)
+        );
+        assert!(call_timeout(deadline, now, None).is_err());
+        assert!(call_timeout(now, now + Duration::from_micros(10), None).is_err());
+    }
+
+    #[test]
+    fn check_job_deadline() {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert_eq!(current_deadline(), None);
+        let seen = with_job_deadline(Some(deadline), current_deadline);
+        assert_eq!(seen, Some(deadline));
+        assert_eq!(current_deadline(), None);
+    }
+}
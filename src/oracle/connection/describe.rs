@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use diesel::result::QueryResult;
+
+use super::column_info::ColumnInfo;
+use super::stmt::Statement;
+use super::OciConnection;
+
+impl OciConnection {
+    /// The columns a query returns, without executing it
+    ///
+    /// Fails for invalid queries, e.g. of missing tables or columns, which
+    /// makes it a cheap way to validate generated sql. Statements other than
+    /// queries return no columns.
+    ///
+    /// ```ignore
+    /// for column in conn.describe("SELECT * FROM employees")? {
+    ///     println!("{}: {:?}", column.name, column.data_type());
+    /// }
+    /// ```
+    pub fn describe(&self, sql: &str) -> QueryResult<Vec<ColumnInfo>> {
+        let raw = Arc::clone(&*self.raw());
+        let mut stmt = Statement::prepare(&raw, sql)?;
+        stmt.describe()
+    }
+}
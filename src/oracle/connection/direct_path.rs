@@ -0,0 +1,469 @@
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Arc;
+
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use oci_sys as ffi;
+
+use super::raw::{ErrorHandle, RawConnection};
+use super::stmt::Statement;
+use super::OciConnection;
+
+/// A column loaded by a `DirectPathLoader`
+///
+/// The values are passed as text and converted by oracle to the type of
+/// the column, dates and timestamps using `date_format`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectPathColumn {
+    name: String,
+    max_length: u32,
+    date_format: Option<String>,
+}
+
+impl DirectPathColumn {
+    /// The column `name`, whose values are at most `max_length` bytes long
+    pub fn new(name: &str, max_length: u32) -> Self {
+        DirectPathColumn {
+            name: name.to_uppercase(),
+            max_length,
+            date_format: None,
+        }
+    }
+
+    /// The format of the values of a date or timestamp column, e.g.
+    /// `YYYY-MM-DD HH24:MI:SS`
+    pub fn date_format(self, format: &str) -> Self {
+        DirectPathColumn {
+            date_format: Some(format.to_owned()),
+            ..self
+        }
+    }
+}
+
+/// The table loaded by a `DirectPathLoader`
+///
+/// Names are upper cased like the identifiers of diesel's queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectPathTable {
+    name: String,
+    schema: Option<String>,
+    partition: Option<String>,
+    columns: Vec<DirectPathColumn>,
+}
+
+impl DirectPathTable {
+    pub fn new(name: &str) -> Self {
+        DirectPathTable {
+            name: name.to_uppercase(),
+            schema: None,
+            partition: None,
+            columns: Vec::new(),
+        }
+    }
+
+    /// The schema of the table, the one of the session by default
+    pub fn schema(self, schema: &str) -> Self {
+        DirectPathTable {
+            schema: Some(schema.to_uppercase()),
+            ..self
+        }
+    }
+
+    /// Loads only the partition (or subpartition) `partition`, rows
+    /// belonging to another partition are rejected
+    pub fn partition(self, partition: &str) -> Self {
+        DirectPathTable {
+            partition: Some(partition.to_uppercase()),
+            ..self
+        }
+    }
+
+    /// Adds a column, the values of each row are in the order of the columns
+    pub fn column(mut self, column: DirectPathColumn) -> Self {
+        self.columns.push(column);
+        self
+    }
+}
+
+/// Appends rows to a table bypassing the sql layer, c.f.
+/// `OciConnection::direct_path_loader`
+///
+/// The rows are formatted into data blocks on the client and written above
+/// the high water mark of the table. They are only visible once `finish`
+/// returns, dropping the loader without calling `finish` discards all
+/// rows.
+pub struct DirectPathLoader {
+    raw: Arc<RawConnection>,
+    error_handle: ErrorHandle,
+    context: *mut ffi::OCIDirPathCtx,
+    column_array: *mut ffi::OCIDirPathColArray,
+    stream: *mut ffi::OCIDirPathStream,
+    column_count: usize,
+    // the capacity of the column array
+    max_rows: usize,
+    // the values of the buffered rows, oci only keeps pointers to them
+    values: Vec<Option<String>>,
+    loaded_rows: u64,
+    finished: bool,
+}
+
+impl OciConnection {
+    /// Prepares a direct path load of `table`
+    ///
+    /// Direct path loads are much faster than inserts for millions of rows,
+    /// but lock the table (or partition) until the load finishes, do not
+    /// fire triggers and only check not null, unique and primary key
+    /// constraints. The load is not part of a transaction, it is committed
+    /// by `finish`.
+    ///
+    /// ```ignore
+    /// let table = DirectPathTable::new("events")
+    ///     .partition("p_2024")
+    ///     .column(DirectPathColumn::new("id", 20))
+    ///     .column(DirectPathColumn::new("created_at", 19).date_format("YYYY-MM-DD HH24:MI:SS"))
+    ///     .column(DirectPathColumn::new("payload", 4000));
+    /// let mut loader = conn.direct_path_loader(&table)?;
+    /// for event in events {
+    ///     loader.append_row(&[Some(&event.id), Some(&event.created_at), event.payload.as_deref()])?;
+    /// }
+    /// let rows = loader.finish()?;
+    /// ```
+    pub fn direct_path_loader(&self, table: &DirectPathTable) -> QueryResult<DirectPathLoader> {
+        if table.columns.is_empty() {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!(
+                    "A direct path load of {} needs at least one column",
+                    table.name
+                )),
+            ));
+        }
+        let raw = Arc::clone(&*self.raw());
+        let mut loader = DirectPathLoader {
+            context: raw.alloc_direct_path_context(),
+            error_handle: raw.env.alloc_error_handle(),
+            raw,
+            column_array: ptr::null_mut(),
+            stream: ptr::null_mut(),
+            column_count: table.columns.len(),
+            max_rows: 0,
+            values: Vec::new(),
+            loaded_rows: 0,
+            // nothing to abort until the load is prepared
+            finished: true,
+        };
+        loader.prepare(table)?;
+        Ok(loader)
+    }
+}
+
+impl DirectPathLoader {
+    /// Buffers a row, `None` for null values
+    ///
+    /// The buffered rows are converted and sent to the server whenever the
+    /// column array is full.
+    pub fn append_row(&mut self, values: &[Option<&str>]) -> QueryResult<()> {
+        if values.len() != self.column_count {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!(
+                    "Expected {} values for the direct path load, got {}",
+                    self.column_count,
+                    values.len()
+                )),
+            ));
+        }
+        self.values
+            .extend(values.iter().map(|v| v.map(str::to_owned)));
+        if self.buffered_rows() >= self.max_rows {
+            self.load_buffered_rows()?;
+        }
+        Ok(())
+    }
+
+    /// The number of rows sent to the server so far
+    pub fn loaded_rows(&self) -> u64 {
+        self.loaded_rows
+    }
+
+    /// Loads the remaining rows and completes the load, returning the
+    /// number of loaded rows
+    ///
+    /// Indexes of the table are maintained at this point, a unique index
+    /// violated by the loaded rows is marked unusable.
+    pub fn finish(mut self) -> QueryResult<u64> {
+        self.load_buffered_rows()?;
+        let status = unsafe { ffi::OCIDirPathFinish(self.context, self.error_handle.handle) };
+        self.check(status)?;
+        self.finished = true;
+        Ok(self.loaded_rows)
+    }
+
+    fn prepare(&mut self, table: &DirectPathTable) -> QueryResult<()> {
+        let context = self.context as *mut c_void;
+        let htype = ffi::OCI_HTYPE_DIRPATH_CTX;
+        self.set_text_attr(context, htype, ffi::OCI_ATTR_NAME, &table.name)?;
+        if let Some(ref schema) = table.schema {
+            self.set_text_attr(context, htype, ffi::OCI_ATTR_SCHEMA_NAME, schema)?;
+        }
+        if let Some(ref partition) = table.partition {
+            self.set_text_attr(context, htype, ffi::OCI_ATTR_SUB_NAME, partition)?;
+        }
+        let mut input = ffi::OCI_DIRPATH_INPUT_TEXT as u8;
+        self.set_attr(context, htype, ffi::OCI_ATTR_DIRPATH_INPUT, &mut input)?;
+        let mut column_count = self.column_count as u16;
+        self.set_attr(context, htype, ffi::OCI_ATTR_NUM_COLS, &mut column_count)?;
+
+        let mut columns: *mut c_void = ptr::null_mut();
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                context,
+                htype,
+                &mut columns as *mut _ as *mut c_void,
+                ptr::null_mut(),
+                ffi::OCI_ATTR_LIST_COLUMNS,
+                self.error_handle.handle,
+            )
+        };
+        self.check(status)?;
+        for (i, column) in table.columns.iter().enumerate() {
+            let mut param: *mut c_void = ptr::null_mut();
+            let status = unsafe {
+                ffi::OCIParamGet(
+                    columns,
+                    ffi::OCI_DTYPE_PARAM,
+                    self.error_handle.handle,
+                    &mut param,
+                    i as u32 + 1,
+                )
+            };
+            self.check(status)?;
+            let ret = self.describe_column(param, column);
+            unsafe {
+                ffi::OCIDescriptorFree(param, ffi::OCI_DTYPE_PARAM);
+            }
+            ret?;
+        }
+
+        let status = unsafe {
+            ffi::OCIDirPathPrepare(
+                self.context,
+                self.raw.service_handle,
+                self.error_handle.handle,
+            )
+        };
+        self.check(status)?;
+        self.finished = false;
+        self.column_array = self.alloc_handle(ffi::OCI_HTYPE_DIRPATH_COLUMN_ARRAY)?;
+        self.stream = self.alloc_handle(ffi::OCI_HTYPE_DIRPATH_STREAM)?;
+
+        let mut max_rows: u32 = 0;
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                self.column_array as *mut c_void,
+                ffi::OCI_HTYPE_DIRPATH_COLUMN_ARRAY,
+                &mut max_rows as *mut u32 as *mut c_void,
+                ptr::null_mut(),
+                ffi::OCI_ATTR_NUM_ROWS,
+                self.error_handle.handle,
+            )
+        };
+        self.check(status)?;
+        self.max_rows = max_rows.max(1) as usize;
+        Ok(())
+    }
+
+    fn describe_column(&self, param: *mut c_void, column: &DirectPathColumn) -> QueryResult<()> {
+        let htype = ffi::OCI_DTYPE_PARAM;
+        self.set_text_attr(param, htype, ffi::OCI_ATTR_NAME, &column.name)?;
+        let mut data_type = ffi::SQLT_CHR as u16;
+        self.set_attr(param, htype, ffi::OCI_ATTR_DATA_TYPE, &mut data_type)?;
+        let mut max_length = column.max_length;
+        self.set_attr(param, htype, ffi::OCI_ATTR_DATA_SIZE, &mut max_length)?;
+        if let Some(ref format) = column.date_format {
+            self.set_text_attr(param, htype, ffi::OCI_ATTR_DATEFORMAT, format)?;
+        }
+        Ok(())
+    }
+
+    fn buffered_rows(&self) -> usize {
+        self.values.len() / self.column_count
+    }
+
+    // converts the buffered rows into streams and loads them, a stream may
+    // be too small for all rows of the column array
+    fn load_buffered_rows(&mut self) -> QueryResult<()> {
+        let rows = self.buffered_rows();
+        if rows == 0 {
+            return Ok(());
+        }
+        let error_handle = self.error_handle.handle;
+        for (i, value) in self.values.iter().enumerate() {
+            let (data, len, flag) = match *value {
+                Some(ref v) => (
+                    v.as_ptr() as *mut u8,
+                    v.len() as u32,
+                    ffi::OCI_DIRPATH_COL_COMPLETE,
+                ),
+                None => (ptr::null_mut(), 0, ffi::OCI_DIRPATH_COL_NULL),
+            };
+            let status = unsafe {
+                ffi::OCIDirPathColArrayEntrySet(
+                    self.column_array,
+                    error_handle,
+                    (i / self.column_count) as u32,
+                    (i % self.column_count) as u16,
+                    data,
+                    len,
+                    flag as u8,
+                )
+            };
+            self.check(status)?;
+        }
+
+        let mut offset: u32 = 0;
+        loop {
+            let conversion = unsafe {
+                ffi::OCIDirPathColArrayToStream(
+                    self.column_array,
+                    self.context,
+                    self.stream,
+                    error_handle,
+                    rows as u32,
+                    offset,
+                )
+            };
+            // a conversion error of a row fails the whole load
+            self.check(conversion)?;
+            let status =
+                unsafe { ffi::OCIDirPathLoadStream(self.context, self.stream, error_handle) };
+            self.check(status)?;
+            let status = unsafe { ffi::OCIDirPathStreamReset(self.stream, error_handle) };
+            self.check(status)?;
+            // `OCI_CONTINUE` if the stream is full before all rows are
+            // converted
+            if conversion != ffi::OCI_CONTINUE {
+                break;
+            }
+            let mut converted: u32 = 0;
+            let status = unsafe {
+                ffi::OCIAttrGet(
+                    self.column_array as *mut c_void,
+                    ffi::OCI_HTYPE_DIRPATH_COLUMN_ARRAY,
+                    &mut converted as *mut u32 as *mut c_void,
+                    ptr::null_mut(),
+                    ffi::OCI_ATTR_ROW_COUNT,
+                    error_handle,
+                )
+            };
+            self.check(status)?;
+            offset += converted;
+        }
+
+        let status = unsafe { ffi::OCIDirPathColArrayReset(self.column_array, error_handle) };
+        self.check(status)?;
+        self.values.clear();
+        self.loaded_rows += rows as u64;
+        Ok(())
+    }
+
+    fn alloc_handle<R>(&self, htype: u32) -> QueryResult<*mut R> {
+        let mut handle: *mut c_void = ptr::null_mut();
+        let status = unsafe {
+            ffi::OCIHandleAlloc(
+                self.context as *const c_void,
+                &mut handle,
+                htype,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        self.check(status)?;
+        Ok(handle as *mut R)
+    }
+
+    fn set_attr<T>(
+        &self,
+        handle: *mut c_void,
+        htype: u32,
+        attr: u32,
+        value: &mut T,
+    ) -> QueryResult<()> {
+        let status = unsafe {
+            ffi::OCIAttrSet(
+                handle,
+                htype,
+                value as *mut T as *mut c_void,
+                0,
+                attr,
+                self.error_handle.handle,
+            )
+        };
+        self.check(status)
+    }
+
+    fn set_text_attr(
+        &self,
+        handle: *mut c_void,
+        htype: u32,
+        attr: u32,
+        value: &str,
+    ) -> QueryResult<()> {
+        let status = unsafe {
+            ffi::OCIAttrSet(
+                handle,
+                htype,
+                value.as_ptr() as *mut c_void,
+                value.len() as u32,
+                attr,
+                self.error_handle.handle,
+            )
+        };
+        self.check(status)
+    }
+
+    fn check(&self, status: i32) -> QueryResult<()> {
+        Statement::check_error(self.error_handle.handle, status)
+    }
+}
+
+impl Drop for DirectPathLoader {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.finished {
+                ffi::OCIDirPathAbort(self.context, self.error_handle.handle);
+            }
+            if !self.stream.is_null() {
+                ffi::OCIHandleFree(self.stream as *mut c_void, ffi::OCI_HTYPE_DIRPATH_STREAM);
+            }
+            if !self.column_array.is_null() {
+                ffi::OCIHandleFree(
+                    self.column_array as *mut c_void,
+                    ffi::OCI_HTYPE_DIRPATH_COLUMN_ARRAY,
+                );
+            }
+            ffi::OCIHandleFree(self.context as *mut c_void, ffi::OCI_HTYPE_DIRPATH_CTX);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirectPathColumn, DirectPathTable};
+
+    #[test]
+    fn check_direct_path_table() {
+        let table = DirectPathTable::new("events")
+            .schema("etl")
+            .partition("p_2024")
+            .column(DirectPathColumn::new("id", 20))
+            .column(DirectPathColumn::new("created_at", 19).date_format("YYYY-MM-DD"));
+        assert_eq!(table.name, "EVENTS");
+        assert_eq!(table.schema.as_ref().unwrap(), "ETL");
+        assert_eq!(table.partition.as_ref().unwrap(), "P_2024");
+        assert_eq!(table.columns[0].name, "ID");
+        assert_eq!(table.columns[1].max_length, 19);
+        // formats are case sensitive, e.g. for month names
+        assert_eq!(table.columns[1].date_format.as_ref().unwrap(), "YYYY-MM-DD");
+    }
+}
@@ -0,0 +1,245 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use byteorder::ReadBytesExt;
+#[cfg(feature = "chrono-time")]
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use diesel::backend::Backend;
+#[cfg(feature = "chrono-time")]
+use diesel::deserialize::FromSql;
+use diesel::result::{Error, QueryResult};
+#[cfg(feature = "chrono-time")]
+use diesel::sql_types::Timestamp;
+use oci_sys as ffi;
+
+use super::super::backend::Oracle;
+#[cfg(feature = "chrono-time")]
+use super::super::sql_types::TimestampTz;
+use super::column_info::ColumnInfo;
+#[cfg(feature = "chrono-time")]
+use super::oracle_value::OracleValue;
+use super::stmt::Statement;
+use super::OciConnection;
+
+type ByteOrder = <Oracle as Backend>::ByteOrder;
+
+/// A value of a column whose type is only known at runtime, c.f.
+/// `OciConnection::query_dynamic`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Null,
+    /// A `NUMBER` without decimal places, numbers exceeding an `i64` are
+    /// returned as `String`
+    Number(i64),
+    /// A `NUMBER` with decimal places, `BINARY_FLOAT` or `BINARY_DOUBLE`
+    Float(f64),
+    String(String),
+    /// A `DATE`, which includes the time of the day
+    #[cfg(feature = "chrono-time")]
+    Date(NaiveDateTime),
+    /// A `TIMESTAMP`, including its fractional seconds
+    #[cfg(feature = "chrono-time")]
+    Timestamp(NaiveDateTime),
+    /// A `TIMESTAMP WITH TIME ZONE` with its offset to UTC, or a `TIMESTAMP
+    /// WITH LOCAL TIME ZONE` in the session time zone
+    #[cfg(feature = "chrono-time")]
+    TimestampTz(DateTime<FixedOffset>),
+    /// The bytes of a `RAW` column, or of a value of a type without a
+    /// variant
+    Raw(Vec<u8>),
+}
+
+impl OciConnection {
+    /// Executes a query whose result types are not known at compile time,
+    /// e.g. in a schema browser, returning the column names and values of
+    /// each row
+    ///
+    /// ```ignore
+    /// for row in conn.query_dynamic("SELECT * FROM employees")? {
+    ///     for (column, value) in row {
+    ///         println!("{}: {:?}", column, value);
+    ///     }
+    /// }
+    /// ```
+    pub fn query_dynamic(&self, sql: &str) -> QueryResult<Vec<Vec<(String, DynamicValue)>>> {
+        let raw = Arc::clone(&*self.raw());
+        let mut stmt = Statement::prepare(&raw, sql)?;
+        let mut cursor = stmt.run_with_raw_cursor()?;
+        let columns = cursor.columns().into_iter().cloned().collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        while let Some(values) = cursor.next_row()? {
+            let row = columns
+                .iter()
+                .zip(values)
+                .map(|(column, value)| Ok((column.name.clone(), decode(column, value)?)))
+                .collect::<QueryResult<Vec<_>>>()?;
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+// the value of `column` as fetched by `Statement::define_all_columns`
+fn decode(column: &ColumnInfo, value: Option<&[u8]>) -> QueryResult<DynamicValue> {
+    let mut bytes = match value {
+        Some(bytes) => bytes,
+        None => return Ok(DynamicValue::Null),
+    };
+    let value = match (u32::from(column.type_code), bytes.len()) {
+        (ffi::SQLT_NUM, 2) if column.scale == 0 => {
+            DynamicValue::Number(i64::from(bytes.read_i16::<ByteOrder>().map_err(io_error)?))
+        }
+        (ffi::SQLT_NUM, 4) if column.scale == 0 => {
+            DynamicValue::Number(i64::from(bytes.read_i32::<ByteOrder>().map_err(io_error)?))
+        }
+        (ffi::SQLT_NUM, 8) | (ffi::SQLT_INT, 8) | (ffi::SQLT_UIN, 8) if column.scale == 0 => {
+            DynamicValue::Number(bytes.read_i64::<ByteOrder>().map_err(io_error)?)
+        }
+        // numbers with more than 19 digits, which are kept as text if they
+        // exceed an `i64`
+        (ffi::SQLT_NUM, 16) if column.scale == 0 => {
+            let value = bytes.read_i128::<ByteOrder>().map_err(io_error)?;
+            match i64::try_from(value) {
+                Ok(value) => DynamicValue::Number(value),
+                Err(_) => DynamicValue::String(value.to_string()),
+            }
+        }
+        (ffi::SQLT_NUM, 8) | (ffi::SQLT_BDOUBLE, 8) | (ffi::SQLT_IBDOUBLE, 8) => {
+            DynamicValue::Float(bytes.read_f64::<ByteOrder>().map_err(io_error)?)
+        }
+        (ffi::SQLT_FLT, 4) | (ffi::SQLT_BFLOAT, 4) | (ffi::SQLT_IBFLOAT, 4) => {
+            DynamicValue::Float(f64::from(bytes.read_f32::<ByteOrder>().map_err(io_error)?))
+        }
+        (ffi::SQLT_CHR, _)
+        | (ffi::SQLT_VCS, _)
+        | (ffi::SQLT_LVC, _)
+        | (ffi::SQLT_AFC, _)
+        | (ffi::SQLT_VST, _) => DynamicValue::String(
+            String::from_utf8(bytes.to_vec())
+                .map_err(|e| Error::DeserializationError(Box::new(e)))?,
+        ),
+        #[cfg(feature = "chrono-time")]
+        (ffi::SQLT_DAT, 7) => DynamicValue::Date(date_time(bytes)?),
+        #[cfg(feature = "chrono-time")]
+        (ffi::SQLT_TIMESTAMP, 7) => DynamicValue::Timestamp(date_time(bytes)?),
+        // fetched with the offset of the session time zone, c.f.
+        // `FetchType::TimestampTz`, which is no part of a `TIMESTAMP`
+        #[cfg(feature = "chrono-time")]
+        (ffi::SQLT_TIMESTAMP, 13) => DynamicValue::Timestamp(date_time(bytes)?),
+        #[cfg(feature = "chrono-time")]
+        (ffi::SQLT_TIMESTAMP_TZ, 13) | (ffi::SQLT_TIMESTAMP_LTZ, 13) => DynamicValue::TimestampTz(
+            <DateTime<FixedOffset> as FromSql<TimestampTz, Oracle>>::from_sql(Some(
+                OracleValue::new(bytes),
+            ))
+            .map_err(Error::DeserializationError)?,
+        ),
+        _ => DynamicValue::Raw(bytes.to_vec()),
+    };
+    Ok(value)
+}
+
+#[cfg(feature = "chrono-time")]
+fn date_time(bytes: &[u8]) -> QueryResult<NaiveDateTime> {
+    <NaiveDateTime as FromSql<Timestamp, Oracle>>::from_sql(Some(OracleValue::new(bytes)))
+        .map_err(Error::DeserializationError)
+}
+
+fn io_error(e: ::std::io::Error) -> Error {
+    Error::DeserializationError(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, DynamicValue};
+    use oci_sys as ffi;
+    use oracle::connection::ColumnInfo;
+
+    fn column(type_code: u32, scale: i8) -> ColumnInfo {
+        ColumnInfo {
+            name: "C".to_owned(),
+            type_code: type_code as u16,
+            precision: 0,
+            scale,
+            char_length: 0,
+            byte_length: 0,
+            nullable: true,
+            national: false,
+        }
+    }
+
+    #[test]
+    fn check_decode() {
+        let number = column(ffi::SQLT_NUM, 0);
+        assert_eq!(decode(&number, None).unwrap(), DynamicValue::Null);
+        assert_eq!(
+            decode(&number, Some(&42i32.to_ne_bytes())).unwrap(),
+            DynamicValue::Number(42)
+        );
+        assert_eq!(
+            decode(&number, Some(&(-7i64).to_ne_bytes())).unwrap(),
+            DynamicValue::Number(-7)
+        );
+        assert_eq!(
+            decode(&number, Some(&10i128.pow(20).to_ne_bytes())).unwrap(),
+            DynamicValue::String("100000000000000000000".to_owned())
+        );
+        assert_eq!(
+            decode(&column(ffi::SQLT_NUM, 2), Some(&1.5f64.to_ne_bytes())).unwrap(),
+            DynamicValue::Float(1.5)
+        );
+        assert_eq!(
+            decode(&column(ffi::SQLT_IBFLOAT, 0), Some(&0.25f32.to_ne_bytes())).unwrap(),
+            DynamicValue::Float(0.25)
+        );
+        assert_eq!(
+            decode(&column(ffi::SQLT_CHR, 0), Some(b"abc")).unwrap(),
+            DynamicValue::String("abc".to_owned())
+        );
+        assert!(decode(&column(ffi::SQLT_CHR, 0), Some(&[0xff])).is_err());
+        assert_eq!(
+            decode(&column(ffi::SQLT_BIN, 0), Some(&[1, 2])).unwrap(),
+            DynamicValue::Raw(vec![1, 2])
+        );
+    }
+
+    #[cfg(feature = "chrono-time")]
+    #[test]
+    fn check_decode_dates() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let bytes = [120, 120, 3, 14, 16, 10, 1];
+        let expected = NaiveDate::from_ymd_opt(2020, 3, 14)
+            .and_then(|d| d.and_hms_opt(15, 9, 0))
+            .unwrap();
+        assert_eq!(
+            decode(&column(ffi::SQLT_DAT, 0), Some(&bytes)).unwrap(),
+            DynamicValue::Date(expected)
+        );
+        assert_eq!(
+            decode(&column(ffi::SQLT_TIMESTAMP, 0), Some(&bytes)).unwrap(),
+            DynamicValue::Timestamp(expected)
+        );
+
+        // timestamps are fetched with their fractional seconds
+        let mut bytes = bytes.to_vec();
+        bytes.extend_from_slice(&250_000_000u32.to_ne_bytes());
+        bytes.extend_from_slice(&[2, 0]);
+        assert_eq!(
+            decode(&column(ffi::SQLT_TIMESTAMP, 0), Some(&bytes)).unwrap(),
+            DynamicValue::Timestamp(expected + chrono::Duration::milliseconds(250))
+        );
+
+        // the offset of a time zone is kept
+        let (len, offset) = (bytes.len(), FixedOffset::east_opt(2 * 3600).unwrap());
+        bytes[len - 2..].copy_from_slice(&[2, 0]);
+        let expected = offset
+            .from_local_datetime(&(expected + chrono::Duration::milliseconds(250)))
+            .unwrap();
+        for &type_code in &[ffi::SQLT_TIMESTAMP_TZ, ffi::SQLT_TIMESTAMP_LTZ] {
+            assert_eq!(
+                decode(&column(type_code, 0), Some(&bytes)).unwrap(),
+                DynamicValue::TimestampTz(expected)
+            );
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use diesel::result::{ConnectionError, ConnectionResult};
+
+/// An easy connect (plus) string like
+/// `tcps://host1,host2:1522/service:dedicated?retry_count=3`
+///
+/// Older oracle clients only understand the plain `host:port/service` form,
+/// so everything else is translated into a connect descriptor.
+#[derive(Debug, PartialEq)]
+pub(super) struct EasyConnect<'a> {
+    pub(super) tcps: bool,
+    hosts: Vec<(&'a str, Option<u16>)>,
+    service: &'a str,
+    server: Option<&'a str>,
+    instance: Option<&'a str>,
+    params: Vec<(&'a str, &'a str)>,
+}
+
+// the parameters and the section of the connect descriptor they belong to
+const DESCRIPTION_PARAMS: &[&str] = &[
+    "connect_timeout",
+    "expire_time",
+    "failover",
+    "load_balance",
+    "recv_buf_size",
+    "retry_count",
+    "retry_delay",
+    "sdu",
+    "send_buf_size",
+    "source_route",
+    "transport_connect_timeout",
+];
+const ADDRESS_PARAMS: &[&str] = &["https_proxy", "https_proxy_port"];
+const CONNECT_DATA_PARAMS: &[&str] = &["pool_boundary", "pool_connection_class", "pool_purity"];
+
+impl<'a> EasyConnect<'a> {
+    /// Parses `[protocol://]host[:port][,host[:port]...][/service][:server][/instance][?params]`,
+    /// returns `None` for aliases of `tnsnames.ora`
+    pub(super) fn parse(connect_string: &'a str) -> ConnectionResult<Option<Self>> {
+        let invalid = |reason: &str| {
+            ConnectionError::InvalidConnectionUrl(format!(
+                "Invalid easy connect string {}: {}",
+                connect_string, reason
+            ))
+        };
+        let (tcps, rest) = if let Some(rest) = strip_prefix_ignore_case(connect_string, "tcps://") {
+            (true, rest)
+        } else if let Some(rest) = strip_prefix_ignore_case(connect_string, "tcp://") {
+            (false, rest)
+        } else if connect_string.contains('/') {
+            (false, connect_string.trim_start_matches("//"))
+        } else {
+            return Ok(None);
+        };
+        let (rest, query) = match rest.find('?') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        let (addresses, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        let mut hosts = addresses
+            .split(',')
+            .map(split_host_port)
+            .collect::<ConnectionResult<Vec<_>>>()?;
+        if hosts.iter().any(|h| h.0.is_empty()) {
+            return Err(invalid("missing host"));
+        }
+        // a port applies to the preceding hosts without port, e.g. both
+        // hosts of `db1,db2:1522` use port 1522
+        let mut next_port = None;
+        for host in hosts.iter_mut().rev() {
+            match host.1 {
+                Some(port) => next_port = Some(port),
+                None => host.1 = next_port,
+            }
+        }
+        let (service, instance) = match path.find('/') {
+            Some(i) => (&path[..i], Some(&path[i + 1..])),
+            None => (path, None),
+        };
+        let (service, server) = match service.find(':') {
+            Some(i) => (&service[..i], Some(&service[i + 1..])),
+            None => (service, None),
+        };
+        let mut params = Vec::new();
+        for param in query.into_iter().flat_map(|q| q.split('&')) {
+            match param.find('=') {
+                Some(i) => params.push((&param[..i], &param[i + 1..])),
+                None => return Err(invalid(&format!("parameter {} without value", param))),
+            }
+        }
+        Ok(Some(EasyConnect {
+            tcps,
+            hosts,
+            service,
+            server,
+            instance,
+            params,
+        }))
+    }
+
+    /// Whether oracle clients without easy connect plus support understand
+    /// this connect string
+    pub(super) fn is_plain(&self) -> bool {
+        !self.tcps && self.hosts.len() == 1 && self.params.is_empty()
+    }
+
+    /// The equivalent connect descriptor including the given `SECURITY`
+    /// section
+    pub(super) fn to_descriptor(&self, security: &str) -> ConnectionResult<String> {
+        let mut description = String::new();
+        let mut address_params = String::new();
+        let mut connect_data_params = String::new();
+        for &(key, value) in &self.params {
+            let lower = key.to_lowercase();
+            let section = if DESCRIPTION_PARAMS.contains(&&*lower) {
+                &mut description
+            } else if ADDRESS_PARAMS.contains(&&*lower) {
+                &mut address_params
+            } else if CONNECT_DATA_PARAMS.contains(&&*lower) {
+                &mut connect_data_params
+            } else {
+                return Err(ConnectionError::InvalidConnectionUrl(format!(
+                    "Unsupported easy connect parameter {}",
+                    key
+                )));
+            };
+            section.push_str(&format!("({}={})", key.to_uppercase(), value));
+        }
+
+        let protocol = if self.tcps { "TCPS" } else { "TCP" };
+        for &(host, port) in &self.hosts {
+            let port = port.unwrap_or(if self.tcps { 2484 } else { 1521 });
+            description.push_str(&format!(
+                "(ADDRESS=(PROTOCOL={})(HOST={})(PORT={}){})",
+                protocol, host, port, address_params
+            ));
+        }
+        description.push_str("(CONNECT_DATA=");
+        if !self.service.is_empty() {
+            description.push_str(&format!("(SERVICE_NAME={})", self.service));
+        }
+        if let Some(server) = self.server {
+            description.push_str(&format!("(SERVER={})", server));
+        }
+        if let Some(instance) = self.instance {
+            description.push_str(&format!("(INSTANCE_NAME={})", instance));
+        }
+        description.push_str(&connect_data_params);
+        description.push(')');
+        Ok(format!("(DESCRIPTION={}{})", description, security))
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+// splits `host:port`, `host` or `[ipv6]:port`
+fn split_host_port(address: &str) -> ConnectionResult<(&str, Option<u16>)> {
+    let colon = if address.starts_with('[') {
+        address
+            .find(']')
+            .and_then(|i| address[i..].find(':').map(|j| i + j))
+    } else {
+        address.rfind(':')
+    };
+    match colon {
+        Some(i) => address[i + 1..]
+            .parse()
+            .map(|port| (&address[..i], Some(port)))
+            .map_err(|_| {
+                ConnectionError::InvalidConnectionUrl(format!("Invalid port in {}", address))
+            }),
+        None => Ok((address, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_host_port, EasyConnect};
+
+    fn descriptor(connect_string: &str) -> String {
+        EasyConnect::parse(connect_string)
+            .unwrap()
+            .unwrap()
+            .to_descriptor("")
+            .unwrap()
+    }
+
+    #[test]
+    fn check_parse_easy_connect() {
+        assert_eq!(EasyConnect::parse("orcl_alias").unwrap(), None);
+        let plain = EasyConnect::parse("//localhost:1521/orcl")
+            .unwrap()
+            .unwrap();
+        assert!(plain.is_plain());
+        let tcps = EasyConnect::parse("TCPS://db:1522/orcl").unwrap().unwrap();
+        assert!(tcps.tcps);
+        assert!(!tcps.is_plain());
+
+        assert!(EasyConnect::parse("tcps://:1522/orcl").is_err());
+        assert!(EasyConnect::parse("tcps://db/orcl?retry_count").is_err());
+        assert!(EasyConnect::parse("db:port/orcl").is_err());
+    }
+
+    #[test]
+    fn check_easy_connect_descriptor() {
+        assert_eq!(
+            descriptor(
+                "tcps://adb.eu-frankfurt-1.oraclecloud.com:1522/abc_high.adb.oraclecloud.com\
+                 ?retry_count=3&retry_delay=1&https_proxy=proxy&https_proxy_port=80"
+            ),
+            "(DESCRIPTION=(RETRY_COUNT=3)(RETRY_DELAY=1)\
+             (ADDRESS=(PROTOCOL=TCPS)(HOST=adb.eu-frankfurt-1.oraclecloud.com)(PORT=1522)\
+             (HTTPS_PROXY=proxy)(HTTPS_PROXY_PORT=80))\
+             (CONNECT_DATA=(SERVICE_NAME=abc_high.adb.oraclecloud.com)))"
+        );
+        assert_eq!(
+            descriptor("db1,db2:1522/orcl:pooled/orcl1?pool_connection_class=app"),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=db1)(PORT=1522))\
+             (ADDRESS=(PROTOCOL=TCP)(HOST=db2)(PORT=1522))\
+             (CONNECT_DATA=(SERVICE_NAME=orcl)(SERVER=pooled)(INSTANCE_NAME=orcl1)\
+             (POOL_CONNECTION_CLASS=app)))"
+        );
+
+        assert_eq!(
+            descriptor("db1:1523,db2,db3:1522,db4/orcl"),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=db1)(PORT=1523))\
+             (ADDRESS=(PROTOCOL=TCP)(HOST=db2)(PORT=1522))\
+             (ADDRESS=(PROTOCOL=TCP)(HOST=db3)(PORT=1522))\
+             (ADDRESS=(PROTOCOL=TCP)(HOST=db4)(PORT=1521))\
+             (CONNECT_DATA=(SERVICE_NAME=orcl)))"
+        );
+
+        let unknown = EasyConnect::parse("db/orcl?colour=blue").unwrap().unwrap();
+        assert!(unknown.to_descriptor("").is_err());
+    }
+
+    #[test]
+    fn check_split_host_port() {
+        assert_eq!(split_host_port("db:1522").unwrap(), ("db", Some(1522)));
+        assert_eq!(split_host_port("[::1]").unwrap(), ("[::1]", None));
+        assert_eq!(
+            split_host_port("[::1]:1522").unwrap(),
+            ("[::1]", Some(1522))
+        );
+        assert!(split_host_port("db:port").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind};
+use std::fmt;
+
+/// An error reported by oracle
+///
+/// This is the payload of every `diesel::result::Error::DatabaseError`
+/// returned by this crate for a failed OCI call.
+pub struct OciError {
+    code: i32,
+    message: String,
+    statement: Option<String>,
+    bind_count: usize,
+    details: Option<String>,
+    // line, column and caret annotated snippet of a parse error
+    position: Option<(usize, usize, String)>,
+    table: Option<String>,
+    column: Option<String>,
+    constraint: Option<String>,
+}
+
+impl OciError {
+    pub(crate) fn new(code: i32, message: String) -> Self {
+        let (table, column) = match code {
+            // ORA-01400: cannot insert NULL into ("SCHEMA"."TABLE"."COLUMN")
+            // ORA-01407: cannot update ("SCHEMA"."TABLE"."COLUMN") to NULL
+            // ORA-12899: value too large for column "SCHEMA"."TABLE"."COLUMN" (actual: 3, maximum: 2)
+            1400 | 1407 | 12899 => parse_column(&message),
+            _ => (None, None),
+        };
+        let constraint = match code {
+            // ORA-00001: unique constraint (SCHEMA.NAME) violated
+            // ORA-02290: check constraint (SCHEMA.NAME) violated
+            // ORA-02291: integrity constraint (SCHEMA.NAME) violated - parent key not found
+            // ORA-02292: integrity constraint (SCHEMA.NAME) violated - child record found
+            1 | 2290 | 2291 | 2292 => parse_constraint(&message),
+            _ => None,
+        };
+        OciError {
+            code,
+            message,
+            statement: None,
+            bind_count: 0,
+            details: None,
+            position: None,
+            table,
+            column,
+            constraint,
+        }
+    }
+
+    pub(crate) fn with_statement(mut self, sql: &str, bind_count: usize) -> Self {
+        self.details = Some(format!(
+            "while executing `{}` with {} bind parameter(s)",
+            sql, bind_count
+        ));
+        self.statement = Some(sql.to_owned());
+        self.bind_count = bind_count;
+        self
+    }
+
+    /// Sets the column the error refers to
+    pub(crate) fn with_column(mut self, column: &str) -> Self {
+        self.column = Some(column.to_owned());
+        self
+    }
+
+    /// Adds the position of a parse error, `offset` is the byte offset
+    /// of the error in the sql text reported by oracle
+    pub(crate) fn with_parse_error_offset(mut self, offset: usize) -> Self {
+        if let Some(ref sql) = self.statement {
+            self.position = Some(locate(sql, offset));
+        }
+        self
+    }
+
+    /// Whether this is a syntax error, for which oracle reports the
+    /// position of the error in the sql text
+    pub(crate) fn is_parse_error(&self) -> bool {
+        match self.code {
+            // ORA-00900: invalid SQL statement
+            // ORA-00904: invalid identifier
+            // ORA-00905: missing keyword
+            // ORA-00906: missing left parenthesis
+            // ORA-00907: missing right parenthesis
+            // ORA-00911: invalid character
+            // ORA-00917: missing comma
+            // ORA-00923: FROM keyword not found where expected
+            // ORA-00933: SQL command not properly ended
+            // ORA-00936: missing expression
+            // ORA-00942: table or view does not exist
+            900 | 904 | 905 | 906 | 907 | 911 | 917 | 923 | 933 | 936 | 942 => true,
+            _ => false,
+        }
+    }
+
+    /// The line and column (both starting at 1) of a syntax error in the sql
+    /// text of the statement
+    pub fn parse_error_position(&self) -> Option<(usize, usize)> {
+        self.position
+            .as_ref()
+            .map(|&(line, column, _)| (line, column))
+    }
+
+    /// The numeric oracle error code, e.g. `1` for `ORA-00001`
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The sql text of the statement that caused this error, if any
+    pub fn statement(&self) -> Option<&str> {
+        self.statement.as_deref()
+    }
+
+    /// The number of parameters bound to the statement when the error occurred
+    pub fn bind_count(&self) -> usize {
+        self.bind_count
+    }
+
+    /// Extracts the oracle error code from the information attached to a
+    /// `diesel::result::Error::DatabaseError`
+    ///
+    /// ```ignore
+    /// match diesel::insert_into(users).values(&new_user).execute(&conn) {
+    ///     Err(Error::DatabaseError(_, ref info)) if OciError::code_of(&**info) == Some(1400) => {
+    ///         // a required column was null
+    ///     }
+    ///     _ => {}
+    /// }
+    /// ```
+    pub fn code_of(info: &dyn DatabaseErrorInformation) -> Option<i32> {
+        let message = info.message();
+        if !message.starts_with("ORA-") {
+            return None;
+        }
+        message[4..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    /// Whether a fetched value did not fit into its buffer (`ORA-01406`),
+    /// `column_name` is the column of the value
+    pub fn is_truncation(&self) -> bool {
+        self.code == 1406
+    }
+
+    /// Whether this error leaves the session unusable
+    pub fn is_fatal(&self) -> bool {
+        match self.code {
+            // ORA-00028: your session has been killed
+            // ORA-01012: not logged on
+            // ORA-01089: immediate shutdown or close in progress
+            // ORA-01092: ORACLE instance terminated. Disconnection forced
+            // ORA-03113: end-of-file on communication channel
+            // ORA-03114: not connected to ORACLE
+            // ORA-03135: connection lost contact
+            // ORA-12541: TNS:no listener
+            28 | 1012 | 1089 | 1092 | 3113 | 3114 | 3135 | 12541 => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn kind(&self) -> DatabaseErrorKind {
+        match self.code {
+            1 => DatabaseErrorKind::UniqueViolation,
+            2291 | 2292 => DatabaseErrorKind::ForeignKeyViolation,
+            8177 => DatabaseErrorKind::SerializationFailure,
+            // connection lost / not connected
+            3113 | 3114 | 3135 | 12170 | 12541 | 12543 => DatabaseErrorKind::UnableToSendCommand,
+            // session killed / not logged on / instance shutdown
+            28 | 1012 | 1089 | 1092 => DatabaseErrorKind::UnableToSendCommand,
+            // diesel 1.x has no kind for not null or check violations
+            // (ORA-01400, ORA-01407, ORA-02290, ORA-12899)
+            _ => DatabaseErrorKind::__Unknown,
+        }
+    }
+}
+
+// translates a byte offset into line, column and the offending line of the
+// sql text with a caret pointing to the column
+fn locate(sql: &str, offset: usize) -> (usize, usize, String) {
+    let mut offset = offset.min(sql.len());
+    while !sql.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = sql[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = sql[offset..]
+        .find('\n')
+        .map(|i| offset + i)
+        .unwrap_or_else(|| sql.len());
+    let line = sql[..offset].matches('\n').count() + 1;
+    let column = sql[line_start..offset].chars().count() + 1;
+    let snippet = format!(
+        "{}\n{}^",
+        sql[line_start..line_end].trim_end_matches('\r'),
+        " ".repeat(column - 1)
+    );
+    (line, column, snippet)
+}
+
+fn parse_constraint(message: &str) -> Option<String> {
+    let start = message.find('(')? + 1;
+    let end = start + message[start..].find(')')?;
+    Some(message[start..end].to_owned())
+}
+
+fn parse_column(message: &str) -> (Option<String>, Option<String>) {
+    // collect the quoted identifiers of the first `"SCHEMA"."TABLE"."COLUMN"` path
+    let start = match message.find('"') {
+        Some(start) => start,
+        None => return (None, None),
+    };
+    let mut parts = Vec::new();
+    let mut rest = &message[start..];
+    while rest.starts_with('"') {
+        let end = match rest[1..].find('"') {
+            Some(end) => end + 1,
+            None => break,
+        };
+        parts.push(rest[1..end].to_owned());
+        rest = &rest[end + 1..];
+        if rest.starts_with('.') {
+            rest = &rest[1..];
+        } else {
+            break;
+        }
+    }
+    let column = parts.pop();
+    let table = parts.pop();
+    (table, column)
+}
+
+impl fmt::Debug for OciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OciError")
+            .field("code", &self.code)
+            .field("message", &self.message)
+            .field("statement", &self.statement)
+            .field("bind_count", &self.bind_count)
+            .finish()
+    }
+}
+
+impl fmt::Display for OciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(ref details) = self.details {
+            write!(f, " ({})", details)?;
+        }
+        if let Some((line, column, ref snippet)) = self.position {
+            write!(f, "\nat line {}, column {}:\n{}", line, column, snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl DatabaseErrorInformation for OciError {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn details(&self) -> Option<&str> {
+        self.details.as_deref()
+    }
+
+    fn hint(&self) -> Option<&str> {
+        self.position.as_ref().map(|p| &*p.2)
+    }
+
+    fn table_name(&self) -> Option<&str> {
+        self.table.as_deref()
+    }
+
+    fn column_name(&self) -> Option<&str> {
+        self.column.as_deref()
+    }
+
+    fn constraint_name(&self) -> Option<&str> {
+        self.constraint.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locate, OciError};
+    use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind};
+
+    #[test]
+    fn check_unique_violation() {
+        let info = OciError::new(
+            1,
+            "ORA-00001: unique constraint (DIESEL.SYS_C0012345) violated".into(),
+        );
+        assert!(matches!(info.kind(), DatabaseErrorKind::UniqueViolation));
+        assert_eq!(info.constraint_name(), Some("DIESEL.SYS_C0012345"));
+    }
+
+    #[test]
+    fn check_foreign_key_violation() {
+        let info = OciError::new(
+            2291,
+            "ORA-02291: integrity constraint (DIESEL.FK_POSTS_USERS) violated - parent key not found"
+                .into(),
+        );
+        assert!(matches!(
+            info.kind(),
+            DatabaseErrorKind::ForeignKeyViolation
+        ));
+        assert_eq!(info.constraint_name(), Some("DIESEL.FK_POSTS_USERS"));
+    }
+
+    #[test]
+    fn check_not_null_violation() {
+        let info = OciError::new(
+            1400,
+            "ORA-01400: cannot insert NULL into (\"DIESEL\".\"USERS\".\"NAME\")".into(),
+        );
+        assert!(matches!(info.kind(), DatabaseErrorKind::__Unknown));
+        assert_eq!(info.table_name(), Some("USERS"));
+        assert_eq!(info.column_name(), Some("NAME"));
+        assert_eq!(info.constraint_name(), None);
+    }
+
+    #[test]
+    fn check_value_too_large() {
+        let info = OciError::new(
+            12899,
+            "ORA-12899: value too large for column \"DIESEL\".\"USERS\".\"NAME\" (actual: 60, maximum: 50)"
+                .into(),
+        );
+        assert_eq!(info.table_name(), Some("USERS"));
+        assert_eq!(info.column_name(), Some("NAME"));
+    }
+
+    #[test]
+    fn check_truncation() {
+        let info = OciError::new(1406, "ORA-01406: fetched column value was truncated".into())
+            .with_column("NAME");
+        assert!(info.is_truncation());
+        assert_eq!(info.column_name(), Some("NAME"));
+        assert_eq!(info.table_name(), None);
+    }
+
+    #[test]
+    fn check_fatal_errors() {
+        let info = OciError::new(
+            3113,
+            "ORA-03113: end-of-file on communication channel".into(),
+        );
+        assert!(info.is_fatal());
+        assert!(matches!(
+            info.kind(),
+            DatabaseErrorKind::UnableToSendCommand
+        ));
+        let info = OciError::new(1, "ORA-00001: unique constraint (A.B) violated".into());
+        assert!(!info.is_fatal());
+    }
+
+    #[test]
+    fn check_statement_information() {
+        let info = OciError::new(942, "ORA-00942: table or view does not exist".into())
+            .with_statement("SELECT * FROM missing WHERE id = :1", 1);
+        assert_eq!(info.code(), 942);
+        assert_eq!(
+            info.statement(),
+            Some("SELECT * FROM missing WHERE id = :1")
+        );
+        assert_eq!(info.bind_count(), 1);
+        assert_eq!(OciError::code_of(&info), Some(942));
+        assert_eq!(
+            info.details(),
+            Some("while executing `SELECT * FROM missing WHERE id = :1` with 1 bind parameter(s)")
+        );
+    }
+
+    #[test]
+    fn check_parse_error_position() {
+        let sql = "SELECT id,\n  name\nFROM users WHERE (id = 1";
+        assert_eq!(
+            locate(sql, 24),
+            (3, 7, "FROM users WHERE (id = 1\n      ^".to_owned())
+        );
+        assert_eq!(locate(sql, 0), (1, 1, "SELECT id,\n^".to_owned()));
+
+        let info = OciError::new(907, "ORA-00907: missing right parenthesis".into())
+            .with_statement(sql, 0)
+            .with_parse_error_offset(sql.len());
+        assert!(info.is_parse_error());
+        assert_eq!(info.parse_error_position(), Some((3, 25)));
+        assert_eq!(
+            info.hint(),
+            Some("FROM users WHERE (id = 1\n                        ^")
+        );
+        assert!(info.to_string().contains("at line 3, column 25:"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use oci_sys as ffi;
+
+/// The kind of transparent application failover (TAF), configured by the
+/// `FAILOVER_MODE` of the service or connect descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverType {
+    /// The session is not failed over
+    None,
+    /// A new session is opened, the session state is lost
+    Session,
+    /// A new session is opened and open cursors continue fetching
+    Select,
+    /// The transaction of the session is failed over
+    Transaction,
+}
+
+/// The events of a failover, c.f. `OciConnectionBuilder::on_failover`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverEvent {
+    /// The connection was lost, oracle starts reconnecting
+    Begin,
+    /// The failover completed successfully
+    End,
+    /// The failover failed and will not be retried
+    Abort,
+    /// A session of a proxy user was reauthenticated
+    Reauthenticate,
+    /// A failover attempt failed, return `FailoverAction::Retry` to try
+    /// again
+    Error,
+}
+
+/// What oracle does after the failover hook returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverAction {
+    Continue,
+    /// Tries again to fail over, only for `FailoverEvent::Error`
+    Retry,
+}
+
+/// A hook called on failover events, c.f. `OciConnectionBuilder::on_failover`
+#[derive(Clone)]
+pub(super) struct FailoverHook(
+    pub(super) Arc<dyn Fn(FailoverEvent, FailoverType) -> FailoverAction + Send + Sync>,
+);
+
+impl fmt::Debug for FailoverHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FailoverHook")
+    }
+}
+
+fn failover_type(fo_type: u32) -> FailoverType {
+    match fo_type {
+        ffi::OCI_FO_SESSION => FailoverType::Session,
+        ffi::OCI_FO_SELECT => FailoverType::Select,
+        ffi::OCI_FO_TXNAL => FailoverType::Transaction,
+        _ => FailoverType::None,
+    }
+}
+
+fn failover_event(fo_event: u32) -> Option<FailoverEvent> {
+    match fo_event {
+        ffi::OCI_FO_BEGIN => Some(FailoverEvent::Begin),
+        ffi::OCI_FO_END => Some(FailoverEvent::End),
+        ffi::OCI_FO_ABORT => Some(FailoverEvent::Abort),
+        ffi::OCI_FO_REAUTH => Some(FailoverEvent::Reauthenticate),
+        ffi::OCI_FO_ERROR => Some(FailoverEvent::Error),
+        _ => None,
+    }
+}
+
+/// The failover callback registered with `OCI_ATTR_FOCBK`, `fo_ctx` points
+/// to the `Option<FailoverHook>` owned by the `RawConnection`
+pub(super) unsafe extern "C" fn failover_callback(
+    _svcctx: *mut c_void,
+    _envctx: *mut c_void,
+    fo_ctx: *mut c_void,
+    fo_type: u32,
+    fo_event: u32,
+) -> i32 {
+    let event = match failover_event(fo_event) {
+        Some(event) => event,
+        None => return 0,
+    };
+    let tpe = failover_type(fo_type);
+    match event {
+        FailoverEvent::Begin | FailoverEvent::End | FailoverEvent::Reauthenticate => {
+            info!("Failover {:?} ({:?})", event, tpe)
+        }
+        FailoverEvent::Abort | FailoverEvent::Error => warn!("Failover {:?} ({:?})", event, tpe),
+    }
+    let hook = &*(fo_ctx as *const Option<FailoverHook>);
+    let action = match *hook {
+        // unwinding into oci is undefined behaviour
+        Some(ref hook) => panic::catch_unwind(AssertUnwindSafe(|| (hook.0)(event, tpe)))
+            .unwrap_or(FailoverAction::Continue),
+        None => FailoverAction::Continue,
+    };
+    match (event, action) {
+        (FailoverEvent::Error, FailoverAction::Retry) => ffi::OCI_FO_RETRY as i32,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{failover_callback, FailoverAction, FailoverEvent, FailoverHook, FailoverType};
+    use oci_sys as ffi;
+    use std::ptr;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn check_failover_callback() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let hook = Some(FailoverHook(Arc::new(move |event, tpe| {
+            recorded.lock().unwrap().push((event, tpe));
+            FailoverAction::Retry
+        })));
+        let ctx = &hook as *const Option<FailoverHook> as *mut _;
+        let callback = |tpe, event| unsafe {
+            failover_callback(ptr::null_mut(), ptr::null_mut(), ctx, tpe, event)
+        };
+
+        assert_eq!(callback(ffi::OCI_FO_SELECT, ffi::OCI_FO_BEGIN), 0);
+        assert_eq!(
+            callback(ffi::OCI_FO_SELECT, ffi::OCI_FO_ERROR),
+            ffi::OCI_FO_RETRY as i32
+        );
+        assert_eq!(callback(ffi::OCI_FO_SESSION, ffi::OCI_FO_END), 0);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (FailoverEvent::Begin, FailoverType::Select),
+                (FailoverEvent::Error, FailoverType::Select),
+                (FailoverEvent::End, FailoverType::Session),
+            ]
+        );
+
+        let panicking = Some(FailoverHook(Arc::new(|_, _| panic!("hook failed"))));
+        let ctx = &panicking as *const Option<FailoverHook> as *mut _;
+        let ret = unsafe {
+            failover_callback(
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ctx,
+                ffi::OCI_FO_SELECT,
+                ffi::OCI_FO_ERROR,
+            )
+        };
+        assert_eq!(ret, 0);
+    }
+}
@@ -0,0 +1,217 @@
+use std::os::raw::c_long;
+use std::time::Duration;
+
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use oci_sys as ffi;
+
+use super::stmt::Statement;
+use super::OciConnection;
+
+// the limits of `xid_t` defined by the XA specification
+const XID_DATA_SIZE: usize = 128;
+const MAX_GTRID_SIZE: usize = 64;
+const MAX_BQUAL_SIZE: usize = 64;
+
+/// The `xid_t` of the XA specification, which oracle expects as
+/// `OCI_ATTR_XID`
+#[repr(C)]
+pub(super) struct XidData {
+    format_id: c_long,
+    gtrid_length: c_long,
+    bqual_length: c_long,
+    data: [u8; XID_DATA_SIZE],
+}
+
+/// The id of a branch of a global transaction, c.f.
+/// `OciConnection::start_global_transaction`
+///
+/// The transaction manager chooses the format, the global transaction id
+/// (shared by all branches) and the branch qualifier (unique per branch).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Xid {
+    format_id: i32,
+    global_transaction_id: Vec<u8>,
+    branch_qualifier: Vec<u8>,
+}
+
+impl Xid {
+    /// Fails if `global_transaction_id` or `branch_qualifier` is longer than
+    /// 64 bytes
+    pub fn new(
+        format_id: i32,
+        global_transaction_id: &[u8],
+        branch_qualifier: &[u8],
+    ) -> QueryResult<Self> {
+        if global_transaction_id.len() > MAX_GTRID_SIZE || branch_qualifier.len() > MAX_BQUAL_SIZE {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!(
+                    "The global transaction id and branch qualifier of a xid \
+                     must not exceed {} bytes",
+                    MAX_GTRID_SIZE
+                )),
+            ));
+        }
+        Ok(Xid {
+            format_id,
+            global_transaction_id: global_transaction_id.to_vec(),
+            branch_qualifier: branch_qualifier.to_vec(),
+        })
+    }
+
+    pub fn format_id(&self) -> i32 {
+        self.format_id
+    }
+
+    pub fn global_transaction_id(&self) -> &[u8] {
+        &self.global_transaction_id
+    }
+
+    pub fn branch_qualifier(&self) -> &[u8] {
+        &self.branch_qualifier
+    }
+
+    pub(super) fn to_raw(&self) -> XidData {
+        let gtrid_length = self.global_transaction_id.len();
+        let bqual_length = self.branch_qualifier.len();
+        let mut data = [0; XID_DATA_SIZE];
+        data[..gtrid_length].copy_from_slice(&self.global_transaction_id);
+        data[gtrid_length..gtrid_length + bqual_length].copy_from_slice(&self.branch_qualifier);
+        XidData {
+            format_id: c_long::from(self.format_id),
+            gtrid_length: gtrid_length as c_long,
+            bqual_length: bqual_length as c_long,
+            data,
+        }
+    }
+}
+
+impl OciConnection {
+    /// Starts the branch `xid` of a global transaction coordinated by an
+    /// external transaction manager
+    ///
+    /// All statements of this connection belong to the branch until it is
+    /// detached, prepared, committed or rolled back. Diesel's transactions
+    /// must not be used meanwhile. `timeout` is the time oracle keeps a
+    /// detached branch before rolling it back.
+    ///
+    /// ```ignore
+    /// let xid = Xid::new(0x1234, b"order-42", b"inventory")?;
+    /// conn.start_global_transaction(&xid, Duration::from_secs(60))?;
+    /// diesel::update(stock::table).set(stock::count.eq(stock::count - 1)).execute(&conn)?;
+    /// conn.detach_global_transaction()?;
+    /// if conn.prepare_global_transaction(&xid)? {
+    ///     conn.commit_global_transaction(&xid, false)?;
+    /// }
+    /// ```
+    pub fn start_global_transaction(&self, xid: &Xid, timeout: Duration) -> QueryResult<()> {
+        self.trans_start(xid, timeout, ffi::OCI_TRANS_NEW)
+    }
+
+    /// Continues the detached branch `xid`, which may have been started by
+    /// another connection
+    pub fn resume_global_transaction(&self, xid: &Xid, timeout: Duration) -> QueryResult<()> {
+        self.trans_start(xid, timeout, ffi::OCI_TRANS_RESUME)
+    }
+
+    /// Detaches the connection from the current branch, which can then be
+    /// resumed, prepared, committed or rolled back by any connection
+    pub fn detach_global_transaction(&self) -> QueryResult<()> {
+        let raw = self.raw();
+        let status = unsafe {
+            ffi::OCITransDetach(raw.service_handle, raw.env.error_handle, ffi::OCI_DEFAULT)
+        };
+        Statement::check_error(raw.env.error_handle, status)?;
+        raw.use_local_transaction_handle()
+    }
+
+    /// Prepares the branch `xid` for the second phase of the commit
+    ///
+    /// Returns `false` if the branch did not change any data, it is then
+    /// already completed and must not be committed.
+    pub fn prepare_global_transaction(&self, xid: &Xid) -> QueryResult<bool> {
+        let raw = self.raw();
+        raw.use_global_transaction_handle(xid)?;
+        let status = unsafe {
+            ffi::OCITransPrepare(raw.service_handle, raw.env.error_handle, ffi::OCI_DEFAULT)
+        };
+        let ret = Statement::check_error(raw.env.error_handle, status);
+        raw.use_local_transaction_handle()?;
+        ret?;
+        // ORA-24767: transaction branch prepare returns read-only
+        Ok(status != ffi::OCI_SUCCESS_WITH_INFO as i32)
+    }
+
+    /// Commits the branch `xid`, `one_phase` commits a branch which was not
+    /// prepared (e.g. the only branch of the global transaction)
+    ///
+    /// The branch may have been prepared by another connection, e.g. when
+    /// the transaction manager recovers after a failure.
+    pub fn commit_global_transaction(&self, xid: &Xid, one_phase: bool) -> QueryResult<()> {
+        let flags = if one_phase {
+            ffi::OCI_DEFAULT
+        } else {
+            ffi::OCI_TRANS_TWOPHASE
+        };
+        let raw = self.raw();
+        raw.use_global_transaction_handle(xid)?;
+        let status =
+            unsafe { ffi::OCITransCommit(raw.service_handle, raw.env.error_handle, flags) };
+        let ret = Statement::check_error(raw.env.error_handle, status);
+        raw.use_local_transaction_handle()?;
+        ret
+    }
+
+    /// Rolls back the branch `xid`, which may be prepared
+    pub fn rollback_global_transaction(&self, xid: &Xid) -> QueryResult<()> {
+        let raw = self.raw();
+        raw.use_global_transaction_handle(xid)?;
+        let status = unsafe {
+            ffi::OCITransRollback(
+                raw.service_handle as *mut _,
+                raw.env.error_handle,
+                ffi::OCI_DEFAULT,
+            )
+        };
+        let ret = Statement::check_error(raw.env.error_handle, status);
+        raw.use_local_transaction_handle()?;
+        ret
+    }
+
+    fn trans_start(&self, xid: &Xid, timeout: Duration, flags: u32) -> QueryResult<()> {
+        let raw = self.raw();
+        raw.use_global_transaction_handle(xid)?;
+        let status = unsafe {
+            ffi::OCITransStart(
+                raw.service_handle,
+                raw.env.error_handle,
+                timeout.as_secs() as _,
+                flags,
+            )
+        };
+        let ret = Statement::check_error(raw.env.error_handle, status);
+        if ret.is_err() {
+            raw.use_local_transaction_handle()?;
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Xid;
+
+    #[test]
+    fn check_xid_data() {
+        let xid = Xid::new(0x1234, b"order-42", b"inventory").unwrap();
+        let raw = xid.to_raw();
+        assert_eq!(raw.format_id, 0x1234);
+        assert_eq!(raw.gtrid_length, 8);
+        assert_eq!(raw.bqual_length, 9);
+        assert_eq!(&raw.data[..18], b"order-42inventory\0");
+
+        assert!(Xid::new(1, &[0; 64], &[0; 64]).is_ok());
+        assert!(Xid::new(1, &[0; 65], b"").is_err());
+        assert!(Xid::new(1, b"", &[0; 65]).is_err());
+    }
+}
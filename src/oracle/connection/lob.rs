@@ -0,0 +1,328 @@
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Arc;
+
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use oci_sys as ffi;
+
+use super::cursor;
+use super::raw::{ErrorHandle, RawConnection};
+use super::stmt::Statement;
+use super::OciConnection;
+
+/// Text and binary values longer than this are bound as temporary LOBs, as
+/// they exceed the maximal size of a `VARCHAR2` or `RAW`
+pub(super) const LOB_BIND_THRESHOLD: usize = 32767;
+
+// the size of the pieces written by `Lob::append`
+const PIECE_SIZE: usize = 1024 * 1024;
+
+// c.f. orl.h, temporary LOBs live until they are freed or the session ends
+const OCI_DURATION_SESSION: ffi::OCIDuration = 10;
+
+/// The type of a `Lob`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LobType {
+    Blob,
+    Clob,
+    /// A `CLOB` in the national character set
+    NClob,
+}
+
+impl LobType {
+    fn sqlt(self) -> u32 {
+        match self {
+            LobType::Blob => ffi::SQLT_BLOB,
+            LobType::Clob | LobType::NClob => ffi::SQLT_CLOB,
+        }
+    }
+
+    fn charset_form(self) -> u8 {
+        match self {
+            LobType::NClob => ffi::SQLCS_NCHAR as u8,
+            _ => ffi::SQLCS_IMPLICIT as u8,
+        }
+    }
+}
+
+/// A locator of a `BLOB`, `CLOB` or `NCLOB` value, which is written
+/// without passing the whole value to oracle at once
+///
+/// Offsets and lengths count bytes for `BLOB`s and characters for `CLOB`s,
+/// the data of `CLOB`s is text in the character set of the connection.
+/// Writing needs a locator of a locked row (c.f.
+/// `OciConnection::select_lob`) or a temporary LOB (c.f.
+/// `OciConnection::create_temporary_lob`).
+pub struct Lob {
+    raw: Arc<RawConnection>,
+    error_handle: ErrorHandle,
+    // bound or defined by address, so a `Lob` is boxed while it is used by
+    // a statement
+    pub(super) locator: *mut ffi::OCILobLocator,
+    lob_type: LobType,
+    temporary: bool,
+}
+
+impl OciConnection {
+    /// Executes a query selecting a single LOB column, returning the LOB of
+    /// the first row or `None` if there is no row or the value is null
+    ///
+    /// The row needs to be locked by `FOR UPDATE` to write the LOB. As with
+    /// `UPDATE`, the changes are part of the current transaction.
+    ///
+    /// ```ignore
+    /// conn.execute("UPDATE documents SET body = EMPTY_CLOB() WHERE id = 1")?;
+    /// let mut body = conn
+    ///     .select_lob("SELECT body FROM documents WHERE id = 1 FOR UPDATE")?
+    ///     .expect("document 1 exists");
+    /// for line in lines {
+    ///     body.append(line.as_bytes())?;
+    /// }
+    /// ```
+    pub fn select_lob(&self, sql: &str) -> QueryResult<Option<Lob>> {
+        let raw = Arc::clone(&*self.raw());
+        let mut stmt = Statement::prepare(&raw, sql)?;
+        stmt.run()?;
+        let (lob, indicator) = stmt.define_lob(1)?;
+        if !cursor::fetch(&stmt)? || *indicator == -1 {
+            return Ok(None);
+        }
+        Ok(Some(*lob))
+    }
+
+    /// Creates an empty temporary LOB, e.g. to pass a large value to a
+    /// PL/SQL procedure (c.f. `PlSqlBind::lob`)
+    ///
+    /// The LOB uses temporary tablespace until it is dropped, or until the
+    /// session ends if the connection is lost.
+    pub fn create_temporary_lob(&self, lob_type: LobType) -> QueryResult<Lob> {
+        Lob::temporary(&self.raw(), lob_type)
+    }
+}
+
+impl Lob {
+    /// An unused locator, which is set by oracle once it is defined or
+    /// bound as an `OUT` parameter
+    pub(super) fn new(raw: &Arc<RawConnection>, lob_type: LobType) -> QueryResult<Self> {
+        let locator = raw.alloc_lob_locator();
+        if locator.is_null() {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new("Could not allocate a LOB locator".to_owned()),
+            ));
+        }
+        Ok(Lob {
+            raw: Arc::clone(raw),
+            error_handle: raw.env.alloc_error_handle(),
+            locator,
+            lob_type,
+            temporary: false,
+        })
+    }
+
+    pub(super) fn temporary(raw: &Arc<RawConnection>, lob_type: LobType) -> QueryResult<Self> {
+        let mut lob = Lob::new(raw, lob_type)?;
+        let lob_kind = match lob_type {
+            LobType::Blob => ffi::OCI_TEMP_BLOB,
+            LobType::Clob | LobType::NClob => ffi::OCI_TEMP_CLOB,
+        };
+        let status = unsafe {
+            ffi::OCILobCreateTemporary(
+                raw.service_handle,
+                lob.error_handle.handle,
+                lob.locator,
+                0,
+                lob_type.charset_form(),
+                lob_kind as u8,
+                0,
+                OCI_DURATION_SESSION,
+            )
+        };
+        lob.check(status)?;
+        lob.temporary = true;
+        Ok(lob)
+    }
+
+    pub fn lob_type(&self) -> LobType {
+        self.lob_type
+    }
+
+    /// Whether this is a temporary LOB, which is freed when it is dropped
+    pub fn is_temporary(&self) -> bool {
+        self.temporary
+    }
+
+    /// The `SQLT_*` type this LOB is bound or defined with
+    pub(super) fn sqlt(&self) -> u32 {
+        self.lob_type.sqlt()
+    }
+
+    /// The length in bytes (`BLOB`) or characters (`CLOB`)
+    pub fn len(&self) -> QueryResult<u64> {
+        let mut len = 0;
+        let status = unsafe {
+            ffi::OCILobGetLength2(
+                self.raw.service_handle,
+                self.error_handle.handle,
+                self.locator,
+                &mut len,
+            )
+        };
+        self.check(status)?;
+        Ok(len)
+    }
+
+    pub fn is_empty(&self) -> QueryResult<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// The amount of data oracle stores in a chunk of this LOB, writes of a
+    /// multiple of this size are the most efficient
+    pub fn chunk_size(&self) -> QueryResult<u32> {
+        let mut size = 0;
+        let status = unsafe {
+            ffi::OCILobGetChunkSize(
+                self.raw.service_handle,
+                self.error_handle.handle,
+                self.locator,
+                &mut size,
+            )
+        };
+        self.check(status)?;
+        Ok(size)
+    }
+
+    /// Overwrites the LOB starting at `offset` (counted from 0) with `data`,
+    /// extending it if necessary
+    pub fn write_chunk(&mut self, offset: u64, data: &[u8]) -> QueryResult<()> {
+        let mut byte_amount = data.len() as u64;
+        let mut char_amount = 0;
+        let status = unsafe {
+            ffi::OCILobWrite2(
+                self.raw.service_handle,
+                self.error_handle.handle,
+                self.locator,
+                &mut byte_amount,
+                &mut char_amount,
+                // oracle counts from 1
+                offset + 1,
+                data.as_ptr() as *mut c_void,
+                data.len() as u64,
+                ffi::OCI_ONE_PIECE as u8,
+                ptr::null_mut(),
+                None,
+                0,
+                self.lob_type.charset_form(),
+            )
+        };
+        self.check(status)
+    }
+
+    /// Appends `data` to the end of the LOB
+    ///
+    /// Large values are sent in pieces of 1 MiB, the pieces of a `CLOB`
+    /// end at character boundaries of UTF-8 text.
+    pub fn append(&mut self, mut data: &[u8]) -> QueryResult<()> {
+        while !data.is_empty() {
+            let len = piece_len(data, self.lob_type != LobType::Blob);
+            let mut byte_amount = len as u64;
+            let mut char_amount = 0;
+            let status = unsafe {
+                ffi::OCILobWriteAppend2(
+                    self.raw.service_handle,
+                    self.error_handle.handle,
+                    self.locator,
+                    &mut byte_amount,
+                    &mut char_amount,
+                    data.as_ptr() as *mut c_void,
+                    len as u64,
+                    ffi::OCI_ONE_PIECE as u8,
+                    ptr::null_mut(),
+                    None,
+                    0,
+                    self.lob_type.charset_form(),
+                )
+            };
+            self.check(status)?;
+            data = &data[len..];
+        }
+        Ok(())
+    }
+
+    /// Shortens the LOB to `len` bytes (`BLOB`) or characters (`CLOB`)
+    pub fn trim(&mut self, len: u64) -> QueryResult<()> {
+        let status = unsafe {
+            ffi::OCILobTrim2(
+                self.raw.service_handle,
+                self.error_handle.handle,
+                self.locator,
+                len,
+            )
+        };
+        self.check(status)
+    }
+
+    fn check(&self, status: i32) -> QueryResult<()> {
+        Statement::check_error(self.error_handle.handle, status)
+    }
+}
+
+// the length of the next piece of `data` to append, text is not split
+// within a character
+fn piece_len(data: &[u8], text: bool) -> usize {
+    if data.len() <= PIECE_SIZE {
+        return data.len();
+    }
+    let mut len = PIECE_SIZE;
+    if text {
+        // continuation bytes of UTF-8 are `10xxxxxx`
+        while len > 0 && data[len] & 0xC0 == 0x80 {
+            len -= 1;
+        }
+    }
+    len
+}
+
+impl fmt::Debug for Lob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Lob")
+            .field("lob_type", &self.lob_type)
+            .field("temporary", &self.temporary)
+            .finish()
+    }
+}
+
+impl Drop for Lob {
+    fn drop(&mut self) {
+        unsafe {
+            if self.temporary {
+                ffi::OCILobFreeTemporary(
+                    self.raw.service_handle,
+                    self.error_handle.handle,
+                    self.locator,
+                );
+            }
+            ffi::OCIDescriptorFree(self.locator as *mut c_void, ffi::OCI_DTYPE_LOB);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{piece_len, PIECE_SIZE};
+
+    #[test]
+    fn check_piece_len() {
+        assert_eq!(piece_len(b"abc", true), 3);
+        let data = vec![b'a'; PIECE_SIZE + 10];
+        assert_eq!(piece_len(&data, true), PIECE_SIZE);
+
+        // a three byte character starting one byte before the end of the
+        // piece is moved to the next one
+        let mut data = vec![b'a'; PIECE_SIZE - 1];
+        data.extend_from_slice("€€".as_bytes());
+        assert_eq!(piece_len(&data, true), PIECE_SIZE - 1);
+        assert_eq!(piece_len(&data, false), PIECE_SIZE);
+    }
+}
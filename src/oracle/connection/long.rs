@@ -0,0 +1,144 @@
+use std::os::raw::c_void;
+
+use oci_sys as ffi;
+
+// the size of the pieces oracle is asked to write
+const PIECE_SIZE: usize = 64 * 1024;
+
+/// The value of a `LONG` or `LONG RAW` column, which is fetched piecewise
+/// as the size of these values is unknown before they are fetched
+///
+/// Oracle calls `define_callback` for each piece of the value, the pieces
+/// are appended to `data`.
+#[derive(Debug)]
+pub(super) struct LongValue {
+    data: Vec<u8>,
+    // the length of all pieces before the current one
+    len: usize,
+    piece_len: u32,
+    indicator: i16,
+    return_code: u16,
+}
+
+impl LongValue {
+    pub(super) fn new() -> Self {
+        LongValue {
+            data: Vec::new(),
+            len: 0,
+            piece_len: 0,
+            indicator: -1,
+            return_code: 0,
+        }
+    }
+
+    /// The value of the current row, followed by a null byte
+    pub(super) fn value(&self) -> Option<&[u8]> {
+        if self.is_null() {
+            None
+        } else {
+            Some(&self.data[..=self.len + self.piece_len as usize])
+        }
+    }
+
+    pub(super) fn is_null(&self) -> bool {
+        self.indicator == -1 || self.data.is_empty()
+    }
+
+    // returns the buffer for the next piece, `first` starts a new row
+    fn next_piece(&mut self, first: bool) -> *mut u8 {
+        if first {
+            self.len = 0;
+        } else {
+            self.len += self.piece_len as usize;
+        }
+        // the byte following the piece stays 0, so the value is always
+        // terminated by a null byte
+        let end = self.len + PIECE_SIZE + 1;
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        for b in &mut self.data[self.len..end] {
+            *b = 0;
+        }
+        self.piece_len = PIECE_SIZE as u32;
+        self.data[self.len..].as_mut_ptr()
+    }
+}
+
+/// The callback registered with `OCIDefineDynamic`, `octxp` points to the
+/// `LongValue` of the column
+pub(super) unsafe extern "C" fn define_callback(
+    octxp: *mut c_void,
+    _defnp: *mut ffi::OCIDefine,
+    _iter: u32,
+    bufpp: *mut *mut c_void,
+    alenp: *mut *mut u32,
+    piecep: *mut u8,
+    indp: *mut *mut c_void,
+    rcodep: *mut *mut u16,
+) -> i32 {
+    let value = &mut *(octxp as *mut LongValue);
+    let first = u32::from(*piecep) != ffi::OCI_NEXT_PIECE;
+    *bufpp = value.next_piece(first) as *mut c_void;
+    *alenp = &mut value.piece_len;
+    *indp = &mut value.indicator as *mut i16 as *mut c_void;
+    *rcodep = &mut value.return_code;
+    ffi::OCI_CONTINUE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{define_callback, LongValue, PIECE_SIZE};
+    use oci_sys as ffi;
+    use std::os::raw::c_void;
+    use std::ptr;
+
+    // simulates oracle writing `piece` as the next piece of the value
+    fn write_piece(value: &mut LongValue, piece: &[u8], first: bool) {
+        let mut buf = ptr::null_mut();
+        let mut alen = ptr::null_mut();
+        let mut ind = ptr::null_mut();
+        let mut rcode = ptr::null_mut();
+        let mut piece_kind = if first {
+            ffi::OCI_FIRST_PIECE as u8
+        } else {
+            ffi::OCI_NEXT_PIECE as u8
+        };
+        let ret = unsafe {
+            define_callback(
+                value as *mut LongValue as *mut c_void,
+                ptr::null_mut(),
+                0,
+                &mut buf,
+                &mut alen,
+                &mut piece_kind,
+                &mut ind,
+                &mut rcode,
+            )
+        };
+        assert_eq!(ret, ffi::OCI_CONTINUE);
+        unsafe {
+            assert_eq!(*alen as usize, PIECE_SIZE);
+            ptr::copy_nonoverlapping(piece.as_ptr(), buf as *mut u8, piece.len());
+            *alen = piece.len() as u32;
+            *(ind as *mut i16) = 0;
+        }
+    }
+
+    #[test]
+    fn check_piecewise_fetch() {
+        let mut value = LongValue::new();
+        assert_eq!(value.value(), None);
+
+        let first = vec![b'a'; PIECE_SIZE];
+        write_piece(&mut value, &first, true);
+        write_piece(&mut value, b"bc", false);
+        let fetched = value.value().unwrap();
+        assert_eq!(fetched.len(), PIECE_SIZE + 3);
+        assert_eq!(&fetched[PIECE_SIZE - 1..], b"abc\0");
+
+        // the next row starts again at the beginning
+        write_piece(&mut value, b"d", true);
+        assert_eq!(value.value().unwrap(), b"d\0");
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use oracle::types::OCIDataType;
+
+use super::OciConnection;
+
+/// Timings and row counts of a single execution of a statement, c.f.
+/// `MetricsSink`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatementStats {
+    pub sql: String,
+    /// The time oracle needed to prepare the statement, `0` if a prepared
+    /// statement was executed again (e.g. from the statement cache)
+    pub prepare_time: Duration,
+    pub execute_time: Duration,
+    /// The total time of all fetch calls
+    pub fetch_time: Duration,
+    pub rows_fetched: u64,
+    /// The number of fetch calls, each of them is a round trip unless the
+    /// row was prefetched, c.f. `OciConnectionBuilder::prefetch_rows`
+    pub fetch_calls: u64,
+    /// The binds compared with a column of another type, c.f.
+    /// `ImplicitConversion`
+    pub implicit_conversions: Vec<ImplicitConversion>,
+}
+
+/// A bind compared with a column oracle converts to the type of the bind,
+/// which prevents the use of an index on the column, e.g. a `BINARY_DOUBLE`
+/// compared with a `NUMBER` column (`TO_BINARY_DOUBLE("PRICE") = :1`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplicitConversion {
+    /// The position of the placeholder, starting at 1
+    pub position: u32,
+    pub bind_type: OCIDataType,
+    /// The column as written in the statement, e.g. `"ITEMS"."PRICE"`
+    pub column: String,
+    /// `None` for types diesel-oci does not know
+    pub column_type: Option<OCIDataType>,
+}
+
+/// Receives the statistics of each executed statement, e.g. to export them
+/// as metrics
+///
+/// The statistics of a query are recorded once all rows were fetched (or
+/// the cursor was dropped).
+///
+/// ```ignore
+/// struct Histogram(prometheus::Histogram);
+///
+/// impl MetricsSink for Histogram {
+///     fn record(&self, stats: &StatementStats) {
+///         let total = stats.prepare_time + stats.execute_time + stats.fetch_time;
+///         self.0.observe(total.as_secs_f64());
+///     }
+/// }
+/// ```
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, stats: &StatementStats);
+}
+
+impl OciConnection {
+    /// Records the statistics of all statements executed by this connection
+    /// with `sink`
+    pub fn set_metrics_sink(&self, sink: Arc<dyn MetricsSink>) {
+        self.raw().set_metrics_sink(Some(sink));
+    }
+
+    pub fn clear_metrics_sink(&self) {
+        self.raw().set_metrics_sink(None);
+    }
+}
@@ -0,0 +1,50 @@
+// diesel_migrations implements `MigrationConnection` for every connection,
+// but creates its bookkeeping table with `CREATE TABLE IF NOT EXISTS`, which
+// is not valid in oracle. We translate that statement into a PL/SQL block
+// ignoring `ORA-00955: name is already used by an existing object`.
+//
+// Keep in mind that every DDL statement of a migration implicitly commits
+// the transaction diesel_migrations wraps each migration into, so a failing
+// migration cannot roll back the DDL statements it already executed.
+
+const DIESEL_CREATE_MIGRATIONS_TABLE: &str =
+    "CREATE TABLE IF NOT EXISTS __diesel_schema_migrations (";
+
+const CREATE_MIGRATIONS_TABLE: &str = "BEGIN \
+     EXECUTE IMMEDIATE 'CREATE TABLE \"__DIESEL_SCHEMA_MIGRATIONS\" (\
+     \"VERSION\" VARCHAR2(50) PRIMARY KEY NOT NULL, \
+     \"RUN_ON\" TIMESTAMP DEFAULT SYSTIMESTAMP NOT NULL\
+     )'; \
+     EXCEPTION WHEN OTHERS THEN \
+     IF SQLCODE != -955 THEN RAISE; END IF; \
+     END;";
+
+/// Returns the oracle equivalent of the given statement if it is the
+/// statement used by diesel_migrations to set up its bookkeeping table
+pub(crate) fn translate_setup_statement(query: &str) -> Option<&'static str> {
+    if query.starts_with(DIESEL_CREATE_MIGRATIONS_TABLE) {
+        Some(CREATE_MIGRATIONS_TABLE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::translate_setup_statement;
+
+    #[test]
+    fn check_translate_setup_statement() {
+        let setup = "CREATE TABLE IF NOT EXISTS __diesel_schema_migrations (\
+                     version VARCHAR(50) PRIMARY KEY NOT NULL,\
+                     run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP\
+                     )";
+        let translated = translate_setup_statement(setup).unwrap();
+        assert!(translated.contains("VARCHAR2(50)"));
+        assert!(!translated.contains("IF NOT EXISTS"));
+        assert_eq!(
+            translate_setup_statement("CREATE TABLE users (id NUMBER)"),
+            None
+        );
+    }
+}
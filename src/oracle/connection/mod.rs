@@ -3,41 +3,162 @@ use diesel::connection::{Connection, MaybeCached, SimpleConnection};
 use diesel::deserialize::{Queryable, QueryableByName};
 use diesel::query_builder::bind_collector::RawBytesBindCollector;
 use diesel::query_builder::QueryId;
-use diesel::query_builder::{AsQuery, QueryFragment};
+use diesel::query_builder::{AsQuery, QueryBuilder, QueryFragment};
 use diesel::result::*;
 use diesel::sql_types::HasSqlType;
-use std::rc::Rc;
+use std::cell::{Ref, RefCell};
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
 
-use self::cursor::Cursor;
+use self::query_log::{describe_query, describe_sql, QueryLogger};
 use self::stmt::Statement;
 use self::transaction::OCITransactionManager;
 use super::backend::Oracle;
+use super::query_builder::OciQueryBuilder;
+use super::types::OCIDataType;
 mod oracle_value;
+#[cfg(test)]
+pub(crate) use self::async_connection::block_on;
+pub use self::async_connection::{AsyncOciConnection, Establish, Interact};
+#[cfg(feature = "debug-binds")]
+pub use self::bind_dump::BoundParameter;
+pub use self::bind_value::BindValue;
+pub use self::builder::{OciConnectionBuilder, Unredacted};
+pub use self::cancel::CancelToken;
+pub use self::checksum::QueryChecksum;
+pub use self::column_info::ColumnInfo;
+pub use self::cursor::{Cursor, NamedCursor, OwnedCursor, ScrollPosition};
+#[cfg(feature = "tower")]
+pub use self::deadline::{current_deadline, with_deadline, Deadline, DeadlineLayer};
+pub use self::direct_path::{DirectPathColumn, DirectPathLoader, DirectPathTable};
+pub use self::dynamic::DynamicValue;
+pub use self::error::OciError;
+pub use self::failover::{FailoverAction, FailoverEvent, FailoverType};
+pub use self::global_transaction::Xid;
+pub use self::lob::{Lob, LobType};
+pub use self::metrics::{ImplicitConversion, MetricsSink, StatementStats};
+pub use self::non_blocking::NonBlockingStatement;
+#[cfg(feature = "rust-oracle")]
+pub use self::oracle_params::named_params;
 pub use self::oracle_value::OracleValue;
+pub use self::plan_statistics::PlanStep;
+pub use self::plsql::{Call, CallResult, OutParamType, PlSqlBind, RefCursor};
+pub use self::prepared_statement::{ExecResult, PreparedStatement};
+pub use self::query_log::LoggedQuery;
+#[cfg(feature = "arrow")]
+pub use self::record_batch::to_record_batch;
+pub use self::returning::Returning;
+pub use self::row::OciRow;
+pub use self::script::{ScriptError, ScriptStatement};
+pub use self::server_version::ServerVersion;
+pub use self::statement_options::StatementOptions;
+pub use self::test_transaction::{test_transaction, TestTransaction};
+pub use self::transaction::TransactionBehavior;
+pub(crate) use self::type_map::integer_type;
+pub use self::type_map::{default_fetch_type, FetchType, TypeMap};
 
+mod async_connection;
+#[cfg(feature = "debug-binds")]
+mod bind_dump;
+mod bind_value;
+mod builder;
+mod cancel;
+mod checksum;
+mod column_info;
+mod current_schema;
 mod cursor;
+mod dbms_output;
+#[cfg(feature = "tower")]
+mod deadline;
+mod describe;
+mod direct_path;
+mod dynamic;
+mod easy_connect;
+mod error;
+mod failover;
+mod global_transaction;
+mod lob;
+mod long;
+mod metrics;
+mod migration;
+mod nls;
+mod non_blocking;
+#[cfg(feature = "rust-oracle")]
+mod oracle_params;
+mod plan_statistics;
+mod plsql;
+mod prepared_statement;
+mod query_log;
 mod raw;
+mod reconnect;
+#[cfg(feature = "arrow")]
+mod record_batch;
+mod returning;
 mod row;
+mod script;
+mod server_version;
+mod session_info;
+mod spans;
+mod statement_options;
+mod statement_timeout;
 mod stmt;
+mod test_transaction;
+mod timezone;
 mod transaction;
+mod type_map;
 
+/// A connection to an oracle database
+///
+/// Connections can be moved to another thread (e.g. by a connection pool),
+/// but not be used by several threads at once.
 pub struct OciConnection {
-    raw: Rc<raw::RawConnection>,
+    // replaced when reconnecting, c.f. `reconnect`
+    raw: RefCell<Arc<raw::RawConnection>>,
+    config: OciConnectionBuilder,
     transaction_manager: OCITransactionManager,
     statement_cache: StatementCache<Oracle, Statement>,
+    // the `ALTER SESSION` statements replayed when reconnecting
+    session_statements: RefCell<Vec<String>>,
+    query_logger: RefCell<Option<QueryLogger>>,
 }
 
-// This relies on the invariant that RawConnection or Statement are never
-// leaked. If a reference to one of those was held on a different thread, this
-// would not be thread safe.
-// Similar to diesel::sqlite::SqliteConnection;
-unsafe impl Send for OciConnection {}
-
+// the configuration prints the connection without its password
+impl fmt::Debug for OciConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OciConnection")
+            .field("config", &self.config)
+            .field("raw", &*self.raw())
+            .finish()
+    }
+}
 
 impl SimpleConnection for OciConnection {
+    /// Executes the statements of `query` one after another
+    ///
+    /// Statements are separated by `;`, PL/SQL blocks (`BEGIN`, `DECLARE`,
+    /// `CREATE PACKAGE`, `CREATE TRIGGER`, ...) contain `;` themselves and
+    /// are separated by a line containing a single `/` instead, c.f.
+    /// `run_script`. If a statement of several fails, the
+    /// error is a `ScriptError` containing its line.
     fn batch_execute(&self, query: &str) -> QueryResult<()> {
-        let stmt = try!(Statement::prepare(&self.raw, query));
-        try!(stmt.run());
+        let statements = script::split_script(query);
+        if statements.len() > 1 {
+            return self.run_script(query).map(|_| ());
+        }
+        let query = match statements.first() {
+            Some(statement) => &*statement.sql,
+            None => return Ok(()),
+        };
+        self.with_query_log(
+            |include_binds| describe_sql(query, include_binds),
+            || {
+                let mut stmt = try!(Statement::prepare(&self.raw(), query));
+                try!(stmt.run());
+                Ok(())
+            },
+        )?;
+        self.note_session_statement(query);
         Ok(())
     }
 }
@@ -50,20 +171,22 @@ impl Connection for OciConnection {
     /// should be a valid connection string for a given backend. See the
     /// documentation for the specific backend for specifics.
     fn establish(database_url: &str) -> ConnectionResult<Self> {
-        let r = try!(raw::RawConnection::establish(database_url));
-        let ret = OciConnection {
-            raw: Rc::new(r),
-            transaction_manager: OCITransactionManager::new(),
-            statement_cache: StatementCache::new(),
-        };
-        Ok(ret)
+        OciConnectionBuilder::from_url(database_url)?.establish()
     }
 
     #[doc(hidden)]
     fn execute(&self, query: &str) -> QueryResult<usize> {
-        let stmt = try!(Statement::prepare(&self.raw, query));
-        try!(stmt.run());
-        Ok(try!(stmt.get_affected_rows()))
+        let query = migration::translate_setup_statement(query).unwrap_or(query);
+        let affected_rows = self.with_query_log(
+            |include_binds| describe_sql(query, include_binds),
+            || {
+                let mut stmt = try!(Statement::prepare(&self.raw(), query));
+                try!(stmt.run());
+                stmt.get_affected_rows().map(row_count)
+            },
+        )?;
+        self.note_session_statement(query);
+        Ok(affected_rows)
     }
 
     #[doc(hidden)]
@@ -71,9 +194,14 @@ impl Connection for OciConnection {
     where
         T: QueryFragment<Self::Backend> + QueryId,
     {
-        let stmt = try!(self.prepare_query(source));
-        try!(stmt.run());
-        Ok(try!(stmt.get_affected_rows()))
+        self.with_query_log(
+            |include_binds| describe_query(source, include_binds),
+            || {
+                let mut stmt = try!(self.prepare_query(source));
+                try!(stmt.run());
+                stmt.get_affected_rows().map(row_count)
+            },
+        )
     }
 
     fn transaction_manager(&self) -> &Self::TransactionManager {
@@ -87,40 +215,186 @@ impl Connection for OciConnection {
         Self::Backend: HasSqlType<T::SqlType>,
         U: Queryable<T::SqlType, Self::Backend>,
     {
-        let stmt = self.prepare_query(&source.as_query())?;
-        let cursor: Cursor<T::SqlType, U> = stmt.run_with_cursor()?;
-        let mut ret = Vec::new();
-        for el in cursor {
-            ret.push(el?);
-        }
-        Ok(ret)
+        let query = source.as_query();
+        self.with_query_log(
+            |include_binds| describe_query(&query, include_binds),
+            || {
+                self.with_reconnect(|| {
+                    let mut stmt = self.prepare_query(&query)?;
+                    let cursor: Cursor<T::SqlType, U> = stmt.run_with_cursor()?;
+                    let mut ret = Vec::new();
+                    for el in cursor {
+                        ret.push(el?);
+                    }
+                    Ok(ret)
+                })
+            },
+        )
     }
 
-    fn query_by_name<T, U>(&self, _source: &T) -> QueryResult<Vec<U>>
+    fn query_by_name<T, U>(&self, source: &T) -> QueryResult<Vec<U>>
     where
         T: QueryFragment<Self::Backend> + QueryId,
         U: QueryableByName<Self::Backend>,
     {
-        unimplemented!()
+        self.with_query_log(
+            |include_binds| describe_query(source, include_binds),
+            || {
+                self.with_reconnect(|| {
+                    let mut stmt = self.prepare_query(source)?;
+                    let cursor = stmt.run_with_named_cursor::<U>()?;
+                    cursor.collect()
+                })
+            },
+        )
     }
 }
 
 impl OciConnection {
+    /// Configures a new connection, c.f. `OciConnectionBuilder`
+    pub fn builder() -> OciConnectionBuilder {
+        OciConnectionBuilder::new()
+    }
+
+    fn from_raw(raw: Arc<raw::RawConnection>, config: OciConnectionBuilder) -> Self {
+        OciConnection {
+            raw: RefCell::new(raw),
+            config,
+            transaction_manager: OCITransactionManager::new(),
+            statement_cache: StatementCache::new(),
+            session_statements: RefCell::new(Vec::new()),
+            query_logger: RefCell::new(None),
+        }
+    }
+
+    pub(crate) fn raw(&self) -> Ref<'_, Arc<raw::RawConnection>> {
+        self.raw.borrow()
+    }
+
+    /// Opens a new session with the configuration this connection was
+    /// established with
+    ///
+    /// Each thread needs its own connection, this allows worker threads to
+    /// get an equivalent connection without passing the configuration
+    /// around. Session state changed after establishing this connection
+    /// (e.g. by `ALTER SESSION`) is not copied.
+    pub fn duplicate(&self) -> ConnectionResult<OciConnection> {
+        self.config.establish()
+    }
+
+    /// Whether a fatal error (e.g. `ORA-03113`) occurred on this connection
+    ///
+    /// A broken connection refuses to execute any further statement, so it
+    /// should be discarded (e.g. evicted from a connection pool).
+    pub fn is_broken(&self) -> bool {
+        self.raw().is_broken()
+    }
+
+    /// Runs the given query with a scrollable cursor, which is passed to `f`
+    ///
+    /// The rows can be iterated as usual, `Cursor::seek` moves to any other
+    /// row of the result set without executing the query again.
+    ///
+    /// ```ignore
+    /// let last = conn.with_scrollable_cursor(users::table.order(users::id), |cursor| {
+    ///     cursor.seek(ScrollPosition::Last)
+    /// })?;
+    /// ```
+    pub fn with_scrollable_cursor<T, U, F, R>(&self, source: T, f: F) -> QueryResult<R>
+    where
+        T: AsQuery,
+        T::Query: QueryFragment<Oracle> + QueryId,
+        Oracle: HasSqlType<T::SqlType>,
+        U: Queryable<T::SqlType, Oracle>,
+        F: FnOnce(&mut Cursor<T::SqlType, U>) -> QueryResult<R>,
+    {
+        let query = source.as_query();
+        let mut stmt = self.prepare_query(&query)?;
+        stmt.set_scrollable(true);
+        let ret = stmt.run_with_cursor().and_then(|mut cursor| f(&mut cursor));
+        // the statement may be cached
+        stmt.set_scrollable(false);
+        ret
+    }
+
+    /// Calls `f` with each row of the result of `source`
+    ///
+    /// The rows borrow the buffers the values are fetched into, which are
+    /// reused for all rows, so values can be read without copying them
+    /// (e.g. by `OciRow::get_str`). Returns the number of rows.
+    ///
+    /// ```ignore
+    /// let mut total = 0;
+    /// conn.for_each_row(&users::table.select(users::name), |row| {
+    ///     total += row.get_str(0)?.map_or(0, str::len);
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn for_each_row<T, F>(&self, source: &T, mut f: F) -> QueryResult<usize>
+    where
+        T: QueryFragment<Oracle> + QueryId,
+        F: FnMut(&OciRow) -> QueryResult<()>,
+    {
+        let mut stmt = self.prepare_query(source)?;
+        let mut cursor = stmt.run_with_named_cursor::<()>()?;
+        let mut count = 0;
+        while let Some(row) = cursor.next_row()? {
+            f(&row)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Loads the result of `source` lazily, like `load_iter` of diesel 2
+    ///
+    /// The rows are fetched in batches of the prefetch size (c.f.
+    /// `OciConnectionBuilder::prefetch_rows`) while iterating, so the memory
+    /// used does not grow with the number of rows, e.g. when exporting a
+    /// large table. The statement is not cached.
+    ///
+    /// ```ignore
+    /// for user in conn.load_lazy::<_, User>(users::table)? {
+    ///     writer.serialize(user?)?;
+    /// }
+    /// ```
+    pub fn load_lazy<T, U>(&self, source: T) -> QueryResult<OwnedCursor<T::SqlType, U>>
+    where
+        T: AsQuery,
+        T::Query: QueryFragment<Oracle> + QueryId,
+        Oracle: HasSqlType<T::SqlType>,
+        U: Queryable<T::SqlType, Oracle>,
+    {
+        let query = source.as_query();
+        let mut query_builder = OciQueryBuilder::new();
+        self.with_sql_dialect(|| query.to_sql(&mut query_builder))?;
+        let mut statement = Statement::prepare(&self.raw(), &query_builder.finish())?;
+        bind_query(&mut statement, &query)?;
+        statement.into_cursor()
+    }
+
     fn prepare_query<T: QueryFragment<Oracle> + QueryId>(
         &self,
         source: &T,
     ) -> QueryResult<MaybeCached<Statement>> {
-        let mut statement = try!(self.cached_prepared_statement(source));
+        let mut statement = try!(self.with_sql_dialect(|| self.cached_prepared_statement(source)));
+        try!(bind_query(&mut statement, source));
+        Ok(statement)
+    }
 
-        let mut bind_collector = RawBytesBindCollector::<Oracle>::new();
-        try!(source.collect_binds(&mut bind_collector, &()));
-        let metadata = bind_collector.metadata;
-        let binds = bind_collector.binds;
+    /// Executes a statement with binds collected outside of a
+    /// `QueryFragment`, returning the number of affected rows
+    pub(crate) fn execute_with_binds(
+        &self,
+        sql: &str,
+        metadata: Vec<OCIDataType>,
+        binds: Vec<Option<Vec<u8>>>,
+    ) -> QueryResult<u64> {
+        let mut stmt = Statement::prepare(&self.raw(), sql)?;
         for (tpe, value) in metadata.into_iter().zip(binds) {
-            try!(statement.bind(tpe, value));
+            stmt.bind(tpe, value)?;
         }
-
-        Ok(statement)
+        stmt.run()?;
+        stmt.get_affected_rows()
     }
 
     fn cached_prepared_statement<T: QueryFragment<Oracle> + QueryId>(
@@ -128,6 +402,45 @@ impl OciConnection {
         source: &T,
     ) -> QueryResult<MaybeCached<Statement>> {
         self.statement_cache
-            .cached_statement(source, &[], |sql| Statement::prepare(&self.raw, sql))
+            .cached_statement(source, &[], |sql| Statement::prepare(&self.raw(), sql))
+    }
+}
+
+// diesel counts rows as `usize`, which holds every count on 64 bit targets
+fn row_count(rows: u64) -> usize {
+    usize::try_from(rows).unwrap_or(usize::MAX)
+}
+
+// binds the values collected from `source` to the statement prepared for it
+fn bind_query<T: QueryFragment<Oracle>>(statement: &mut Statement, source: &T) -> QueryResult<()> {
+    // cached statements still hold the values of their last execution
+    statement.reset_binds();
+    let mut bind_collector = RawBytesBindCollector::<Oracle>::new();
+    source.collect_binds(&mut bind_collector, &())?;
+    let metadata = bind_collector.metadata;
+    let binds = bind_collector.binds;
+    for (tpe, value) in metadata.into_iter().zip(binds) {
+        statement.bind(tpe, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{row_count, CancelToken, OciConnection, RefCursor};
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn check_connection_is_send() {
+        assert_send::<OciConnection>();
+        assert_send::<RefCursor>();
+        assert_send::<CancelToken>();
+    }
+
+    #[test]
+    fn check_row_count() {
+        assert_eq!(row_count(42), 42);
+        assert_eq!(row_count(u64::MAX), usize::MAX);
     }
 }
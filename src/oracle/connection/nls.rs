@@ -0,0 +1,63 @@
+use diesel::dsl::sql;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use diesel::sql_types::{Nullable, Text};
+use diesel::{Connection, RunQueryDsl};
+
+use super::OciConnection;
+
+impl OciConnection {
+    /// Changes a NLS parameter of the current session, e.g.
+    /// `set_nls_parameter("NLS_DATE_FORMAT", "YYYY-MM-DD")`
+    pub fn set_nls_parameter(&self, name: &str, value: &str) -> QueryResult<()> {
+        let sql = alter_session_nls(name, value).map_err(|message| {
+            Error::DatabaseError(DatabaseErrorKind::__Unknown, Box::new(message))
+        })?;
+        Connection::execute(self, &sql).map(|_| ())
+    }
+
+    /// The current value of a NLS parameter of the session, `None` for
+    /// unknown parameters
+    pub fn nls_parameter(&self, name: &str) -> QueryResult<Option<String>> {
+        let value =
+            sql::<Nullable<Text>>("SELECT value FROM nls_session_parameters WHERE parameter = ")
+                .bind::<Text, _>(name.to_uppercase())
+                .get_results::<Option<String>>(self)?;
+        Ok(value.into_iter().next().and_then(|v| v))
+    }
+}
+
+// the statement setting a NLS parameter, which are identifiers like
+// `NLS_DATE_FORMAT`
+pub(super) fn alter_session_nls(name: &str, value: &str) -> Result<String, String> {
+    let valid_name = name.len() > "NLS_".len()
+        && name[.."NLS_".len()].eq_ignore_ascii_case("NLS_")
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(format!("Invalid NLS parameter {}", name));
+    }
+    Ok(format!(
+        "ALTER SESSION SET {} = '{}'",
+        name.to_uppercase(),
+        value.replace('\'', "''")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::alter_session_nls;
+
+    #[test]
+    fn check_alter_session_nls() {
+        assert_eq!(
+            alter_session_nls("nls_date_format", "YYYY-MM-DD").unwrap(),
+            "ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY-MM-DD'"
+        );
+        assert_eq!(
+            alter_session_nls("NLS_DATE_FORMAT", "DD \"o'clock\"").unwrap(),
+            "ALTER SESSION SET NLS_DATE_FORMAT = 'DD \"o''clock\"'"
+        );
+        assert!(alter_session_nls("TIME_ZONE", "UTC").is_err());
+        assert!(alter_session_nls("NLS_", "x").is_err());
+        assert!(alter_session_nls("NLS_SORT = 'BINARY', SQL_TRACE", "TRUE").is_err());
+    }
+}
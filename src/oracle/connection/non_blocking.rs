@@ -0,0 +1,167 @@
+use std::fmt;
+use std::task::Poll;
+
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use oci_sys as ffi;
+
+use super::cursor::{self, Field};
+use super::stmt::Statement;
+use super::OciConnection;
+
+// the values of a row, `None` for `NULL`
+type RawRow<'a> = Vec<Option<&'a [u8]>>;
+
+/// A statement executed in non-blocking mode, c.f.
+/// `OciConnection::execute_non_blocking`
+///
+/// Each `poll_*` call sends or continues a round trip to the database and
+/// returns `Poll::Pending` instead of waiting for the response. Polling again
+/// (e.g. from a timer of a custom event loop) continues the call, the
+/// connection does not notify the caller when the response arrives.
+///
+/// While the statement exists the connection stays in non-blocking mode,
+/// other statements of the connection fail with `ORA-03123: operation would
+/// block` until it finished. Dropping the statement before it finished
+/// aborts the running call.
+pub struct NonBlockingStatement<'a> {
+    conn: &'a OciConnection,
+    stmt: Statement,
+    // the defined columns once the statement was executed, empty if there
+    // is no result set
+    fields: Option<Vec<Field>>,
+    // whether the last call returned `OCI_STILL_EXECUTING`
+    in_call: bool,
+}
+
+impl<'a> NonBlockingStatement<'a> {
+    /// Polls the execution of the statement, returning the number of
+    /// affected rows
+    pub fn poll_execute(&mut self) -> Poll<QueryResult<u64>> {
+        match self.poll_executed() {
+            Poll::Ready(Ok(())) => Poll::Ready(self.stmt.get_affected_rows()),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Polls the next row of a query, executing the statement first if
+    /// needed
+    ///
+    /// Returns `None` if there are no more rows. Values of string columns
+    /// are cut at the terminating null byte.
+    pub fn poll_next_row(&mut self) -> Poll<QueryResult<Option<RawRow<'_>>>> {
+        match self.poll_executed() {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+        match self.fields {
+            Some(ref fields) if !fields.is_empty() => {}
+            _ => return Poll::Ready(Ok(None)),
+        }
+        let status = cursor::fetch_raw(&self.stmt);
+        match self.check(status) {
+            Poll::Ready(Ok(())) if status as u32 == ffi::OCI_NO_DATA => Poll::Ready(Ok(None)),
+            Poll::Ready(Ok(())) => {
+                let fields = self.fields.as_mut().map_or(&mut [][..], |f| &mut f[..]);
+                if let Err(e) = cursor::finish_row(&self.stmt, fields) {
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Ready(Ok(Some(cursor::raw_values(fields))))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_executed(&mut self) -> Poll<QueryResult<()>> {
+        if self.fields.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+        let status = self.stmt.execute_raw();
+        match self.check(status) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+        // defining the columns is local to the client, no need to poll
+        let fields = if self.stmt.is_select() {
+            self.stmt.define_all_columns()
+        } else {
+            Ok(Vec::new())
+        };
+        Poll::Ready(fields.map(|f| self.fields = Some(f)))
+    }
+
+    // `OCI_STILL_EXECUTING` is pending, all other statuses are checked
+    fn check(&mut self, status: i32) -> Poll<QueryResult<()>> {
+        self.in_call = status == ffi::OCI_STILL_EXECUTING;
+        if self.in_call {
+            Poll::Pending
+        } else {
+            Poll::Ready(self.stmt.check(status))
+        }
+    }
+}
+
+impl<'a> Drop for NonBlockingStatement<'a> {
+    fn drop(&mut self) {
+        let raw = self.conn.raw();
+        if self.in_call {
+            if let Err(e) = raw.abort_non_blocking_call() {
+                debug!("Could not abort non-blocking call: {:?}", e);
+                raw.mark_broken();
+            }
+        }
+        if let Err(e) = raw.set_non_blocking(false) {
+            debug!("Could not leave non-blocking mode: {:?}", e);
+            raw.mark_broken();
+        }
+    }
+}
+
+impl<'a> fmt::Debug for NonBlockingStatement<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NonBlockingStatement")
+            .field("executed", &self.fields.is_some())
+            .field("in_call", &self.in_call)
+            .finish()
+    }
+}
+
+impl OciConnection {
+    /// Starts executing `sql` in non-blocking mode, c.f.
+    /// `NonBlockingStatement`
+    ///
+    /// Preparing the statement does not need a round trip, so this does not
+    /// block. Only one non-blocking statement can exist per connection.
+    ///
+    /// ```ignore
+    /// let mut stmt = conn.execute_non_blocking("SELECT id FROM users")?;
+    /// loop {
+    ///     match stmt.poll_next_row() {
+    ///         Poll::Ready(Ok(Some(row))) => println!("{:?}", row),
+    ///         Poll::Ready(Ok(None)) => break,
+    ///         Poll::Ready(Err(e)) => return Err(e),
+    ///         Poll::Pending => event_loop.run_other_tasks(),
+    ///     }
+    /// }
+    /// ```
+    pub fn execute_non_blocking(&self, sql: &str) -> QueryResult<NonBlockingStatement<'_>> {
+        let mut stmt = Statement::prepare(&self.raw(), sql)?;
+        stmt.prepare_execution()?;
+        if self.raw().set_non_blocking(true)? {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(String::from(
+                    "Another non-blocking statement is executed on this connection",
+                )),
+            ));
+        }
+        Ok(NonBlockingStatement {
+            conn: self,
+            stmt,
+            fields: None,
+            in_call: false,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use rust_oracle::sql_type::{OracleType, SqlValue, ToSql};
+use rust_oracle::{Connection, Result};
+
+use super::dynamic::DynamicValue;
+
+/// Binds a fetched value to a statement of the rust-oracle crate, `Null`
+/// is bound as `VARCHAR2` `NULL`
+impl ToSql for DynamicValue {
+    fn oratype(&self, conn: &Connection) -> Result<OracleType> {
+        match *self {
+            DynamicValue::Null => Ok(OracleType::Varchar2(1)),
+            DynamicValue::Number(ref n) => n.oratype(conn),
+            DynamicValue::Float(ref f) => f.oratype(conn),
+            DynamicValue::String(ref s) => s.oratype(conn),
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::Date(ref t) | DynamicValue::Timestamp(ref t) => t.oratype(conn),
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::TimestampTz(ref t) => t.oratype(conn),
+            DynamicValue::Raw(ref bytes) => bytes.oratype(conn),
+        }
+    }
+
+    fn to_sql(&self, val: &mut SqlValue) -> Result<()> {
+        match *self {
+            DynamicValue::Null => val.set_null(),
+            DynamicValue::Number(ref n) => n.to_sql(val),
+            DynamicValue::Float(ref f) => f.to_sql(val),
+            DynamicValue::String(ref s) => s.to_sql(val),
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::Date(ref t) | DynamicValue::Timestamp(ref t) => t.to_sql(val),
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::TimestampTz(ref t) => t.to_sql(val),
+            DynamicValue::Raw(ref bytes) => bytes.to_sql(val),
+        }
+    }
+}
+
+/// A row returned by `OciConnection::query_dynamic` as the named parameters
+/// of a statement of the rust-oracle crate, e.g. to copy rows to another
+/// database without fetching them again
+///
+/// ```ignore
+/// for row in conn.query_dynamic("SELECT id, name FROM users")? {
+///     other.execute_named("INSERT INTO users VALUES (:ID, :NAME)", &named_params(&row))?;
+/// }
+/// ```
+pub fn named_params(row: &[(String, DynamicValue)]) -> Vec<(&str, &dyn ToSql)> {
+    row.iter()
+        .map(|(name, value)| (&name[..], value as &dyn ToSql))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::named_params;
+    use oracle::connection::DynamicValue;
+
+    #[test]
+    fn check_named_params() {
+        let row = vec![
+            ("ID".to_owned(), DynamicValue::Number(1)),
+            ("NAME".to_owned(), DynamicValue::Null),
+        ];
+        let params = named_params(&row);
+        assert_eq!(
+            params.iter().map(|p| p.0).collect::<Vec<_>>(),
+            vec!["ID", "NAME"]
+        );
+    }
+}
@@ -7,4 +7,10 @@ impl OracleValue {
     pub fn new(bytes: &[u8]) -> &Self {
         unsafe { &*(bytes as *const [u8] as *const Self) }
     }
+
+    /// The raw bytes of the value, string values include the terminating
+    /// null byte
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
 }
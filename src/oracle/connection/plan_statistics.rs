@@ -0,0 +1,137 @@
+use diesel::dsl::sql;
+use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use diesel::{Connection, RunQueryDsl};
+
+use super::OciConnection;
+
+/// A step of the execution plan of a statement with the estimated and the
+/// actual number of rows, c.f. `OciConnection::last_plan_statistics`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub id: i32,
+    pub depth: i32,
+    /// e.g. `TABLE ACCESS`
+    pub operation: String,
+    /// e.g. `FULL`
+    pub options: Option<String>,
+    pub object_name: Option<String>,
+    /// The rows the optimizer expected for a single execution of this step
+    pub estimated_rows: Option<i64>,
+    /// How often this step was executed
+    pub starts: Option<i64>,
+    /// The rows actually returned by all executions of this step
+    pub actual_rows: Option<i64>,
+}
+
+impl PlanStep {
+    /// The factor by which the actual rows differ from the estimate, `1.0`
+    /// means the estimate was exact
+    ///
+    /// Values far from `1.0` (in both directions) point to stale
+    /// statistics or a bad cardinality estimate.
+    pub fn estimate_ratio(&self) -> Option<f64> {
+        let estimated = self.estimated_rows? * self.starts?;
+        let actual = self.actual_rows?;
+        // a step returning no rows at all is estimated as one row by oracle
+        Some(actual.max(1) as f64 / estimated.max(1) as f64)
+    }
+}
+
+// The statistics of the previous statement of this session, the statement
+// querying them is the current one
+const PLAN_STATISTICS_QUERY: &str = "SELECT CAST(p.id AS NUMBER(10)), \
+     CAST(p.depth AS NUMBER(10)), p.operation, p.options, p.object_name, \
+     CAST(p.cardinality AS NUMBER(19)), CAST(p.last_starts AS NUMBER(19)), \
+     CAST(p.last_output_rows AS NUMBER(19)) \
+     FROM v$sql_plan_statistics_all p \
+     JOIN v$session s \
+     ON p.sql_id = s.prev_sql_id AND p.child_number = s.prev_child_number \
+     WHERE s.sid = SYS_CONTEXT('USERENV', 'SID') \
+     ORDER BY p.id";
+
+impl OciConnection {
+    /// Enables (`STATISTICS_LEVEL = ALL`) or disables collecting the row
+    /// counts of each plan step for all statements of this session
+    ///
+    /// Alternatively add the `GATHER_PLAN_STATISTICS` hint to single
+    /// statements.
+    pub fn set_gather_plan_statistics(&self, enabled: bool) -> QueryResult<()> {
+        let level = if enabled { "ALL" } else { "TYPICAL" };
+        Connection::execute(
+            self,
+            &format!("ALTER SESSION SET STATISTICS_LEVEL = {}", level),
+        )
+        .map(|_| ())
+    }
+
+    /// Returns the estimated and actual row counts of each plan step of the
+    /// previously executed statement
+    ///
+    /// Call this directly after all rows of the statement were loaded. The
+    /// actual row counts are only collected if plan statistics are
+    /// enabled, c.f. `set_gather_plan_statistics`, otherwise they are
+    /// `None`. Reading the statistics requires the `SELECT` privilege on
+    /// `V$SESSION` and `V$SQL_PLAN_STATISTICS_ALL`.
+    pub fn last_plan_statistics(&self) -> QueryResult<Vec<PlanStep>> {
+        let steps = sql::<(
+            Integer,
+            Integer,
+            Text,
+            Nullable<Text>,
+            Nullable<Text>,
+            Nullable<BigInt>,
+            Nullable<BigInt>,
+            Nullable<BigInt>,
+        )>(PLAN_STATISTICS_QUERY)
+        .load::<(
+            i32,
+            i32,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<i64>,
+            Option<i64>,
+            Option<i64>,
+        )>(self)?;
+        Ok(steps
+            .into_iter()
+            .map(|s| PlanStep {
+                id: s.0,
+                depth: s.1,
+                operation: s.2,
+                options: s.3,
+                object_name: s.4,
+                estimated_rows: s.5,
+                starts: s.6,
+                actual_rows: s.7,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlanStep;
+
+    fn step(estimated_rows: i64, starts: i64, actual_rows: Option<i64>) -> PlanStep {
+        PlanStep {
+            id: 1,
+            depth: 1,
+            operation: "TABLE ACCESS".into(),
+            options: Some("FULL".into()),
+            object_name: Some("USERS".into()),
+            estimated_rows: Some(estimated_rows),
+            starts: Some(starts),
+            actual_rows,
+        }
+    }
+
+    #[test]
+    fn check_estimate_ratio() {
+        assert_eq!(step(10, 1, Some(10)).estimate_ratio(), Some(1.0));
+        assert_eq!(step(10, 3, Some(300)).estimate_ratio(), Some(10.0));
+        assert_eq!(step(100, 1, Some(0)).estimate_ratio(), Some(0.01));
+        assert_eq!(step(10, 1, None).estimate_ratio(), None);
+    }
+}
@@ -0,0 +1,522 @@
+use diesel::deserialize::{FromSql, Queryable};
+use diesel::result::{Error, QueryResult};
+use diesel::serialize::{IsNull, Output, ToSql};
+use diesel::sql_types::{
+    BigInt, Binary, Date, Double, HasSqlType, Integer, NotNull, Nullable, SmallInt, Text, Timestamp,
+};
+
+use super::stmt::Statement;
+use super::{BindValue, Cursor, Lob, OciConnection, OracleValue, OwnedCursor};
+use oracle::backend::Oracle;
+use oracle::types::OCIDataType;
+
+/// A parameter of a PL/SQL block executed by `OciConnection::execute_plsql`
+///
+/// Parameters are bound by position, i.e. the first one to `:1`, the second
+/// one to `:2`, ...
+pub struct PlSqlBind {
+    tpe: OCIDataType,
+    value: Option<Vec<u8>>,
+    // the size of the buffer for values returned by oracle, `None` for
+    // `IN` parameters
+    capacity: Option<usize>,
+    is_ref_cursor: bool,
+    cursor: Option<RefCursor>,
+    // boxed, as the locator is bound by its address
+    lob: Option<Box<Lob>>,
+    // the maximal number of elements of an array parameter and the
+    // returned elements, c.f. `output_array`
+    array_len: Option<usize>,
+    elements: Vec<Option<Vec<u8>>>,
+}
+
+impl PlSqlBind {
+    /// An `IN` parameter
+    pub fn input<ST, T>(value: &T) -> QueryResult<Self>
+    where
+        Oracle: HasSqlType<ST>,
+        T: ToSql<ST, Oracle> + ?Sized,
+    {
+        Ok(PlSqlBind {
+            tpe: <Oracle as HasSqlType<ST>>::metadata(&()),
+            value: serialize(value)?,
+            capacity: None,
+            is_ref_cursor: false,
+            cursor: None,
+            lob: None,
+            array_len: None,
+            elements: Vec::new(),
+        })
+    }
+
+    /// An `IN` parameter bound in the representation of `value`, e.g. a
+    /// `NULL` of a specific type
+    ///
+    /// ```ignore
+    /// let binds = &mut [PlSqlBind::value(BindValue::Null(OCIDataType::InternDate))];
+    /// ```
+    pub fn value(value: BindValue) -> Self {
+        if let BindValue::Lob(lob) = value {
+            return Self::lob(lob);
+        }
+        let (tpe, value) = value.into_raw();
+        PlSqlBind {
+            tpe,
+            value,
+            capacity: None,
+            is_ref_cursor: false,
+            cursor: None,
+            lob: None,
+            array_len: None,
+            elements: Vec::new(),
+        }
+    }
+
+    /// An `OUT` parameter returning at most `capacity` bytes
+    ///
+    /// The capacity has to match the size diesel uses for `ST`, e.g. `4`
+    /// for `Integer` or `8` for `BigInt`. For `Text` it is the maximal
+    /// length of the returned string in bytes.
+    pub fn output<ST>(capacity: usize) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+    {
+        PlSqlBind {
+            tpe: <Oracle as HasSqlType<ST>>::metadata(&()),
+            value: None,
+            capacity: Some(capacity),
+            is_ref_cursor: false,
+            cursor: None,
+            lob: None,
+            array_len: None,
+            elements: Vec::new(),
+        }
+    }
+
+    /// An `OUT` parameter of a PL/SQL associative array (`TABLE OF ...
+    /// INDEX BY BINARY_INTEGER`) returning at most `len` elements of at
+    /// most `capacity` bytes, c.f. `get_array`
+    pub fn output_array<ST>(capacity: usize, len: usize) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+    {
+        let mut bind = Self::output::<ST>(capacity);
+        bind.array_len = Some(len);
+        bind
+    }
+
+    /// A `SYS_REFCURSOR` `OUT` parameter, c.f. `take_cursor`
+    pub fn ref_cursor() -> Self {
+        PlSqlBind {
+            // not used for REF CURSOR binds
+            tpe: OCIDataType::Binary,
+            value: None,
+            capacity: None,
+            is_ref_cursor: true,
+            cursor: None,
+            lob: None,
+            array_len: None,
+            elements: Vec::new(),
+        }
+    }
+
+    /// An `IN` parameter passing `lob`, e.g. a temporary LOB created by
+    /// `OciConnection::create_temporary_lob`
+    ///
+    /// The parameter owns the LOB, a temporary LOB is freed when the
+    /// parameter is dropped.
+    pub fn lob(lob: Lob) -> Self {
+        PlSqlBind {
+            // not used for LOB binds
+            tpe: OCIDataType::Binary,
+            value: None,
+            capacity: None,
+            is_ref_cursor: false,
+            cursor: None,
+            lob: Some(Box::new(lob)),
+            array_len: None,
+            elements: Vec::new(),
+        }
+    }
+
+    /// An `IN OUT` parameter returning at most `capacity` bytes
+    pub fn in_out<ST, T>(value: &T, capacity: usize) -> QueryResult<Self>
+    where
+        Oracle: HasSqlType<ST>,
+        T: ToSql<ST, Oracle> + ?Sized,
+    {
+        let mut bind = Self::input::<ST, T>(value)?;
+        bind.capacity = Some(capacity);
+        Ok(bind)
+    }
+
+    /// Whether the (returned) value is `NULL`
+    pub fn is_null(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Deserializes the value of this parameter, for `OUT` and `IN OUT`
+    /// parameters this is the value returned by the PL/SQL block
+    pub fn get<ST, T>(&self) -> QueryResult<T>
+    where
+        T: FromSql<ST, Oracle>,
+    {
+        deserialize_out(self.tpe, self.value.as_ref().map(|v| &v[..]))
+    }
+
+    /// Deserializes the elements returned for an array parameter, c.f.
+    /// `output_array`
+    pub fn get_array<ST, T>(&self) -> QueryResult<Vec<T>>
+    where
+        T: FromSql<ST, Oracle>,
+    {
+        self.elements
+            .iter()
+            .map(|v| deserialize_out(self.tpe, v.as_ref().map(|v| &v[..])))
+            .collect()
+    }
+
+    /// Takes the result set returned for a `SYS_REFCURSOR` parameter
+    ///
+    /// Returns `None` if this is not a REF CURSOR parameter, the block was
+    /// not executed yet or the cursor was already taken.
+    pub fn take_cursor(&mut self) -> Option<RefCursor> {
+        self.cursor.take()
+    }
+}
+
+/// Deserializes the value oracle returned for an `OUT` bind of type `tpe`
+pub(super) fn deserialize_out<ST, T>(tpe: OCIDataType, value: Option<&[u8]>) -> QueryResult<T>
+where
+    T: FromSql<ST, Oracle>,
+{
+    let value = out_bytes(tpe, value);
+    T::from_sql(value.as_ref().map(|v| OracleValue::new(v))).map_err(Error::DeserializationError)
+}
+
+/// The bytes oracle returned for an `OUT` bind of type `tpe`, as expected
+/// by the `FromSql` impls of `Oracle`
+pub(super) fn out_bytes(tpe: OCIDataType, value: Option<&[u8]>) -> Option<Vec<u8>> {
+    value.map(|v| {
+        let mut v = v.to_vec();
+        // strings are returned without the trailing nul byte expected by
+        // `FromSql<Text, Oracle>`
+        if tpe == OCIDataType::Char || tpe == OCIDataType::NationalChar {
+            v.push(0);
+        }
+        v
+    })
+}
+
+/// A result set returned by a `SYS_REFCURSOR` parameter
+pub struct RefCursor {
+    stmt: Statement,
+}
+
+impl RefCursor {
+    /// Iterates over the rows of the result set
+    pub fn cursor<ST, T>(&mut self) -> QueryResult<Cursor<'_, ST, T>> {
+        self.stmt.run_with_cursor()
+    }
+
+    /// Iterates over the rows of the result set with a cursor owning it,
+    /// which can be returned from the function executing the block
+    pub fn into_cursor<ST, T>(self) -> QueryResult<OwnedCursor<ST, T>> {
+        self.stmt.into_cursor()
+    }
+
+    /// Loads all rows of the result set
+    pub fn load<ST, T>(mut self) -> QueryResult<Vec<T>>
+    where
+        Oracle: HasSqlType<ST>,
+        T: Queryable<ST, Oracle>,
+    {
+        self.cursor::<ST, T>()?.collect()
+    }
+}
+
+fn serialize<ST, T>(value: &T) -> QueryResult<Option<Vec<u8>>>
+where
+    T: ToSql<ST, Oracle> + ?Sized,
+{
+    let mut out = Output::new(Vec::new(), &());
+    match value.to_sql(&mut out).map_err(Error::SerializationError)? {
+        IsNull::No => Ok(Some(out.into_inner())),
+        IsNull::Yes => Ok(None),
+    }
+}
+
+impl OciConnection {
+    /// Executes an anonymous PL/SQL block, e.g. `BEGIN my_proc(:1, :2); END;`
+    ///
+    /// The values returned for `OUT` and `IN OUT` parameters are written
+    /// back to `binds`.
+    ///
+    /// ```ignore
+    /// let mut binds = [
+    ///     PlSqlBind::input::<Integer, _>(&21)?,
+    ///     PlSqlBind::output::<Integer>(4),
+    /// ];
+    /// conn.execute_plsql("BEGIN :2 := :1 * 2; END;", &mut binds)?;
+    /// assert_eq!(binds[1].get::<Integer, i32>()?, 42);
+    /// ```
+    pub fn execute_plsql(&self, block: &str, binds: &mut [PlSqlBind]) -> QueryResult<()> {
+        let mut stmt = Statement::prepare(&self.raw(), block)?;
+        let mut out_binds = Vec::new();
+        let mut out_arrays = Vec::new();
+        let mut cursor_indices = Vec::new();
+        for (i, bind) in binds.iter_mut().enumerate() {
+            if bind.is_ref_cursor {
+                cursor_indices.push((i, stmt.bind_ref_cursor()?));
+                continue;
+            }
+            if let Some(ref mut lob) = bind.lob {
+                stmt.bind_lob_locator(lob)?;
+                continue;
+            }
+            if let (Some(len), Some(capacity)) = (bind.array_len, bind.capacity) {
+                out_arrays.push((i, stmt.bind_out_array(bind.tpe, capacity, len)?));
+                continue;
+            }
+            match bind.capacity {
+                Some(capacity) => {
+                    let out = stmt.bind_in_out(bind.tpe, bind.value.take(), capacity)?;
+                    out_binds.push((i, out));
+                }
+                None => stmt.bind(bind.tpe, bind.value.clone())?,
+            }
+        }
+        stmt.run()?;
+        for (i, out) in out_binds {
+            binds[i].value = out.value(&stmt).map(|v| v.to_vec());
+        }
+        for (i, out) in out_arrays {
+            binds[i].elements = out
+                .values(&stmt)
+                .into_iter()
+                .map(|v| v.map(|v| v.to_vec()))
+                .collect();
+        }
+        for (i, index) in cursor_indices {
+            binds[i].cursor = Some(RefCursor {
+                stmt: stmt.take_ref_cursor(index),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Sql types which can be returned by an `OUT` parameter of a procedure
+/// called with `OciConnection::call`
+pub trait OutParamType {
+    /// The size of the buffer receiving the returned value
+    fn capacity() -> usize;
+}
+
+macro_rules! out_param_type {
+    ($($ty:ty => $capacity:expr,)*) => {
+        $(
+            impl OutParamType for $ty {
+                fn capacity() -> usize {
+                    $capacity
+                }
+            }
+        )*
+    };
+}
+
+out_param_type! {
+    SmallInt => 2,
+    Integer => 4,
+    BigInt => 8,
+    Double => 8,
+    // the maximal size of a PL/SQL VARCHAR2/RAW
+    Text => 32767,
+    Binary => 32767,
+    Date => 7,
+    Timestamp => 7,
+}
+
+impl<ST: OutParamType + NotNull> OutParamType for Nullable<ST> {
+    fn capacity() -> usize {
+        ST::capacity()
+    }
+}
+
+/// A call of a stored procedure, c.f. `OciConnection::call`
+pub struct Call<'a> {
+    conn: &'a OciConnection,
+    procedure: String,
+    binds: Vec<PlSqlBind>,
+    error: Option<Error>,
+}
+
+impl<'a> Call<'a> {
+    fn push(mut self, bind: QueryResult<PlSqlBind>) -> Self {
+        match bind {
+            Ok(bind) => self.binds.push(bind),
+            Err(e) => {
+                if self.error.is_none() {
+                    self.error = Some(e);
+                }
+            }
+        }
+        self
+    }
+
+    /// Adds an `IN` parameter
+    pub fn param<ST, T>(self, value: &T) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+        T: ToSql<ST, Oracle> + ?Sized,
+    {
+        let bind = PlSqlBind::input::<ST, T>(value);
+        self.push(bind)
+    }
+
+    /// Adds an `OUT` parameter
+    pub fn out_param<ST>(self) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+        ST: OutParamType,
+    {
+        let bind = PlSqlBind::output::<ST>(ST::capacity());
+        self.push(Ok(bind))
+    }
+
+    /// Adds an `IN OUT` parameter
+    pub fn in_out_param<ST, T>(self, value: &T) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+        ST: OutParamType,
+        T: ToSql<ST, Oracle> + ?Sized,
+    {
+        let bind = PlSqlBind::in_out::<ST, T>(value, ST::capacity());
+        self.push(bind)
+    }
+
+    /// Adds an `IN` parameter passing `lob`, which is freed with the call if
+    /// it is temporary
+    ///
+    /// ```ignore
+    /// let mut document = conn.create_temporary_lob(LobType::Clob)?;
+    /// document.append(xml.as_bytes())?;
+    /// conn.call("documents_api.import").lob_param(document).execute()?;
+    /// ```
+    pub fn lob_param(self, lob: Lob) -> Self {
+        self.push(Ok(PlSqlBind::lob(lob)))
+    }
+
+    /// Adds a `SYS_REFCURSOR` `OUT` parameter
+    pub fn out_cursor(self) -> Self {
+        self.push(Ok(PlSqlBind::ref_cursor()))
+    }
+
+    /// The PL/SQL block executed for this call
+    pub fn sql(&self) -> String {
+        call_block(&self.procedure, self.binds.len())
+    }
+
+    /// Executes the procedure
+    pub fn execute(mut self) -> QueryResult<CallResult> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let sql = self.sql();
+        self.conn.execute_plsql(&sql, &mut self.binds)?;
+        Ok(CallResult { binds: self.binds })
+    }
+}
+
+fn call_block(procedure: &str, param_count: usize) -> String {
+    let params = (1..=param_count)
+        .map(|i| format!(":{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("BEGIN {}({}); END;", procedure, params)
+}
+
+/// The parameters of an executed procedure call
+pub struct CallResult {
+    binds: Vec<PlSqlBind>,
+}
+
+impl CallResult {
+    /// Deserializes the parameter at `index` (starting at `0`, counting all
+    /// parameters), for `OUT` and `IN OUT` parameters this is the value
+    /// returned by the procedure
+    pub fn get<ST, T>(&self, index: usize) -> QueryResult<T>
+    where
+        T: FromSql<ST, Oracle>,
+    {
+        match self.binds.get(index) {
+            Some(bind) => bind.get::<ST, T>(),
+            None => Err(Error::DeserializationError(
+                format!("there is no parameter with index {}", index).into(),
+            )),
+        }
+    }
+
+    /// Takes the result set returned for the `SYS_REFCURSOR` parameter at
+    /// `index`
+    pub fn take_cursor(&mut self, index: usize) -> QueryResult<RefCursor> {
+        self.binds
+            .get_mut(index)
+            .and_then(PlSqlBind::take_cursor)
+            .ok_or_else(|| {
+                Error::DeserializationError(
+                    format!("there is no REF CURSOR with index {}", index).into(),
+                )
+            })
+    }
+}
+
+impl OciConnection {
+    /// Calls the stored procedure `procedure`, e.g. `my_package.my_proc`
+    ///
+    /// The name is inserted verbatim into the executed PL/SQL block, so it
+    /// must not come from untrusted input.
+    ///
+    /// ```ignore
+    /// let result = conn
+    ///     .call("my_package.add")
+    ///     .param::<Integer, _>(&20)
+    ///     .param::<Integer, _>(&22)
+    ///     .out_param::<BigInt>()
+    ///     .execute()?;
+    /// assert_eq!(result.get::<BigInt, i64>(2)?, 42);
+    /// ```
+    pub fn call(&self, procedure: &str) -> Call<'_> {
+        Call {
+            conn: self,
+            procedure: procedure.to_owned(),
+            binds: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{call_block, PlSqlBind};
+    use diesel::sql_types::{Integer, Nullable, Text};
+
+    #[test]
+    fn check_bind_roundtrip() {
+        let bind = PlSqlBind::input::<Integer, _>(&42).unwrap();
+        assert_eq!(bind.get::<Integer, i32>().unwrap(), 42);
+
+        let bind = PlSqlBind::input::<Text, _>("diesel").unwrap();
+        assert_eq!(bind.get::<Text, String>().unwrap(), "diesel");
+
+        let bind = PlSqlBind::output::<Integer>(4);
+        assert!(bind.is_null());
+        assert_eq!(bind.get::<Nullable<Integer>, Option<i32>>().unwrap(), None);
+    }
+
+    #[test]
+    fn check_call_block() {
+        assert_eq!(call_block("pkg.proc", 0), "BEGIN pkg.proc(); END;");
+        assert_eq!(call_block("pkg.proc", 2), "BEGIN pkg.proc(:1, :2); END;");
+    }
+}
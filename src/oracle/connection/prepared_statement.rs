@@ -0,0 +1,85 @@
+use diesel::result::QueryResult;
+
+use super::bind_value::BindValue;
+use super::cursor::Cursor;
+use super::stmt::Statement;
+use super::OciConnection;
+
+/// The result of `PreparedStatement::execute`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecResult {
+    /// The rows affected by a DML statement, `0` for other statements
+    pub affected_rows: u64,
+}
+
+/// A statement prepared by `OciConnection::prepare`, which can be executed
+/// repeatedly with new values
+///
+/// The handle of the statement is released once this is dropped, all
+/// values bound for an execution are dropped with the next execution.
+pub struct PreparedStatement<'a> {
+    // the statement must not outlive the connection it was prepared on
+    _conn: &'a OciConnection,
+    stmt: Statement,
+}
+
+impl<'a> PreparedStatement<'a> {
+    /// The sql the statement was prepared with
+    pub fn sql(&self) -> &str {
+        self.stmt.sql()
+    }
+
+    /// Executes the statement with `params` bound to its placeholders in
+    /// order
+    ///
+    /// ```ignore
+    /// let mut stmt = conn.prepare("UPDATE users SET name = :1 WHERE id = :2")?;
+    /// for (id, name) in renamed {
+    ///     stmt.execute(vec![BindValue::from(name), BindValue::Int(id)])?;
+    /// }
+    /// ```
+    pub fn execute<I>(&mut self, params: I) -> QueryResult<ExecResult>
+    where
+        I: IntoIterator<Item = BindValue>,
+    {
+        self.bind(params)?;
+        self.stmt.run()?;
+        Ok(ExecResult {
+            affected_rows: self.stmt.get_affected_rows()?,
+        })
+    }
+
+    /// Executes a query with `params` bound to its placeholders in order,
+    /// returning its rows as `T`
+    pub fn query<ST, T, I>(&mut self, params: I) -> QueryResult<Cursor<'_, ST, T>>
+    where
+        I: IntoIterator<Item = BindValue>,
+    {
+        self.bind(params)?;
+        self.stmt.run_with_cursor()
+    }
+
+    fn bind<I>(&mut self, params: I) -> QueryResult<()>
+    where
+        I: IntoIterator<Item = BindValue>,
+    {
+        self.stmt.reset_binds();
+        for param in params {
+            self.stmt.bind_value(param)?;
+        }
+        Ok(())
+    }
+}
+
+impl OciConnection {
+    /// Prepares `sql` to be executed repeatedly, c.f. `PreparedStatement`
+    ///
+    /// Placeholders are bound by position, e.g. `:1` or `:name` to the
+    /// first value.
+    pub fn prepare(&self, sql: &str) -> QueryResult<PreparedStatement<'_>> {
+        Ok(PreparedStatement {
+            _conn: self,
+            stmt: Statement::prepare(&self.raw(), sql)?,
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use diesel::debug_query;
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::result::QueryResult;
+
+use super::super::backend::Oracle;
+use super::super::query_builder::OciQueryBuilder;
+use super::OciConnection;
+
+/// A statement executed by a connection with a query logger, c.f.
+/// `OciConnection::set_query_logger`
+#[derive(Debug, Clone, Copy)]
+pub struct LoggedQuery<'a> {
+    pub sql: &'a str,
+    /// The bind values formatted like `debug_query` does, `None` if bind
+    /// values are redacted
+    pub binds: Option<&'a str>,
+    /// The time from preparing the statement until all rows were fetched
+    pub duration: Duration,
+    /// The affected rows of a DML statement or the number of loaded rows,
+    /// `None` if the statement failed or has no row count
+    pub rows: Option<usize>,
+    pub succeeded: bool,
+}
+
+#[derive(Clone)]
+pub(super) struct QueryLogger {
+    callback: Arc<dyn Fn(&LoggedQuery) + Send + Sync>,
+    include_binds: bool,
+}
+
+impl fmt::Debug for QueryLogger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QueryLogger")
+            .field("include_binds", &self.include_binds)
+            .finish()
+    }
+}
+
+/// The row count reported to the query logger
+pub(super) trait LoggedRows {
+    fn rows(&self) -> Option<usize>;
+}
+
+impl LoggedRows for () {
+    fn rows(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl LoggedRows for usize {
+    fn rows(&self) -> Option<usize> {
+        Some(*self)
+    }
+}
+
+impl<T> LoggedRows for Vec<T> {
+    fn rows(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+/// The sql of a query and, unless redacted, its bind values
+pub(super) fn describe_query<T>(
+    source: &T,
+    include_binds: bool,
+) -> QueryResult<(String, Option<String>)>
+where
+    T: QueryFragment<Oracle>,
+{
+    if include_binds {
+        // `debug_query` prints `<sql> -- binds: [<binds>]`
+        let debug = debug_query::<Oracle, _>(source).to_string();
+        if let Some(i) = debug.rfind(" -- binds: ") {
+            return Ok((debug[..i].to_owned(), Some(debug[i + 11..].to_owned())));
+        }
+    }
+    let mut query_builder = OciQueryBuilder::new();
+    source.to_sql(&mut query_builder)?;
+    Ok((query_builder.finish(), None))
+}
+
+/// `describe_query` of sql executed as is, i.e. without binds
+pub(super) fn describe_sql(
+    sql: &str,
+    include_binds: bool,
+) -> QueryResult<(String, Option<String>)> {
+    let binds = if include_binds {
+        Some("[]".to_owned())
+    } else {
+        None
+    };
+    Ok((sql.to_owned(), binds))
+}
+
+impl OciConnection {
+    /// Calls `logger` after each statement executed by this connection
+    /// with its sql, duration and row count
+    ///
+    /// Bind values are only passed to the logger if `include_binds` is set,
+    /// as they may contain personal data or credentials. Statements of
+    /// extension apis (e.g. `Call`) are not logged.
+    ///
+    /// ```ignore
+    /// conn.set_query_logger(false, |q| {
+    ///     info!("{} ({:?}, {:?} rows)", q.sql, q.duration, q.rows)
+    /// });
+    /// ```
+    pub fn set_query_logger<F>(&self, include_binds: bool, logger: F)
+    where
+        F: Fn(&LoggedQuery) + Send + Sync + 'static,
+    {
+        *self.query_logger.borrow_mut() = Some(QueryLogger {
+            callback: Arc::new(logger),
+            include_binds,
+        });
+    }
+
+    pub fn clear_query_logger(&self) {
+        *self.query_logger.borrow_mut() = None;
+    }
+
+    /// Runs `f` and passes the statement described by `describe` to the
+    /// query logger, `describe` is only called if a logger is set
+    pub(super) fn with_query_log<R, D, F>(&self, describe: D, f: F) -> QueryResult<R>
+    where
+        R: LoggedRows,
+        D: FnOnce(bool) -> QueryResult<(String, Option<String>)>,
+        F: FnOnce() -> QueryResult<R>,
+    {
+        let logger = match *self.query_logger.borrow() {
+            Some(ref logger) => logger.clone(),
+            None => return f(),
+        };
+        let start = Instant::now();
+        let ret = f();
+        let duration = start.elapsed();
+        match self.with_sql_dialect(|| describe(logger.include_binds)) {
+            Ok((sql, binds)) => (logger.callback)(&LoggedQuery {
+                sql: &sql,
+                binds: binds.as_deref(),
+                duration,
+                rows: ret.as_ref().ok().and_then(LoggedRows::rows),
+                succeeded: ret.is_ok(),
+            }),
+            Err(e) => debug!("Could not log the statement: {}", e),
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_query, describe_sql};
+    use diesel::prelude::*;
+
+    table! {
+        users (id) {
+            id -> Integer,
+            password -> Text,
+        }
+    }
+
+    #[test]
+    fn check_describe_query() {
+        let query = users::table
+            .select(users::id)
+            .filter(users::password.eq("secret"));
+        let (sql, binds) = describe_query(&query, true).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"USERS\".\"ID\" FROM \"USERS\" WHERE \"USERS\".\"PASSWORD\" = :1"
+        );
+        assert_eq!(binds.unwrap(), "[\"secret\"]");
+
+        let (redacted, binds) = describe_query(&query, false).unwrap();
+        assert_eq!(redacted, sql);
+        assert_eq!(binds, None);
+    }
+
+    #[test]
+    fn check_describe_sql() {
+        let (sql, binds) = describe_sql("DELETE FROM users", true).unwrap();
+        assert_eq!(sql, "DELETE FROM users");
+        assert_eq!(binds.unwrap(), "[]");
+
+        let (_, binds) = describe_sql("DELETE FROM users", false).unwrap();
+        assert_eq!(binds, None);
+    }
+}
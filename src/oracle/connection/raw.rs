@@ -1,77 +1,348 @@
 use oci_sys as ffi;
+use std::collections::HashMap;
 use std::ffi::CString;
+use std::fmt;
 use std::os::raw as libc;
 use std::ptr;
 use std::str;
 
 use diesel::result::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 
+use super::builder::OciConnectionBuilder;
+use super::cancel::BreakHandles;
+use super::column_info::ColumnInfo;
+use super::error::OciError;
+use super::failover::{self, FailoverHook};
+use super::global_transaction::Xid;
+use super::metrics::MetricsSink;
+use super::server_version::ServerVersion;
+use super::statement_options::StatementOptions;
 use super::stmt::Statement;
+use super::type_map::TypeMap;
+
+// An oci environment, which is freed once the last connection using it is
+// dropped
+struct EnvHandle {
+    handle: *mut ffi::OCIEnv,
+    cs_id: u16,
+    max_char_width: u32,
+}
+
+// The environment is created with `OCI_THREADED`, so it can be used by the
+// connections of all threads.
+unsafe impl Send for EnvHandle {}
+unsafe impl Sync for EnvHandle {}
+
+// the environments shared by the connections of this process, by the name
+// of their character set, c.f. `OciConnectionBuilder::shared_environment`
+//
+// The connections own the environment, so it is freed with the last one.
+static SHARED_ENVIRONMENTS: Mutex<Vec<(String, Weak<EnvHandle>)>> = Mutex::new(Vec::new());
+
+impl EnvHandle {
+    // creates an environment using `charset` (e.g. `AL32UTF8`) for both the
+    // character set and the national character set
+    fn new(charset: &str) -> Result<EnvHandle, ConnectionError> {
+        let cs_id = charset_id(charset)?;
+        unsafe {
+            let handle = create_env(cs_id)?;
+            let error_handle = alloc_handle::<ffi::OCIError>(handle, ffi::OCI_HTYPE_ERROR);
+            let max_char_width = max_char_width(handle, error_handle);
+            ffi::OCIHandleFree(error_handle as *mut libc::c_void, ffi::OCI_HTYPE_ERROR);
+            Ok(EnvHandle {
+                handle,
+                cs_id,
+                max_char_width,
+            })
+        }
+    }
+
+    // the environment of `charset` shared by all open connections, which is
+    // created by the first one
+    fn shared(charset: &str) -> Result<Arc<EnvHandle>, ConnectionError> {
+        let mut shared = SHARED_ENVIRONMENTS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        shared.retain(|(_, env)| env.strong_count() > 0);
+        let existing = shared
+            .iter()
+            .filter(|e| e.0.eq_ignore_ascii_case(charset))
+            .find_map(|(_, env)| env.upgrade());
+        if let Some(env) = existing {
+            return Ok(env);
+        }
+        let env = Arc::new(EnvHandle::new(charset)?);
+        shared.push((charset.to_owned(), Arc::downgrade(&env)));
+        Ok(env)
+    }
+}
+
+impl Drop for EnvHandle {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::OCIHandleFree(self.handle as *mut libc::c_void, ffi::OCI_HTYPE_ENV);
+        }
+    }
+}
 
 pub struct ConnectionEnviroment {
     handle: *mut ffi::OCIEnv,
     pub error_handle: *mut ffi::OCIError,
     pub cs_id: u16,
+    /// The maximal size of a character in bytes in the character set of
+    /// the environment
+    pub max_char_width: u32,
+    // keeps the environment alive, it is dropped after the error handle
+    // was freed
+    env: Arc<EnvHandle>,
 }
 
 impl ConnectionEnviroment {
-    pub fn new() -> Result<ConnectionEnviroment, ConnectionError> {
-        let env_handle = unsafe {
-            let mut handle: *mut ffi::OCIEnv = ptr::null_mut();
-            let code = ffi::OCIEnvNlsCreate(
-                &mut handle as *mut _,
-                ffi::OCI_DEFAULT,
-                ptr::null_mut(),
-                None,
-                None,
-                None,
-                0,
-                ptr::null_mut(),
-                0,
-                0,
-            );
-            if code != 0 {
-                return Err(ConnectionError::BadConnection(format!(
-                    "Couldn't create Environment: {:?}",
-                    code
-                )));
-            }
-            handle
+    /// Uses the environment of `charset` shared by all connections, or a
+    /// new one for this connection only unless `shared`
+    ///
+    /// Each connection has its own error handle, as oci writes the errors
+    /// of all calls using a handle into it.
+    pub fn new(charset: &str, shared: bool) -> Result<ConnectionEnviroment, ConnectionError> {
+        let env = if shared {
+            EnvHandle::shared(charset)?
+        } else {
+            Arc::new(EnvHandle::new(charset)?)
         };
         let error_handle =
-            unsafe { alloc_handle::<ffi::OCIError>(env_handle, ffi::OCI_HTYPE_ERROR) };
-        // we are certain that our string doesn't have 0 bytes in the middle,
-        // so we can .unwrap()
-        let enc = CString::new("UTF8").unwrap();
-        let cs_id = unsafe {
-            ffi::OCINlsCharSetNameToId(
-                env_handle as *mut libc::c_void,
-                enc.as_ptr() as *const ffi::OraText,
-            )
-        };
+            unsafe { alloc_handle::<ffi::OCIError>(env.handle, ffi::OCI_HTYPE_ERROR) };
         Ok(ConnectionEnviroment {
-            handle: env_handle,
+            handle: env.handle,
             error_handle,
-            cs_id,
+            cs_id: env.cs_id,
+            max_char_width: env.max_char_width,
+            env,
         })
     }
+
+    /// Whether both connections use the same environment
+    pub fn is_shared_with(&self, other: &ConnectionEnviroment) -> bool {
+        Arc::ptr_eq(&self.env, &other.env)
+    }
+
+    /// A new error handle for an object which may be used on another thread
+    /// than the connection, c.f. `ErrorHandle`
+    pub fn alloc_error_handle(&self) -> ErrorHandle {
+        ErrorHandle {
+            handle: unsafe { alloc_handle(self.env.handle, ffi::OCI_HTYPE_ERROR) },
+            _env: Arc::clone(&self.env),
+        }
+    }
+}
+
+/// The error handle of a statement, LOB or direct path load
+///
+/// OCI writes the errors of a call into the error handle passed to it, so
+/// objects which may be used concurrently to their connection (e.g. a
+/// cursor sent to another thread) must not use the error handle of the
+/// connection.
+pub struct ErrorHandle {
+    pub handle: *mut ffi::OCIError,
+    // the environment must be freed after the handle
+    _env: Arc<EnvHandle>,
+}
+
+// The handle is only used by the object owning it.
+unsafe impl Send for ErrorHandle {}
+
+impl Drop for ErrorHandle {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::OCIHandleFree(self.handle as *mut libc::c_void, ffi::OCI_HTYPE_ERROR);
+        }
+    }
+}
+
+unsafe fn max_char_width(env: *mut ffi::OCIEnv, error_handle: *mut ffi::OCIError) -> u32 {
+    let mut width = 0;
+    let status = ffi::OCINlsNumericInfoGet(
+        env as *mut libc::c_void,
+        error_handle,
+        &mut width,
+        ffi::OCI_NLS_CHARSET_MAXBYTESZ as u16,
+    );
+    if status == ffi::OCI_SUCCESS as i32 && width > 0 {
+        width as u32
+    } else {
+        // the maximum of all character sets (e.g. AL32UTF8)
+        4
+    }
+}
+
+unsafe fn create_env(cs_id: u16) -> Result<*mut ffi::OCIEnv, ConnectionError> {
+    let mut handle: *mut ffi::OCIEnv = ptr::null_mut();
+    // threaded, so that statements can be cancelled from another
+    // thread, c.f. `CancelToken`
+    let code = ffi::OCIEnvNlsCreate(
+        &mut handle as *mut _,
+        ffi::OCI_THREADED,
+        ptr::null_mut(),
+        None,
+        None,
+        None,
+        0,
+        ptr::null_mut(),
+        cs_id,
+        cs_id,
+    );
+    if code != 0 {
+        return Err(ConnectionError::BadConnection(format!(
+            "Couldn't create Environment: {:?}",
+            code
+        )));
+    }
+    Ok(handle)
+}
+
+/// Checks that strings of `charset` are UTF-8, which is how they are read
+/// and bound
+pub(super) fn check_charset(charset: &str) -> Result<(), String> {
+    if charset.eq_ignore_ascii_case("AL32UTF8") || charset.eq_ignore_ascii_case("UTF8") {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unsupported character set {}, strings are exchanged as UTF-8, \
+             use AL32UTF8 or UTF8",
+            charset
+        ))
+    }
+}
+
+// looks up the id of a character set name, which needs an environment
+// itself
+fn charset_id(charset: &str) -> Result<u16, ConnectionError> {
+    check_charset(charset).map_err(ConnectionError::BadConnection)?;
+    let enc = CString::new(charset).map_err(|_| {
+        ConnectionError::BadConnection(format!("Invalid character set {:?}", charset))
+    })?;
+    let cs_id = unsafe {
+        let env_handle = create_env(0)?;
+        let cs_id = ffi::OCINlsCharSetNameToId(
+            env_handle as *mut libc::c_void,
+            enc.as_ptr() as *const ffi::OraText,
+        );
+        ffi::OCIHandleFree(env_handle as *mut libc::c_void, ffi::OCI_HTYPE_ENV);
+        cs_id
+    };
+    if cs_id == 0 {
+        return Err(ConnectionError::BadConnection(format!(
+            "Unknown character set {}",
+            charset
+        )));
+    }
+    Ok(cs_id)
 }
 
 impl Drop for ConnectionEnviroment {
     fn drop(&mut self) {
         unsafe {
             ffi::OCIHandleFree(self.error_handle as *mut libc::c_void, ffi::OCI_HTYPE_ERROR);
-            ffi::OCIHandleFree(self.handle as *mut libc::c_void, ffi::OCI_HTYPE_ENV);
         }
     }
 }
 
 pub struct RawConnection {
-    pub env: ConnectionEnviroment,
     pub service_handle: *mut ffi::OCISvcCtx,
     server_handle: *mut ffi::OCIServer,
     session_handle: *mut ffi::OCISession,
     transaction_handle: *mut ffi::OCITrans,
+    // the handle of the global transaction branch the session is attached
+    // to, c.f. `use_global_transaction_handle`
+    global_transaction: Mutex<Option<(*mut ffi::OCITrans, Xid)>>,
+    broken: AtomicBool,
+    autocommit: AtomicBool,
+    // whether diesel's transaction manager opened a transaction, which
+    // disables autocommit
+    in_transaction: AtomicBool,
+    // whether statements were executed since the last commit or rollback
+    // without being committed by autocommit
+    pending_work: AtomicBool,
+    implicit_commits: AtomicU64,
+    call_timeout: Mutex<Option<Duration>>,
+    // the string attributes set by `set_session_attribute`
+    session_attributes: Mutex<Vec<(u32, String)>>,
+    metrics_sink: Mutex<Option<Arc<dyn MetricsSink>>>,
+    type_map: Mutex<Option<Arc<dyn TypeMap>>>,
+    // the columns binds were compared with, c.f. `compared_column_info`,
+    // `None` for expressions which could not be described
+    compared_columns: Mutex<HashMap<String, Option<ColumnInfo>>>,
+    break_error_handle: *mut ffi::OCIError,
+    pub(super) cancel_handles: Arc<Mutex<Option<BreakHandles>>>,
+    // the context of the failover callback, boxed as oci keeps a pointer
+    #[allow(dead_code)]
+    failover_hook: Box<Option<FailoverHook>>,
+    pub prefetch_rows: Option<u32>,
+    pub prefetch_memory: Option<u32>,
+    server_version: ServerVersion,
+    // declared last, so the environment is dropped after `drop` freed the
+    // other handles
+    pub env: ConnectionEnviroment,
+}
+
+// The environment is created with `OCI_THREADED`, so oci serializes
+// concurrent access to the handles of a connection. The error handle of the
+// connection is only used through the `OciConnection` owning it, which is
+// not `Sync`, the objects sharing the connection with it (statements,
+// cursors, LOBs, ...) have their own error handles. The remaining state
+// consists of atomics and mutexes.
+unsafe impl Send for RawConnection {}
+unsafe impl Sync for RawConnection {}
+
+// only the state of the connection, the handles are meaningless
+impl fmt::Debug for RawConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RawConnection")
+            .field("broken", &self.is_broken())
+            .field("implicit_commits", &self.implicit_commits())
+            .field("call_timeout", &self.call_timeout())
+            .field("prefetch_rows", &self.prefetch_rows)
+            .field("prefetch_memory", &self.prefetch_memory)
+            .field("server_version", &self.server_version)
+            .finish()
+    }
+}
+
+// the release of the server `service_handle` is connected to
+unsafe fn server_release(
+    service_handle: *mut ffi::OCISvcCtx,
+    error_handle: *mut ffi::OCIError,
+) -> QueryResult<ServerVersion> {
+    let mut banner = [0u8; 512];
+    let mut release = 0u32;
+    let status = ffi::OCIServerRelease(
+        service_handle as *mut libc::c_void,
+        error_handle,
+        banner.as_mut_ptr(),
+        banner.len() as u32,
+        ffi::OCI_HTYPE_SVCCTX as u8,
+        &mut release,
+    );
+    Statement::check_error(error_handle, status)?;
+    Ok(ServerVersion::from_release(release))
+}
+
+// not part of the bindings, available since oracle client 18c
+const OCI_ATTR_CALL_TIMEOUT: u32 = 531;
+
+// oci expects timeouts in milliseconds, where 0 disables the timeout
+fn as_millis(timeout: Duration) -> Result<u32, String> {
+    match timeout.as_millis() {
+        0 if timeout > Duration::from_millis(0) => Ok(1),
+        millis if millis <= u128::from(u32::MAX) => Ok(millis as u32),
+        _ => Err(format!(
+            "The timeout {:?} exceeds the maximum of {} milliseconds",
+            timeout,
+            u32::MAX
+        )),
+    }
 }
 
 unsafe fn alloc_handle<R>(env: *mut ffi::OCIEnv, tpe: libc::c_uint) -> *mut R {
@@ -86,31 +357,14 @@ unsafe fn alloc_handle<R>(env: *mut ffi::OCIEnv, tpe: libc::c_uint) -> *mut R {
     handle
 }
 
-fn parse_db_string(database_url: &str) -> ConnectionResult<(String, String, String)> {
-    if !database_url.starts_with("oci://") {
-        let msg = format!("Could not use {} with oci backend", database_url);
-        return Err(ConnectionError::InvalidConnectionUrl(msg));
-    }
-
-    // example: oci://\"diesel\"/diesel@//192.168.2.81:1521/orcl, c.f. sqplus manual
-
-    let splits: Vec<&str> = database_url.split("//").collect();
-    assert_eq!(splits.len(), 3);
-    let userandpw: Vec<&str> = splits[1].split('/').collect();
-    let user = userandpw[0].to_string();
-    let mut password = userandpw[1].to_string();
-    password.pop();
-    let db_url = splits[2].to_string();
-
-    Ok((user, password, db_url))
-}
-
 impl RawConnection {
-    pub fn establish(database_url: &str) -> ConnectionResult<Self> {
-        let (username, password, database) = parse_db_string(database_url)?;
+    pub fn establish(options: &OciConnectionBuilder) -> ConnectionResult<Self> {
+        let username = options.session_username();
+        let password = options.password.expose();
+        let database = &options.server_connect_string()?;
 
         // Initialize environment
-        let env = ConnectionEnviroment::new()?;
+        let env = ConnectionEnviroment::new(&options.charset, options.shared_environment)?;
 
         unsafe {
             // Allocate the server handle
@@ -127,14 +381,62 @@ impl RawConnection {
             let transaction_handle =
                 alloc_handle(env.handle, ffi::OCI_HTYPE_TRANS);
 
+            // frees the handles if any of the following steps fails
+            let mut guard = EstablishGuard {
+                error_handle: env.error_handle,
+                service_handle,
+                server_handle,
+                session_handle,
+                transaction_handle,
+                attached: false,
+                session_begun: false,
+                established: false,
+            };
+
             let status = ffi::OCIServerAttach(
                 server_handle,
                 env.error_handle,
-                (&database).as_ptr() as *const libc::c_uchar,
+                database.as_ptr() as *const libc::c_uchar,
                 database.len() as i32,
                 ffi::OCI_DEFAULT,
             );
 
+            Statement::check_error(env.error_handle, status)
+                .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
+            guard.attached = true;
+
+            for &(timeout, attr) in &[
+                (options.send_timeout, ffi::OCI_ATTR_SEND_TIMEOUT),
+                (options.receive_timeout, ffi::OCI_ATTR_RECEIVE_TIMEOUT),
+            ] {
+                if let Some(timeout) = timeout {
+                    let mut millis = as_millis(timeout).map_err(ConnectionError::BadConnection)?;
+                    let status = ffi::OCIAttrSet(
+                        server_handle as *mut libc::c_void,
+                        ffi::OCI_HTYPE_SERVER,
+                        &mut millis as *mut u32 as *mut libc::c_void,
+                        0,
+                        attr,
+                        env.error_handle,
+                    );
+                    Statement::check_error(env.error_handle, status)
+                        .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
+                }
+            }
+
+            let failover_hook = Box::new(options.failover_hook.clone());
+            let mut callback = ffi::OCIFocbkStruct {
+                callback_function: Some(failover::failover_callback),
+                fo_ctx: &*failover_hook as *const Option<FailoverHook> as *mut libc::c_void,
+            };
+            let status = ffi::OCIAttrSet(
+                server_handle as *mut libc::c_void,
+                ffi::OCI_HTYPE_SERVER,
+                &mut callback as *mut ffi::OCIFocbkStruct as *mut libc::c_void,
+                0,
+                ffi::OCI_ATTR_FOCBK,
+                env.error_handle,
+            );
             Statement::check_error(env.error_handle, status)
                 .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
 
@@ -147,34 +449,53 @@ impl RawConnection {
                 ffi::OCI_ATTR_SERVER,
                 env.error_handle,
             );
-            // Set attribute username in the session context
-            ffi::OCIAttrSet(
-                session_handle as *mut libc::c_void,
-                ffi::OCI_HTYPE_SESSION,
-                username.as_ptr() as *mut libc::c_void,
-                username.len() as u32,
-                ffi::OCI_ATTR_USERNAME,
-                env.error_handle,
-            );
-            // Set attribute password in the session context
-            ffi::OCIAttrSet(
-                session_handle as *mut libc::c_void,
-                ffi::OCI_HTYPE_SESSION,
-                password.as_ptr() as *mut libc::c_void,
-                password.len() as u32,
-                ffi::OCI_ATTR_PASSWORD,
-                env.error_handle,
-            );
+            let credentials = if options.external_auth {
+                if let Some(ref target) = options.proxy_client {
+                    // proxy sessions with external credentials only name
+                    // the target user, e.g. `[scott]`
+                    let username = format!("[{}]", target);
+                    ffi::OCIAttrSet(
+                        session_handle as *mut libc::c_void,
+                        ffi::OCI_HTYPE_SESSION,
+                        username.as_ptr() as *mut libc::c_void,
+                        username.len() as u32,
+                        ffi::OCI_ATTR_USERNAME,
+                        env.error_handle,
+                    );
+                }
+                ffi::OCI_CRED_EXT
+            } else {
+                // Set attribute username in the session context
+                ffi::OCIAttrSet(
+                    session_handle as *mut libc::c_void,
+                    ffi::OCI_HTYPE_SESSION,
+                    username.as_ptr() as *mut libc::c_void,
+                    username.len() as u32,
+                    ffi::OCI_ATTR_USERNAME,
+                    env.error_handle,
+                );
+                // Set attribute password in the session context
+                ffi::OCIAttrSet(
+                    session_handle as *mut libc::c_void,
+                    ffi::OCI_HTYPE_SESSION,
+                    password.as_ptr() as *mut libc::c_void,
+                    password.len() as u32,
+                    ffi::OCI_ATTR_PASSWORD,
+                    env.error_handle,
+                );
+                ffi::OCI_CRED_RDBMS
+            };
             // Begin session
             let status = ffi::OCISessionBegin(
                 service_handle,
                 env.error_handle,
                 session_handle,
-                ffi::OCI_CRED_RDBMS,
+                credentials,
                 ffi::OCI_DEFAULT,
             );
             Statement::check_error(env.error_handle, status)
                 .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
+            guard.session_begun = true;
 
             // Set session context in the service context
             ffi::OCIAttrSet(
@@ -195,73 +516,533 @@ impl RawConnection {
                 env.error_handle,
             );
 
+            if options.statement_cache_size > 0 {
+                let mut size = options.statement_cache_size;
+                let status = ffi::OCIAttrSet(
+                    service_handle as *mut libc::c_void,
+                    ffi::OCI_HTYPE_SVCCTX,
+                    &mut size as *mut u32 as *mut libc::c_void,
+                    0,
+                    ffi::OCI_ATTR_STMTCACHESIZE,
+                    env.error_handle,
+                );
+                Statement::check_error(env.error_handle, status)
+                    .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
+            }
+
+            let server_version = server_release(service_handle, env.error_handle)
+                .map_err(|e| ConnectionError::BadConnection(format!("{:?}", e)))?;
+
+            let break_error_handle = alloc_handle(env.handle, ffi::OCI_HTYPE_ERROR);
+            guard.established = true;
+            let cancel_handles = Arc::new(Mutex::new(Some(BreakHandles {
+                service_handle,
+                error_handle: break_error_handle,
+            })));
+
             Ok(RawConnection {
                 env,
                 service_handle,
                 server_handle,
                 session_handle,
                 transaction_handle,
+                global_transaction: Mutex::new(None),
+                broken: AtomicBool::new(false),
+                autocommit: AtomicBool::new(options.autocommit),
+                in_transaction: AtomicBool::new(false),
+                pending_work: AtomicBool::new(false),
+                implicit_commits: AtomicU64::new(0),
+                call_timeout: Mutex::new(None),
+                session_attributes: Mutex::new(Vec::new()),
+                metrics_sink: Mutex::new(None),
+                type_map: Mutex::new(None),
+                compared_columns: Mutex::new(HashMap::new()),
+                break_error_handle,
+                cancel_handles,
+                failover_hook,
+                prefetch_rows: options.prefetch_rows,
+                prefetch_memory: options.prefetch_memory,
+                server_version,
             })
         }
     }
-}
 
-impl Drop for RawConnection {
-    fn drop(&mut self) {
+    /// The prefetch limits of this connection, the limits oracle uses
+    /// unless configured otherwise (one row, no memory limit) are set
+    /// explicitly so they can be restored after a statement used other
+    /// limits
+    pub fn default_statement_options(&self) -> StatementOptions {
+        StatementOptions {
+            prefetch_rows: Some(self.prefetch_rows.unwrap_or(1)),
+            prefetch_memory: Some(self.prefetch_memory.unwrap_or(0)),
+        }
+    }
+
+    pub fn server_version(&self) -> ServerVersion {
+        self.server_version
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::SeqCst)
+    }
+
+    pub fn mark_broken(&self) {
+        self.broken.store(true, Ordering::SeqCst);
+    }
+
+    /// Allocates a statement handle, which is not prepared from sql text
+    /// (e.g. to receive a REF CURSOR)
+    pub fn alloc_statement_handle(&self) -> *mut ffi::OCIStmt {
+        unsafe { alloc_handle(self.env.handle, ffi::OCI_HTYPE_STMT) }
+    }
+
+    /// An unused LOB locator, c.f. `Lob`
+    pub fn alloc_lob_locator(&self) -> *mut ffi::OCILobLocator {
+        let mut locator = ptr::null_mut();
         unsafe {
-            ffi::OCISessionEnd(
-                self.service_handle,
-                self.env.error_handle,
-                self.session_handle,
-                ffi::OCI_DEFAULT,
+            ffi::OCIDescriptorAlloc(
+                self.env.handle as *const libc::c_void,
+                (&mut locator as *mut *mut ffi::OCILobLocator) as *mut _,
+                ffi::OCI_DTYPE_LOB,
+                0,
+                ptr::null_mut(),
             );
-            ffi::OCIServerDetach(self.server_handle, self.env.error_handle, ffi::OCI_DEFAULT);
-            ffi::OCIHandleFree(
-                self.session_handle as *mut libc::c_void,
-                ffi::OCI_HTYPE_SESSION,
+        }
+        locator
+    }
+
+    /// An unused `TIMESTAMP WITH TIME ZONE` descriptor
+    pub fn alloc_timestamp_tz(&self) -> *mut ffi::OCIDateTime {
+        let mut datetime = ptr::null_mut();
+        unsafe {
+            ffi::OCIDescriptorAlloc(
+                self.env.handle as *const libc::c_void,
+                (&mut datetime as *mut *mut ffi::OCIDateTime) as *mut _,
+                ffi::OCI_DTYPE_TIMESTAMP_TZ,
+                0,
+                ptr::null_mut(),
             );
-            ffi::OCIHandleFree(
+        }
+        datetime
+    }
+
+    /// The handle to pass to the `OCIDateTime` functions, which use the time
+    /// zone of the session
+    pub fn datetime_handle(&self) -> *mut libc::c_void {
+        self.session_handle as *mut libc::c_void
+    }
+
+    /// A context for a direct path load, c.f. `DirectPathLoader`
+    pub fn alloc_direct_path_context(&self) -> *mut ffi::OCIDirPathCtx {
+        unsafe { alloc_handle(self.env.handle, ffi::OCI_HTYPE_DIRPATH_CTX) }
+    }
+
+    /// The number of DDL statements executed on this connection, each of
+    /// them implicitly committed the current transaction
+    pub fn implicit_commits(&self) -> u64 {
+        self.implicit_commits.load(Ordering::SeqCst)
+    }
+
+    pub fn note_implicit_commit(&self) {
+        self.implicit_commits.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn set_autocommit(&self, enabled: bool) {
+        self.autocommit.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn autocommit(&self) -> bool {
+        self.autocommit.load(Ordering::SeqCst)
+    }
+
+    pub fn set_in_transaction(&self, in_transaction: bool) {
+        self.in_transaction.store(in_transaction, Ordering::SeqCst);
+    }
+
+    pub fn set_pending_work(&self, pending_work: bool) {
+        self.pending_work.store(pending_work, Ordering::SeqCst);
+    }
+
+    /// Whether the session may have uncommitted changes
+    pub fn has_pending_work(&self) -> bool {
+        self.pending_work.load(Ordering::SeqCst)
+    }
+
+    /// Whether the session is attached to a branch of a global transaction
+    pub fn in_global_transaction(&self) -> bool {
+        self.global_transaction
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Whether statements are executed with `OCI_COMMIT_ON_SUCCESS`
+    pub fn commit_on_success(&self) -> bool {
+        self.autocommit() && !self.in_transaction.load(Ordering::SeqCst)
+    }
+
+    /// Limits the time of each round trip to the database, `None` waits
+    /// forever
+    ///
+    /// A round trip exceeding the timeout is cancelled with
+    /// `ORA-03156: OCI call timed out`, the connection stays usable.
+    pub fn set_call_timeout(&self, timeout: Option<Duration>) -> QueryResult<()> {
+        let mut millis = match timeout {
+            Some(timeout) => as_millis(timeout)
+                .map_err(|e| Error::DatabaseError(DatabaseErrorKind::__Unknown, Box::new(e)))?,
+            None => 0,
+        };
+        let status = unsafe {
+            ffi::OCIAttrSet(
                 self.service_handle as *mut libc::c_void,
                 ffi::OCI_HTYPE_SVCCTX,
-            );
-            ffi::OCIHandleFree(self.server_handle as *mut libc::c_void, ffi::OCI_HTYPE_ENV);
-            ffi::OCIHandleFree(
-                self.transaction_handle as *mut libc::c_void,
+                &mut millis as *mut u32 as *mut libc::c_void,
+                0,
+                OCI_ATTR_CALL_TIMEOUT,
+                self.env.error_handle,
+            )
+        };
+        Statement::check_error(self.env.error_handle, status)?;
+        *self.call_timeout.lock().unwrap_or_else(|e| e.into_inner()) = timeout;
+        Ok(())
+    }
+
+    pub fn call_timeout(&self) -> Option<Duration> {
+        *self.call_timeout.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_metrics_sink(&self, sink: Option<Arc<dyn MetricsSink>>) {
+        *self.metrics_sink.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+    }
+
+    pub fn metrics_sink(&self) -> Option<Arc<dyn MetricsSink>> {
+        self.metrics_sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_type_map(&self, type_map: Option<Arc<dyn TypeMap>>) {
+        *self.type_map.lock().unwrap_or_else(|e| e.into_inner()) = type_map;
+    }
+
+    pub fn type_map(&self) -> Option<Arc<dyn TypeMap>> {
+        self.type_map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// The description of a column binds are compared with (e.g.
+    /// `"ITEMS"."PRICE"`), `describe` is only called for the first bind
+    /// compared with it
+    pub fn compared_column_info<F>(&self, column: &str, describe: F) -> Option<ColumnInfo>
+    where
+        F: FnOnce() -> Option<ColumnInfo>,
+    {
+        if let Some(info) = self
+            .compared_columns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(column)
+        {
+            return info.clone();
+        }
+        let info = describe();
+        self.compared_columns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(column.to_owned(), info.clone());
+        info
+    }
+
+    /// Forgets the described columns, as a DDL statement may change them
+    pub fn clear_compared_columns(&self) {
+        self.compared_columns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Switches the connection into or out of non-blocking mode
+    ///
+    /// In non-blocking mode calls sending a request to the server return
+    /// `OCI_STILL_EXECUTING` instead of waiting for the response. Returns
+    /// whether the connection was in non-blocking mode before.
+    pub fn set_non_blocking(&self, enabled: bool) -> QueryResult<bool> {
+        let mut current: u8 = 0;
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                self.server_handle as *const libc::c_void,
+                ffi::OCI_HTYPE_SERVER,
+                &mut current as *mut u8 as *mut libc::c_void,
+                ptr::null_mut(),
+                ffi::OCI_ATTR_NONBLOCKING_MODE,
+                self.env.error_handle,
+            )
+        };
+        Statement::check_error(self.env.error_handle, status)?;
+        let was_enabled = current != 0;
+        if was_enabled == enabled {
+            return Ok(was_enabled);
+        }
+        // setting the attribute toggles the mode
+        let status = unsafe {
+            ffi::OCIAttrSet(
+                self.server_handle as *mut libc::c_void,
+                ffi::OCI_HTYPE_SERVER,
+                ptr::null_mut(),
+                0,
+                ffi::OCI_ATTR_NONBLOCKING_MODE,
+                self.env.error_handle,
+            )
+        };
+        Statement::check_error(self.env.error_handle, status)?;
+        Ok(was_enabled)
+    }
+
+    /// Aborts the call currently executing in non-blocking mode
+    pub fn abort_non_blocking_call(&self) -> QueryResult<()> {
+        unsafe {
+            let status = ffi::OCIBreak(self.service_handle as *mut _, self.env.error_handle);
+            Statement::check_error(self.env.error_handle, status)?;
+            let status = ffi::OCIReset(self.server_handle as *mut _, self.env.error_handle);
+            Statement::check_error(self.env.error_handle, status)
+        }
+    }
+
+    /// Sets a string attribute of the session like `OCI_ATTR_MODULE`
+    ///
+    /// The value is sent to the server with the next round trip.
+    pub fn set_session_attribute(&self, attribute: u32, value: &str) -> QueryResult<()> {
+        let status = unsafe {
+            ffi::OCIAttrSet(
+                self.session_handle as *mut libc::c_void,
+                ffi::OCI_HTYPE_SESSION,
+                value.as_ptr() as *mut libc::c_void,
+                value.len() as u32,
+                attribute,
+                self.env.error_handle,
+            )
+        };
+        Statement::check_error(self.env.error_handle, status)?;
+        let mut attributes = self
+            .session_attributes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        attributes.retain(|a| a.0 != attribute);
+        attributes.push((attribute, value.to_owned()));
+        Ok(())
+    }
+
+    /// The attributes set by `set_session_attribute`
+    pub fn session_attributes(&self) -> Vec<(u32, String)> {
+        self.session_attributes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Attaches a transaction handle with the id `xid` to the service
+    /// context, unless it is already attached
+    ///
+    /// Diesel's transactions use a separate handle, which is attached again
+    /// by `use_local_transaction_handle`.
+    pub fn use_global_transaction_handle(&self, xid: &Xid) -> QueryResult<()> {
+        let mut global_transaction = self
+            .global_transaction
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if global_transaction.as_ref().is_some_and(|t| t.1 == *xid) {
+            return Ok(());
+        }
+        unsafe {
+            let handle: *mut ffi::OCITrans = alloc_handle(self.env.handle, ffi::OCI_HTYPE_TRANS);
+            let mut data = xid.to_raw();
+            let status = ffi::OCIAttrSet(
+                handle as *mut libc::c_void,
                 ffi::OCI_HTYPE_TRANS,
+                &mut data as *mut _ as *mut libc::c_void,
+                ::std::mem::size_of_val(&data) as u32,
+                ffi::OCI_ATTR_XID,
+                self.env.error_handle,
             );
+            let status = if status == ffi::OCI_SUCCESS as i32 {
+                ffi::OCIAttrSet(
+                    self.service_handle as *mut libc::c_void,
+                    ffi::OCI_HTYPE_SVCCTX,
+                    handle as *mut libc::c_void,
+                    0,
+                    ffi::OCI_ATTR_TRANS,
+                    self.env.error_handle,
+                )
+            } else {
+                status
+            };
+            if let Err(e) = Statement::check_error(self.env.error_handle, status) {
+                ffi::OCIHandleFree(handle as *mut libc::c_void, ffi::OCI_HTYPE_TRANS);
+                return Err(e);
+            }
+            if let Some((previous, _)) = global_transaction.take() {
+                ffi::OCIHandleFree(previous as *mut libc::c_void, ffi::OCI_HTYPE_TRANS);
+            }
+            *global_transaction = Some((handle, xid.clone()));
+        }
+        Ok(())
+    }
+
+    /// Attaches the transaction handle of diesel's transactions to the
+    /// service context again
+    pub fn use_local_transaction_handle(&self) -> QueryResult<()> {
+        let mut global_transaction = self
+            .global_transaction
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some((handle, _)) = global_transaction.take() {
+            unsafe {
+                let status = ffi::OCIAttrSet(
+                    self.service_handle as *mut libc::c_void,
+                    ffi::OCI_HTYPE_SVCCTX,
+                    self.transaction_handle as *mut libc::c_void,
+                    0,
+                    ffi::OCI_ATTR_TRANS,
+                    self.env.error_handle,
+                );
+                ffi::OCIHandleFree(handle as *mut libc::c_void, ffi::OCI_HTYPE_TRANS);
+                Statement::check_error(self.env.error_handle, status)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the session with a round trip to the database
+    pub fn ping(&self) -> QueryResult<()> {
+        self.check_usable()?;
+        let status =
+            unsafe { ffi::OCIPing(self.service_handle, self.env.error_handle, ffi::OCI_DEFAULT) };
+        Statement::check_error_with(self.env.error_handle, status, |e| {
+            if e.is_fatal() {
+                self.mark_broken();
+            }
+            e
+        })
+    }
+
+    /// Returns an error if a previous fatal error left the session unusable
+    pub fn check_usable(&self) -> QueryResult<()> {
+        if self.is_broken() {
+            let error = OciError::new(
+                3114,
+                "ORA-03114: not connected to ORACLE \
+                 (the connection was marked as broken after a fatal error)"
+                    .into(),
+            );
+            Err(Error::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand,
+                Box::new(error),
+            ))
+        } else {
+            Ok(())
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::parse_db_string;
-
-    #[test]
-    fn check_parse_database_url_1() {
-        let input = "oci://user/password@//localhost:1234/my_database";
-        let output = parse_db_string(input).unwrap();
-        assert_eq!(
-            output,
-            (
-                "user".into(),
-                "password".into(),
-                "localhost:1234/my_database".into()
-            )
+// Ends the session (if it was begun), detaches from the server (if it was
+// attached) and frees the handles of both. The environment and its error
+// handle are freed after this.
+unsafe fn free_session(
+    error_handle: *mut ffi::OCIError,
+    service_handle: *mut ffi::OCISvcCtx,
+    server_handle: *mut ffi::OCIServer,
+    session_handle: *mut ffi::OCISession,
+    transaction_handle: *mut ffi::OCITrans,
+    session_begun: bool,
+    attached: bool,
+) {
+    if session_begun {
+        ffi::OCISessionEnd(
+            service_handle,
+            error_handle,
+            session_handle,
+            ffi::OCI_DEFAULT,
         );
     }
+    if attached {
+        ffi::OCIServerDetach(server_handle, error_handle, ffi::OCI_DEFAULT);
+    }
+    ffi::OCIHandleFree(service_handle as *mut libc::c_void, ffi::OCI_HTYPE_SVCCTX);
+    ffi::OCIHandleFree(session_handle as *mut libc::c_void, ffi::OCI_HTYPE_SESSION);
+    ffi::OCIHandleFree(
+        transaction_handle as *mut libc::c_void,
+        ffi::OCI_HTYPE_TRANS,
+    );
+    ffi::OCIHandleFree(server_handle as *mut libc::c_void, ffi::OCI_HTYPE_SERVER);
+}
 
-    #[test]
-    fn check_parse_database_url_2() {
-        let input = "oci://user/password@//localhost/my_database";
-        let output = parse_db_string(input).unwrap();
-        assert_eq!(
-            output,
-            (
-                "user".into(),
-                "password".into(),
-                "localhost/my_database".into()
-            )
-        );
+// Frees the handles of a connection that could not be established. The
+// environment is shared by other connections, so they would not be freed
+// with it.
+struct EstablishGuard {
+    error_handle: *mut ffi::OCIError,
+    service_handle: *mut ffi::OCISvcCtx,
+    server_handle: *mut ffi::OCIServer,
+    session_handle: *mut ffi::OCISession,
+    transaction_handle: *mut ffi::OCITrans,
+    attached: bool,
+    session_begun: bool,
+    established: bool,
+}
+
+impl Drop for EstablishGuard {
+    fn drop(&mut self) {
+        if !self.established {
+            unsafe {
+                free_session(
+                    self.error_handle,
+                    self.service_handle,
+                    self.server_handle,
+                    self.session_handle,
+                    self.transaction_handle,
+                    self.session_begun,
+                    self.attached,
+                );
+            }
+        }
+    }
+}
+
+// Statements hold an `Arc` of their connection, so they were all released
+// when the connection is dropped. The session is ended and detached from the
+// server before the handles are freed, the environment is freed last.
+impl Drop for RawConnection {
+    fn drop(&mut self) {
+        // waits for a running cancellation
+        let mut cancel_handles = self
+            .cancel_handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *cancel_handles = None;
+        unsafe {
+            free_session(
+                self.env.error_handle,
+                self.service_handle,
+                self.server_handle,
+                self.session_handle,
+                self.transaction_handle,
+                true,
+                true,
+            );
+            let global_transaction = self
+                .global_transaction
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if let Some((handle, _)) = *global_transaction {
+                ffi::OCIHandleFree(handle as *mut libc::c_void, ffi::OCI_HTYPE_TRANS);
+            }
+            ffi::OCIHandleFree(
+                self.break_error_handle as *mut libc::c_void,
+                ffi::OCI_HTYPE_ERROR,
+            );
+        }
     }
 }
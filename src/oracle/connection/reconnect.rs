@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use diesel::connection::{Connection, TransactionManager};
+use diesel::result::{ConnectionError, ConnectionResult, Error, QueryResult};
+
+use super::raw::RawConnection;
+use super::stmt::Statement;
+use super::OciConnection;
+
+impl OciConnection {
+    /// Checks the session with a round trip to the database
+    ///
+    /// A failing ping with a fatal error (e.g. `ORA-03113`) marks the
+    /// connection as broken, c.f. `is_broken`.
+    pub fn ping(&self) -> QueryResult<()> {
+        self.raw().ping()
+    }
+
+    /// Replaces the session by a new one with the same configuration
+    ///
+    /// The statement timeout, the session attributes (c.f.
+    /// `set_client_identifier`) and all `ALTER SESSION` statements executed
+    /// on the previous session are applied to the new one. Cancel tokens of
+    /// the previous session have no effect on the new one. Fails inside of a
+    /// transaction.
+    pub fn reconnect(&self) -> ConnectionResult<()> {
+        if self.transaction_manager().get_transaction_depth() > 0 {
+            return Err(ConnectionError::BadConnection(
+                "Cannot reconnect inside of a transaction".into(),
+            ));
+        }
+        let raw = Arc::new(RawConnection::establish(&self.config)?);
+        let bad_connection = |e: Error| ConnectionError::BadConnection(e.to_string());
+        let previous = self.raw().clone();
+        raw.set_call_timeout(previous.call_timeout())
+            .map_err(bad_connection)?;
+        raw.set_metrics_sink(previous.metrics_sink());
+        raw.set_type_map(previous.type_map());
+        raw.set_autocommit(previous.autocommit());
+        for (attribute, value) in previous.session_attributes() {
+            raw.set_session_attribute(attribute, &value)
+                .map_err(bad_connection)?;
+        }
+        for sql in self.session_statements.borrow().iter() {
+            Statement::prepare(&raw, sql)
+                .and_then(|mut stmt| stmt.run())
+                .map_err(bad_connection)?;
+        }
+        // the cached statements belong to the previous session
+        self.statement_cache.cache.borrow_mut().clear();
+        *self.raw.borrow_mut() = raw;
+        Ok(())
+    }
+
+    /// Runs the query `f` again on a new session if it failed because the
+    /// session was lost and `auto_reconnect` is enabled
+    ///
+    /// `f` must only read, as the session may have been lost after the
+    /// statement was executed. Queries of a session with uncommitted changes
+    /// or an open (global) transaction are not retried, the new session
+    /// would silently lack these changes.
+    pub(super) fn with_reconnect<T, F>(&self, f: F) -> QueryResult<T>
+    where
+        F: Fn() -> QueryResult<T>,
+    {
+        let raw = self.raw();
+        let retryable = self.config.auto_reconnect
+            && self.transaction_manager().get_transaction_depth() == 0
+            && !raw.has_pending_work()
+            && !raw.in_global_transaction();
+        drop(raw);
+        let ret = f();
+        if ret.is_err() && retryable && self.is_broken() {
+            match self.reconnect() {
+                Ok(()) => return f(),
+                Err(e) => debug!("Could not reconnect: {}", e),
+            }
+        }
+        ret
+    }
+
+    // remembers successful `ALTER SESSION` statements for `reconnect`
+    pub(super) fn note_session_statement(&self, sql: &str) {
+        let words = sql.split_whitespace().take(2).collect::<Vec<_>>();
+        let is_alter_session = words.len() == 2
+            && words[0].eq_ignore_ascii_case("ALTER")
+            && words[1].eq_ignore_ascii_case("SESSION");
+        if is_alter_session {
+            self.session_statements.borrow_mut().push(sql.to_owned());
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::sync::Arc;
+
+#[cfg(feature = "chrono-time")]
+use arrow::array::TimestampMicrosecondArray;
+use arrow::array::{ArrayRef, BinaryArray, Float64Array, Int64Array, NullArray, StringArray};
+#[cfg(feature = "chrono-time")]
+use arrow::datatypes::TimeUnit;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+
+use super::dynamic::DynamicValue;
+
+/// The arrow type of a column, derived from its values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Null,
+    Int64,
+    Float64,
+    Utf8,
+    Binary,
+    #[cfg(feature = "chrono-time")]
+    Timestamp,
+    #[cfg(feature = "chrono-time")]
+    TimestampTz,
+}
+
+impl ColumnType {
+    fn of(value: &DynamicValue) -> Self {
+        match *value {
+            DynamicValue::Null => ColumnType::Null,
+            DynamicValue::Number(_) => ColumnType::Int64,
+            DynamicValue::Float(_) => ColumnType::Float64,
+            DynamicValue::String(_) => ColumnType::Utf8,
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::Date(_) | DynamicValue::Timestamp(_) => ColumnType::Timestamp,
+            #[cfg(feature = "chrono-time")]
+            DynamicValue::TimestampTz(_) => ColumnType::TimestampTz,
+            DynamicValue::Raw(_) => ColumnType::Binary,
+        }
+    }
+
+    // the type of a column with values of both types, numbers exceeding an
+    // `i64` are returned as `String`, so numbers and text give text
+    fn merge(self, other: Self) -> Option<Self> {
+        use self::ColumnType::*;
+
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (Null, t) | (t, Null) => Some(t),
+            (Int64, Float64) | (Float64, Int64) => Some(Float64),
+            (Int64, Utf8) | (Utf8, Int64) | (Float64, Utf8) | (Utf8, Float64) => Some(Utf8),
+            _ => None,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            ColumnType::Null => DataType::Null,
+            ColumnType::Int64 => DataType::Int64,
+            ColumnType::Float64 => DataType::Float64,
+            ColumnType::Utf8 => DataType::Utf8,
+            ColumnType::Binary => DataType::Binary,
+            #[cfg(feature = "chrono-time")]
+            ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+            #[cfg(feature = "chrono-time")]
+            ColumnType::TimestampTz => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some(Arc::from(UTC)))
+            }
+        }
+    }
+}
+
+// the time zone of `TIMESTAMP WITH TIME ZONE` columns, arrow keeps a single
+// time zone per column
+#[cfg(feature = "chrono-time")]
+const UTC: &str = "+00:00";
+
+/// Converts rows returned by `OciConnection::query_dynamic` into an arrow
+/// `RecordBatch`, e.g. to query them with DataFusion
+///
+/// The arrow type of each column is derived from its values:
+///
+/// * `Number` gives `Int64`, `Float` (or a mix of both) `Float64`
+/// * `String` gives `Utf8`, numbers in the same column are formatted as text
+///   (`NUMBER`s exceeding an `i64` are returned as `String`)
+/// * `Date` and `Timestamp` give `Timestamp(Microsecond, None)`,
+///   `TimestampTz` gives `Timestamp(Microsecond, "+00:00")`, fractional
+///   seconds are truncated to microseconds
+/// * `Raw` gives `Binary`
+/// * a column of `NULL`s gives `Null`
+///
+/// All fields are nullable. Other mixes of types in a column are an error,
+/// as are rows with different columns. Without rows, the batch has no
+/// columns.
+pub fn to_record_batch(rows: &[Vec<(String, DynamicValue)>]) -> QueryResult<RecordBatch> {
+    let first = match rows.first() {
+        Some(first) => first,
+        None => return Ok(RecordBatch::new_empty(Arc::new(Schema::empty()))),
+    };
+    for row in rows {
+        let same_columns = row.len() == first.len()
+            && row
+                .iter()
+                .zip(first)
+                .all(|(value, first)| value.0 == first.0);
+        if !same_columns {
+            return Err(conversion_error("The rows have different columns"));
+        }
+    }
+    let mut fields = Vec::with_capacity(first.len());
+    let mut columns = Vec::with_capacity(first.len());
+    for (index, (name, _)) in first.iter().enumerate() {
+        let values = rows.iter().map(|row| &row[index].1).collect::<Vec<_>>();
+        let column_type = values
+            .iter()
+            .try_fold(ColumnType::Null, |t, value| t.merge(ColumnType::of(value)))
+            .ok_or_else(|| {
+                conversion_error(&format!(
+                    "The column {} has values of different types",
+                    name
+                ))
+            })?;
+        fields.push(Field::new(name.clone(), column_type.data_type(), true));
+        columns.push(column(column_type, &values));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| conversion_error(&e.to_string()))
+}
+
+fn column(column_type: ColumnType, values: &[&DynamicValue]) -> ArrayRef {
+    match column_type {
+        ColumnType::Null => Arc::new(NullArray::new(values.len())),
+        ColumnType::Int64 => Arc::new(Int64Array::from(
+            values
+                .iter()
+                .map(|value| match **value {
+                    DynamicValue::Number(n) => Some(n),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        ColumnType::Float64 => Arc::new(Float64Array::from(
+            values
+                .iter()
+                .map(|value| match **value {
+                    DynamicValue::Number(n) => Some(n as f64),
+                    DynamicValue::Float(f) => Some(f),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        ColumnType::Utf8 => Arc::new(StringArray::from(
+            values
+                .iter()
+                .map(|value| match **value {
+                    DynamicValue::Number(n) => Some(n.to_string()),
+                    DynamicValue::Float(f) => Some(f.to_string()),
+                    DynamicValue::String(ref s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        ColumnType::Binary => Arc::new(BinaryArray::from(
+            values
+                .iter()
+                .map(|value| match **value {
+                    DynamicValue::Raw(ref bytes) => Some(&bytes[..]),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        #[cfg(feature = "chrono-time")]
+        ColumnType::Timestamp => Arc::new(TimestampMicrosecondArray::from(
+            values
+                .iter()
+                .map(|value| match **value {
+                    DynamicValue::Date(ref t) | DynamicValue::Timestamp(ref t) => {
+                        Some(t.and_utc().timestamp_micros())
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )),
+        #[cfg(feature = "chrono-time")]
+        ColumnType::TimestampTz => Arc::new(
+            TimestampMicrosecondArray::from(
+                values
+                    .iter()
+                    .map(|value| match **value {
+                        DynamicValue::TimestampTz(ref t) => Some(t.timestamp_micros()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone(UTC),
+        ),
+    }
+}
+
+fn conversion_error(message: &str) -> Error {
+    Error::DatabaseError(DatabaseErrorKind::__Unknown, Box::new(message.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_record_batch;
+    use arrow::array::{Array, Float64Array, Int64Array, StringArray};
+    use arrow::datatypes::DataType;
+    use oracle::connection::DynamicValue;
+
+    fn row(values: Vec<DynamicValue>) -> Vec<(String, DynamicValue)> {
+        ["ID", "NAME", "PRICE", "NOTE"]
+            .iter()
+            .map(|name| name.to_string())
+            .zip(values)
+            .collect()
+    }
+
+    #[test]
+    fn check_to_record_batch() {
+        let rows = vec![
+            row(vec![
+                DynamicValue::Number(1),
+                DynamicValue::String("a".to_owned()),
+                DynamicValue::Number(3),
+                DynamicValue::Null,
+            ]),
+            row(vec![
+                DynamicValue::String("100000000000000000000".to_owned()),
+                DynamicValue::Null,
+                DynamicValue::Float(2.5),
+                DynamicValue::Null,
+            ]),
+        ];
+        let batch = to_record_batch(&rows).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let schema = batch.schema();
+        let types = (0..4)
+            .map(|i| schema.field(i).data_type().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Utf8,
+                DataType::Utf8,
+                DataType::Float64,
+                DataType::Null
+            ]
+        );
+        assert_eq!(schema.field(0).name(), "ID");
+        assert!(schema.field(0).is_nullable());
+
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(ids.value(0), "1");
+        assert_eq!(ids.value(1), "100000000000000000000");
+        let names = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert!(names.is_null(1));
+        let prices = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(prices.value(0), 3.0);
+        assert_eq!(prices.value(1), 2.5);
+        assert_eq!(batch.column(3).len(), 2);
+
+        let batch = to_record_batch(&[vec![("N".to_owned(), DynamicValue::Number(7))]]).unwrap();
+        let ns = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ns.value(0), 7);
+
+        assert_eq!(to_record_batch(&[]).unwrap().num_columns(), 0);
+        // mixed types and different columns
+        assert!(to_record_batch(&[
+            vec![("N".to_owned(), DynamicValue::Number(7))],
+            vec![("N".to_owned(), DynamicValue::Raw(vec![1]))],
+        ])
+        .is_err());
+        assert!(to_record_batch(&[
+            vec![("N".to_owned(), DynamicValue::Number(7))],
+            vec![("M".to_owned(), DynamicValue::Number(7))],
+        ])
+        .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "chrono-time")]
+    fn check_to_record_batch_timestamps() {
+        use arrow::array::TimestampMicrosecondArray;
+        use arrow::datatypes::TimeUnit;
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let t = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_micro_opt(3, 4, 5, 6)
+            .unwrap();
+        let tz = FixedOffset::east_opt(3600)
+            .unwrap()
+            .from_local_datetime(&t)
+            .unwrap();
+        let batch = to_record_batch(&[vec![
+            ("D".to_owned(), DynamicValue::Date(t)),
+            ("T".to_owned(), DynamicValue::TimestampTz(tz)),
+        ]])
+        .unwrap();
+        let schema = batch.schema();
+        assert_eq!(
+            *schema.field(0).data_type(),
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        );
+        assert_eq!(
+            *schema.field(1).data_type(),
+            DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+        );
+        let dates = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(dates.value(0), t.and_utc().timestamp_micros());
+        let times = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(times.value(0), dates.value(0) - 3_600_000_000);
+    }
+}
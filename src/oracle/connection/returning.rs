@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use diesel::deserialize::{FromSqlRow, Queryable};
+use diesel::query_builder::bind_collector::RawBytesBindCollector;
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::result::{Error, QueryResult};
+use diesel::row::Row;
+use diesel::sql_types::HasSqlType;
+
+use super::super::backend::Oracle;
+use super::super::query_builder::OciQueryBuilder;
+use super::oracle_value::OracleValue;
+use super::plsql::out_bytes;
+use super::stmt::Statement;
+use super::OciConnection;
+use oracle::types::OCIDataType;
+
+/// A column returned by `OciConnection::execute_returning`
+#[derive(Debug, Clone)]
+pub struct Returning {
+    column: String,
+    tpe: OCIDataType,
+    capacity: usize,
+}
+
+impl Returning {
+    /// The column (or expression) `column` of type `ST`, returning at most
+    /// `capacity` bytes
+    ///
+    /// As for `PlSqlBind::output`, the capacity has to match the size diesel
+    /// uses for `ST`, e.g. `4` for `Integer` or `8` for `BigInt`. For `Text`
+    /// it is the maximal length of the returned string in bytes.
+    pub fn column<ST>(column: &str, capacity: usize) -> Self
+    where
+        Oracle: HasSqlType<ST>,
+    {
+        Returning {
+            column: column.to_owned(),
+            tpe: <Oracle as HasSqlType<ST>>::metadata(&()),
+            capacity,
+        }
+    }
+}
+
+impl OciConnection {
+    /// Executes an `INSERT`, `UPDATE` or `DELETE` of a single row, returning
+    /// the values of `returning` (e.g. a key generated by a sequence or an
+    /// identity column) as `T`
+    ///
+    /// Appends `RETURNING ... INTO ...` to the statement, which oracle only
+    /// supports for single rows. Fails with `NotFound` if no row was
+    /// affected.
+    ///
+    /// ```ignore
+    /// let (id, created) = conn.execute_returning::<(BigInt, Timestamp), (i64, NaiveDateTime), _>(
+    ///     &diesel::insert_into(users::table).values(users::name.eq("Ann")),
+    ///     &[
+    ///         Returning::column::<BigInt>("id", 8),
+    ///         Returning::column::<Timestamp>("created", 7),
+    ///     ],
+    /// )?;
+    /// ```
+    pub fn execute_returning<ST, T, Q>(&self, query: &Q, returning: &[Returning]) -> QueryResult<T>
+    where
+        Q: QueryFragment<Oracle>,
+        T: Queryable<ST, Oracle>,
+    {
+        let mut bind_collector = RawBytesBindCollector::<Oracle>::new();
+        query.collect_binds(&mut bind_collector, &())?;
+        let mut query_builder = OciQueryBuilder::new();
+        self.with_sql_dialect(|| query.to_sql(&mut query_builder))?;
+        let sql = returning_sql(
+            &query_builder.finish(),
+            bind_collector.binds.len(),
+            returning,
+        );
+
+        let raw = Arc::clone(&*self.raw());
+        let mut stmt = Statement::prepare(&raw, &sql)?;
+        let binds = bind_collector.binds;
+        for (tpe, value) in bind_collector.metadata.into_iter().zip(binds) {
+            stmt.bind(tpe, value)?;
+        }
+        let out_binds = returning
+            .iter()
+            .map(|r| stmt.bind_out(r.tpe, r.capacity))
+            .collect::<QueryResult<Vec<_>>>()?;
+        stmt.run()?;
+        if stmt.get_affected_rows()? == 0 {
+            return Err(Error::NotFound);
+        }
+
+        let values = out_binds
+            .iter()
+            .map(|out| out_bytes(out.data_type(), out.value(&stmt)))
+            .collect();
+        let mut row = ReturnedRow { values, index: 0 };
+        T::Row::build_from_row(&mut row)
+            .map(T::build)
+            .map_err(Error::DeserializationError)
+    }
+}
+
+// `sql` followed by `RETURNING <columns> INTO <placeholders>`, numbered
+// after the `binds` placeholders of `sql`
+fn returning_sql(sql: &str, binds: usize, returning: &[Returning]) -> String {
+    let columns = returning
+        .iter()
+        .map(|r| &*r.column)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = (binds + 1..=binds + returning.len())
+        .map(|i| format!(":{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} RETURNING {} INTO {}", sql, columns, placeholders)
+}
+
+// the values returned into the `OUT` binds of a statement
+struct ReturnedRow {
+    values: Vec<Option<Vec<u8>>>,
+    index: usize,
+}
+
+impl Row<Oracle> for ReturnedRow {
+    fn take(&mut self) -> Option<&OracleValue> {
+        let value = self.values.get(self.index).and_then(|v| v.as_ref());
+        self.index += 1;
+        value.map(|v| OracleValue::new(v))
+    }
+
+    fn next_is_null(&self, count: usize) -> bool {
+        self.values[self.index..]
+            .iter()
+            .take(count)
+            .all(Option::is_none)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{returning_sql, Returning};
+    use diesel::sql_types::{BigInt, Text};
+
+    #[test]
+    fn check_returning_sql() {
+        let returning = [
+            Returning::column::<BigInt>("id", 8),
+            Returning::column::<Text>("UPPER(name)", 40),
+        ];
+        assert_eq!(
+            returning_sql("UPDATE users SET name = :1 WHERE id = :2", 2, &returning),
+            "UPDATE users SET name = :1 WHERE id = :2 RETURNING id, UPPER(name) INTO :3, :4"
+        );
+        assert_eq!(
+            returning_sql("DELETE FROM users", 0, &returning[..1]),
+            "DELETE FROM users RETURNING id INTO :1"
+        );
+    }
+}
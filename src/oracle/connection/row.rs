@@ -1,40 +1,130 @@
 use super::super::backend::Oracle;
-use diesel::row::Row;
+use super::super::types::str_from_bytes;
+use diesel::deserialize::FromSql;
+use diesel::result::Error::DeserializationError;
+use diesel::result::QueryResult;
+use diesel::row::{NamedRow, Row};
+use std::collections::HashMap;
 
+use super::cursor::Field;
 use super::oracle_value::OracleValue;
 
+/// A row borrowing the buffers of the fields defined for a cursor
+///
+/// The name to index map is built once per cursor and shared by all rows,
+/// so building a row does not allocate.
 pub struct OciRow<'a> {
-    buf: Vec<&'a [u8]>,
-    is_null: Vec<bool>,
+    fields: &'a [Field],
+    column_indices: &'a HashMap<String, usize>,
     col_idx: usize,
 }
 
 impl<'a> OciRow<'a> {
-    pub fn new(row_buf: Vec<&'a [u8]>, is_null: Vec<bool>) -> Self {
+    pub fn new(fields: &'a [Field], column_indices: &'a HashMap<String, usize>) -> Self {
         OciRow {
-            buf: row_buf,
-            is_null,
+            fields,
+            column_indices,
             col_idx: 0,
         }
     }
+
+    /// The names of the columns as reported by oracle, i.e. unquoted
+    /// identifiers in upper case
+    pub fn column_names(&self) -> Vec<&str> {
+        self.fields.iter().map(Field::name).collect()
+    }
+
+    /// The name of the column with the given index
+    pub fn column_name(&self, index: usize) -> Option<&str> {
+        self.fields.get(index).map(Field::name)
+    }
+
+    /// Deserializes the value of the column `name`, which is matched case
+    /// insensitively
+    ///
+    /// ```ignore
+    /// let query = sql::<(Integer, Nullable<Text>)>("SELECT id, name FROM users");
+    /// conn.for_each_row(&query, |row| {
+    ///     let name = row.get_by_name::<Nullable<Text>, Option<String>>("name")?;
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn get_by_name<ST, T>(&self, name: &str) -> QueryResult<T>
+    where
+        T: FromSql<ST, Oracle>,
+    {
+        match self.index_of(name) {
+            Some(index) => T::from_sql(self.get_raw_value(index)).map_err(DeserializationError),
+            None => Err(DeserializationError(
+                format!("Column `{}` was not found in the row", name).into(),
+            )),
+        }
+    }
+
+    /// The raw value of the column with the given index, borrowed from the
+    /// define buffer of the column
+    ///
+    /// Values of string columns include the terminating null byte.
+    pub fn get_bytes(&self, index: usize) -> Option<&[u8]> {
+        self.fields.get(index).and_then(Field::value)
+    }
+
+    /// The value of the string column with the given index, borrowed from
+    /// the define buffer of the column
+    pub fn get_str(&self, index: usize) -> QueryResult<Option<&str>> {
+        match self.get_bytes(index) {
+            Some(bytes) => str_from_bytes(bytes)
+                .map(Some)
+                .map_err(DeserializationError),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Maps the column names of the given fields to their index
+///
+/// Oracle reports unquoted identifiers in upper case, while
+/// `QueryableByName` uses the (lower case) field names, so the lower cased
+/// names are included as well.
+pub fn column_indices(fields: &[Field]) -> HashMap<String, usize> {
+    let mut indices = HashMap::with_capacity(fields.len() * 2);
+    for (i, f) in fields.iter().enumerate().rev() {
+        indices.insert(f.name().to_lowercase(), i);
+    }
+    for (i, f) in fields.iter().enumerate().rev() {
+        indices.insert(f.name().to_owned(), i);
+    }
+    indices
 }
 
 impl<'a> Row<Oracle> for OciRow<'a> {
     fn take(&mut self) -> Option<&OracleValue> {
-        let ret = if self.col_idx < self.buf.len() {
-            if self.is_null[self.col_idx] {
-                None
-            } else {
-                Some(OracleValue::new(self.buf[self.col_idx]))
-            }
-        } else {
-            None
-        };
+        let ret = self
+            .fields
+            .get(self.col_idx)
+            .and_then(Field::value)
+            .map(OracleValue::new);
         self.col_idx += 1;
         ret
     }
 
     fn next_is_null(&self, count: usize) -> bool {
-        (0..count).all(|i| self.is_null[i + self.col_idx])
+        self.fields[self.col_idx..self.col_idx + count]
+            .iter()
+            .all(Field::is_null)
+    }
+}
+
+impl<'a> NamedRow<Oracle> for OciRow<'a> {
+    fn index_of(&self, column_name: &str) -> Option<usize> {
+        self.column_indices.get(column_name).cloned().or_else(|| {
+            self.fields
+                .iter()
+                .position(|f| f.name().eq_ignore_ascii_case(column_name))
+        })
+    }
+
+    fn get_raw_value(&self, index: usize) -> Option<&OracleValue> {
+        self.fields[index].value().map(OracleValue::new)
     }
 }
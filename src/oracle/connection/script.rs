@@ -0,0 +1,423 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+
+use diesel::connection::Connection;
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind, Error, QueryResult};
+
+use super::OciConnection;
+
+/// A single statement of a sql script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptStatement {
+    /// The statement without its terminating `;` (which is kept for PL/SQL
+    /// blocks) or `/`
+    pub sql: String,
+    /// The line of the script the statement starts at, starting with 1
+    pub line: usize,
+}
+
+/// The error of a failed statement of a script, c.f.
+/// `OciConnection::execute_script_from_reader`
+pub struct ScriptError {
+    /// The line of the script the failing statement starts at
+    pub line: usize,
+    pub statement: String,
+    message: String,
+    inner: Box<dyn DatabaseErrorInformation + Send + Sync>,
+}
+
+impl fmt::Debug for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScriptError")
+            .field("line", &self.line)
+            .field("statement", &self.statement)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl DatabaseErrorInformation for ScriptError {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn details(&self) -> Option<&str> {
+        self.inner.details()
+    }
+
+    fn hint(&self) -> Option<&str> {
+        self.inner.hint()
+    }
+
+    fn table_name(&self) -> Option<&str> {
+        self.inner.table_name()
+    }
+
+    fn column_name(&self) -> Option<&str> {
+        self.inner.column_name()
+    }
+
+    fn constraint_name(&self) -> Option<&str> {
+        self.inner.constraint_name()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Normal,
+    SingleQuote,
+    DoubleQuote,
+    BlockComment,
+}
+
+/// Splits a script line by line into statements
+///
+/// Statements are terminated by `;` outside of string literals, quoted
+/// identifiers and comments. PL/SQL blocks (`BEGIN`, `DECLARE`,
+/// `CREATE PROCEDURE`, `CREATE PACKAGE`, ...) contain `;` themselves, so they
+/// are only terminated by a line containing a single `/`, as in SQL*Plus.
+#[derive(Debug)]
+pub(crate) struct StatementSplitter {
+    current: String,
+    start_line: usize,
+    line: usize,
+    state: State,
+}
+
+impl StatementSplitter {
+    pub(crate) fn new() -> Self {
+        StatementSplitter {
+            current: String::new(),
+            start_line: 0,
+            line: 0,
+            state: State::Normal,
+        }
+    }
+
+    /// Adds the next line of the script (without the line break), returning
+    /// the statements completed by it
+    pub(crate) fn push_line(&mut self, line: &str) -> Vec<ScriptStatement> {
+        self.line += 1;
+        let mut statements = Vec::new();
+        if self.state == State::Normal && line.trim() == "/" {
+            statements.extend(self.take_statement());
+            return statements;
+        }
+
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let is_empty = self.current.trim().is_empty();
+            match self.state {
+                State::Normal => match c {
+                    '-' if chars.peek().map(|n| n.1) == Some('-') => {
+                        // comments in front of a statement are dropped
+                        if !is_empty {
+                            self.current.push_str(&line[i..]);
+                        }
+                        break;
+                    }
+                    '/' if chars.peek().map(|n| n.1) == Some('*') => {
+                        chars.next();
+                        self.state = State::BlockComment;
+                        if !is_empty {
+                            self.current.push_str("/*");
+                        }
+                    }
+                    ';' if !self.is_plsql() => {
+                        statements.extend(self.take_statement());
+                    }
+                    _ => {
+                        if is_empty && !c.is_whitespace() {
+                            self.current.clear();
+                            self.start_line = self.line;
+                        }
+                        self.state = match c {
+                            '\'' => State::SingleQuote,
+                            '"' => State::DoubleQuote,
+                            _ => State::Normal,
+                        };
+                        self.current.push(c);
+                    }
+                },
+                State::SingleQuote | State::DoubleQuote => {
+                    // a doubled quote is an escaped quote, which is handled
+                    // as two consecutive literals
+                    if (c == '\'' && self.state == State::SingleQuote)
+                        || (c == '"' && self.state == State::DoubleQuote)
+                    {
+                        self.state = State::Normal;
+                    }
+                    self.current.push(c);
+                }
+                State::BlockComment => {
+                    if c == '*' && chars.peek().map(|n| n.1) == Some('/') {
+                        chars.next();
+                        self.state = State::Normal;
+                        if !is_empty {
+                            self.current.push_str("*/");
+                        }
+                    } else if !is_empty {
+                        self.current.push(c);
+                    }
+                }
+            }
+        }
+        if !self.current.is_empty() {
+            self.current.push('\n');
+        }
+        statements
+    }
+
+    /// Returns the last statement if it was not terminated
+    pub(crate) fn finish(&mut self) -> Option<ScriptStatement> {
+        self.take_statement()
+    }
+
+    fn take_statement(&mut self) -> Option<ScriptStatement> {
+        let sql = self.current.trim();
+        let sql = if self.is_plsql() {
+            sql
+        } else {
+            sql.trim_end_matches(';').trim_end()
+        };
+        let statement = if sql.is_empty() {
+            None
+        } else {
+            Some(ScriptStatement {
+                sql: sql.to_owned(),
+                line: self.start_line,
+            })
+        };
+        self.current.clear();
+        self.state = State::Normal;
+        statement
+    }
+
+    fn is_plsql(&self) -> bool {
+        let mut words = self
+            .current
+            .split(|c: char| c.is_whitespace() || c == '(')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_uppercase());
+        match words.next().as_deref() {
+            Some("BEGIN") | Some("DECLARE") => return true,
+            Some("CREATE") => {}
+            _ => return false,
+        }
+        for word in words {
+            match &*word {
+                "OR" | "REPLACE" | "EDITIONABLE" | "NONEDITIONABLE" | "EDITIONING" => {}
+                "FUNCTION" | "PROCEDURE" | "PACKAGE" | "TRIGGER" | "TYPE" | "LIBRARY" => {
+                    return true
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+}
+
+/// Splits a whole script into statements, c.f. `StatementSplitter`
+pub(crate) fn split_script(script: &str) -> Vec<ScriptStatement> {
+    let mut splitter = StatementSplitter::new();
+    let mut statements = Vec::new();
+    for line in script.lines() {
+        statements.extend(splitter.push_line(line));
+    }
+    statements.extend(splitter.finish());
+    statements
+}
+
+impl OciConnection {
+    /// Executes a sql script statement by statement while reading it
+    ///
+    /// Statements are terminated by `;`, PL/SQL blocks (including
+    /// `CREATE PROCEDURE`, `CREATE PACKAGE`, ...) by a line containing a
+    /// single `/`. `progress` is called after each executed statement. Returns the
+    /// number of executed statements. If a statement fails the error is a
+    /// `ScriptError` containing the line of that statement.
+    pub fn execute_script_from_reader<R, F>(&self, reader: R, mut progress: F) -> QueryResult<usize>
+    where
+        R: Read,
+        F: FnMut(&ScriptStatement),
+    {
+        let mut reader = BufReader::new(reader);
+        let mut splitter = StatementSplitter::new();
+        let mut line = String::new();
+        let mut executed = 0;
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(|e| {
+                Error::DatabaseError(
+                    DatabaseErrorKind::__Unknown,
+                    Box::new(format!("Could not read the script: {}", e)),
+                )
+            })?;
+            let statements = if read == 0 {
+                let last = splitter.finish();
+                last.into_iter().collect::<Vec<_>>()
+            } else {
+                splitter.push_line(line.trim_end_matches(['\n', '\r']))
+            };
+            for statement in &statements {
+                self.execute_script_statement(statement)?;
+                executed += 1;
+                progress(statement);
+            }
+            if read == 0 {
+                return Ok(executed);
+            }
+        }
+    }
+
+    /// Executes a sql script statement by statement
+    ///
+    /// The script is split like by `execute_script_from_reader`, e.g. a
+    /// `CREATE OR REPLACE PACKAGE ... END;` is terminated by a line
+    /// containing a single `/`, while other statements are terminated by
+    /// `;`. Returns the number of executed statements.
+    pub fn run_script(&self, script: &str) -> QueryResult<usize> {
+        let statements = split_script(script);
+        for statement in &statements {
+            self.execute_script_statement(statement)?;
+        }
+        Ok(statements.len())
+    }
+
+    pub(crate) fn execute_script_statement(&self, statement: &ScriptStatement) -> QueryResult<()> {
+        match self.execute(&statement.sql) {
+            Ok(_) => Ok(()),
+            Err(Error::DatabaseError(kind, inner)) => Err(Error::DatabaseError(
+                kind,
+                Box::new(ScriptError {
+                    line: statement.line,
+                    statement: statement.sql.clone(),
+                    message: format!("{} (statement at line {})", inner.message(), statement.line),
+                    inner,
+                }),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_script as split, ScriptStatement};
+
+    fn statement(sql: &str, line: usize) -> ScriptStatement {
+        ScriptStatement {
+            sql: sql.into(),
+            line,
+        }
+    }
+
+    #[test]
+    fn check_split_statements() {
+        let script = "-- create the tables\n\
+                      CREATE TABLE a (id NUMBER(10));\n\
+                      \n\
+                      INSERT INTO a VALUES (1); INSERT INTO a VALUES (2);\n\
+                      /* multi line\n\
+                      comment; */\n\
+                      SELECT 'a;b', \"c;\"\n\
+                      FROM dual -- trailing; comment\n\
+                      ;\n\
+                      DELETE FROM a";
+        assert_eq!(
+            split(script),
+            vec![
+                statement("CREATE TABLE a (id NUMBER(10))", 2),
+                statement("INSERT INTO a VALUES (1)", 4),
+                statement("INSERT INTO a VALUES (2)", 4),
+                statement("SELECT 'a;b', \"c;\"\nFROM dual -- trailing; comment", 7),
+                statement("DELETE FROM a", 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_split_plsql_blocks() {
+        let script = "CREATE OR REPLACE PROCEDURE p AS\n\
+                      BEGIN\n\
+                      \x20 NULL; -- nothing to do\n\
+                      END;\n\
+                      /\n\
+                      BEGIN p; END;\n\
+                      /\n\
+                      SELECT 'it''s' FROM dual;\n\
+                      /\n\
+                      CREATE TABLE b (id NUMBER(10))\n\
+                      /";
+        assert_eq!(
+            split(script),
+            vec![
+                statement(
+                    "CREATE OR REPLACE PROCEDURE p AS\nBEGIN\n  NULL; -- nothing to do\nEND;",
+                    1
+                ),
+                statement("BEGIN p; END;", 6),
+                statement("SELECT 'it''s' FROM dual", 8),
+                statement("CREATE TABLE b (id NUMBER(10))", 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_split_triggers() {
+        let script = "CREATE TABLE t (id NUMBER(10)); CREATE OR REPLACE TRIGGER t_bi\n\
+                      BEFORE INSERT ON t FOR EACH ROW\n\
+                      BEGIN :new.id := 1; END;\n\
+                      /\n\
+                      INSERT INTO t VALUES (NULL)";
+        assert_eq!(
+            split(script),
+            vec![
+                statement("CREATE TABLE t (id NUMBER(10))", 1),
+                statement(
+                    "CREATE OR REPLACE TRIGGER t_bi\nBEFORE INSERT ON t FOR EACH ROW\n\
+                     BEGIN :new.id := 1; END;",
+                    1
+                ),
+                statement("INSERT INTO t VALUES (NULL)", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_split_packages() {
+        let script = "CREATE OR REPLACE PACKAGE pkg AS\n\
+                      \x20 PROCEDURE p;\n\
+                      END pkg;\n\
+                      /\n\
+                      CREATE OR REPLACE PACKAGE BODY pkg AS\n\
+                      \x20 PROCEDURE p IS BEGIN NULL; END;\n\
+                      END pkg;\n\
+                      /\n\
+                      GRANT EXECUTE ON pkg TO PUBLIC;";
+        assert_eq!(
+            split(script),
+            vec![
+                statement(
+                    "CREATE OR REPLACE PACKAGE pkg AS\n  PROCEDURE p;\nEND pkg;",
+                    1
+                ),
+                statement(
+                    "CREATE OR REPLACE PACKAGE BODY pkg AS\n  \
+                     PROCEDURE p IS BEGIN NULL; END;\nEND pkg;",
+                    5
+                ),
+                statement("GRANT EXECUTE ON pkg TO PUBLIC", 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_split_multi_line_strings() {
+        let script = "INSERT INTO a VALUES ('first;\nsecond');";
+        assert_eq!(
+            split(script),
+            vec![statement("INSERT INTO a VALUES ('first;\nsecond')", 1)]
+        );
+    }
+}
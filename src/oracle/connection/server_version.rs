@@ -0,0 +1,123 @@
+use std::fmt;
+
+use super::OciConnection;
+use oracle::query_builder::{self, IdentifierQuoting};
+
+/// The release of the database server, e.g. `19.3.0.0.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub update: u8,
+    pub port_release: u8,
+    pub port_update: u8,
+}
+
+impl ServerVersion {
+    pub fn new(major: u8, minor: u8) -> Self {
+        ServerVersion {
+            major,
+            minor,
+            update: 0,
+            port_release: 0,
+            port_update: 0,
+        }
+    }
+
+    /// Decodes the release number of `OCIServerRelease`, whose layout
+    /// changed with 18c
+    pub(super) fn from_release(release: u32) -> Self {
+        let major = (release >> 24) as u8;
+        if major >= 18 {
+            ServerVersion {
+                major,
+                minor: (release >> 16) as u8,
+                update: ((release >> 12) & 0x0f) as u8,
+                port_release: (release >> 4) as u8,
+                port_update: (release & 0x0f) as u8,
+            }
+        } else {
+            ServerVersion {
+                major,
+                minor: ((release >> 20) & 0x0f) as u8,
+                update: (release >> 12) as u8,
+                port_release: ((release >> 8) & 0x0f) as u8,
+                port_update: release as u8,
+            }
+        }
+    }
+
+    /// Whether the server knows the row limiting clause (`FETCH FIRST`),
+    /// which was introduced with 12.1
+    pub fn supports_fetch_first(&self) -> bool {
+        *self >= ServerVersion::new(12, 1)
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}.{}",
+            self.major, self.minor, self.update, self.port_release, self.port_update
+        )
+    }
+}
+
+impl OciConnection {
+    /// The release of the database server, as reported when connecting
+    ///
+    /// The sql of queries is built for this version, e.g. `fetch_first` is
+    /// rewritten to a `ROWNUM` condition for servers before 12.1.
+    pub fn server_version(&self) -> ServerVersion {
+        self.raw().server_version()
+    }
+
+    /// How the identifiers of queries are written, c.f.
+    /// `OciConnectionBuilder::identifier_quoting`
+    pub fn identifier_quoting(&self) -> IdentifierQuoting {
+        self.config.identifier_quoting
+    }
+
+    /// The owner of the tables of queries, c.f.
+    /// `OciConnectionBuilder::default_owner`
+    pub fn default_owner(&self) -> Option<&str> {
+        self.config.default_owner.as_ref().map(|owner| &owner[..])
+    }
+
+    /// Runs `f`, which builds sql for the server, the identifier quoting and
+    /// the default owner of this connection
+    pub(crate) fn with_sql_dialect<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        query_builder::with_server_version(self.server_version(), || {
+            query_builder::with_identifier_quoting(self.identifier_quoting(), || {
+                query_builder::with_default_owner(self.default_owner(), f)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerVersion;
+
+    #[test]
+    fn check_server_version() {
+        // 19.3.0.0.0 and 11.2.0.4.0
+        let v19 = ServerVersion::from_release(0x1303_0000);
+        assert_eq!(v19.to_string(), "19.3.0.0.0");
+        let v11 = ServerVersion::from_release(0x0b20_0400);
+        assert_eq!(v11.to_string(), "11.2.0.4.0");
+        assert_eq!(
+            ServerVersion::from_release(0x0c10_0200).to_string(),
+            "12.1.0.2.0"
+        );
+
+        assert!(v19.supports_fetch_first());
+        assert!(!v11.supports_fetch_first());
+        assert!(ServerVersion::new(12, 1).supports_fetch_first());
+        assert!(v11 < v19);
+    }
+}
@@ -0,0 +1,37 @@
+use diesel::result::QueryResult;
+use oci_sys as ffi;
+
+use super::OciConnection;
+
+// All values are sent to the server with the next statement, they are
+// visible in the corresponding columns of `V$SESSION` and with
+// `SYS_CONTEXT('USERENV', ...)`.
+impl OciConnection {
+    /// Identifies the end user of this session (`CLIENT_IDENTIFIER`), e.g.
+    /// the user logged into a web application
+    pub fn set_client_identifier(&self, client_identifier: &str) -> QueryResult<()> {
+        self.raw()
+            .set_session_attribute(ffi::OCI_ATTR_CLIENT_IDENTIFIER, client_identifier)
+    }
+
+    /// Names the module of the application running the next statements
+    /// (`MODULE`), at most 48 bytes
+    pub fn set_module(&self, module: &str) -> QueryResult<()> {
+        self.raw()
+            .set_session_attribute(ffi::OCI_ATTR_MODULE, module)
+    }
+
+    /// Names the action of the module running the next statements
+    /// (`ACTION`), at most 32 bytes
+    pub fn set_action(&self, action: &str) -> QueryResult<()> {
+        self.raw()
+            .set_session_attribute(ffi::OCI_ATTR_ACTION, action)
+    }
+
+    /// Additional free form information about the client (`CLIENT_INFO`),
+    /// at most 64 bytes
+    pub fn set_client_info(&self, client_info: &str) -> QueryResult<()> {
+        self.raw()
+            .set_session_attribute(ffi::OCI_ATTR_CLIENT_INFO, client_info)
+    }
+}
@@ -0,0 +1,167 @@
+//! The spans of the `tracing` feature
+//!
+//! Without the feature `Span` does nothing, so the call sites don't need to
+//! be feature gated.
+
+use std::time::Duration;
+
+use oci_sys as ffi;
+
+#[cfg(feature = "tracing")]
+pub(super) use tracing::Span;
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone)]
+pub(super) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+pub(super) struct Entered;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub(super) fn none() -> Self {
+        Span
+    }
+
+    pub(super) fn enter(&self) -> Entered {
+        Entered
+    }
+
+    pub(super) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+
+    pub(super) fn is_disabled(&self) -> bool {
+        true
+    }
+}
+
+pub(super) fn connect(connect_string: &str, username: &str) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        ::tracing::info_span!(
+            "oracle.connect",
+            db.system = "oracle",
+            db.connection_string = connect_string,
+            db.user = username,
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (connect_string, username);
+        Span
+    }
+}
+
+/// `statement_type` and `prepare_time_us` are recorded once the statement
+/// is prepared
+pub(super) fn prepare() -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        ::tracing::debug_span!(
+            "oracle.prepare",
+            db.system = "oracle",
+            statement_type = ::tracing::field::Empty,
+            prepare_time_us = ::tracing::field::Empty,
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        Span
+    }
+}
+
+/// `rows` (the affected rows, not recorded for queries) and
+/// `execute_time_us` are recorded once the statement is executed
+pub(super) fn execute(stmt_type: u16) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        ::tracing::info_span!(
+            "oracle.execute",
+            db.system = "oracle",
+            statement_type = statement_type(stmt_type),
+            rows = ::tracing::field::Empty,
+            execute_time_us = ::tracing::field::Empty,
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = stmt_type;
+        Span
+    }
+}
+
+/// Spans all fetch calls of an execution, the totals are recorded once the
+/// cursor is done, c.f. `record_fetch`
+pub(super) fn fetch(stmt_type: u16) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        ::tracing::debug_span!(
+            "oracle.fetch",
+            db.system = "oracle",
+            statement_type = statement_type(stmt_type),
+            rows = ::tracing::field::Empty,
+            fetch_calls = ::tracing::field::Empty,
+            fetch_time_us = ::tracing::field::Empty,
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = stmt_type;
+        Span
+    }
+}
+
+/// `action` is `begin`, `commit` or `rollback`, `depth` the transaction
+/// depth before the action
+pub(super) fn transaction(action: &'static str, depth: i32) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        ::tracing::info_span!(
+            "oracle.transaction",
+            db.system = "oracle",
+            action = action,
+            depth = i64::from(depth),
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (action, depth);
+        Span
+    }
+}
+
+pub(super) fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+/// The statement type reported by oracle (`OCI_ATTR_STMT_TYPE`) as a keyword
+pub(super) fn statement_type(stmt_type: u16) -> &'static str {
+    match u32::from(stmt_type) {
+        ffi::OCI_STMT_SELECT => "SELECT",
+        ffi::OCI_STMT_UPDATE => "UPDATE",
+        ffi::OCI_STMT_DELETE => "DELETE",
+        ffi::OCI_STMT_INSERT => "INSERT",
+        ffi::OCI_STMT_CREATE => "CREATE",
+        ffi::OCI_STMT_DROP => "DROP",
+        ffi::OCI_STMT_ALTER => "ALTER",
+        ffi::OCI_STMT_BEGIN => "BEGIN",
+        ffi::OCI_STMT_DECLARE => "DECLARE",
+        ffi::OCI_STMT_CALL => "CALL",
+        ffi::OCI_STMT_MERGE => "MERGE",
+        _ => "UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_statement_type() {
+        assert_eq!(statement_type(ffi::OCI_STMT_SELECT as u16), "SELECT");
+        assert_eq!(statement_type(ffi::OCI_STMT_MERGE as u16), "MERGE");
+        assert_eq!(statement_type(ffi::OCI_STMT_UNKNOWN as u16), "UNKNOWN");
+        assert_eq!(micros(Duration::new(2, 3_000)), 2_000_003);
+    }
+}
@@ -0,0 +1,74 @@
+use diesel::deserialize::Queryable;
+use diesel::query_builder::{AsQuery, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::HasSqlType;
+
+use super::super::backend::Oracle;
+use super::OciConnection;
+
+/// Options of a single statement, c.f. `OciConnection::load_with_options`
+///
+/// Options which are `None` keep the defaults of the connection, c.f.
+/// `OciConnectionBuilder::prefetch_rows` and
+/// `OciConnectionBuilder::prefetch_memory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementOptions {
+    /// The number of rows oracle fetches in advance
+    pub prefetch_rows: Option<u32>,
+    /// The maximal number of bytes used for prefetched rows, which limits
+    /// the prefetched rows of wide rows
+    ///
+    /// If both limits are set, oracle prefetches as many rows as fit into
+    /// both of them.
+    pub prefetch_memory: Option<u32>,
+}
+
+impl StatementOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prefetch_rows(mut self, rows: u32) -> Self {
+        self.prefetch_rows = Some(rows);
+        self
+    }
+
+    pub fn prefetch_memory(mut self, bytes: u32) -> Self {
+        self.prefetch_memory = Some(bytes);
+        self
+    }
+}
+
+impl OciConnection {
+    /// Loads the result of `source` like `RunQueryDsl::load`, using the
+    /// given options for this query only
+    ///
+    /// ```ignore
+    /// let options = StatementOptions::new().prefetch_rows(1000).prefetch_memory(1 << 20);
+    /// let documents = conn.load_with_options(documents::table, &options)?;
+    /// ```
+    pub fn load_with_options<T, U>(
+        &self,
+        source: T,
+        options: &StatementOptions,
+    ) -> QueryResult<Vec<U>>
+    where
+        T: AsQuery,
+        T::Query: QueryFragment<Oracle> + QueryId,
+        Oracle: HasSqlType<T::SqlType>,
+        U: Queryable<T::SqlType, Oracle>,
+    {
+        let query = source.as_query();
+        self.with_reconnect(|| {
+            let mut stmt = self.prepare_query(&query)?;
+            stmt.set_options(options)?;
+            let ret = stmt
+                .run_with_cursor::<T::SqlType, U>()
+                .and_then(Iterator::collect);
+            // the statement may be cached, so the defaults are restored
+            let defaults = self.raw().default_statement_options();
+            stmt.set_options(&defaults)?;
+            ret
+        })
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use diesel::result::QueryResult;
+
+use super::OciConnection;
+
+impl OciConnection {
+    /// Cancels every statement of this connection running longer than
+    /// `timeout` with `ORA-03156: OCI call timed out`
+    ///
+    /// The timeout applies to each round trip to the database, so loading
+    /// a large result may take longer in total. Requires an oracle client
+    /// of version 18c or later.
+    pub fn set_statement_timeout(&self, timeout: Duration) -> QueryResult<()> {
+        self.raw().set_call_timeout(Some(timeout))
+    }
+
+    /// Removes the timeout set with `set_statement_timeout`
+    pub fn clear_statement_timeout(&self) -> QueryResult<()> {
+        self.raw().set_call_timeout(None)
+    }
+
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        self.raw().call_timeout()
+    }
+
+    /// Runs `f` with a different statement timeout, restoring the previous
+    /// timeout afterwards (even if `f` panics)
+    ///
+    /// The result of `f` is returned, unless `f` succeeded but the previous
+    /// timeout could not be restored.
+    pub fn with_statement_timeout<T, F>(&self, timeout: Duration, f: F) -> QueryResult<T>
+    where
+        F: FnOnce() -> QueryResult<T>,
+    {
+        let previous = self.raw().call_timeout();
+        self.raw().set_call_timeout(Some(timeout))?;
+        let mut guard = RestoreTimeout {
+            conn: self,
+            previous: Some(previous),
+        };
+        let ret = f();
+        let restored = guard.restore();
+        ret.and_then(|value| restored.map(|_| value))
+    }
+}
+
+// restores the previous timeout when `with_statement_timeout` unwinds
+struct RestoreTimeout<'a> {
+    conn: &'a OciConnection,
+    previous: Option<Option<Duration>>,
+}
+
+impl<'a> RestoreTimeout<'a> {
+    fn restore(&mut self) -> QueryResult<()> {
+        match self.previous.take() {
+            Some(previous) => self.conn.raw().set_call_timeout(previous),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Drop for RestoreTimeout<'a> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
@@ -1,82 +1,315 @@
-use super::cursor::{Cursor, Field};
-use super::raw::RawConnection;
+#[cfg(feature = "debug-binds")]
+use super::bind_dump::{self, BoundParameter};
+use super::bind_value::{oracle_number, BindValue};
+use super::column_info::ColumnInfo;
+use super::cursor::{Cursor, Field, NamedCursor, OwnedCursor, RawCursor};
+#[cfg(feature = "tower")]
+use super::deadline;
+use super::error::OciError;
+use super::lob::{Lob, LobType, LOB_BIND_THRESHOLD};
+use super::long::{define_callback, LongValue};
+use super::metrics::{ImplicitConversion, StatementStats};
+use super::plsql::deserialize_out;
+use super::raw::{ErrorHandle, RawConnection};
+use super::spans::{self, Span};
+use super::statement_options::StatementOptions;
+use super::type_map::{default_fetch_type, FetchType};
+use diesel::deserialize::FromSql;
 use diesel::result::Error;
 use diesel::result::*;
 use libc;
 use oci_sys as ffi;
+use oracle::backend::Oracle;
 use oracle::types::OCIDataType;
+use std::cell::{Cell, RefCell};
+use std::mem;
 use std::os::raw::{c_int, c_void};
 use std::ptr;
 use std::rc::Rc;
+use std::slice;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct Statement {
-    pub connection: Rc<RawConnection>,
-    pub inner_statement: *mut ffi::OCIStmt,
+    pub(super) connection: Arc<RawConnection>,
+    pub(super) inner_statement: *mut ffi::OCIStmt,
+    // used by this statement only, c.f. `ErrorHandle`
+    pub(super) error_handle: ErrorHandle,
+    sql: String,
+    stmt_type: u16,
+    executed: bool,
     bind_index: libc::c_uint,
-    is_select: bool,
     buffers: Vec<Box<[u8]>>,
-    sizes: Vec<i32>,
+    // the actual length of each bound value, oracle writes the length of
+    // returned values for OUT parameters here, so the address must not change
+    #[allow(clippy::vec_box)]
+    lengths: Vec<Box<u16>>,
     indicators: Vec<Box<ffi::OCIInd>>,
+    // handles bound to REF CURSOR (`SQLT_RSET`) parameters
+    #[allow(clippy::vec_box)]
+    ref_cursors: Vec<Box<*mut ffi::OCIStmt>>,
+    // LOBs bound by the address of their locator
+    #[allow(clippy::vec_box)]
+    lobs: Vec<Box<Lob>>,
+    // the buffers of PL/SQL array binds, c.f. `bind_out_array`
+    arrays: Vec<ArrayBuffer>,
+    // whether this statement is a REF CURSOR returned by another statement,
+    // such statements are already executed and are freed instead of released
+    is_ref_cursor: bool,
+    // whether the statement is executed with `OCI_STMT_SCROLLABLE_READONLY`
+    scrollable: bool,
+    // reported with the statistics of the first execution
+    prepare_time: Duration,
+    // the statistics of the current execution, recorded once it is finished
+    stats: RefCell<StatementStats>,
+    stats_pending: Cell<bool>,
+    // spans the fetch calls of a query, closed with `finish_stats`
+    fetch_span: RefCell<Span>,
+    // the binds since the last `reset_binds` which convert the column they
+    // are compared with, c.f. `check_compared_column`
+    implicit_conversions: Vec<ImplicitConversion>,
+    // the values bound since the last `reset_binds`
+    #[cfg(feature = "debug-binds")]
+    bound_parameters: Vec<BoundParameter>,
 }
 
+/// A buffer bound by `Statement::bind_out`, holding the value oracle
+/// returned once the statement was executed
+#[derive(Debug, Clone, Copy)]
+pub struct OutBind {
+    index: usize,
+    tpe: OCIDataType,
+}
+
+impl OutBind {
+    pub fn data_type(&self) -> OCIDataType {
+        self.tpe
+    }
+
+    /// The bytes returned by the last execution of `stmt`, `None` for
+    /// `NULL`
+    pub fn value<'a>(&self, stmt: &'a Statement) -> Option<&'a [u8]> {
+        stmt.out_value(self.index)
+    }
+
+    /// Deserializes the value returned by the last execution of `stmt`
+    pub fn get<ST, T>(&self, stmt: &Statement) -> QueryResult<T>
+    where
+        T: FromSql<ST, Oracle>,
+    {
+        deserialize_out(self.tpe, self.value(stmt))
+    }
+}
+
+/// The buffers bound by `Statement::bind_out_array`, holding the elements
+/// oracle returned once the statement was executed
+#[derive(Debug, Clone, Copy)]
+pub struct OutArray {
+    index: usize,
+    tpe: OCIDataType,
+}
+
+impl OutArray {
+    pub fn data_type(&self) -> OCIDataType {
+        self.tpe
+    }
+
+    /// The elements returned by the last execution of `stmt`, `None` for
+    /// `NULL`
+    pub fn values<'a>(&self, stmt: &'a Statement) -> Vec<Option<&'a [u8]>> {
+        stmt.out_array(self.index)
+    }
+}
+
+// the elements of a PL/SQL array bind, oracle writes them and their number,
+// so the addresses must not change
+struct ArrayBuffer {
+    buffer: Box<[u8]>,
+    capacity: usize,
+    lengths: Box<[u16]>,
+    indicators: Box<[ffi::OCIInd]>,
+    len: Box<u32>,
+}
+
+// Statement handles can be used from any thread of an `OCI_THREADED`
+// environment, each statement has its own error handle, c.f.
+// `RawConnection`
+unsafe impl Send for Statement {}
+
 const NUM_ELEMENTS: usize = 20;
 
+// the buffer size of numbers and dates fetched as `FetchType::Text`
+const FORMATTED_VALUE_SIZE: u32 = 128;
+
 impl Statement {
-    pub fn prepare(raw_connection: &Rc<RawConnection>, sql: &str) -> QueryResult<Self> {
-        let mysql = sql.to_string();
+    pub fn prepare(raw_connection: &Arc<RawConnection>, sql: &str) -> QueryResult<Self> {
+        raw_connection.check_usable()?;
+        let error_handle = raw_connection.env.alloc_error_handle();
+        let span = spans::prepare();
+        let _entered = span.enter();
+        let start = Instant::now();
+        let stmt = Self::prepare_raw(raw_connection, &error_handle, sql)?;
+        let prepare_time = start.elapsed();
+        let stmt_type = Self::get_statement_type(&error_handle, stmt)?;
+        span.record("statement_type", spans::statement_type(stmt_type))
+            .record("prepare_time_us", spans::micros(prepare_time));
+        let stmt = Statement {
+            connection: raw_connection.clone(),
+            inner_statement: stmt,
+            error_handle,
+            sql: sql.to_owned(),
+            stmt_type,
+            executed: false,
+            bind_index: 0,
+            buffers: Vec::with_capacity(NUM_ELEMENTS),
+            lengths: Vec::with_capacity(NUM_ELEMENTS),
+            indicators: Vec::with_capacity(NUM_ELEMENTS),
+            ref_cursors: Vec::new(),
+            lobs: Vec::new(),
+            arrays: Vec::new(),
+            is_ref_cursor: false,
+            scrollable: false,
+            prepare_time,
+            stats: RefCell::new(StatementStats::default()),
+            stats_pending: Cell::new(false),
+            fetch_span: RefCell::new(Span::none()),
+            implicit_conversions: Vec::new(),
+            #[cfg(feature = "debug-binds")]
+            bound_parameters: Vec::new(),
+        };
+        stmt.set_options(&raw_connection.default_statement_options())?;
+        Ok(stmt)
+    }
 
-        let stmt = unsafe {
-            let mut stmt: *mut ffi::OCIStmt = ptr::null_mut();
+    /// Sets the prefetch limits of queries, other statements ignore them
+    pub(super) fn set_options(&self, options: &StatementOptions) -> QueryResult<()> {
+        if !self.is_select() {
+            return Ok(());
+        }
+        let attrs = [
+            (options.prefetch_rows, ffi::OCI_ATTR_PREFETCH_ROWS),
+            (options.prefetch_memory, ffi::OCI_ATTR_PREFETCH_MEMORY),
+        ];
+        for &(value, attr) in &attrs {
+            if let Some(mut value) = value {
+                let status = unsafe {
+                    ffi::OCIAttrSet(
+                        self.inner_statement as *mut c_void,
+                        ffi::OCI_HTYPE_STMT,
+                        &mut value as *mut u32 as *mut c_void,
+                        0,
+                        attr,
+                        self.error_handle.handle,
+                    )
+                };
+                self.check(status)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare_raw(
+        raw_connection: &RawConnection,
+        error_handle: &ErrorHandle,
+        sql: &str,
+    ) -> QueryResult<*mut ffi::OCIStmt> {
+        let mut stmt: *mut ffi::OCIStmt = ptr::null_mut();
+        unsafe {
             let status = ffi::OCIStmtPrepare2(
                 raw_connection.service_handle,
                 &mut stmt,
-                raw_connection.env.error_handle,
-                mysql.as_ptr(),
-                mysql.len() as u32,
+                error_handle.handle,
+                sql.as_ptr(),
+                sql.len() as u32,
                 ptr::null(),
                 0,
                 ffi::OCI_NTV_SYNTAX,
                 ffi::OCI_DEFAULT,
             );
+            Self::check_error_with(error_handle.handle, status, |e| {
+                if e.is_fatal() {
+                    raw_connection.mark_broken();
+                }
+                e.with_statement(sql, 0)
+            })?;
+        }
+        Ok(stmt)
+    }
 
-            Self::check_error(raw_connection.env.error_handle, status)?;
-
-            // for create statements we need to run OCIStmtPrepare2 twice
-            // c.f. https://docs.oracle.com/database/121/LNOCI/oci17msc001.htm#LNOCI17165
-            // "To reexecute a DDL statement, you must prepare the statement again using OCIStmtPrepare2()."
-            if let Some(u) = mysql.to_string().find("CREATE") {
-                if u < 10 {
-                    let status = ffi::OCIStmtPrepare2(
-                        raw_connection.service_handle,
-                        &mut stmt,
-                        raw_connection.env.error_handle,
-                        mysql.as_ptr(),
-                        mysql.len() as u32,
-                        ptr::null(),
-                        0,
-                        ffi::OCI_NTV_SYNTAX,
-                        ffi::OCI_DEFAULT,
-                    );
+    fn get_statement_type(error_handle: &ErrorHandle, stmt: *mut ffi::OCIStmt) -> QueryResult<u16> {
+        let mut stmt_type: u16 = 0;
+        unsafe {
+            let status = ffi::OCIAttrGet(
+                stmt as *const _,
+                ffi::OCI_HTYPE_STMT,
+                (&mut stmt_type as *mut u16) as *mut _,
+                &mut 0,
+                ffi::OCI_ATTR_STMT_TYPE,
+                error_handle.handle,
+            );
+            Self::check_error(error_handle.handle, status)?;
+        }
+        Ok(stmt_type)
+    }
 
-                    Self::check_error(raw_connection.env.error_handle, status)?;
-                }
-            }
+    pub(super) fn is_select(&self) -> bool {
+        u32::from(self.stmt_type) == ffi::OCI_STMT_SELECT
+    }
 
-            stmt
-        };
-        Ok(Statement {
-            connection: raw_connection.clone(),
-            inner_statement: stmt,
-            bind_index: 0,
-            // TODO: this can go wrong: `UPDATE table SET k='select';`
-            is_select: sql.contains("SELECT") || sql.contains("select"),
-            buffers: Vec::with_capacity(NUM_ELEMENTS),
-            sizes: Vec::with_capacity(NUM_ELEMENTS),
-            indicators: Vec::with_capacity(NUM_ELEMENTS),
-        })
+    fn is_ddl(&self) -> bool {
+        matches!(
+            u32::from(self.stmt_type),
+            ffi::OCI_STMT_CREATE | ffi::OCI_STMT_DROP | ffi::OCI_STMT_ALTER
+        )
+    }
+
+    // c.f. https://docs.oracle.com/database/121/LNOCI/oci17msc001.htm#LNOCI17165
+    // "To reexecute a DDL statement, you must prepare the statement again using OCIStmtPrepare2()."
+    fn reprepare(&mut self) -> QueryResult<()> {
+        let stmt = Self::prepare_raw(&self.connection, &self.error_handle, &self.sql)?;
+        self.release();
+        self.inner_statement = stmt;
+        Ok(())
+    }
+
+    fn release(&self) {
+        unsafe {
+            if self.is_ref_cursor {
+                ffi::OCIHandleFree(self.inner_statement as *mut _, ffi::OCI_HTYPE_STMT);
+                return;
+            }
+            // DDL statements need to be prepared again before each
+            // execution, so they are not kept in oci's statement cache
+            let mode = if self.is_ddl() {
+                ffi::OCI_STRLS_CACHE_DELETE
+            } else {
+                ffi::OCI_DEFAULT
+            };
+            let status = ffi::OCIStmtRelease(
+                self.inner_statement,
+                self.error_handle.handle,
+                ptr::null(),
+                0,
+                mode,
+            );
+            if let Some(err) = Self::check_error(self.error_handle.handle, status).err() {
+                println!("{:?}", err);
+            }
+        }
     }
 
     pub fn check_error(error_handle: *mut ffi::OCIError, status: i32) -> Result<(), Error> {
+        Self::check_error_with(error_handle, status, |e| e)
+    }
+
+    pub(super) fn check_error_with<F>(
+        error_handle: *mut ffi::OCIError,
+        status: i32,
+        f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(OciError) -> OciError,
+    {
         match status {
             ffi::OCI_ERROR => {
                 // c.f. https://github.com/Mingun/rust-oci/blob/2e0f2acb35066b5f510b46826937a634017cda5d/src/ffi/mod.rs#L102
@@ -106,13 +339,18 @@ impl Statement {
                     errbuf.resize(nul_byte_pos, 0);
                 }
 
-                Err(Error::DatabaseError(
-                    DatabaseErrorKind::UnableToSendCommand,
-                    Box::new(format!(
-                        "OCI_ERROR {:?}",
-                        String::from_utf8(errbuf).expect("Invalid UTF-8 from OCIErrorGet")
-                    )),
-                ))
+                let message = String::from_utf8(errbuf).expect("Invalid UTF-8 from OCIErrorGet");
+                let error = f(OciError::new(errcode, message.trim_end().to_owned()));
+                Err(Error::DatabaseError(error.kind(), Box::new(error)))
+            }
+            ffi::OCI_STILL_EXECUTING => {
+                let error = f(OciError::new(
+                    3123,
+                    "ORA-03123: operation would block (a non-blocking call is \
+                     still executing on this connection)"
+                        .into(),
+                ));
+                Err(Error::DatabaseError(error.kind(), Box::new(error)))
             }
             ffi::OCI_INVALID_HANDLE => Err(Error::DatabaseError(
                 DatabaseErrorKind::UnableToSendCommand,
@@ -122,25 +360,249 @@ impl Statement {
         }
     }
 
-    pub fn run(&self) -> QueryResult<()> {
-        let iters = if self.is_select { 0 } else { 1 };
+    pub fn check(&self, status: i32) -> QueryResult<()> {
+        Self::check_error_with(self.error_handle.handle, status, |e| {
+            if e.is_fatal() {
+                self.connection.mark_broken();
+            }
+            let e = e.with_statement(&self.sql, self.bind_index as usize);
+            if e.is_parse_error() {
+                match self.get_parse_error_offset() {
+                    Some(offset) => e.with_parse_error_offset(offset),
+                    None => e,
+                }
+            } else {
+                e
+            }
+        })
+    }
+
+    /// `ORA-01406` for a value of `column` which did not fit into its
+    /// define buffer
+    pub(super) fn truncation_error(&self, column: &str) -> Error {
+        let error = OciError::new(
+            1406,
+            "ORA-01406: fetched column value was truncated".to_owned(),
+        )
+        .with_statement(&self.sql, self.bind_index as usize)
+        .with_column(column);
+        Error::DatabaseError(error.kind(), Box::new(error))
+    }
+
+    fn get_parse_error_offset(&self) -> Option<usize> {
+        let mut offset = 0u16;
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                self.inner_statement as *const _,
+                ffi::OCI_HTYPE_STMT,
+                (&mut offset as *mut u16) as *mut _,
+                &mut 0,
+                ffi::OCI_ATTR_PARSE_ERROR_OFFSET,
+                self.error_handle.handle,
+            )
+        };
+        if status == ffi::OCI_SUCCESS as i32 {
+            Some(offset as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The sql this statement was prepared with
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The values bound since the last `reset_binds`, ordered by their
+    /// position, without REF CURSOR parameters
+    #[cfg(feature = "debug-binds")]
+    pub fn bound_parameters(&self) -> &[BoundParameter] {
+        &self.bound_parameters
+    }
+
+    /// The sql of the statement with the bound values as literals, e.g. to
+    /// run a failing query in SQL*Plus
+    ///
+    /// `OUT` parameters and LOBs are replaced by `NULL`, the placeholders of
+    /// REF CURSOR parameters are kept.
+    #[cfg(feature = "debug-binds")]
+    pub fn sql_with_binds(&self) -> String {
+        bind_dump::inline_binds(&self.sql, &self.bound_parameters)
+    }
+
+    pub fn run(&mut self) -> QueryResult<()> {
+        self.prepare_execution()?;
+        self.finish_stats();
+        #[cfg(feature = "tower")]
+        let _deadline = deadline::apply_deadline(&self.connection)?;
+        let span = spans::execute(self.stmt_type);
+        let start = Instant::now();
+        let status = {
+            let _entered = span.enter();
+            self.execute_raw()
+        };
+        let execute_time = start.elapsed();
+        span.record("execute_time_us", spans::micros(execute_time));
+        *self.stats.borrow_mut() = StatementStats {
+            sql: self.sql.clone(),
+            prepare_time: mem::replace(&mut self.prepare_time, Duration::from_secs(0)),
+            execute_time,
+            implicit_conversions: self.implicit_conversions.clone(),
+            ..StatementStats::default()
+        };
+        self.stats_pending.set(true);
+        let ret = self.check(status);
+        #[cfg(feature = "debug-binds")]
+        {
+            if let Err(ref e) = ret {
+                debug!("{} failed: {}", self.sql_with_binds(), e);
+            }
+        }
+        // the statistics of queries are recorded once the cursor is done
+        if ret.is_err() || !self.is_select() {
+            self.finish_stats();
+        } else {
+            *self.fetch_span.borrow_mut() = spans::fetch(self.stmt_type);
+        }
+        if ret.is_ok() && !self.is_select() && !span.is_disabled() {
+            if let Ok(rows) = self.get_affected_rows() {
+                span.record("rows", rows);
+            }
+        }
+        ret
+    }
+
+    /// The columns of the result set of a query, which oracle describes
+    /// without executing it (`OCI_DESCRIBE_ONLY`)
+    ///
+    /// Values don't need to be bound. Statements other than queries have no
+    /// columns, oracle still reports errors like missing tables or invalid
+    /// sql for queries.
+    pub fn describe(&mut self) -> QueryResult<Vec<ColumnInfo>> {
+        if !self.is_select() {
+            return Ok(Vec::new());
+        }
+        self.connection.check_usable()?;
+        let status = unsafe {
+            ffi::OCIStmtExecute(
+                self.connection.service_handle,
+                self.inner_statement,
+                self.error_handle.handle,
+                0,
+                0,
+                ptr::null(),
+                ptr::null_mut(),
+                ffi::OCI_DESCRIBE_ONLY,
+            )
+        };
+        self.check(status)?;
+        (1..=self.get_column_count()? as usize)
+            .map(|col_number| self.describe_column(self.param_descriptor(col_number)?))
+            .collect()
+    }
+
+    /// Adds a fetch call to the statistics of the current execution
+    pub(super) fn note_fetch(&self, fetch_time: Duration, fetched_row: bool) {
+        let mut stats = self.stats.borrow_mut();
+        stats.fetch_time += fetch_time;
+        stats.fetch_calls += 1;
+        if fetched_row {
+            stats.rows_fetched += 1;
+        }
+    }
+
+    /// Passes the statistics of the current execution to the metrics sink
+    /// of the connection and closes the fetch span of a query
+    pub(super) fn finish_stats(&self) {
+        if !self.stats_pending.replace(false) {
+            return;
+        }
+        let fetch_span = self.fetch_span.replace(Span::none());
+        {
+            let stats = self.stats.borrow();
+            fetch_span
+                .record("rows", stats.rows_fetched)
+                .record("fetch_calls", stats.fetch_calls)
+                .record("fetch_time_us", spans::micros(stats.fetch_time));
+        }
+        if let Some(sink) = self.connection.metrics_sink() {
+            sink.record(&self.stats.borrow());
+        }
+    }
+
+    /// Everything to be done once before (repeatedly, in non-blocking mode)
+    /// calling `execute_raw`
+    pub(super) fn prepare_execution(&mut self) -> QueryResult<()> {
+        self.connection.check_usable()?;
+        if self.executed && self.is_ddl() {
+            self.reprepare()?;
+        }
+        if self.is_ddl() {
+            // oracle commits before executing a DDL statement, even if the
+            // statement itself fails
+            self.connection.note_implicit_commit();
+            self.connection.clear_compared_columns();
+        }
+        if !self.is_select() && !self.is_ddl() && !self.connection.commit_on_success() {
+            self.connection.set_pending_work(true);
+        }
+        self.executed = true;
+        Ok(())
+    }
+
+    /// Calls `OCIStmtExecute`, returning its unchecked status
+    pub(super) fn execute_raw(&self) -> i32 {
+        let iters = if self.is_select() { 0 } else { 1 };
+        let mode = if self.scrollable && self.is_select() {
+            ffi::OCI_STMT_SCROLLABLE_READONLY
+        } else if !self.is_select() && self.connection.commit_on_success() {
+            ffi::OCI_COMMIT_ON_SUCCESS
+        } else {
+            ffi::OCI_DEFAULT
+        };
         unsafe {
-            let status = ffi::OCIStmtExecute(
+            ffi::OCIStmtExecute(
                 self.connection.service_handle,
                 self.inner_statement,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
                 iters,
                 0,
                 ptr::null(),
                 ptr::null_mut(),
-                ffi::OCI_DEFAULT,
-            );
-            Self::check_error(self.connection.env.error_handle, status)?;
+                mode,
+            )
         }
-        Ok(())
     }
 
-    pub fn get_affected_rows(&self) -> QueryResult<usize> {
+    /// Executes this statement with a scrollable cursor, c.f.
+    /// `Cursor::seek`
+    pub(super) fn set_scrollable(&mut self, scrollable: bool) {
+        self.scrollable = scrollable;
+    }
+
+    pub(super) fn is_scrollable(&self) -> bool {
+        self.scrollable
+    }
+
+    /// The number of rows affected by the last execution
+    ///
+    /// Clients before 12.1 don't know `OCI_ATTR_UB8_ROW_COUNT`, they report
+    /// the count as 32 bit number.
+    pub fn get_affected_rows(&self) -> QueryResult<u64> {
+        let mut affected_rows: u64 = 0;
+        let status = unsafe {
+            ffi::OCIAttrGet(
+                self.inner_statement as *const _,
+                ffi::OCI_HTYPE_STMT,
+                (&mut affected_rows as *mut u64) as *mut _,
+                &mut 0,
+                ffi::OCI_ATTR_UB8_ROW_COUNT,
+                self.error_handle.handle,
+            )
+        };
+        if status == ffi::OCI_SUCCESS as i32 {
+            return Ok(affected_rows);
+        }
         let mut affected_rows: u32 = 0;
         unsafe {
             let status = ffi::OCIAttrGet(
@@ -149,11 +611,11 @@ impl Statement {
                 (&mut affected_rows as *mut u32) as *mut _,
                 &mut 0,
                 ffi::OCI_ATTR_ROW_COUNT,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
             );
-            Self::check_error(self.connection.env.error_handle, status)?;
+            self.check(status)?;
         }
-        Ok(affected_rows as usize)
+        Ok(u64::from(affected_rows))
     }
 
     fn get_column_count(&self) -> QueryResult<u32> {
@@ -165,108 +627,93 @@ impl Statement {
                 (&mut col_count as *mut u32) as *mut _,
                 &mut 0,
                 ffi::OCI_ATTR_PARAM_COUNT,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
             );
 
-            Self::check_error(self.connection.env.error_handle, status)?;
+            self.check(status)?;
         }
         Ok(col_count)
     }
 
-    fn get_attr_type_and_size(&self, col_handle: *mut ffi::OCIStmt) -> QueryResult<(u32, u32)> {
-        let mut tpe: u32 = 0;
-        let mut tpe_size: u32 = 0;
+    fn describe_column(&self, col_handle: *mut ffi::OCIStmt) -> QueryResult<ColumnInfo> {
+        let mut type_code = 0u16;
+        let mut precision = 0i16;
+        let mut scale = 0i8;
+        let mut char_length = 0u16;
+        let mut byte_length = 0u16;
+        let mut nullable = 0u8;
+        let mut charset_form = 0u8;
         unsafe {
-            let status = ffi::OCIAttrGet(
-                col_handle as *mut _,
-                ffi::OCI_DTYPE_PARAM,
-                (&mut tpe as *mut u32) as *mut _,
-                &mut 0,
-                ffi::OCI_ATTR_DATA_TYPE,
-                self.connection.env.error_handle,
-            );
-            Self::check_error(self.connection.env.error_handle, status)?;
-
-            match tpe {
-                ffi::SQLT_INT | ffi::SQLT_UIN => {
-                    tpe_size = 8;
-                }
-                ffi::SQLT_NUM => {
-                    let mut attributesize = 16u32; //sb2
-                    let mut scale = 0i8;
-                    let mut precision = 0i16;
-                    let status = ffi::OCIAttrGet(
-                        col_handle as *mut _,
-                        ffi::OCI_DTYPE_PARAM,
-                        (&mut precision as *mut i16) as *mut _,
-                        &mut attributesize as *mut u32,
-                        ffi::OCI_ATTR_PRECISION,
-                        self.connection.env.error_handle,
-                    );
-                    Self::check_error(self.connection.env.error_handle, status)?;
-                    let mut attributesize = 8u32; // sb1
-                    let status = ffi::OCIAttrGet(
-                        col_handle as *mut _,
-                        ffi::OCI_DTYPE_PARAM,
-                        (&mut scale as *mut i8) as *mut _,
-                        &mut attributesize as *mut u32,
-                        ffi::OCI_ATTR_SCALE,
-                        self.connection.env.error_handle,
-                    );
-                    Self::check_error(self.connection.env.error_handle, status)?;
-                    if scale == 0 {
-                        tpe_size = match precision {
-                            5 => 2,  // number(5) -> smallint
-                            10 => 4, // number(10) -> int
-                            19 => 8, // number(19) -> bigint
-                            _ => 21, // number(38) -> consume_all
-                        };
-                        tpe = ffi::SQLT_INT;
-                    } else {
-                        tpe = ffi::SQLT_FLT;
-                        tpe_size = 8;
-                    }
-                }
-                ffi::SQLT_BDOUBLE | ffi::SQLT_LNG | ffi::SQLT_IBDOUBLE => {
-                    tpe_size = 8;
-                    tpe = ffi::SQLT_BDOUBLE;
-                }
-                ffi::SQLT_FLT | ffi::SQLT_BFLOAT | ffi::SQLT_IBFLOAT => {
-                    tpe_size = 4;
-                    tpe = ffi::SQLT_BFLOAT;
-                }
-                ffi::SQLT_CHR
-                | ffi::SQLT_VCS
-                | ffi::SQLT_LVC
-                | ffi::SQLT_AFC
-                | ffi::SQLT_VST
-                | ffi::SQLT_ODT
-                | ffi::SQLT_DATE
-                | ffi::SQLT_TIMESTAMP
-                | ffi::SQLT_TIMESTAMP_TZ
-                | ffi::SQLT_TIMESTAMP_LTZ => {
-                    let mut length = 0u32;
-                    let status = ffi::OCIAttrGet(
-                        col_handle as *mut _,
-                        ffi::OCI_DTYPE_PARAM,
-                        (&mut tpe_size as *mut u32) as *mut _,
-                        &mut length as *mut u32,
-                        ffi::OCI_ATTR_CHAR_SIZE,
-                        self.connection.env.error_handle,
-                    );
-                    Self::check_error(self.connection.env.error_handle, status)?;
-                    //tpe_size += 1;
-                    tpe = ffi::SQLT_STR;
-                }
-                _ => {
-                    return Err(Error::DatabaseError(
-                        DatabaseErrorKind::__Unknown,
-                        Box::new(format!("unsupported type {}", tpe)),
-                    ))
-                }
-            }
+            self.get_param_attr(col_handle, &mut type_code, ffi::OCI_ATTR_DATA_TYPE)?;
+            self.get_param_attr(col_handle, &mut precision, ffi::OCI_ATTR_PRECISION)?;
+            self.get_param_attr(col_handle, &mut scale, ffi::OCI_ATTR_SCALE)?;
+            self.get_param_attr(col_handle, &mut char_length, ffi::OCI_ATTR_CHAR_SIZE)?;
+            self.get_param_attr(col_handle, &mut byte_length, ffi::OCI_ATTR_DATA_SIZE)?;
+            self.get_param_attr(col_handle, &mut nullable, ffi::OCI_ATTR_IS_NULL)?;
+            self.get_param_attr(col_handle, &mut charset_form, ffi::OCI_ATTR_CHARSET_FORM)?;
         }
-        Ok((tpe, tpe_size))
+        Ok(ColumnInfo {
+            name: self.get_column_name(col_handle)?,
+            type_code,
+            precision,
+            scale,
+            char_length,
+            byte_length,
+            nullable: nullable != 0,
+            national: u32::from(charset_form) == ffi::SQLCS_NCHAR,
+        })
+    }
+
+    unsafe fn get_param_attr<A>(
+        &self,
+        col_handle: *mut ffi::OCIStmt,
+        value: &mut A,
+        attr: u32,
+    ) -> QueryResult<()> {
+        let status = ffi::OCIAttrGet(
+            col_handle as *mut _,
+            ffi::OCI_DTYPE_PARAM,
+            (value as *mut A) as *mut _,
+            &mut 0,
+            attr,
+            self.error_handle.handle,
+        );
+        self.check(status)
+    }
+
+    fn get_attr_type_and_size(&self, column: &ColumnInfo) -> QueryResult<(u32, u32)> {
+        let fetch_type = self
+            .connection
+            .type_map()
+            .and_then(|type_map| type_map.fetch_type(column))
+            .or_else(|| default_fetch_type(column))
+            .ok_or_else(|| {
+                Error::DatabaseError(
+                    DatabaseErrorKind::__Unknown,
+                    Box::new(format!("unsupported type {}", column.type_code)),
+                )
+            })?;
+        let ret = match fetch_type {
+            FetchType::SmallInt => (ffi::SQLT_INT, 2),
+            FetchType::Integer => (ffi::SQLT_INT, 4),
+            FetchType::BigInt => (ffi::SQLT_INT, 8),
+            FetchType::LargeInt => (ffi::SQLT_INT, 16),
+            FetchType::Float => (ffi::SQLT_BFLOAT, 4),
+            FetchType::Double => (ffi::SQLT_BDOUBLE, 8),
+            FetchType::Text => match u32::from(column.type_code) {
+                ffi::SQLT_CHR | ffi::SQLT_VCS | ffi::SQLT_LVC | ffi::SQLT_AFC | ffi::SQLT_VST => (
+                    ffi::SQLT_STR,
+                    string_buffer_size(column, self.connection.env.max_char_width),
+                ),
+                // numbers and dates formatted by the NLS settings
+                _ => (ffi::SQLT_STR, FORMATTED_VALUE_SIZE),
+            },
+            // fractional seconds are cut off, c.f. `FetchType::TimestampTz`
+            FetchType::Timestamp => (ffi::SQLT_DAT, 7),
+            FetchType::TimestampTz => (ffi::SQLT_TIMESTAMP_TZ, 13),
+            FetchType::Binary => (ffi::SQLT_BIN, u32::from(column.byte_length)),
+        };
+        Ok(ret)
     }
 
     pub fn define(
@@ -275,30 +722,80 @@ impl Statement {
         tpe: u32,
         tpe_size: u32,
         col_number: usize,
+        info: ColumnInfo,
     ) -> QueryResult<()> {
         let mut v = Vec::with_capacity(tpe_size as usize);
         v.resize(tpe_size as usize, 0);
         let mut null_indicator: Box<i16> = Box::new(-1);
+        let mut return_code: Box<u16> = Box::new(0);
+        // numbers are fetched as `OCINumber` and converted after each fetch,
+        // which reports values not fitting into `v` instead of cutting them
+        let mut number = match (u32::from(info.type_code), tpe) {
+            (ffi::SQLT_NUM, ffi::SQLT_INT)
+            | (ffi::SQLT_NUM, ffi::SQLT_BFLOAT)
+            | (ffi::SQLT_NUM, ffi::SQLT_BDOUBLE) => Some(Box::new(ffi::OCINumber {
+                OCINumberPart: [0; ffi::OCI_NUMBER_SIZE as usize],
+            })),
+            _ => None,
+        };
+        // the same for timestamps with a time zone, which are fetched into a
+        // descriptor
+        let mut timestamp = match tpe {
+            ffi::SQLT_TIMESTAMP_TZ => Some(Box::new(self.connection.alloc_timestamp_tz())),
+            _ => None,
+        };
+        let (value_ptr, value_size, value_type) = match (&mut number, &mut timestamp) {
+            (&mut Some(ref mut number), _) => (
+                &mut **number as *mut ffi::OCINumber as *mut c_void,
+                ffi::OCI_NUMBER_SIZE as i32,
+                ffi::SQLT_VNU,
+            ),
+            (_, &mut Some(ref mut timestamp)) => (
+                &mut **timestamp as *mut *mut ffi::OCIDateTime as *mut c_void,
+                mem::size_of::<*mut ffi::OCIDateTime>() as i32,
+                tpe,
+            ),
+            _ => (v.as_ptr() as *mut c_void, v.len() as i32, tpe),
+        };
         let def = unsafe {
             let mut def = ptr::null_mut();
             let status = ffi::OCIDefineByPos(
                 self.inner_statement,
                 &mut def,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
                 col_number as u32,
-                v.as_ptr() as *mut _,
-                v.len() as i32,
-                tpe as libc::c_ushort,
+                value_ptr,
+                value_size,
+                value_type as libc::c_ushort,
                 &mut *null_indicator as *mut i16 as *mut c_void,
                 ptr::null_mut(),
-                ptr::null_mut(),
+                &mut *return_code,
                 ffi::OCI_DEFAULT,
             );
-            Self::check_error(self.connection.env.error_handle, status)?;
+            self.check(status)?;
+            if info.national {
+                // otherwise oracle converts the value to the database
+                // character set first
+                let mut form = ffi::SQLCS_NCHAR as u8;
+                let status = ffi::OCIAttrSet(
+                    def as *mut c_void,
+                    ffi::OCI_HTYPE_DEFINE,
+                    &mut form as *mut u8 as *mut c_void,
+                    0,
+                    ffi::OCI_ATTR_CHARSET_FORM,
+                    self.error_handle.handle,
+                );
+                self.check(status)?;
+            }
             def
         };
         if let Some(tpe) = ::oracle::types::OCIDataType::from_raw(tpe) {
-            fields.push(Field::new(def, v, null_indicator, tpe));
+            let field = Field::new(def, v, null_indicator, return_code, tpe, info);
+            fields.push(match (number, timestamp) {
+                (Some(number), _) => field.with_number(number),
+                (_, Some(timestamp)) => field.with_timestamp(timestamp),
+                _ => field,
+            });
         } else {
             return Err(Error::DatabaseError(
                 DatabaseErrorKind::__Unknown,
@@ -309,27 +806,148 @@ impl Statement {
         Ok(())
     }
 
+    /// Defines the `BLOB`, `CLOB` or `NCLOB` column `col_number` as a locator,
+    /// which oracle points to the value of the fetched row
+    ///
+    /// Returns the locator and the null indicator of the value.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn define_lob(&self, col_number: usize) -> QueryResult<(Box<Lob>, Box<i16>)> {
+        let col_handle = self.param_descriptor(col_number)?;
+        let info = self.describe_column(col_handle)?;
+        let lob_type = match u32::from(info.type_code) {
+            ffi::SQLT_BLOB => LobType::Blob,
+            ffi::SQLT_CLOB if info.national => LobType::NClob,
+            ffi::SQLT_CLOB => LobType::Clob,
+            tpe => {
+                return Err(Error::DatabaseError(
+                    DatabaseErrorKind::__Unknown,
+                    Box::new(format!(
+                        "Column {} is not a LOB but of type {}",
+                        info.name, tpe
+                    )),
+                ))
+            }
+        };
+        let mut lob = Box::new(Lob::new(&self.connection, lob_type)?);
+        let mut null_indicator: Box<i16> = Box::new(-1);
+        unsafe {
+            let mut def = ptr::null_mut();
+            let status = ffi::OCIDefineByPos(
+                self.inner_statement,
+                &mut def,
+                self.error_handle.handle,
+                col_number as u32,
+                &mut lob.locator as *mut *mut ffi::OCILobLocator as *mut c_void,
+                mem::size_of::<*mut ffi::OCILobLocator>() as i32,
+                lob.sqlt() as libc::c_ushort,
+                &mut *null_indicator as *mut i16 as *mut c_void,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ffi::OCI_DEFAULT,
+            );
+            self.check(status)?;
+        }
+        Ok((lob, null_indicator))
+    }
+
+    /// Defines a `LONG` or `LONG RAW` column, which is fetched piecewise
+    /// using `OCI_DYNAMIC_FETCH` as its values may have up to 2GB
+    fn define_long(
+        &self,
+        fields: &mut Vec<Field>,
+        tpe: OCIDataType,
+        col_number: usize,
+        info: ColumnInfo,
+    ) -> QueryResult<()> {
+        let raw_tpe = match tpe {
+            OCIDataType::Binary => ffi::SQLT_BIN,
+            _ => ffi::SQLT_CHR,
+        };
+        let mut long = Box::new(LongValue::new());
+        let def = unsafe {
+            let mut def = ptr::null_mut();
+            let status = ffi::OCIDefineByPos(
+                self.inner_statement,
+                &mut def,
+                self.error_handle.handle,
+                col_number as u32,
+                ptr::null_mut(),
+                i32::MAX,
+                raw_tpe as libc::c_ushort,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ffi::OCI_DYNAMIC_FETCH,
+            );
+            self.check(status)?;
+            let status = ffi::OCIDefineDynamic(
+                def,
+                self.error_handle.handle,
+                &mut *long as *mut LongValue as *mut c_void,
+                Some(define_callback),
+            );
+            self.check(status)?;
+            def
+        };
+        fields.push(Field::new_long(def, long, tpe, info));
+        Ok(())
+    }
+
     fn define_column(&self, mut fields: &mut Vec<Field>, col_number: usize) -> QueryResult<()> {
-        let col_handle = unsafe {
+        let col_handle = self.param_descriptor(col_number)?;
+        let info = self.describe_column(col_handle)?;
+        match u32::from(info.type_code) {
+            ffi::SQLT_LNG => {
+                return self.define_long(fields, OCIDataType::String, col_number, info)
+            }
+            ffi::SQLT_LBI => {
+                return self.define_long(fields, OCIDataType::Binary, col_number, info)
+            }
+            _ => {}
+        }
+        let (tpe, tpe_size): (u32, u32) = self.get_attr_type_and_size(&info)?;
+
+        self.define(fields, tpe, tpe_size, col_number, info)?;
+        Ok(())
+    }
+
+    fn param_descriptor(&self, col_number: usize) -> QueryResult<*mut ffi::OCIStmt> {
+        unsafe {
             let mut parameter_descriptor: *mut ffi::OCIStmt = ptr::null_mut();
             let status = ffi::OCIParamGet(
                 self.inner_statement as *const _,
                 ffi::OCI_HTYPE_STMT,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
                 (&mut parameter_descriptor as *mut *mut ffi::OCIStmt) as *mut _,
                 col_number as u32,
             );
-            Self::check_error(self.connection.env.error_handle, status)?;
-            parameter_descriptor
-        };
-
-        let (tpe, tpe_size): (u32, u32) = self.get_attr_type_and_size(col_handle)?;
+            self.check(status)?;
+            Ok(parameter_descriptor)
+        }
+    }
 
-        self.define(&mut fields, tpe, tpe_size, col_number)?;
-        Ok(())
+    fn get_column_name(&self, col_handle: *mut ffi::OCIStmt) -> QueryResult<String> {
+        let mut name: *mut u8 = ptr::null_mut();
+        let mut name_len = 0u32;
+        unsafe {
+            let status = ffi::OCIAttrGet(
+                col_handle as *mut _,
+                ffi::OCI_DTYPE_PARAM,
+                (&mut name as *mut *mut u8) as *mut _,
+                &mut name_len,
+                ffi::OCI_ATTR_NAME,
+                self.error_handle.handle,
+            );
+            self.check(status)?;
+            if name.is_null() {
+                return Ok(String::new());
+            }
+            let name = slice::from_raw_parts(name, name_len as usize);
+            Ok(String::from_utf8_lossy(name).into_owned())
+        }
     }
 
-    fn define_all_columns(&self) -> QueryResult<Vec<Field>> {
+    pub(super) fn define_all_columns(&self) -> QueryResult<Vec<Field>> {
         let col_count = self.get_column_count()?;
         let mut fields = Vec::<Field>::with_capacity(col_count as usize);
         for i in 0..col_count as usize {
@@ -339,14 +957,383 @@ impl Statement {
         Ok(fields)
     }
 
-    pub fn run_with_cursor<ST, T>(&self) -> QueryResult<Cursor<ST, T>> {
-        self.run()?;
+    /// Executes the statement and defines the columns of its result set
+    ///
+    /// Statements without a result set (e.g. `BEGIN NULL; END;`) have no
+    /// columns to define, so the cursors built for them are empty.
+    fn run_and_define(&mut self) -> QueryResult<Vec<Field>> {
+        if !self.is_ref_cursor {
+            self.run()?;
+        }
+        if !self.is_select() {
+            return Ok(Vec::new());
+        }
         let fields = self.define_all_columns()?;
+        if fields.is_empty() {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!(
+                    "`{}` returned a result set without columns",
+                    self.sql
+                )),
+            ));
+        }
+        Ok(fields)
+    }
 
+    pub fn run_with_cursor<ST, T>(&mut self) -> QueryResult<Cursor<'_, ST, T>> {
+        let fields = self.run_and_define()?;
         Ok(Cursor::new(self, fields))
     }
 
+    pub fn run_with_named_cursor<T>(&mut self) -> QueryResult<NamedCursor<'_, T>> {
+        let fields = self.run_and_define()?;
+        Ok(NamedCursor::new(self, fields))
+    }
+
+    pub fn run_with_raw_cursor(&mut self) -> QueryResult<RawCursor<'_>> {
+        let fields = self.run_and_define()?;
+        Ok(RawCursor::new(self, fields))
+    }
+
+    /// Executes the statement and returns a cursor owning it, unlike the
+    /// cursor of `run_with_cursor`, which borrows the statement
+    pub fn into_cursor<ST, T>(mut self) -> QueryResult<OwnedCursor<ST, T>> {
+        let fields = self.run_and_define()?;
+        Ok(OwnedCursor::new(Rc::new(self), fields))
+    }
+
     pub fn bind(&mut self, tpe: OCIDataType, value: Option<Vec<u8>>) -> QueryResult<()> {
+        self.bind_value(BindValue::from_sql(tpe, value))
+    }
+
+    /// Binds `value` to the next placeholder
+    pub fn bind_value(&mut self, value: BindValue) -> QueryResult<()> {
+        let value = self.check_compared_column(value);
+        #[cfg(feature = "debug-binds")]
+        self.bound_parameters
+            .push(BoundParameter::new(self.bind_index + 1, &value));
+        if let BindValue::Lob(lob) = value {
+            return self.bind_lob(Box::new(lob));
+        }
+        let (tpe, value) = value.into_raw();
+        if let Some(ref value) = value {
+            let lob_type = match tpe {
+                OCIDataType::Char | OCIDataType::String => Some(LobType::Clob),
+                OCIDataType::NationalChar => Some(LobType::NClob),
+                OCIDataType::Binary => Some(LobType::Blob),
+                _ => None,
+            };
+            match lob_type {
+                Some(lob_type) if value.len() > LOB_BIND_THRESHOLD => {
+                    return self.bind_large_value(lob_type, value);
+                }
+                _ => {}
+            }
+        }
+        self.bind_buffer(tpe, value, None).map(|_| ())
+    }
+
+    // Comparing a bind with a column of another type converts one of them,
+    // converting the column (e.g. `TO_BINARY_DOUBLE("PRICE") = :1`) prevents
+    // the use of an index on it. Such binds are recorded with the statistics
+    // of the execution. Integers compared with `NUMBER` columns larger than
+    // `NUMBER(18)` are bound as `NUMBER`.
+    fn check_compared_column(&mut self, value: BindValue) -> BindValue {
+        let bind_type = match value {
+            BindValue::Int(_) => OCIDataType::Int,
+            BindValue::Float(_) => OCIDataType::BFloat,
+            BindValue::Double(_) => OCIDataType::BDouble,
+            BindValue::NationalText(_) => OCIDataType::NationalChar,
+            _ => return value,
+        };
+        let position = self.bind_index + 1;
+        let column = match compared_column(&self.sql, position) {
+            Some(column) => column.to_owned(),
+            None => return value,
+        };
+        let connection = &self.connection;
+        let info = connection.compared_column_info(&column, || {
+            let table = &column[..column.rfind('.')?];
+            let sql = format!("SELECT {} FROM {}", column, table);
+            let mut stmt = Statement::prepare(connection, &sql).ok()?;
+            stmt.describe().ok()?.into_iter().next()
+        });
+        let info = match info {
+            Some(info) => info,
+            None => return value,
+        };
+        if converts_column(bind_type, &info) {
+            self.implicit_conversions.push(ImplicitConversion {
+                position,
+                bind_type,
+                column,
+                column_type: info.data_type(),
+            });
+        }
+        match value {
+            BindValue::Int(v) if u32::from(info.type_code) == ffi::SQLT_NUM && !fits_i64(&info) => {
+                BindValue::Raw(OCIDataType::Numeric, oracle_number(v))
+            }
+            value => value,
+        }
+    }
+
+    // values too large for a `VARCHAR2` or `RAW` are written to a temporary
+    // LOB, which is bound instead of a buffer holding the whole value
+    fn bind_large_value(&mut self, lob_type: LobType, value: &[u8]) -> QueryResult<()> {
+        let mut lob = Box::new(Lob::temporary(&self.connection, lob_type)?);
+        lob.append(value)?;
+        self.bind_lob(lob)
+    }
+
+    fn bind_lob(&mut self, mut lob: Box<Lob>) -> QueryResult<()> {
+        let ret = self.bind_lob_locator(&mut lob);
+        self.lobs.push(lob);
+        ret
+    }
+
+    /// Binds the locator of `lob`, which must not be moved or dropped until
+    /// the statement was executed
+    pub(super) fn bind_lob_locator(&mut self, lob: &mut Lob) -> QueryResult<()> {
+        self.bind_index += 1;
+        let mut bndp: *mut ffi::OCIBind = ptr::null_mut();
+        unsafe {
+            let status = ffi::OCIBindByPos(
+                self.inner_statement,
+                &mut bndp,
+                self.error_handle.handle,
+                self.bind_index,
+                &mut lob.locator as *mut *mut ffi::OCILobLocator as *mut c_void,
+                mem::size_of::<*mut ffi::OCILobLocator>() as i32,
+                lob.sqlt() as u16,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                ffi::OCI_DEFAULT,
+            );
+            self.check(status)?;
+            if lob.lob_type() == LobType::NClob {
+                let mut form = ffi::SQLCS_NCHAR as u8;
+                let status = ffi::OCIAttrSet(
+                    bndp as *mut c_void,
+                    ffi::OCI_HTYPE_BIND,
+                    &mut form as *mut u8 as *mut c_void,
+                    0,
+                    ffi::OCI_ATTR_CHARSET_FORM,
+                    self.error_handle.handle,
+                );
+                self.check(status)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds a buffer of `capacity` bytes written by oracle, i.e. an `OUT`
+    /// parameter of a PL/SQL block or the target of `RETURNING ... INTO`
+    ///
+    /// The capacity has to match the size diesel uses for `tpe`, e.g. `8`
+    /// for integers, for text it is the maximal length in bytes.
+    pub fn bind_out(&mut self, tpe: OCIDataType, capacity: usize) -> QueryResult<OutBind> {
+        self.bind_in_out(tpe, None, capacity)
+    }
+
+    /// Binds `value` in a buffer of at least `capacity` bytes, which oracle
+    /// overwrites with the returned value, i.e. an `IN OUT` parameter
+    pub fn bind_in_out(
+        &mut self,
+        tpe: OCIDataType,
+        value: Option<Vec<u8>>,
+        capacity: usize,
+    ) -> QueryResult<OutBind> {
+        #[cfg(feature = "debug-binds")]
+        self.bound_parameters.push(match value {
+            Some(ref v) => {
+                let value = BindValue::from_sql(tpe, Some(v.clone()));
+                BoundParameter::new(self.bind_index + 1, &value)
+            }
+            None => BoundParameter::output(self.bind_index + 1, tpe),
+        });
+        let index = self.bind_buffer(tpe, value, Some(capacity))?;
+        Ok(OutBind { index, tpe })
+    }
+
+    /// Binds `len` buffers of `capacity` bytes written by oracle, i.e. an
+    /// `OUT` parameter of a PL/SQL associative array (`TABLE OF ... INDEX BY
+    /// BINARY_INTEGER`) returning at most `len` elements
+    pub fn bind_out_array(
+        &mut self,
+        tpe: OCIDataType,
+        capacity: usize,
+        len: usize,
+    ) -> QueryResult<OutArray> {
+        #[cfg(feature = "debug-binds")]
+        self.bound_parameters
+            .push(BoundParameter::output(self.bind_index + 1, tpe));
+        self.bind_index += 1;
+        let mut array = ArrayBuffer {
+            buffer: vec![0; capacity * len].into_boxed_slice(),
+            capacity,
+            lengths: vec![0; len].into_boxed_slice(),
+            indicators: vec![-1; len].into_boxed_slice(),
+            len: Box::new(0),
+        };
+        let mut bndp: *mut ffi::OCIBind = ptr::null_mut();
+        unsafe {
+            let status = ffi::OCIBindByPos(
+                self.inner_statement,
+                &mut bndp,
+                self.error_handle.handle,
+                self.bind_index,
+                array.buffer.as_mut_ptr() as *mut c_void,
+                capacity as i32,
+                tpe.to_raw() as u16,
+                array.indicators.as_mut_ptr() as *mut c_void,
+                array.lengths.as_mut_ptr(),
+                ptr::null_mut(),
+                len as u32,
+                &mut *array.len,
+                ffi::OCI_DEFAULT,
+            );
+            self.arrays.push(array);
+            self.check(status)?;
+            self.set_charset(bndp, tpe)?;
+        }
+        Ok(OutArray {
+            index: self.arrays.len() - 1,
+            tpe,
+        })
+    }
+
+    /// Drops all bound values, the next bind is bound to the first
+    /// placeholder again
+    ///
+    /// This allows executing a prepared (e.g. cached) statement repeatedly
+    /// with new values, binding by position replaces the previous values.
+    /// All placeholders need to be bound again before the next execution.
+    pub fn reset_binds(&mut self) {
+        self.bind_index = 0;
+        self.buffers.clear();
+        self.lengths.clear();
+        self.indicators.clear();
+        self.lobs.clear();
+        self.arrays.clear();
+        self.free_ref_cursors();
+        self.ref_cursors.clear();
+        self.implicit_conversions.clear();
+        #[cfg(feature = "debug-binds")]
+        self.bound_parameters.clear();
+    }
+
+    // frees the handles of REF CURSOR binds which were not taken
+    fn free_ref_cursors(&self) {
+        for handle in &self.ref_cursors {
+            if !handle.is_null() {
+                unsafe {
+                    ffi::OCIHandleFree(**handle as *mut _, ffi::OCI_HTYPE_STMT);
+                }
+            }
+        }
+    }
+
+    /// Binds a `SYS_REFCURSOR` `OUT` parameter
+    ///
+    /// Returns the index of the bind to be passed to `take_ref_cursor` after
+    /// the statement was executed.
+    pub fn bind_ref_cursor(&mut self) -> QueryResult<usize> {
+        self.bind_index += 1;
+        let mut bndp: *mut ffi::OCIBind = ptr::null_mut();
+        let mut handle = Box::new(self.connection.alloc_statement_handle());
+        unsafe {
+            let status = ffi::OCIBindByPos(
+                self.inner_statement,
+                &mut bndp,
+                self.error_handle.handle,
+                self.bind_index,
+                &mut *handle as *mut *mut ffi::OCIStmt as *mut c_void,
+                0,
+                ffi::SQLT_RSET as u16,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                ffi::OCI_DEFAULT,
+            );
+            self.ref_cursors.push(handle);
+            self.check(status)?;
+        }
+        Ok(self.ref_cursors.len() - 1)
+    }
+
+    /// The statement oracle returned for the REF CURSOR bound at `index`
+    ///
+    /// # Panics
+    ///
+    /// If the cursor at `index` was already taken.
+    pub fn take_ref_cursor(&mut self, index: usize) -> Statement {
+        let handle = ::std::mem::replace(&mut *self.ref_cursors[index], ptr::null_mut());
+        assert!(!handle.is_null(), "REF CURSOR was already taken");
+        Statement {
+            connection: self.connection.clone(),
+            inner_statement: handle,
+            error_handle: self.connection.env.alloc_error_handle(),
+            sql: self.sql.clone(),
+            stmt_type: ffi::OCI_STMT_SELECT as u16,
+            executed: true,
+            bind_index: 0,
+            buffers: Vec::new(),
+            lengths: Vec::new(),
+            indicators: Vec::new(),
+            ref_cursors: Vec::new(),
+            lobs: Vec::new(),
+            arrays: Vec::new(),
+            is_ref_cursor: true,
+            scrollable: false,
+            prepare_time: Duration::from_secs(0),
+            stats: RefCell::new(StatementStats::default()),
+            stats_pending: Cell::new(false),
+            fetch_span: RefCell::new(Span::none()),
+            implicit_conversions: Vec::new(),
+            #[cfg(feature = "debug-binds")]
+            bound_parameters: Vec::new(),
+        }
+    }
+
+    /// The value oracle returned for the `OUT` parameter bound at `index`
+    fn out_value(&self, index: usize) -> Option<&[u8]> {
+        if *self.indicators[index] == -1 {
+            None
+        } else {
+            let len = *self.lengths[index] as usize;
+            Some(&self.buffers[index][..len])
+        }
+    }
+
+    /// The elements oracle returned for the array bound at `index`
+    fn out_array(&self, index: usize) -> Vec<Option<&[u8]>> {
+        let array = &self.arrays[index];
+        let len = (*array.len as usize).min(array.lengths.len());
+        (0..len)
+            .map(|i| {
+                if array.indicators[i] == -1 {
+                    None
+                } else {
+                    let start = i * array.capacity;
+                    Some(&array.buffer[start..start + array.lengths[i] as usize])
+                }
+            })
+            .collect()
+    }
+
+    fn bind_buffer(
+        &mut self,
+        tpe: OCIDataType,
+        value: Option<Vec<u8>>,
+        capacity: Option<usize>,
+    ) -> QueryResult<usize> {
         self.bind_index += 1;
         let mut bndp = ptr::null_mut() as *mut ffi::OCIBind;
         let mut is_null = false;
@@ -354,18 +1341,35 @@ impl Statement {
         // reaching OCIBindByPos
         let (mut buf, size): (Box<[u8]>, i32) = if let Some(mut value) = value {
             let len = value.len() as i32;
+            if let Some(capacity) = capacity {
+                if value.len() < capacity {
+                    value.resize(capacity, 0);
+                }
+            }
             (value.into_boxed_slice(), len)
         } else {
+            // a null value still needs a buffer matching the bind type,
+            // otherwise oracle cannot resolve the type of the parameter
+            // (e.g. for overloaded functions)
             is_null = true;
-            (Vec::new().into_boxed_slice(), 0)
+            let len = capacity.unwrap_or(0).max(tpe.null_buffer_size());
+            (vec![0; len].into_boxed_slice(), 0)
         };
         let mut nullind: Box<ffi::OCIInd> = if is_null { Box::new(-1) } else { Box::new(0) };
+        let mut length = Box::new(size as u16);
+        // the length is only passed for OUT parameters, as it is limited to
+        // 64k, while input values may be larger
+        let alenp = if capacity.is_some() {
+            &mut *length as *mut u16
+        } else {
+            ptr::null_mut()
+        };
 
         unsafe {
             let status = ffi::OCIBindByPos(
                 self.inner_statement,
                 &mut bndp,
-                self.connection.env.error_handle,
+                self.error_handle.handle,
                 self.bind_index,
                 buf.as_mut_ptr() as *mut c_void,
                 buf.len() as i32,
@@ -375,7 +1379,7 @@ impl Statement {
                     tpe.to_raw() as u16
                 },
                 &mut *nullind as *mut i16 as *mut c_void,
-                ptr::null_mut(),
+                alenp,
                 ptr::null_mut(),
                 0,
                 ptr::null_mut(),
@@ -383,22 +1387,40 @@ impl Statement {
             );
 
             self.buffers.push(buf);
-            self.sizes.push(size);
+            self.lengths.push(length);
             self.indicators.push(nullind);
 
-            Self::check_error(self.connection.env.error_handle, status)?;
+            self.check(status)?;
+            self.set_charset(bndp, tpe)?;
+        }
+        Ok(self.buffers.len() - 1)
+    }
 
-            if tpe == OCIDataType::Char {
-                let mut cs_id = self.connection.env.cs_id;
-                ffi::OCIAttrSet(
-                    bndp as *mut c_void,
-                    ffi::OCI_HTYPE_BIND,
-                    &mut cs_id as *mut u16 as *mut c_void,
-                    0,
-                    ffi::OCI_ATTR_CHARSET_ID,
-                    self.connection.env.error_handle,
-                );
-            }
+    // binds strings in the character set of the client
+    unsafe fn set_charset(&self, bndp: *mut ffi::OCIBind, tpe: OCIDataType) -> QueryResult<()> {
+        if tpe == OCIDataType::NationalChar {
+            // the form needs to be set before the character set
+            let mut form = ffi::SQLCS_NCHAR as u8;
+            let status = ffi::OCIAttrSet(
+                bndp as *mut c_void,
+                ffi::OCI_HTYPE_BIND,
+                &mut form as *mut u8 as *mut c_void,
+                0,
+                ffi::OCI_ATTR_CHARSET_FORM,
+                self.error_handle.handle,
+            );
+            self.check(status)?;
+        }
+        if tpe == OCIDataType::Char || tpe == OCIDataType::NationalChar {
+            let mut cs_id = self.connection.env.cs_id;
+            ffi::OCIAttrSet(
+                bndp as *mut c_void,
+                ffi::OCI_HTYPE_BIND,
+                &mut cs_id as *mut u16 as *mut c_void,
+                0,
+                ffi::OCI_ATTR_CHARSET_ID,
+                self.error_handle.handle,
+            );
         }
         Ok(())
     }
@@ -406,17 +1428,222 @@ impl Statement {
 
 impl Drop for Statement {
     fn drop(&mut self) {
-        unsafe {
-            let status = ffi::OCIStmtRelease(
-                self.inner_statement,
-                self.connection.env.error_handle,
-                ptr::null(),
-                0,
-                ffi::OCI_DEFAULT,
-            );
-            if let Some(err) = Self::check_error(self.connection.env.error_handle, status).err() {
-                println!("{:?}", err);
-            }
+        self.finish_stats();
+        self.free_ref_cursors();
+        self.release();
+    }
+}
+
+// The size of the buffer receiving a value of a character column, which is
+// converted to the character set of the client. `char_length` is the
+// maximal number of characters (`VARCHAR2(10 CHAR)`), `byte_length` the size
+// in the character set of the database (`VARCHAR2(10 BYTE)`). As each
+// character takes at least one byte, a value has at most `byte_length`
+// characters in either case.
+fn string_buffer_size(column: &ColumnInfo, max_char_width: u32) -> u32 {
+    let char_length = u32::from(column.char_length);
+    let byte_length = u32::from(column.byte_length);
+    let max_chars = match (char_length, byte_length) {
+        (0, byte_length) => byte_length,
+        (char_length, 0) => char_length,
+        (char_length, byte_length) => char_length.min(byte_length),
+    };
+    // `SQLT_STR` values are terminated by a null byte
+    max_chars * max_char_width + 1
+}
+
+// The column the placeholder `:index` is compared with, if it is a column
+// of a table (e.g. `"ITEMS"."PRICE" = :1`) as diesel qualifies them
+fn compared_column(sql: &str, index: u32) -> Option<&str> {
+    const OPERATORS: [&str; 7] = ["<>", "!=", "<=", ">=", "=", "<", ">"];
+    let placeholder = format!(":{}", index);
+    let start = sql
+        .match_indices(&*placeholder)
+        .map(|m| m.0)
+        .find(|&i| !sql[i + placeholder.len()..].starts_with(|c: char| c.is_ascii_digit()))?;
+    let before = sql[..start].trim_end();
+    if let Some(op) = OPERATORS.iter().find(|op| before.ends_with(*op)) {
+        let before = before[..before.len() - op.len()].trim_end();
+        let column = &before[before.len() - qualified_name_len(before.as_bytes().iter().rev())..];
+        if is_column(column) {
+            return Some(column);
         }
     }
+    let after = sql[start + placeholder.len()..].trim_start();
+    let op = OPERATORS.iter().find(|op| after.starts_with(*op))?;
+    let after = after[op.len()..].trim_start();
+    let column = &after[..qualified_name_len(after.as_bytes().iter())];
+    if is_column(column) {
+        Some(column)
+    } else {
+        None
+    }
+}
+
+// a qualified name, which is not a number like `1.5`
+fn is_column(name: &str) -> bool {
+    name.contains('.') && !name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+// The length of the dotted identifiers (quoted or not) at the start of
+// `bytes`, which may be reversed
+fn qualified_name_len<'a, I>(bytes: I) -> usize
+where
+    I: Iterator<Item = &'a u8>,
+{
+    #[derive(Clone, Copy)]
+    enum State {
+        Start,
+        Unquoted,
+        Quoted,
+        Closed,
+    }
+    let is_identifier = |b: u8| b.is_ascii_alphanumeric() || b"_$#".contains(&b);
+    let (mut len, mut end, mut state) = (0, 0, State::Start);
+    for &b in bytes {
+        len += 1;
+        state = match (state, b) {
+            (State::Start, b'"') => State::Quoted,
+            (State::Start, b) | (State::Unquoted, b) if is_identifier(b) => {
+                end = len;
+                State::Unquoted
+            }
+            (State::Unquoted, b'.') | (State::Closed, b'.') => State::Start,
+            (State::Quoted, b'"') => {
+                end = len;
+                State::Closed
+            }
+            (State::Quoted, _) => State::Quoted,
+            _ => break,
+        };
+    }
+    end
+}
+
+// Whether oracle converts a column of type `column` compared with a bind of
+// `bind_type` instead of the bind
+fn converts_column(bind_type: OCIDataType, column: &ColumnInfo) -> bool {
+    use oracle::types::OCIDataType::*;
+    match (u32::from(column.type_code), bind_type) {
+        (ffi::SQLT_NUM, BFloat) | (ffi::SQLT_NUM, BDouble) => true,
+        (ffi::SQLT_IBFLOAT, BDouble) | (ffi::SQLT_BFLOAT, BDouble) => true,
+        (ffi::SQLT_CHR, Int) | (ffi::SQLT_AFC, Int) => true,
+        (ffi::SQLT_CHR, BFloat) | (ffi::SQLT_AFC, BFloat) => true,
+        (ffi::SQLT_CHR, BDouble) | (ffi::SQLT_AFC, BDouble) => true,
+        (ffi::SQLT_CHR, NationalChar) | (ffi::SQLT_AFC, NationalChar) => !column.national,
+        _ => false,
+    }
+}
+
+// whether all values of a `NUMBER` column fit into an 8 byte integer
+fn fits_i64(column: &ColumnInfo) -> bool {
+    column.scale == 0 && column.precision > 0 && column.precision <= 18
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compared_column, converts_column, string_buffer_size};
+    use byteorder::{ByteOrder, NativeEndian};
+    use oci_sys as ffi;
+    use oracle::connection::{BindValue, ColumnInfo};
+    use oracle::types::OCIDataType;
+
+    // the bytes an integer serialized by diesel is bound with
+    fn widen_integer(value: Vec<u8>) -> Vec<u8> {
+        BindValue::from_sql(OCIDataType::Int, Some(value))
+            .into_raw()
+            .1
+            .unwrap()
+    }
+
+    #[test]
+    fn check_widen_integer() {
+        let mut value = vec![0; 2];
+        NativeEndian::write_i16(&mut value, -42);
+        assert_eq!(NativeEndian::read_i64(&widen_integer(value)), -42);
+
+        let mut value = vec![0; 4];
+        NativeEndian::write_i32(&mut value, i32::MAX);
+        assert_eq!(
+            NativeEndian::read_i64(&widen_integer(value)),
+            i64::from(i32::MAX)
+        );
+
+        let mut value = vec![0; 8];
+        NativeEndian::write_i64(&mut value, i64::MIN);
+        assert_eq!(NativeEndian::read_i64(&widen_integer(value)), i64::MIN);
+    }
+
+    #[test]
+    fn check_string_buffer_size() {
+        let column = |char_length, byte_length| ColumnInfo {
+            name: "NAME".to_owned(),
+            type_code: 1,
+            precision: 0,
+            scale: 0,
+            char_length,
+            byte_length,
+            nullable: true,
+            national: false,
+        };
+        // VARCHAR2(10 CHAR) in an AL32UTF8 database
+        assert_eq!(string_buffer_size(&column(10, 40), 4), 41);
+        // VARCHAR2(10 BYTE), which may hold 10 single byte characters of
+        // the database that are multi byte characters of the client
+        assert_eq!(string_buffer_size(&column(10, 10), 4), 41);
+        assert_eq!(string_buffer_size(&column(0, 10), 3), 31);
+        assert_eq!(string_buffer_size(&column(10, 40), 1), 11);
+    }
+
+    #[test]
+    fn check_compared_column() {
+        let sql = "SELECT \"ITEMS\".\"ID\" FROM \"ITEMS\" WHERE \"ITEMS\".\"PRICE\" >= :1 \
+                   AND :2 = \"DIESEL\".\"ITEMS\".\"NAME\" AND items.qty<>:11 \
+                   AND \"ITEMS\".\"ID\" + :3 > 0 AND 1.5 = :4";
+        assert_eq!(compared_column(sql, 1), Some("\"ITEMS\".\"PRICE\""));
+        assert_eq!(
+            compared_column(sql, 2),
+            Some("\"DIESEL\".\"ITEMS\".\"NAME\"")
+        );
+        assert_eq!(compared_column(sql, 11), Some("items.qty"));
+        assert_eq!(compared_column(sql, 3), None);
+        assert_eq!(compared_column(sql, 4), None);
+        assert_eq!(compared_column(sql, 5), None);
+        assert_eq!(compared_column("\"ID\" = :1", 1), None);
+    }
+
+    #[test]
+    fn check_converts_column() {
+        let column = |type_code: u32, national| ColumnInfo {
+            name: "C".to_owned(),
+            type_code: type_code as u16,
+            precision: 10,
+            scale: 0,
+            char_length: 0,
+            byte_length: 22,
+            nullable: true,
+            national,
+        };
+        let number = column(ffi::SQLT_NUM, false);
+        assert!(converts_column(OCIDataType::BFloat, &number));
+        assert!(converts_column(OCIDataType::BDouble, &number));
+        assert!(!converts_column(OCIDataType::Int, &number));
+        assert!(!converts_column(OCIDataType::Char, &number));
+
+        let binary_float = column(ffi::SQLT_IBFLOAT, false);
+        assert!(!converts_column(OCIDataType::BFloat, &binary_float));
+        assert!(converts_column(OCIDataType::BDouble, &binary_float));
+        assert!(!converts_column(
+            OCIDataType::BDouble,
+            &column(ffi::SQLT_IBDOUBLE, false)
+        ));
+
+        let varchar = column(ffi::SQLT_CHR, false);
+        assert!(converts_column(OCIDataType::Int, &varchar));
+        assert!(converts_column(OCIDataType::NationalChar, &varchar));
+        assert!(!converts_column(
+            OCIDataType::NationalChar,
+            &column(ffi::SQLT_CHR, true)
+        ));
+    }
 }
@@ -0,0 +1,73 @@
+use diesel::connection::{Connection, TransactionManager};
+use diesel::result::QueryResult;
+use std::fmt::Debug;
+
+use super::OciConnection;
+
+/// A transaction which is always rolled back when dropped
+///
+/// This allows integration tests to run against a shared schema without
+/// persisting their changes, even if the test panics.
+///
+/// Keep in mind that oracle implicitly commits the current transaction for
+/// each DDL statement (e.g. `CREATE TABLE`). Changes made before a DDL
+/// statement cannot be rolled back anymore, a warning is logged if this
+/// happened. DDL statements executed from PL/SQL (`EXECUTE IMMEDIATE`) are
+/// not detected.
+pub struct TestTransaction<'a> {
+    conn: &'a OciConnection,
+    implicit_commits: u64,
+}
+
+impl<'a> TestTransaction<'a> {
+    /// Begins a new transaction (or a savepoint if there is already an open
+    /// transaction) on `conn`
+    pub fn begin(conn: &'a OciConnection) -> QueryResult<Self> {
+        conn.transaction_manager().begin_transaction(conn)?;
+        Ok(TestTransaction {
+            conn,
+            implicit_commits: conn.raw().implicit_commits(),
+        })
+    }
+
+    /// Whether a DDL statement committed the transaction since it was begun
+    pub fn was_implicitly_committed(&self) -> bool {
+        self.conn.raw().implicit_commits() != self.implicit_commits
+    }
+}
+
+impl<'a> Drop for TestTransaction<'a> {
+    fn drop(&mut self) {
+        if self.was_implicitly_committed() {
+            warn!(
+                "A DDL statement committed the test transaction, \
+                 preceding changes were not rolled back"
+            );
+        }
+        let manager = self.conn.transaction_manager();
+        if let Err(e) = manager.rollback_transaction(self.conn) {
+            warn!("Failed to roll back the test transaction: {:?}", e);
+        }
+    }
+}
+
+/// Runs `f` in a transaction which is always rolled back, c.f.
+/// `TestTransaction`
+///
+/// This is the equivalent of `Connection::test_transaction`, but the
+/// transaction is rolled back even if `f` panics.
+///
+/// # Panics
+///
+/// If the transaction cannot be started or `f` returns an error.
+pub fn test_transaction<T, E, F>(conn: &OciConnection, f: F) -> T
+where
+    F: FnOnce() -> Result<T, E>,
+    E: Debug,
+{
+    let _transaction = TestTransaction::begin(conn).expect("Failed to begin the test transaction");
+    match f() {
+        Ok(value) => value,
+        Err(e) => panic!("Test transaction failed: {:?}", e),
+    }
+}
@@ -0,0 +1,97 @@
+use std::env;
+
+use diesel::dsl::sql;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use diesel::sql_types::Text;
+use diesel::{Connection, RunQueryDsl};
+
+#[cfg(feature = "chrono-time")]
+use chrono::FixedOffset;
+
+use super::OciConnection;
+
+impl OciConnection {
+    /// The time zone of the database (`DBTIMEZONE`), used to store
+    /// `TIMESTAMP WITH LOCAL TIME ZONE` values
+    pub fn db_timezone(&self) -> QueryResult<String> {
+        sql::<Text>("SELECT DBTIMEZONE FROM dual").get_result(self)
+    }
+
+    /// The time zone of the current session (`SESSIONTIMEZONE`)
+    ///
+    /// Oracle converts `TIMESTAMP WITH LOCAL TIME ZONE` values from and to
+    /// this time zone, so this is the zone of every `NaiveDateTime` read from
+    /// or written to such a column.
+    pub fn session_timezone(&self) -> QueryResult<String> {
+        sql::<Text>("SELECT SESSIONTIMEZONE FROM dual").get_result(self)
+    }
+
+    /// The time zone the oracle client initializes new sessions with
+    ///
+    /// This is the value of `ORA_SDTZ`, `None` means the client uses the
+    /// time zone of the operating system.
+    pub fn client_timezone() -> Option<String> {
+        env::var("ORA_SDTZ").ok()
+    }
+
+    /// Changes the time zone of the current session, e.g. to `UTC` or
+    /// `+02:00`
+    pub fn set_session_timezone(&self, timezone: &str) -> QueryResult<()> {
+        if timezone.contains('\'') {
+            return Err(Error::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(format!("Invalid time zone {}", timezone)),
+            ));
+        }
+        Connection::execute(
+            self,
+            &format!("ALTER SESSION SET TIME_ZONE = '{}'", timezone),
+        )
+        .map(|_| ())
+    }
+
+    /// The current utc offset of the session time zone
+    ///
+    /// Use this to convert the `NaiveDateTime` values of
+    /// `TIMESTAMP WITH LOCAL TIME ZONE` columns into an absolute point in
+    /// time.
+    #[cfg(feature = "chrono-time")]
+    pub fn session_utc_offset(&self) -> QueryResult<FixedOffset> {
+        let offset = sql::<Text>("SELECT TZ_OFFSET(SESSIONTIMEZONE) FROM dual")
+            .get_result::<String>(self)?;
+        parse_utc_offset(&offset).ok_or_else(|| {
+            Error::DeserializationError(
+                format!("Could not parse time zone offset {}", offset).into(),
+            )
+        })
+    }
+}
+
+// parses the `+hh:mi` format returned by `TZ_OFFSET`
+#[cfg(feature = "chrono-time")]
+fn parse_utc_offset(offset: &str) -> Option<FixedOffset> {
+    let offset = offset.trim();
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let mut parts = offset[1..].splitn(2, ':');
+    let hours = parts.next()?.parse::<i32>().ok()?;
+    let minutes = parts.next()?.parse::<i32>().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(all(test, feature = "chrono-time"))]
+mod tests {
+    use super::parse_utc_offset;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn check_parse_utc_offset() {
+        assert_eq!(parse_utc_offset("+00:00"), FixedOffset::east_opt(0));
+        assert_eq!(parse_utc_offset("+05:30"), FixedOffset::east_opt(19800));
+        assert_eq!(parse_utc_offset("-08:00 "), FixedOffset::east_opt(-28800));
+        assert_eq!(parse_utc_offset("Europe/Berlin"), None);
+    }
+}
@@ -1,7 +1,8 @@
+use super::spans;
 use super::OciConnection;
 use diesel::connection::SimpleConnection;
 use diesel::connection::TransactionManager;
-use diesel::result::QueryResult;
+use diesel::result::{Error, QueryResult};
 use oci_sys as ffi;
 use std::cell::Cell;
 
@@ -26,12 +27,17 @@ impl OCITransactionManager {
         query
     }
 
+    // statements of open transactions must not be committed by autocommit
+    fn sync_autocommit(&self, conn: &OciConnection) {
+        conn.raw()
+            .set_in_transaction(self.transaction_depth.get() > 0);
+    }
+
     /// Begin a transaction with custom SQL
     ///
     /// This is used by connections to implement more complex transaction APIs
     /// to set things such as isolation levels.
     /// Returns an error if already inside of a transaction.
-    #[allow(dead_code)]
     pub fn begin_transaction_sql<Conn>(&self, conn: &Conn, sql: &str) -> QueryResult<()>
     where
         Conn: SimpleConnection,
@@ -49,11 +55,13 @@ impl OCITransactionManager {
 impl TransactionManager<OciConnection> for OCITransactionManager {
     fn begin_transaction(&self, conn: &OciConnection) -> QueryResult<()> {
         let transaction_depth = self.transaction_depth.get();
+        let span = spans::transaction("begin", transaction_depth);
+        let _entered = span.enter();
         let query = if transaction_depth == 0 {
             let _status = unsafe {
                 ffi::OCITransStart(
-                    conn.raw.service_handle,
-                    conn.raw.env.error_handle,
+                    conn.raw().service_handle,
+                    conn.raw().env.error_handle,
                     0,
                     ffi::OCI_TRANS_NEW,
                 )
@@ -62,7 +70,9 @@ impl TransactionManager<OciConnection> for OCITransactionManager {
         } else {
             conn.batch_execute(&format!("SAVEPOINT diesel_savepoint_{}", transaction_depth))
         };
-        self.change_transaction_depth(1, query)
+        let ret = self.change_transaction_depth(1, query);
+        self.sync_autocommit(conn);
+        ret
     }
 
     fn rollback_transaction(&self, conn: &OciConnection) -> QueryResult<()> {
@@ -70,14 +80,17 @@ impl TransactionManager<OciConnection> for OCITransactionManager {
         // all preceding DML will be commited with a DDL statement !!!
         // c.f. https://docs.oracle.com/cd/E25054_01/server.1111/e25789/transact.htm#sthref1318
         let transaction_depth = self.transaction_depth.get();
+        let span = spans::transaction("rollback", transaction_depth);
+        let _entered = span.enter();
         let query = if transaction_depth == 1 {
             let _status = unsafe {
                 ffi::OCITransRollback(
-                    conn.raw.service_handle,
-                    conn.raw.env.error_handle,
+                    conn.raw().service_handle,
+                    conn.raw().env.error_handle,
                     ffi::OCI_DEFAULT,
                 )
             };
+            conn.raw().set_pending_work(false);
             Ok(())
         } else {
             conn.batch_execute(&format!(
@@ -85,19 +98,24 @@ impl TransactionManager<OciConnection> for OCITransactionManager {
                 transaction_depth - 1
             ))
         };
-        self.change_transaction_depth(-1, query)
+        let ret = self.change_transaction_depth(-1, query);
+        self.sync_autocommit(conn);
+        ret
     }
 
     fn commit_transaction(&self, conn: &OciConnection) -> QueryResult<()> {
         let transaction_depth = self.transaction_depth.get();
+        let span = spans::transaction("commit", transaction_depth);
+        let _entered = span.enter();
         let query = if transaction_depth <= 1 {
             let _status = unsafe {
                 ffi::OCITransCommit(
-                    conn.raw.service_handle,
-                    conn.raw.env.error_handle,
+                    conn.raw().service_handle,
+                    conn.raw().env.error_handle,
                     ffi::OCI_DEFAULT,
                 )
             };
+            conn.raw().set_pending_work(false);
             Ok(())
         } else {
             conn.batch_execute(&format!(
@@ -105,10 +123,91 @@ impl TransactionManager<OciConnection> for OCITransactionManager {
                 transaction_depth - 1
             ))
         };
-        self.change_transaction_depth(-1, query)
+        let ret = self.change_transaction_depth(-1, query);
+        self.sync_autocommit(conn);
+        ret
     }
 
     fn get_transaction_depth(&self) -> u32 {
         self.transaction_depth.get() as u32
     }
 }
+
+/// The kind of a transaction started by
+/// `OciConnection::transaction_with_behavior`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionBehavior {
+    /// `SET TRANSACTION READ ONLY`, all queries see the data committed
+    /// before the transaction started, changing data fails with `ORA-01456`
+    ReadOnly,
+    /// `SET TRANSACTION ISOLATION LEVEL SERIALIZABLE`, changing rows
+    /// modified by transactions committed after this one started fails with
+    /// `ORA-08177`
+    Serializable,
+}
+
+impl TransactionBehavior {
+    fn sql(self) -> &'static str {
+        match self {
+            TransactionBehavior::ReadOnly => "SET TRANSACTION READ ONLY",
+            TransactionBehavior::Serializable => "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+        }
+    }
+}
+
+impl OciConnection {
+    /// Like `Connection::transaction`, but sets the kind of the transaction
+    /// first
+    ///
+    /// Returns `Error::AlreadyInTransaction` inside of another transaction,
+    /// as oracle only accepts `SET TRANSACTION` as the first statement of a
+    /// transaction.
+    ///
+    /// ```ignore
+    /// let report = conn.transaction_with_behavior(TransactionBehavior::ReadOnly, || {
+    ///     // both queries see the same snapshot
+    ///     let orders = orders::table.count().get_result::<i64>(&conn)?;
+    ///     let items = items::table.count().get_result::<i64>(&conn)?;
+    ///     Ok::<_, Error>((orders, items))
+    /// })?;
+    /// ```
+    pub fn transaction_with_behavior<T, E, F>(
+        &self,
+        behavior: TransactionBehavior,
+        f: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<Error>,
+    {
+        let transaction_manager = &self.transaction_manager;
+        // `SET TRANSACTION` must not be committed by autocommit
+        self.raw().set_in_transaction(true);
+        let ret = transaction_manager.begin_transaction_sql(self, behavior.sql());
+        transaction_manager.sync_autocommit(self);
+        ret?;
+        match f() {
+            Ok(value) => {
+                transaction_manager.commit_transaction(self)?;
+                Ok(value)
+            }
+            Err(e) => {
+                transaction_manager.rollback_transaction(self)?;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl OciConnection {
+    /// Switches autocommit on or off, c.f. `OciConnectionBuilder::autocommit`
+    ///
+    /// Autocommit does not apply to statements of `Connection::transaction`.
+    pub fn set_autocommit(&self, enabled: bool) {
+        self.raw().set_autocommit(enabled);
+    }
+
+    pub fn autocommit(&self) -> bool {
+        self.raw().autocommit()
+    }
+}
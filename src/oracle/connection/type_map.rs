@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use oci_sys as ffi;
+
+use super::column_info::ColumnInfo;
+use super::OciConnection;
+
+/// The representation a column is fetched in, named after the diesel type
+/// able to read it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FetchType {
+    /// A 2 byte integer
+    SmallInt,
+    /// A 4 byte integer
+    Integer,
+    /// An 8 byte integer
+    BigInt,
+    /// A 16 byte integer converted from a `NUMBER` with all its digits,
+    /// which is read as `LargeInt`
+    LargeInt,
+    /// A 4 byte floating point number
+    Float,
+    /// An 8 byte floating point number
+    Double,
+    /// Text in the character set of the client, e.g. to read a `NUMBER` as
+    /// `Numeric`
+    Text,
+    /// The 7 byte format of `DATE`, which is read as `Timestamp`
+    Timestamp,
+    /// The local time in the 7 byte format of `DATE`, followed by the
+    /// nanoseconds (4 bytes) and the offset to UTC in hours and minutes (1
+    /// byte each), which is read as `TimestampTz`, `TimestampLtz` or
+    /// `Timestamp` (keeping the fractional seconds)
+    TimestampTz,
+    Binary,
+}
+
+/// Decides how the columns of result sets are fetched, c.f.
+/// `OciConnection::set_type_map`
+///
+/// ```ignore
+/// // fetch NUMBER(38) (e.g. INTEGER) columns as text to read them as Numeric
+/// struct LargeNumbers;
+///
+/// impl TypeMap for LargeNumbers {
+///     fn fetch_type(&self, column: &ColumnInfo) -> Option<FetchType> {
+///         match column.data_type() {
+///             Some(OCIDataType::Numeric) if column.precision > 19 => Some(FetchType::Text),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait TypeMap: Send + Sync {
+    /// The type to fetch `column` as, `None` to use the default (c.f.
+    /// `default_fetch_type`)
+    fn fetch_type(&self, column: &ColumnInfo) -> Option<FetchType>;
+}
+
+impl OciConnection {
+    /// Overrides how columns of all following queries are fetched
+    pub fn set_type_map(&self, type_map: Arc<dyn TypeMap>) {
+        self.raw().set_type_map(Some(type_map));
+    }
+
+    pub fn clear_type_map(&self) {
+        self.raw().set_type_map(None);
+    }
+}
+
+/// The type a column is fetched as if there is no `TypeMap`, `None` for
+/// unsupported types
+///
+/// Integral numbers are fetched as the smallest type holding all their
+/// values, i.e. up to `NUMBER(4)` as `SmallInt`, up to `NUMBER(9)` as
+/// `Integer`, up to `NUMBER(18)` as `BigInt` and larger ones as `LargeInt`.
+pub fn default_fetch_type(column: &ColumnInfo) -> Option<FetchType> {
+    let fetch_type = match u32::from(column.type_code) {
+        ffi::SQLT_INT | ffi::SQLT_UIN => FetchType::BigInt,
+        ffi::SQLT_NUM if column.scale == 0 => integer_type(column.precision),
+        ffi::SQLT_NUM | ffi::SQLT_BDOUBLE | ffi::SQLT_IBDOUBLE => FetchType::Double,
+        ffi::SQLT_FLT | ffi::SQLT_BFLOAT | ffi::SQLT_IBFLOAT => FetchType::Float,
+        ffi::SQLT_CHR | ffi::SQLT_VCS | ffi::SQLT_LVC | ffi::SQLT_AFC | ffi::SQLT_VST => {
+            FetchType::Text
+        }
+        ffi::SQLT_DAT | ffi::SQLT_ODT | ffi::SQLT_DATE => FetchType::Timestamp,
+        // `TIMESTAMP WITH LOCAL TIME ZONE` values are in the session time
+        // zone, c.f. `OciConnectionBuilder::session_timezone`. `TIMESTAMP`
+        // values are fetched the same way to keep their fractional seconds,
+        // with the offset of the session time zone.
+        ffi::SQLT_TIMESTAMP | ffi::SQLT_TIMESTAMP_TZ | ffi::SQLT_TIMESTAMP_LTZ => {
+            FetchType::TimestampTz
+        }
+        ffi::SQLT_BIN => FetchType::Binary,
+        _ => return None,
+    };
+    Some(fetch_type)
+}
+
+// numbers without a precision (e.g. the result of `COUNT(*)`) are fetched
+// as `BigInt` as well, values which don't fit are reported when they are
+// fetched
+pub(crate) fn integer_type(precision: i16) -> FetchType {
+    match precision {
+        1..=4 => FetchType::SmallInt,
+        5..=9 => FetchType::Integer,
+        19..=38 => FetchType::LargeInt,
+        _ => FetchType::BigInt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_fetch_type, FetchType};
+    use oci_sys as ffi;
+    use oracle::connection::ColumnInfo;
+
+    fn number(precision: i16, scale: i8) -> ColumnInfo {
+        ColumnInfo {
+            name: "N".to_owned(),
+            type_code: ffi::SQLT_NUM as u16,
+            precision,
+            scale,
+            char_length: 0,
+            byte_length: 22,
+            nullable: true,
+            national: false,
+        }
+    }
+
+    #[test]
+    fn check_integer_precisions() {
+        let types = [1, 3, 4, 5, 7, 9, 10, 12, 18, 19, 38, 0]
+            .iter()
+            .map(|&p| default_fetch_type(&number(p, 0)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                FetchType::SmallInt,
+                FetchType::SmallInt,
+                FetchType::SmallInt,
+                FetchType::Integer,
+                FetchType::Integer,
+                FetchType::Integer,
+                FetchType::BigInt,
+                FetchType::BigInt,
+                FetchType::BigInt,
+                FetchType::LargeInt,
+                FetchType::LargeInt,
+                FetchType::BigInt,
+            ]
+        );
+        assert_eq!(default_fetch_type(&number(10, 2)), Some(FetchType::Double));
+        assert_eq!(
+            default_fetch_type(&number(0, -127)),
+            Some(FetchType::Double)
+        );
+    }
+}
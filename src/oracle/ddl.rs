@@ -0,0 +1,142 @@
+//! Oracle specific ddl statements
+//!
+//! ```ignore
+//! use diesel_oci::oracle::ddl;
+//!
+//! ddl::drop_table_if_exists(users::table).execute(&conn)?;
+//! ddl::truncate(posts::table).execute(&conn)?;
+//! ```
+
+use diesel::query_builder::{AstPass, QueryBuilder, QueryFragment, QueryId};
+use diesel::query_source::Table;
+use diesel::result::QueryResult;
+use diesel::RunQueryDsl;
+
+use super::backend::Oracle;
+use super::query_builder::OciQueryBuilder;
+
+// ORA-00942: table or view does not exist
+const TABLE_DOES_NOT_EXIST: i32 = -942;
+
+/// `TRUNCATE TABLE table`, c.f. `truncate`
+#[derive(Debug, Clone, Copy)]
+pub struct Truncate<T> {
+    table: T,
+}
+
+/// Removes all rows of `table` (`TRUNCATE TABLE`)
+///
+/// Unlike `diesel::delete` this is a ddl statement, which commits the
+/// current transaction and cannot be rolled back.
+pub fn truncate<T: Table>(table: T) -> Truncate<T> {
+    Truncate { table }
+}
+
+impl<T> QueryFragment<Oracle> for Truncate<T>
+where
+    T: Table,
+    T::FromClause: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+        out.push_sql("TRUNCATE TABLE ");
+        self.table.from_clause().walk_ast(out.reborrow())
+    }
+}
+
+impl<T> QueryId for Truncate<T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, Conn> RunQueryDsl<Conn> for Truncate<T> {}
+
+/// `DROP TABLE table`, ignoring a missing table, c.f. `drop_table_if_exists`
+#[derive(Debug, Clone, Copy)]
+pub struct DropTableIfExists<T> {
+    table: T,
+    cascade_constraints: bool,
+}
+
+/// Drops `table` unless it does not exist
+///
+/// Oracle has no `DROP TABLE IF EXISTS`, so the statement is run by a
+/// PL/SQL block ignoring `ORA-00942`.
+pub fn drop_table_if_exists<T: Table>(table: T) -> DropTableIfExists<T> {
+    DropTableIfExists {
+        table,
+        cascade_constraints: false,
+    }
+}
+
+impl<T> DropTableIfExists<T> {
+    /// Drops the foreign keys of other tables referencing this one as well
+    /// (`CASCADE CONSTRAINTS`)
+    pub fn cascade_constraints(self) -> Self {
+        DropTableIfExists {
+            cascade_constraints: true,
+            ..self
+        }
+    }
+}
+
+impl<T> QueryFragment<Oracle> for DropTableIfExists<T>
+where
+    T: Table,
+    T::FromClause: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+        // the statement is a string literal of the PL/SQL block
+        let mut query_builder = OciQueryBuilder::new();
+        query_builder.push_sql("DROP TABLE ");
+        self.table.from_clause().to_sql(&mut query_builder)?;
+        if self.cascade_constraints {
+            query_builder.push_sql(" CASCADE CONSTRAINTS");
+        }
+        let statement = query_builder.finish().replace('\'', "''");
+        out.push_sql(&format!(
+            "BEGIN EXECUTE IMMEDIATE '{}'; EXCEPTION WHEN OTHERS THEN \
+             IF SQLCODE != {} THEN RAISE; END IF; END;",
+            statement, TABLE_DOES_NOT_EXIST
+        ));
+        Ok(())
+    }
+}
+
+impl<T> QueryId for DropTableIfExists<T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, Conn> RunQueryDsl<Conn> for DropTableIfExists<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{drop_table_if_exists, truncate};
+    use diesel::debug_query;
+    use oracle::backend::Oracle;
+
+    table! {
+        users (id) {
+            id -> Integer,
+        }
+    }
+
+    #[test]
+    fn check_ddl_sql() {
+        assert_eq!(
+            debug_query::<Oracle, _>(&truncate(users::table)).to_string(),
+            "TRUNCATE TABLE \"USERS\" -- binds: []"
+        );
+        assert_eq!(
+            debug_query::<Oracle, _>(&drop_table_if_exists(users::table).cascade_constraints())
+                .to_string(),
+            "BEGIN EXECUTE IMMEDIATE 'DROP TABLE \"USERS\" CASCADE CONSTRAINTS'; \
+             EXCEPTION WHEN OTHERS THEN IF SQLCODE != -942 THEN RAISE; END IF; END; \
+             -- binds: []"
+        );
+    }
+}
@@ -2,7 +2,15 @@ extern crate dotenv;
 
 mod backend;
 pub mod connection;
+pub mod ddl;
 mod query_builder;
+pub mod query_dsl;
+pub mod schema;
+pub mod sql_types;
 mod types;
 
+pub use self::backend::Oracle;
+pub use self::query_builder::IdentifierQuoting;
+pub use self::types::OCIDataType;
+
 
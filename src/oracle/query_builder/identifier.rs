@@ -0,0 +1,168 @@
+/// How the query builder writes the identifiers of tables and columns,
+/// c.f. `OciConnectionBuilder::identifier_quoting`
+///
+/// Oracle folds unquoted identifiers to uppercase, while diesel expects
+/// identifiers to be quoted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IdentifierQuoting {
+    /// Quotes all identifiers in uppercase, so the lowercase names of
+    /// `table!` match the names of unquoted ddl
+    #[default]
+    Uppercase,
+    /// Quotes all identifiers with their case, for schemas created with
+    /// quoted lowercase or mixed case names
+    Always,
+    /// Writes identifiers without quotes, oracle folds them to uppercase
+    ///
+    /// Reserved words (e.g. a column `size`) and identifiers that are
+    /// invalid without quotes are still quoted in uppercase.
+    Never,
+    /// Quotes identifiers in uppercase if they are all lowercase or all
+    /// uppercase and with their case otherwise, for schemas mixing unquoted
+    /// and quoted mixed case names
+    Smart,
+}
+
+impl IdentifierQuoting {
+    /// The name used by the `identifier_quoting` parameter of connection
+    /// urls
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            IdentifierQuoting::Uppercase => "uppercase",
+            IdentifierQuoting::Always => "always",
+            IdentifierQuoting::Never => "never",
+            IdentifierQuoting::Smart => "smart",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        [
+            IdentifierQuoting::Uppercase,
+            IdentifierQuoting::Always,
+            IdentifierQuoting::Never,
+            IdentifierQuoting::Smart,
+        ]
+        .iter()
+        .cloned()
+        .find(|quoting| quoting.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// the reserved words of oracle sql, which are only valid identifiers in
+// quotes, sorted for `binary_search`
+#[rustfmt::skip]
+const RESERVED_WORDS: &[&str] = &[
+    "ACCESS", "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "AUDIT", "BETWEEN", "BY",
+    "CHAR", "CHECK", "CLUSTER", "COLUMN", "COLUMN_VALUE", "COMMENT", "COMPRESS", "CONNECT",
+    "CREATE", "CURRENT", "DATE", "DECIMAL", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP",
+    "ELSE", "EXCLUSIVE", "EXISTS", "FILE", "FLOAT", "FOR", "FROM", "GRANT", "GROUP", "HAVING",
+    "IDENTIFIED", "IMMEDIATE", "IN", "INCREMENT", "INDEX", "INITIAL", "INSERT", "INTEGER",
+    "INTERSECT", "INTO", "IS", "LEVEL", "LIKE", "LOCK", "LONG", "MAXEXTENTS", "MINUS",
+    "MLSLABEL", "MODE", "MODIFY", "NESTED_TABLE_ID", "NOAUDIT", "NOCOMPRESS", "NOT", "NOWAIT",
+    "NULL", "NUMBER", "OF", "OFFLINE", "ON", "ONLINE", "OPTION", "OR", "ORDER", "PCTFREE",
+    "PRIOR", "PUBLIC", "RAW", "RENAME", "RESOURCE", "REVOKE", "ROW", "ROWID", "ROWNUM", "ROWS",
+    "SELECT", "SESSION", "SET", "SHARE", "SIZE", "SMALLINT", "START", "SUCCESSFUL", "SYNONYM",
+    "SYSDATE", "TABLE", "THEN", "TO", "TRIGGER", "UID", "UNION", "UNIQUE", "UPDATE", "USER",
+    "VALIDATE", "VALUES", "VARCHAR", "VARCHAR2", "VIEW", "WHENEVER", "WHERE", "WITH",
+];
+
+/// Whether `identifier` is a reserved word of oracle sql (e.g. `NUMBER`,
+/// `DATE`, `LEVEL` or `SIZE`), in any case
+pub(crate) fn is_reserved_word(identifier: &str) -> bool {
+    RESERVED_WORDS
+        .binary_search(&&*identifier.to_uppercase())
+        .is_ok()
+}
+
+/// Whether `identifier` is valid without quotes, apart from reserved words
+pub(crate) fn is_simple_identifier(identifier: &str) -> bool {
+    let mut chars = identifier.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '#')
+}
+
+/// `identifier` as written to the sql with `quoting`
+pub(crate) fn quote_identifier(quoting: IdentifierQuoting, identifier: &str) -> String {
+    // TODO: check if there is a better way for escaping strings
+    let escaped = identifier.replace("`", "``");
+    match quoting {
+        IdentifierQuoting::Uppercase => format!("\"{}\"", escaped.to_uppercase()),
+        IdentifierQuoting::Always => format!("\"{}\"", escaped),
+        IdentifierQuoting::Never
+            if is_reserved_word(&escaped) || !is_simple_identifier(&escaped) =>
+        {
+            format!("\"{}\"", escaped.to_uppercase())
+        }
+        IdentifierQuoting::Never => escaped,
+        IdentifierQuoting::Smart => {
+            let has_lower = escaped.chars().any(char::is_lowercase);
+            let has_upper = escaped.chars().any(char::is_uppercase);
+            if has_lower && has_upper {
+                format!("\"{}\"", escaped)
+            } else {
+                format!("\"{}\"", escaped.to_uppercase())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_reserved_word, quote_identifier, IdentifierQuoting, RESERVED_WORDS};
+
+    #[test]
+    fn check_quote_identifier() {
+        let uppercase = IdentifierQuoting::default();
+        assert_eq!(quote_identifier(uppercase, "users"), "\"USERS\"");
+        assert_eq!(quote_identifier(uppercase, "UserId"), "\"USERID\"");
+
+        let always = IdentifierQuoting::Always;
+        assert_eq!(quote_identifier(always, "users"), "\"users\"");
+        assert_eq!(quote_identifier(always, "UserId"), "\"UserId\"");
+
+        let never = IdentifierQuoting::Never;
+        assert_eq!(quote_identifier(never, "users"), "users");
+        assert_eq!(quote_identifier(never, "UserId"), "UserId");
+        assert_eq!(quote_identifier(never, "number"), "\"NUMBER\"");
+        assert_eq!(quote_identifier(never, "Level"), "\"LEVEL\"");
+        assert_eq!(quote_identifier(never, "user name"), "\"USER NAME\"");
+        assert_eq!(quote_identifier(never, "_id"), "\"_ID\"");
+
+        let smart = IdentifierQuoting::Smart;
+        assert_eq!(quote_identifier(smart, "users"), "\"USERS\"");
+        assert_eq!(quote_identifier(smart, "TST_NUM"), "\"TST_NUM\"");
+        assert_eq!(quote_identifier(smart, "UserId"), "\"UserId\"");
+        assert_eq!(quote_identifier(smart, "user_2"), "\"USER_2\"");
+    }
+
+    #[test]
+    fn check_quoting_names() {
+        for &quoting in &[
+            IdentifierQuoting::Uppercase,
+            IdentifierQuoting::Always,
+            IdentifierQuoting::Never,
+            IdentifierQuoting::Smart,
+        ] {
+            assert_eq!(IdentifierQuoting::from_name(quoting.name()), Some(quoting));
+        }
+        assert_eq!(
+            IdentifierQuoting::from_name("Never"),
+            Some(IdentifierQuoting::Never)
+        );
+        assert_eq!(IdentifierQuoting::from_name("lower"), None);
+    }
+
+    #[test]
+    fn check_reserved_words() {
+        assert!(RESERVED_WORDS.windows(2).all(|w| w[0] < w[1]));
+        for word in &["NUMBER", "date", "Level", "size", "uid"] {
+            assert!(is_reserved_word(word), "{}", word);
+        }
+        for word in &["numbers", "id", "name", "created_at"] {
+            assert!(!is_reserved_word(word), "{}", word);
+        }
+    }
+}
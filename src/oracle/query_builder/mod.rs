@@ -1,45 +1,529 @@
 use super::backend::Oracle;
+use super::connection::ServerVersion;
 
-use diesel::query_builder::QueryBuilder;
+use diesel::query_builder::{AstPass, QueryBuilder};
 use diesel::result::Error as DieselError;
+use std::cell::{Cell, RefCell};
+use std::mem;
 
+mod identifier;
 //mod insert_statement;
 
+pub(crate) use self::identifier::is_simple_identifier;
+pub use self::identifier::IdentifierQuoting;
+
+// A select statement pushed to the query builder, c.f. `add_from_dual`
+#[derive(Debug, Clone, Copy)]
+struct SelectScope {
+    // the number of open parentheses at the start of the statement
+    depth: u32,
+    has_from: bool,
+    // the number of aliased columns, c.f. `push_column_aliases`
+    aliases: Option<u32>,
+}
+
+thread_local! {
+    // the server the sql built on this thread is sent to, c.f.
+    // `with_server_version`
+    static SERVER_VERSION: Cell<Option<ServerVersion>> = const { Cell::new(None) };
+    // the quoting of the connection the sql built on this thread is sent
+    // to, c.f. `with_identifier_quoting`
+    static IDENTIFIER_QUOTING: Cell<IdentifierQuoting> = const {
+        Cell::new(IdentifierQuoting::Uppercase)
+    };
+    // the owner of unqualified tables of the connection the sql built on
+    // this thread is sent to, c.f. `with_default_owner`
+    static DEFAULT_OWNER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// restores the previous version, even if building the sql panicked
+struct ServerVersionGuard(Option<ServerVersion>);
+
+impl Drop for ServerVersionGuard {
+    fn drop(&mut self) {
+        SERVER_VERSION.with(|v| v.set(self.0));
+    }
+}
+
+/// Runs `f` building sql for a server of `version`
+///
+/// diesel creates the query builder itself, so query fragments choosing
+/// their syntax by the server version (c.f. `server_version`) read it from
+/// the thread building them.
+pub(crate) fn with_server_version<R, F>(version: ServerVersion, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = ServerVersionGuard(SERVER_VERSION.with(|v| v.replace(Some(version))));
+    f()
+}
+
+// restores the previous quoting, even if building the sql panicked
+struct IdentifierQuotingGuard(IdentifierQuoting);
+
+impl Drop for IdentifierQuotingGuard {
+    fn drop(&mut self) {
+        IDENTIFIER_QUOTING.with(|q| q.set(self.0));
+    }
+}
+
+/// Runs `f` building sql with identifiers quoted by `quoting`, which is
+/// configured per connection like the server version
+pub(crate) fn with_identifier_quoting<R, F>(quoting: IdentifierQuoting, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = IdentifierQuotingGuard(IDENTIFIER_QUOTING.with(|q| q.replace(quoting)));
+    f()
+}
+
+// restores the previous owner, even if building the sql panicked
+struct DefaultOwnerGuard(Option<String>);
+
+impl Drop for DefaultOwnerGuard {
+    fn drop(&mut self) {
+        DEFAULT_OWNER.with(|o| *o.borrow_mut() = self.0.take());
+    }
+}
+
+/// Runs `f` building sql which qualifies tables without a schema with
+/// `owner`, which is configured per connection like the identifier quoting
+pub(crate) fn with_default_owner<R, F>(owner: Option<&str>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let owner = owner.map(str::to_owned);
+    let _guard = DefaultOwnerGuard(DEFAULT_OWNER.with(|o| o.replace(owner)));
+    f()
+}
+
+/// The version of the server the sql is built for, `None` outside of
+/// `with_server_version` (e.g. for `debug_query`), which means the latest
+/// version
+pub(crate) fn server_version() -> Option<ServerVersion> {
+    SERVER_VERSION.with(Cell::get)
+}
+
+// prefix of a fragment passing an optimizer hint to the query builder, c.f.
+// `push_hint`
+const HINT_MARKER: &str = "\u{1}HINT ";
+
+/// Adds the optimizer `hint` (e.g. `RESULT_CACHE`) directly after the
+/// `SELECT` keyword of the statement walked next
+///
+/// diesel renders the keyword itself, so the hint is handed to the query
+/// builder, which adds it once the statement starts. Hints of other passes
+/// than building the sql are dropped by diesel.
+pub(crate) fn push_hint(out: &mut AstPass<Oracle>, hint: &str) {
+    out.push_sql(&format!("{}{}", HINT_MARKER, hint));
+}
+
+// prefix of a fragment naming a common table expression, c.f.
+// `push_local_name`
+const LOCAL_NAME_MARKER: &str = "\u{1}LOCAL ";
+
+/// Marks `name` as the name of a common table expression of the statement,
+/// which is not qualified with the default owner like the tables
+pub(crate) fn push_local_name(out: &mut AstPass<Oracle>, name: &str) {
+    out.push_sql(&format!("{}{}", LOCAL_NAME_MARKER, name));
+}
+
+/// A piece of sql pushed by a query fragment, c.f.
+/// `OciQueryBuilder::with_fragments`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SqlFragment {
+    /// sql pushed as is, after the given number of open parentheses
+    Sql(String, u32),
+    /// a quoted identifier
+    Identifier(String),
+    /// a bind parameter
+    Bind,
+}
+
+impl SqlFragment {
+    pub(crate) fn is_sql(&self, sql: &str, depth: u32) -> bool {
+        match *self {
+            SqlFragment::Sql(ref s, d) => s == sql && d == depth,
+            _ => false,
+        }
+    }
+}
+
+// a fragment making the query builder alias the columns of the next
+// statement, c.f. `push_column_aliases`
+const ALIAS_MARKER: &str = "\u{1}ALIAS";
+
+/// Gives the columns of the statement walked next the unique names `"C1"`,
+/// `"C2"`, ...
+///
+/// Queries wrapped as `SELECT * FROM (query)` fail with `ORA-00918` if the
+/// query selects columns of the same name, e.g. the ids of joined tables.
+pub(crate) fn push_column_aliases(out: &mut AstPass<Oracle>) {
+    out.push_sql(ALIAS_MARKER);
+}
+
 #[derive(Default)]
 pub struct OciQueryBuilder {
     pub sql: String,
     bind_idx: u32,
+    selects: Vec<SelectScope>,
+    depth: u32,
+    quote: Option<char>,
+    // the hint for the next statement starting at the given depth
+    hint: Option<(u32, String)>,
+    // the depth of the next statement whose columns are aliased
+    alias_columns: Option<u32>,
+    // the pushed pieces of sql, c.f. `with_fragments`
+    fragments: Option<Vec<SqlFragment>>,
+    // the quoted default owner, taken from the thread with the first
+    // identifier as diesel finishes the sql later, c.f. `with_default_owner`
+    owner: Option<String>,
+    // the identifiers of the name being pushed with a default owner (e.g.
+    // `"USERS"."ID"`) and whether a `.` follows them, c.f. `write_name`
+    name: Vec<String>,
+    name_dot: bool,
+    // the names of common table expressions, c.f. `push_local_name`
+    local_names: Vec<String>,
 }
 
 impl OciQueryBuilder {
     pub fn new() -> Self {
+        OciQueryBuilder::default()
+    }
+
+    /// A query builder keeping the pieces of sql pushed by the query
+    /// fragments, which gives their structure (e.g. the columns of an
+    /// insert) without parsing the sql
+    pub(crate) fn with_fragments() -> Self {
         OciQueryBuilder {
-            sql: String::new(),
-            bind_idx: 0,
+            fragments: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// The pushed pieces of sql, empty unless created by `with_fragments`
+    pub(crate) fn fragments(&self) -> &[SqlFragment] {
+        self.fragments.as_ref().map_or(&[], |f| &f[..])
+    }
+
+    fn record(&mut self, fragment: SqlFragment) {
+        if let Some(ref mut fragments) = self.fragments {
+            fragments.push(fragment);
+        }
+    }
+
+    // diesel renders `diesel::select(expr)` without a `FROM` clause, which
+    // oracle does not allow. The statements are tracked while they are
+    // pushed, a statement without `FROM` gets a `FROM DUAL` before its first
+    // other clause or at its end.
+    fn add_from_dual(&mut self) {
+        self.alias_column();
+        if let Some(scope) = self.selects.last_mut() {
+            if scope.depth == self.depth && !scope.has_from {
+                scope.has_from = true;
+                self.sql.push_str(" FROM DUAL");
+            }
+        }
+    }
+
+    // ends the current column of the select list of an aliased statement
+    fn alias_column(&mut self) {
+        let depth = self.depth;
+        let alias = match self.selects.last_mut() {
+            Some(&mut SelectScope {
+                depth: d,
+                has_from: false,
+                aliases: Some(ref mut n),
+            }) if d == depth => {
+                *n += 1;
+                format!(" \"C{}\"", n)
+            }
+            _ => return,
+        };
+        self.sql.push_str(&alias);
+    }
+
+    // Writes the name pushed since the last other sql, qualifying it with
+    // the default owner if it is a table (`FROM "USERS"`), a column of a
+    // table (`"USERS"."ID"`) or a pseudo column of a sequence
+    // (`"SEQ".NEXTVAL`). The owner is only known from the sql following
+    // the first identifier, e.g. `"APP"."USERS"` is a table of the schema
+    // `APP` in a `FROM` clause.
+    fn write_name(&mut self) {
+        if self.name.is_empty() {
+            return;
+        }
+        let name = mem::take(&mut self.name);
+        let dot = mem::replace(&mut self.name_dot, false);
+        let qualify = match (name.len(), dot) {
+            (1, false) => self.at_table(),
+            (2, false) => !self.at_table(),
+            (1, true) => true,
+            _ => false,
+        };
+        if qualify && !self.local_names.contains(&name[0]) {
+            if let Some(owner) = self.owner.take() {
+                self.write_sql(&owner);
+                self.write_sql(".");
+                self.owner = Some(owner);
+            }
+        }
+        self.write_sql(&name.join("."));
+        if dot {
+            self.write_sql(".");
+        }
+    }
+
+    // whether the sql written so far ends with a keyword followed by a
+    // table, e.g. `INSERT INTO`
+    fn at_table(&self) -> bool {
+        let keyword = self.sql.trim_end().rsplit(' ').next();
+        keyword.is_some_and(|k| ["FROM", "JOIN", "INTO", "UPDATE"].contains(&k))
+    }
+
+    fn close_selects(&mut self) {
+        while self
+            .selects
+            .last()
+            .is_some_and(|scope| scope.depth >= self.depth)
+        {
+            self.add_from_dual();
+            self.selects.pop();
+        }
+    }
+
+    fn write_sql(&mut self, sql: &str) {
+        let mut hint = None;
+        match sql {
+            "SELECT " if self.quote.is_none() => {
+                let aliases = if self.alias_columns == Some(self.depth) {
+                    self.alias_columns = None;
+                    Some(0)
+                } else {
+                    None
+                };
+                self.selects.push(SelectScope {
+                    depth: self.depth,
+                    has_from: false,
+                    aliases,
+                });
+                if self.hint.as_ref().is_some_and(|h| h.0 == self.depth) {
+                    hint = self.hint.take().map(|h| h.1);
+                }
+            }
+            ", " if self.quote.is_none() => self.alias_column(),
+            " FROM " => {
+                self.alias_column();
+                if let Some(scope) = self.selects.last_mut() {
+                    scope.has_from = true;
+                }
+            }
+            " WHERE " | " GROUP BY " | " ORDER BY " | " FETCH FIRST " | " FOR UPDATE" => {
+                self.add_from_dual()
+            }
+            _ => {}
+        }
+        for c in sql.chars() {
+            match (self.quote, c) {
+                (Some(q), c) if q == c => self.quote = None,
+                (Some(_), _) => {}
+                (None, '\'') | (None, '"') => self.quote = Some(c),
+                (None, '(') => self.depth += 1,
+                (None, ')') => {
+                    self.close_selects();
+                    self.depth = self.depth.saturating_sub(1);
+                }
+                (None, _) => {}
+            }
+            self.sql.push(c);
+        }
+        if let Some(hint) = hint {
+            self.sql.push_str("/*+ ");
+            self.sql.push_str(&hint);
+            self.sql.push_str(" */ ");
         }
     }
 }
 
 impl QueryBuilder<Oracle> for OciQueryBuilder {
     fn push_sql(&mut self, sql: &str) {
-        self.sql.push_str(sql);
+        if let Some(hint) = sql.strip_prefix(HINT_MARKER) {
+            self.hint = Some((self.depth, hint.to_owned()));
+            return;
+        }
+        if sql == ALIAS_MARKER {
+            self.alias_columns = Some(self.depth);
+            return;
+        }
+        if let Some(name) = sql.strip_prefix(LOCAL_NAME_MARKER) {
+            let quoting = IDENTIFIER_QUOTING.with(Cell::get);
+            let name = identifier::quote_identifier(quoting, name);
+            self.local_names.push(name);
+            return;
+        }
+        let depth = self.depth;
+        self.record(SqlFragment::Sql(sql.to_owned(), depth));
+        if sql == "." && !self.name.is_empty() && !self.name_dot {
+            self.name_dot = true;
+            return;
+        }
+        self.write_name();
+        self.write_sql(sql);
     }
 
     fn push_identifier(&mut self, identifier: &str) -> Result<(), DieselError> {
-        // TODO: check if there is a better way for escaping strings
-        self.push_sql("\"");
-        self.push_sql(&identifier.replace("`", "``").to_uppercase());
-        self.push_sql("\"");
+        let quoting = IDENTIFIER_QUOTING.with(Cell::get);
+        let identifier = identifier::quote_identifier(quoting, identifier);
+        self.record(SqlFragment::Identifier(identifier.clone()));
+        if self.owner.is_none() {
+            self.owner = DEFAULT_OWNER.with(|o| {
+                o.borrow()
+                    .as_ref()
+                    .map(|owner| identifier::quote_identifier(quoting, owner))
+            });
+        }
+        if self.owner.is_none() {
+            self.write_sql(&identifier);
+            return Ok(());
+        }
+        if !self.name_dot {
+            self.write_name();
+        }
+        self.name.push(identifier);
+        self.name_dot = false;
         Ok(())
     }
 
     fn push_bind_param(&mut self) {
+        self.write_name();
         self.bind_idx += 1;
+        self.record(SqlFragment::Bind);
         let sql = format!(":{}", self.bind_idx);
-        self.push_sql(&sql);
+        self.write_sql(&sql);
     }
 
-    fn finish(self) -> String {
+    fn finish(mut self) -> String {
+        self.write_name();
+        self.close_selects();
         self.sql
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use diesel::debug_query;
+    use diesel::dsl::exists;
+    use diesel::prelude::*;
+    use diesel::sql_types::Integer;
+    use oracle::backend::Oracle;
+
+    table! {
+        users (id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    table! {
+        app.accounts (id) {
+            id -> Integer,
+        }
+    }
+
+    #[test]
+    fn check_select_from_dual() {
+        let query = diesel::select(1.into_sql::<Integer>());
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT :1 FROM DUAL -- binds: [1]"
+        );
+
+        let query = diesel::select(exists(users::table.filter(users::name.eq("(\"Sean\""))));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT EXISTS (SELECT \"USERS\".\"ID\", \"USERS\".\"NAME\" FROM \"USERS\" \
+             WHERE \"USERS\".\"NAME\" = :1) FROM DUAL -- binds: [\"(\\\"Sean\\\"\"]"
+        );
+
+        let query = users::table.filter(exists(diesel::select(1.into_sql::<Integer>())));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"USERS\".\"ID\", \"USERS\".\"NAME\" FROM \"USERS\" \
+             WHERE EXISTS (SELECT :1 FROM DUAL) -- binds: [1]"
+        );
+    }
+
+    #[test]
+    fn check_schema_qualified_table() {
+        let query = accounts::table.filter(accounts::id.eq(1));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"APP\".\"ACCOUNTS\".\"ID\" FROM \"APP\".\"ACCOUNTS\" \
+             WHERE \"APP\".\"ACCOUNTS\".\"ID\" = :1 -- binds: [1]"
+        );
+    }
+
+    #[test]
+    fn check_default_owner() {
+        use super::{with_default_owner, with_identifier_quoting, IdentifierQuoting};
+
+        let owned = |sql: &dyn Fn() -> String| with_default_owner(Some("app"), sql);
+        let query = users::table.filter(users::name.eq("Sean"));
+        assert_eq!(
+            owned(&|| debug_query::<Oracle, _>(&query).to_string()),
+            "SELECT \"APP\".\"USERS\".\"ID\", \"APP\".\"USERS\".\"NAME\" \
+             FROM \"APP\".\"USERS\" WHERE \"APP\".\"USERS\".\"NAME\" = :1 \
+             -- binds: [\"Sean\"]"
+        );
+
+        let query = diesel::insert_into(users::table).values(users::name.eq("Sean"));
+        assert_eq!(
+            owned(&|| debug_query::<Oracle, _>(&query).to_string()),
+            "INSERT INTO \"APP\".\"USERS\" (\"NAME\") VALUES (:1) -- binds: [\"Sean\"]"
+        );
+
+        let query =
+            diesel::update(users::table.filter(users::id.eq(1))).set(users::name.eq("Tess"));
+        assert_eq!(
+            owned(&|| debug_query::<Oracle, _>(&query).to_string()),
+            "UPDATE \"APP\".\"USERS\" SET \"NAME\" = :1 \
+             WHERE \"APP\".\"USERS\".\"ID\" = :2 -- binds: [\"Tess\", 1]"
+        );
+
+        let query = diesel::delete(users::table.filter(users::id.eq(1)));
+        assert_eq!(
+            owned(&|| debug_query::<Oracle, _>(&query).to_string()),
+            "DELETE FROM \"APP\".\"USERS\" WHERE \"APP\".\"USERS\".\"ID\" = :1 -- binds: [1]"
+        );
+
+        // tables with a schema keep it
+        let query = accounts::table.filter(accounts::id.eq(1));
+        assert_eq!(
+            owned(&|| debug_query::<Oracle, _>(&query).to_string()),
+            debug_query::<Oracle, _>(&query).to_string()
+        );
+
+        // the owner is quoted like the identifiers
+        let query = users::table.select(users::name);
+        let sql = with_identifier_quoting(IdentifierQuoting::Never, || {
+            owned(&|| debug_query::<Oracle, _>(&query).to_string())
+        });
+        assert_eq!(sql, "SELECT app.users.name FROM app.users -- binds: []");
+    }
+
+    #[test]
+    fn check_identifier_quoting_of_connection() {
+        use super::{with_identifier_quoting, IdentifierQuoting};
+
+        let query = users::table.select(users::name);
+        let sql = with_identifier_quoting(IdentifierQuoting::Never, || {
+            debug_query::<Oracle, _>(&query).to_string()
+        });
+        assert_eq!(sql, "SELECT users.name FROM users -- binds: []");
+        // other sql keeps the default
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"USERS\".\"NAME\" FROM \"USERS\" -- binds: []"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+
+use diesel::expression::coerce::Coerce;
+use diesel::expression::{
+    AppearsOnTable, AsExpression, Expression, NonAggregate, SelectableExpression,
+};
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::{Nullable, Timestamp};
+
+use oracle::backend::Oracle;
+use oracle::sql_types::TimestampTz;
+
+/// One of oracle's date functions without arguments, c.f. `sysdate`
+///
+/// These functions are evaluated once per statement, so all rows of a
+/// statement get the same value.
+#[derive(Debug, Clone, Copy)]
+pub struct DateFunction<ST> {
+    name: &'static str,
+    _marker: PhantomData<ST>,
+}
+
+/// `SYSDATE`, the date and time of the database server as `DATE`
+///
+/// ```ignore
+/// diesel::update(sessions::table.filter(sessions::expires_at.lt(sysdate())))
+///     .set(sessions::expired.eq(true))
+///     .execute(&conn)?;
+/// ```
+pub fn sysdate() -> DateFunction<Timestamp> {
+    DateFunction {
+        name: "SYSDATE",
+        _marker: PhantomData,
+    }
+}
+
+/// `SYSTIMESTAMP`, the date and time of the database server with fractional
+/// seconds and the time zone of the server
+///
+/// The value is read as `chrono::DateTime<FixedOffset>` or `DateTime<Utc>`
+/// and can be compared with or assigned to `Timestamp` columns, which oracle
+/// converts to the time zone of the session.
+///
+/// ```ignore
+/// diesel::insert_into(sessions::table)
+///     .values((sessions::id.eq(1), sessions::expires_at.eq(systimestamp())))
+///     .execute(&conn)?;
+/// ```
+pub fn systimestamp() -> DateFunction<TimestampTz> {
+    DateFunction {
+        name: "SYSTIMESTAMP",
+        _marker: PhantomData,
+    }
+}
+
+/// `CURRENT_TIMESTAMP`, like `systimestamp` in the time zone of the session
+///
+/// `diesel::dsl::now` renders `CURRENT_TIMESTAMP` as well, but has the type
+/// `Timestamp`.
+pub fn current_timestamp() -> DateFunction<TimestampTz> {
+    DateFunction {
+        name: "CURRENT_TIMESTAMP",
+        _marker: PhantomData,
+    }
+}
+
+impl<ST> Expression for DateFunction<ST> {
+    type SqlType = ST;
+}
+
+impl<ST> NonAggregate for DateFunction<ST> {}
+
+impl AsExpression<Nullable<Timestamp>> for DateFunction<Timestamp> {
+    type Expression = Coerce<Self, Nullable<Timestamp>>;
+
+    fn as_expression(self) -> Self::Expression {
+        Coerce::new(self)
+    }
+}
+
+impl AsExpression<Timestamp> for DateFunction<TimestampTz> {
+    type Expression = Coerce<Self, Timestamp>;
+
+    fn as_expression(self) -> Self::Expression {
+        Coerce::new(self)
+    }
+}
+
+impl AsExpression<Nullable<Timestamp>> for DateFunction<TimestampTz> {
+    type Expression = Coerce<Self, Nullable<Timestamp>>;
+
+    fn as_expression(self) -> Self::Expression {
+        Coerce::new(self)
+    }
+}
+
+impl AsExpression<Nullable<TimestampTz>> for DateFunction<TimestampTz> {
+    type Expression = Coerce<Self, Nullable<TimestampTz>>;
+
+    fn as_expression(self) -> Self::Expression {
+        Coerce::new(self)
+    }
+}
+
+impl<ST, QS> AppearsOnTable<QS> for DateFunction<ST> {}
+
+impl<ST, QS> SelectableExpression<QS> for DateFunction<ST> {}
+
+impl<ST> QueryId for DateFunction<ST> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<ST> QueryFragment<Oracle> for DateFunction<ST> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql(self.name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{current_timestamp, sysdate, systimestamp};
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use oracle::backend::Oracle;
+
+    table! {
+        sessions (id) {
+            id -> Integer,
+            expires_at -> Timestamp,
+            closed_at -> Nullable<Timestamp>,
+        }
+    }
+
+    #[test]
+    fn check_date_functions_sql() {
+        let query = diesel::update(sessions::table.filter(sessions::expires_at.lt(sysdate())))
+            .set(sessions::expires_at.eq(sysdate()));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "UPDATE \"SESSIONS\" SET \"EXPIRES_AT\" = SYSDATE \
+             WHERE \"SESSIONS\".\"EXPIRES_AT\" < SYSDATE -- binds: []"
+        );
+
+        let query = sessions::table.select((systimestamp(), current_timestamp()));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT SYSTIMESTAMP, CURRENT_TIMESTAMP FROM \"SESSIONS\" -- binds: []"
+        );
+
+        let query = diesel::update(sessions::table.filter(sessions::expires_at.lt(systimestamp())))
+            .set(sessions::closed_at.eq(current_timestamp()));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "UPDATE \"SESSIONS\" SET \"CLOSED_AT\" = CURRENT_TIMESTAMP \
+             WHERE \"SESSIONS\".\"EXPIRES_AT\" < SYSTIMESTAMP -- binds: []"
+        );
+    }
+}
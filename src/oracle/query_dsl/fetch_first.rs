@@ -0,0 +1,212 @@
+use diesel::query_builder::{AsQuery, AstPass, Query, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, Double};
+use diesel::RunQueryDsl;
+
+use oracle::backend::Oracle;
+use oracle::query_builder::{push_column_aliases, server_version};
+
+// diesel renders `.limit()` as ` LIMIT n`, which oracle does not understand,
+// so the row limiting clause of oracle 12c is provided as a separate dsl
+// c.f. https://docs.oracle.com/database/121/SQLRF/statements_10002.htm#BABHFGAA
+
+#[derive(Debug, Clone, Copy)]
+enum FetchCount {
+    Rows(i64),
+    Percent(f64),
+}
+
+/// A query with a `FETCH FIRST` row limiting clause, c.f. `FetchFirstDsl`
+#[derive(Debug, Clone, Copy)]
+pub struct FetchFirst<Q> {
+    query: Q,
+    count: FetchCount,
+    with_ties: bool,
+}
+
+impl<Q> FetchFirst<Q> {
+    /// Also returns the rows which are equal to the last row according to
+    /// the `ORDER BY` clause of the query (`WITH TIES` instead of `ONLY`)
+    ///
+    /// Without an `ORDER BY` clause no additional rows are returned.
+    pub fn with_ties(self) -> Self {
+        FetchFirst {
+            with_ties: true,
+            ..self
+        }
+    }
+}
+
+/// Adds oracle's `FETCH FIRST` row limiting clause to a query
+///
+/// ```ignore
+/// // the 3 best scores, including all rows sharing the third best score
+/// scores.order(score.desc()).fetch_first(3).with_ties().load(&conn)
+/// ```
+pub trait FetchFirstDsl: AsQuery + Sized {
+    /// `FETCH FIRST n ROWS ONLY`
+    fn fetch_first(self, n: i64) -> FetchFirst<Self::Query> {
+        FetchFirst {
+            query: self.as_query(),
+            count: FetchCount::Rows(n),
+            with_ties: false,
+        }
+    }
+
+    /// `FETCH FIRST percent PERCENT ROWS ONLY`
+    fn fetch_first_percent(self, percent: f64) -> FetchFirst<Self::Query> {
+        FetchFirst {
+            query: self.as_query(),
+            count: FetchCount::Percent(percent),
+            with_ties: false,
+        }
+    }
+}
+
+impl<T: AsQuery> FetchFirstDsl for T {}
+
+impl<Q: Query> Query for FetchFirst<Q> {
+    type SqlType = Q::SqlType;
+}
+
+impl<Q> QueryId for FetchFirst<Q> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q> QueryFragment<Oracle> for FetchFirst<Q>
+where
+    Q: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        let supported = server_version().is_none_or(|v| v.supports_fetch_first());
+        if let (false, FetchCount::Rows(ref n), false) = (supported, self.count, self.with_ties) {
+            // servers before 12.1 limit the rows of the ordered subquery
+            out.push_sql("SELECT ");
+            out.push_sql("*");
+            out.push_sql(" FROM ");
+            out.push_sql("(");
+            push_column_aliases(&mut out);
+            self.query.walk_ast(out.reborrow())?;
+            out.push_sql(")");
+            out.push_sql(" WHERE ");
+            out.push_sql("ROWNUM <= ");
+            return out.push_bind_param::<BigInt, _>(n);
+        }
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(" FETCH FIRST ");
+        match self.count {
+            FetchCount::Rows(ref n) => out.push_bind_param::<BigInt, _>(n)?,
+            FetchCount::Percent(ref p) => {
+                out.push_bind_param::<Double, _>(p)?;
+                out.push_sql(" PERCENT");
+            }
+        }
+        if self.with_ties {
+            out.push_sql(" ROWS WITH TIES");
+        } else {
+            out.push_sql(" ROWS ONLY");
+        }
+        Ok(())
+    }
+}
+
+impl<Q, Conn> RunQueryDsl<Conn> for FetchFirst<Q> {}
+
+#[cfg(test)]
+mod tests {
+    use super::FetchFirstDsl;
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use diesel::sql_types::Integer;
+    use oracle::backend::Oracle;
+    use oracle::connection::ServerVersion;
+    use oracle::query_builder::with_server_version;
+
+    table! {
+        scores (id) {
+            id -> Integer,
+            score -> Integer,
+        }
+    }
+
+    table! {
+        players (id) {
+            id -> Integer,
+            score_id -> Integer,
+        }
+    }
+
+    joinable!(players -> scores (score_id));
+    allow_tables_to_appear_in_same_query!(scores, players);
+
+    #[test]
+    fn check_fetch_first_sql() {
+        let query = scores::table
+            .select(scores::id)
+            .order(scores::score.desc())
+            .fetch_first(3);
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"SCORES\".\"ID\" FROM \"SCORES\" ORDER BY \"SCORES\".\"SCORE\" DESC \
+             FETCH FIRST :1 ROWS ONLY -- binds: [3]"
+        );
+
+        let query = scores::table
+            .order(scores::score.desc())
+            .fetch_first_percent(10.0)
+            .with_ties();
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.ends_with("FETCH FIRST :1 PERCENT ROWS WITH TIES -- binds: [10.0]"));
+    }
+
+    #[test]
+    fn check_fetch_first_before_12c() {
+        let query = scores::table
+            .select(scores::id)
+            .order(scores::score.desc())
+            .fetch_first(3);
+        let sql = with_server_version(ServerVersion::new(11, 2), || {
+            debug_query::<Oracle, _>(&query).to_string()
+        });
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT \"SCORES\".\"ID\" \"C1\" FROM \"SCORES\" \
+             ORDER BY \"SCORES\".\"SCORE\" DESC) WHERE ROWNUM <= :1 -- binds: [3]"
+        );
+
+        // columns of the same name are aliased, including those of
+        // statements without a table
+        let query = scores::table
+            .inner_join(players::table)
+            .select((scores::id, players::id, scores::score))
+            .filter(scores::id.eq_any(players::table.select(players::score_id)))
+            .fetch_first(3);
+        let sql = with_server_version(ServerVersion::new(11, 2), || {
+            debug_query::<Oracle, _>(&query).to_string()
+        });
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT \"SCORES\".\"ID\" \"C1\", \"PLAYERS\".\"ID\" \"C2\", \
+             \"SCORES\".\"SCORE\" \"C3\" FROM (\"SCORES\" INNER JOIN \"PLAYERS\" \
+             ON \"PLAYERS\".\"SCORE_ID\" = \"SCORES\".\"ID\") \
+             WHERE \"SCORES\".\"ID\" IN (SELECT \"PLAYERS\".\"SCORE_ID\" FROM \"PLAYERS\")) \
+             WHERE ROWNUM <= :1 -- binds: [3]"
+        );
+        let query =
+            diesel::select((1.into_sql::<Integer>(), 2.into_sql::<Integer>())).fetch_first(1);
+        let sql = with_server_version(ServerVersion::new(11, 2), || {
+            debug_query::<Oracle, _>(&query).to_string()
+        });
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT :1 \"C1\", :2 \"C2\" FROM DUAL) WHERE ROWNUM <= :3 \
+             -- binds: [1, 2, 1]"
+        );
+        // the version only applies to sql built within `with_server_version`
+        assert!(debug_query::<Oracle, _>(&query)
+            .to_string()
+            .contains("FETCH FIRST"));
+    }
+}
@@ -0,0 +1,170 @@
+use diesel::query_builder::{AsQuery, AstPass, Query, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::RunQueryDsl;
+
+use oracle::backend::Oracle;
+
+// diesel keeps the types of its `.for_update()` clause private, so they
+// cannot be rendered for oracle. The locking clause is provided as a separate
+// dsl instead, which also supports oracle's `WAIT n` and `FOR UPDATE OF`.
+// c.f. https://docs.oracle.com/database/121/SQLRF/statements_10002.htm#BABEJDCG
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LockWait {
+    Wait,
+    NoWait,
+    Seconds(u32),
+    SkipLocked,
+}
+
+/// A query locking the selected rows, c.f. `ForUpdateDsl`
+#[derive(Debug, Clone, Copy)]
+pub struct ForUpdateClause<Q, Of = ()> {
+    query: Q,
+    of: Option<Of>,
+    wait: LockWait,
+}
+
+impl<Q> ForUpdateClause<Q> {
+    /// Only locks the rows of the tables of the given columns
+    /// (`FOR UPDATE OF "TABLE"."COLUMN"`), which is needed for joins
+    pub fn of<C>(self, columns: C) -> ForUpdateClause<Q, C> {
+        ForUpdateClause {
+            query: self.query,
+            of: Some(columns),
+            wait: self.wait,
+        }
+    }
+}
+
+impl<Q, Of> ForUpdateClause<Q, Of> {
+    /// Skips rows locked by other transactions (`SKIP LOCKED`), e.g. to
+    /// take the next unprocessed job of a queue
+    pub fn skip_locked(self) -> Self {
+        ForUpdateClause {
+            wait: LockWait::SkipLocked,
+            ..self
+        }
+    }
+
+    /// Fails with `ORA-00054` instead of waiting for rows locked by other
+    /// transactions (`NOWAIT`)
+    pub fn no_wait(self) -> Self {
+        ForUpdateClause {
+            wait: LockWait::NoWait,
+            ..self
+        }
+    }
+
+    /// Waits at most the given number of seconds for rows locked by other
+    /// transactions (`WAIT n`), then fails with `ORA-30006`
+    pub fn wait(self, seconds: u32) -> Self {
+        ForUpdateClause {
+            wait: LockWait::Seconds(seconds),
+            ..self
+        }
+    }
+}
+
+/// Adds oracle's `FOR UPDATE` locking clause to a query
+///
+/// ```ignore
+/// // take the new jobs, skipping the jobs other workers are processing
+/// let jobs = jobs::table
+///     .filter(jobs::state.eq("new"))
+///     .select_for_update()
+///     .skip_locked()
+///     .load::<Job>(&conn)?;
+/// ```
+///
+/// The rows stay locked until the transaction ends. Oracle does not allow
+/// `FOR UPDATE` together with `DISTINCT`, `GROUP BY` or a `FETCH FIRST`
+/// clause (i.e. `first` or `limit`), and a `ROWNUM` filter is applied before
+/// locked rows are skipped.
+pub trait ForUpdateDsl: AsQuery + Sized {
+    /// `FOR UPDATE`, waiting for rows locked by other transactions
+    fn select_for_update(self) -> ForUpdateClause<Self::Query> {
+        ForUpdateClause {
+            query: self.as_query(),
+            of: None,
+            wait: LockWait::Wait,
+        }
+    }
+}
+
+impl<T: AsQuery> ForUpdateDsl for T {}
+
+impl<Q: Query, Of> Query for ForUpdateClause<Q, Of> {
+    type SqlType = Q::SqlType;
+}
+
+impl<Q, Of> QueryId for ForUpdateClause<Q, Of> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q, Of> QueryFragment<Oracle> for ForUpdateClause<Q, Of>
+where
+    Q: QueryFragment<Oracle>,
+    Of: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(" FOR UPDATE");
+        if let Some(ref of) = self.of {
+            out.push_sql(" OF ");
+            of.walk_ast(out.reborrow())?;
+        }
+        match self.wait {
+            LockWait::Wait => {}
+            LockWait::NoWait => out.push_sql(" NOWAIT"),
+            LockWait::Seconds(seconds) => out.push_sql(&format!(" WAIT {}", seconds)),
+            LockWait::SkipLocked => out.push_sql(" SKIP LOCKED"),
+        }
+        Ok(())
+    }
+}
+
+impl<Q, Of, Conn> RunQueryDsl<Conn> for ForUpdateClause<Q, Of> {}
+
+#[cfg(test)]
+mod tests {
+    use super::ForUpdateDsl;
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use oracle::backend::Oracle;
+
+    table! {
+        jobs (id) {
+            id -> Integer,
+            state -> Text,
+        }
+    }
+
+    #[test]
+    fn check_for_update_sql() {
+        let query = jobs::table
+            .select(jobs::id)
+            .filter(jobs::state.eq("new"))
+            .select_for_update()
+            .skip_locked();
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"JOBS\".\"ID\" FROM \"JOBS\" WHERE \"JOBS\".\"STATE\" = :1 \
+             FOR UPDATE SKIP LOCKED -- binds: [\"new\"]"
+        );
+
+        let query = jobs::table
+            .select(jobs::id)
+            .select_for_update()
+            .of(jobs::state)
+            .wait(5);
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.ends_with("FOR UPDATE OF \"JOBS\".\"STATE\" WAIT 5 -- binds: []"));
+
+        let query = jobs::table.select_for_update().no_wait();
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.ends_with("FOR UPDATE NOWAIT -- binds: []"));
+    }
+}
@@ -1 +1,21 @@
 //mod save_changes_dsl;
+mod date_functions;
+mod fetch_first;
+mod for_update;
+mod result_cache;
+mod sequence;
+mod upsert_batch;
+mod window;
+mod with;
+
+pub use self::date_functions::{current_timestamp, sysdate, systimestamp, DateFunction};
+pub use self::fetch_first::{FetchFirst, FetchFirstDsl};
+pub use self::for_update::{ForUpdateClause, ForUpdateDsl};
+pub use self::result_cache::{ResultCache, ResultCacheDsl};
+pub use self::sequence::{sequence, Sequence, SequenceValue};
+pub use self::upsert_batch::{upsert_batch, UpsertBatch};
+pub use self::window::{
+    dense_rank, lag, lead, rank, row_number, OffsetFunction, Over, OverDsl, PartitionBy,
+    RankingFunction, WindowClause, WindowOrder,
+};
+pub use self::with::{cte, Cte, CteList, UnionAll, WithClause, WithDsl};
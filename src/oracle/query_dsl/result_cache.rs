@@ -0,0 +1,132 @@
+use diesel::query_builder::{AsQuery, AstPass, Query, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::RunQueryDsl;
+
+use oracle::backend::Oracle;
+use oracle::query_builder::push_hint;
+
+// the hint follows the `SELECT` of the query itself, wrapping the query
+// would fail for joins selecting columns of the same name (ORA-00918)
+// c.f. https://docs.oracle.com/database/121/TGDBA/tune_result_cache.htm
+
+/// A query using (or bypassing) the server side result cache, c.f.
+/// `ResultCacheDsl`
+#[derive(Debug, Clone, Copy)]
+pub struct ResultCache<Q> {
+    query: Q,
+    enabled: bool,
+}
+
+/// Adds the `RESULT_CACHE` or `NO_RESULT_CACHE` hint to a query
+///
+/// Results of queries with the hint are cached by the server until one of
+/// the queried tables is changed, which is useful for read heavy lookup
+/// tables.
+///
+/// ```ignore
+/// let countries = countries::table.result_cache().load::<Country>(&conn)?;
+/// ```
+pub trait ResultCacheDsl: AsQuery + Sized {
+    /// `SELECT /*+ RESULT_CACHE */`
+    fn result_cache(self) -> ResultCache<Self::Query> {
+        ResultCache {
+            query: self.as_query(),
+            enabled: true,
+        }
+    }
+
+    /// `SELECT /*+ NO_RESULT_CACHE */`, for tables annotated with
+    /// `RESULT_CACHE (MODE FORCE)`
+    fn no_result_cache(self) -> ResultCache<Self::Query> {
+        ResultCache {
+            query: self.as_query(),
+            enabled: false,
+        }
+    }
+}
+
+impl<T: AsQuery> ResultCacheDsl for T {}
+
+impl<Q: Query> Query for ResultCache<Q> {
+    type SqlType = Q::SqlType;
+}
+
+impl<Q> QueryId for ResultCache<Q> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q> QueryFragment<Oracle> for ResultCache<Q>
+where
+    Q: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        if self.enabled {
+            push_hint(&mut out, "RESULT_CACHE");
+        } else {
+            push_hint(&mut out, "NO_RESULT_CACHE");
+        }
+        self.query.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q, Conn> RunQueryDsl<Conn> for ResultCache<Q> {}
+
+#[cfg(test)]
+mod tests {
+    use super::ResultCacheDsl;
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use oracle::backend::Oracle;
+
+    table! {
+        countries (id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    table! {
+        cities (id) {
+            id -> Integer,
+            country_id -> Integer,
+        }
+    }
+
+    joinable!(cities -> countries (country_id));
+    allow_tables_to_appear_in_same_query!(countries, cities);
+
+    #[test]
+    fn check_result_cache_sql() {
+        let query = countries::table
+            .select(countries::name)
+            .filter(countries::id.eq(1))
+            .result_cache();
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT /*+ RESULT_CACHE */ \"COUNTRIES\".\"NAME\" FROM \"COUNTRIES\" \
+             WHERE \"COUNTRIES\".\"ID\" = :1 -- binds: [1]"
+        );
+
+        let query = countries::table.select(countries::id).no_result_cache();
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.starts_with("SELECT /*+ NO_RESULT_CACHE */ \"COUNTRIES\".\"ID\" FROM"));
+    }
+
+    #[test]
+    fn check_result_cache_of_joins() {
+        let query = countries::table
+            .inner_join(cities::table)
+            .select((countries::id, cities::id))
+            .filter(cities::id.eq_any(cities::table.select(cities::id)))
+            .result_cache();
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT /*+ RESULT_CACHE */ \"COUNTRIES\".\"ID\", \"CITIES\".\"ID\" FROM \
+             (\"COUNTRIES\" INNER JOIN \"CITIES\" ON \"CITIES\".\"COUNTRY_ID\" = \
+             \"COUNTRIES\".\"ID\") WHERE \"CITIES\".\"ID\" IN (SELECT \"CITIES\".\"ID\" \
+             FROM \"CITIES\") -- binds: []"
+        );
+    }
+}
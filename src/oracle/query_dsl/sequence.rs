@@ -0,0 +1,146 @@
+use std::marker::PhantomData;
+
+use diesel::expression::{AppearsOnTable, Expression, NonAggregate, SelectableExpression};
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::BigInt;
+
+use oracle::backend::Oracle;
+
+/// An oracle sequence, c.f. `sequence`
+#[derive(Debug, Clone, Copy)]
+pub struct Sequence<ST = BigInt> {
+    name: &'static str,
+    _marker: PhantomData<ST>,
+}
+
+/// The sequence `name`, whose values are expressions of type `BigInt`
+///
+/// ```ignore
+/// let ids = sequence("user_ids");
+/// diesel::insert_into(users::table)
+///     .values((users::id.eq(ids.nextval()), users::name.eq("Sean")))
+///     .execute(&conn)?;
+/// let id = users::table.select(ids.currval()).first::<i64>(&conn)?;
+/// ```
+pub fn sequence(name: &'static str) -> Sequence {
+    Sequence {
+        name,
+        _marker: PhantomData,
+    }
+}
+
+impl<ST> Sequence<ST> {
+    /// Changes the type of the values, e.g. to `Integer` for a column of
+    /// type `Integer`
+    pub fn with_sql_type<T>(self) -> Sequence<T> {
+        Sequence {
+            name: self.name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `name.NEXTVAL`, increments the sequence and returns the new value
+    ///
+    /// Oracle increments the sequence once per row, even if `NEXTVAL` is
+    /// used several times in a statement.
+    pub fn nextval(&self) -> SequenceValue<ST> {
+        SequenceValue {
+            name: self.name,
+            pseudo_column: "NEXTVAL",
+            _marker: PhantomData,
+        }
+    }
+
+    /// `name.CURRVAL`, the value returned by the last `NEXTVAL` of this
+    /// session, oracle fails with `ORA-08002` if there was none
+    pub fn currval(&self) -> SequenceValue<ST> {
+        SequenceValue {
+            name: self.name,
+            pseudo_column: "CURRVAL",
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The `NEXTVAL` or `CURRVAL` of a sequence
+#[derive(Debug, Clone, Copy)]
+pub struct SequenceValue<ST> {
+    name: &'static str,
+    pseudo_column: &'static str,
+    _marker: PhantomData<ST>,
+}
+
+impl<ST> Expression for SequenceValue<ST> {
+    type SqlType = ST;
+}
+
+impl<ST> NonAggregate for SequenceValue<ST> {}
+
+impl<ST, QS> AppearsOnTable<QS> for SequenceValue<ST> {}
+
+impl<ST, QS> SelectableExpression<QS> for SequenceValue<ST> {}
+
+impl<ST> QueryId for SequenceValue<ST> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<ST> QueryFragment<Oracle> for SequenceValue<ST> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_identifier(self.name)?;
+        out.push_sql(".");
+        out.push_sql(self.pseudo_column);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sequence;
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use diesel::sql_types::Integer;
+    use oracle::backend::Oracle;
+
+    table! {
+        users (id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    #[test]
+    fn check_sequence_sql() {
+        let ids = sequence("user_ids").with_sql_type::<Integer>();
+        let query = diesel::insert_into(users::table)
+            .values((users::id.eq(ids.nextval()), users::name.eq("Sean")));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "INSERT INTO \"USERS\" (\"ID\", \"NAME\") VALUES (\"USER_IDS\".NEXTVAL, :1) \
+             -- binds: [\"Sean\"]"
+        );
+
+        let query = users::table.select(sequence("user_ids").currval());
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"USER_IDS\".CURRVAL FROM \"USERS\" -- binds: []"
+        );
+    }
+
+    #[test]
+    fn check_sequence_sql_of_default_owner() {
+        use oracle::query_builder::with_default_owner;
+
+        let ids = sequence("user_ids").with_sql_type::<Integer>();
+        let query = diesel::insert_into(users::table)
+            .values((users::id.eq(ids.nextval()), users::name.eq("Sean")));
+        let sql = with_default_owner(Some("app"), || debug_query::<Oracle, _>(&query).to_string());
+        assert_eq!(
+            sql,
+            "INSERT INTO \"APP\".\"USERS\" (\"ID\", \"NAME\") \
+             VALUES (\"APP\".\"USER_IDS\".NEXTVAL, :1) -- binds: [\"Sean\"]"
+        );
+    }
+}
@@ -0,0 +1,483 @@
+use diesel::expression::{AppearsOnTable, Expression};
+use diesel::query_builder::bind_collector::RawBytesBindCollector;
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::query_source::Table;
+use diesel::result::{DatabaseErrorKind, Error, QueryResult};
+use diesel::{Connection, Insertable};
+
+use oracle::backend::Oracle;
+use oracle::connection::OciConnection;
+use oracle::query_builder::{OciQueryBuilder, SqlFragment};
+use oracle::types::OCIDataType;
+
+/// Inserts or updates many rows of `target` with a single `MERGE`
+/// statement, c.f. `UpsertBatch`
+///
+/// ```ignore
+/// upsert_batch(users::table)
+///     .key(users::id)
+///     .values(new_users)
+///     .execute(&conn)?;
+/// ```
+pub fn upsert_batch<T: Table>(target: T) -> UpsertBatch<T, (), ()> {
+    UpsertBatch {
+        target,
+        key: (),
+        rows: Vec::new(),
+    }
+}
+
+// the sql, bind types and bind values of a `MERGE` statement
+type MergeStatement = (String, Vec<OCIDataType>, Vec<Option<Vec<u8>>>);
+
+// the fragments of each value of a row and the bind values among them
+type SourceRow = (Vec<Vec<SqlFragment>>, Vec<(OCIDataType, Option<Vec<u8>>)>);
+
+/// A `MERGE` statement using the given rows as source
+///
+/// Rows with the same `key` as an existing row update all other columns of
+/// that row, all other rows are inserted. Each row is selected as one
+/// `SELECT ... FROM dual`, so all rows need to set the same columns. Columns
+/// left to their default value by `Insertable` (e.g. `None`) are not updated
+/// and inserted as `DEFAULT`, other values (binds and expressions) are merged
+/// as they are. Rows leaving different columns to their default are merged
+/// by one statement each, which are executed in a transaction.
+///
+/// Oracle limits the number of binds of a single statement to 65535, larger
+/// datasets need to be split into several batches.
+#[derive(Debug, Clone)]
+pub struct UpsertBatch<T, K, V> {
+    target: T,
+    key: K,
+    rows: Vec<V>,
+}
+
+impl<T: Table, V> UpsertBatch<T, (), V> {
+    /// The column (or tuple of columns) identifying existing rows
+    pub fn key<K>(self, key: K) -> UpsertBatch<T, K, V>
+    where
+        K: Expression + AppearsOnTable<T> + QueryFragment<Oracle>,
+    {
+        UpsertBatch {
+            target: self.target,
+            key,
+            rows: self.rows,
+        }
+    }
+}
+
+impl<T: Table, K> UpsertBatch<T, K, ()> {
+    /// The rows to insert or update
+    pub fn values<I>(self, rows: I) -> UpsertBatch<T, K, I::Item>
+    where
+        I: IntoIterator,
+        I::Item: Insertable<T>,
+    {
+        UpsertBatch {
+            target: self.target,
+            key: self.key,
+            rows: rows.into_iter().collect(),
+        }
+    }
+}
+
+impl<T, K, V> UpsertBatch<T, K, V>
+where
+    T: Table,
+    T::FromClause: QueryFragment<Oracle>,
+    K: Expression + AppearsOnTable<T> + QueryFragment<Oracle>,
+    V: Insertable<T>,
+    V::Values: QueryFragment<Oracle>,
+{
+    /// Executes the `MERGE` statements, returning the number of inserted
+    /// and updated rows
+    pub fn execute(self, conn: &OciConnection) -> QueryResult<u64> {
+        if self.rows.is_empty() {
+            return Ok(0);
+        }
+        let mut statements = conn.with_sql_dialect(|| self.build())?;
+        if statements.len() == 1 {
+            let (sql, metadata, binds) = statements.remove(0);
+            return conn.execute_with_binds(&sql, metadata, binds);
+        }
+        conn.transaction(|| {
+            statements
+                .into_iter()
+                .try_fold(0, |count, (sql, metadata, binds)| {
+                    conn.execute_with_binds(&sql, metadata, binds)
+                        .map(|n| count + n)
+                })
+        })
+    }
+
+    // one statement for the rows of each combination of columns left to
+    // their default
+    fn build(self) -> QueryResult<Vec<MergeStatement>> {
+        let mut target = OciQueryBuilder::new();
+        target.push_sql("MERGE INTO ");
+        self.target.from_clause().to_sql(&mut target)?;
+        let merge = target.finish();
+        let mut key = OciQueryBuilder::with_fragments();
+        self.key.to_sql(&mut key)?;
+        // columns are rendered qualified, e.g. `"USERS"."ID"`, the last
+        // identifier of each expression is the name of the column
+        let key = key
+            .fragments()
+            .split(|f| f.is_sql(", ", 0))
+            .map(|column| match column.last() {
+                Some(SqlFragment::Identifier(name)) => Ok(name.clone()),
+                _ => Err(merge_error(format!("Unsupported key {}", key.sql))),
+            })
+            .collect::<QueryResult<Vec<_>>>()?;
+
+        let mut columns = Vec::new();
+        let mut groups: Vec<(Vec<bool>, Vec<SourceRow>)> = Vec::new();
+        for (i, row) in self.rows.into_iter().enumerate() {
+            let values = row.values();
+            let mut row_sql = OciQueryBuilder::with_fragments();
+            values.to_sql(&mut row_sql)?;
+            let mut collector = RawBytesBindCollector::<Oracle>::new();
+            values.collect_binds(&mut collector, &())?;
+
+            let (row_columns, expressions) = split_values_clause(row_sql.fragments())
+                .ok_or_else(|| merge_error(format!("Unsupported values {}", row_sql.sql)))?;
+            if i == 0 {
+                columns = row_columns;
+            } else if columns != row_columns {
+                return Err(merge_error(format!(
+                    "Row {} sets the columns {} instead of {}",
+                    i,
+                    row_columns.join(", "),
+                    columns.join(", ")
+                )));
+            }
+
+            let defaults = expressions
+                .iter()
+                .map(|expression| match **expression {
+                    [ref value] => value.is_sql("DEFAULT", 1),
+                    _ => false,
+                })
+                .collect::<Vec<_>>();
+            let row = (
+                expressions.iter().map(|e| e.to_vec()).collect(),
+                collector
+                    .metadata
+                    .into_iter()
+                    .zip(collector.binds)
+                    .collect(),
+            );
+            match groups.iter_mut().find(|g| g.0 == defaults) {
+                Some(group) => group.1.push(row),
+                None => groups.push((defaults, vec![row])),
+            }
+        }
+
+        if let Some(k) = key.iter().find(|k| !columns.contains(k)) {
+            return Err(merge_error(format!("The key column {} is not set", k)));
+        }
+        groups
+            .into_iter()
+            .map(|(defaults, rows)| merge_statement(&merge, &key, &columns, &defaults, rows))
+            .collect()
+    }
+}
+
+// the `MERGE` statement of rows leaving the same columns to their default
+fn merge_statement(
+    merge: &str,
+    key: &[String],
+    columns: &[String],
+    defaults: &[bool],
+    rows: Vec<SourceRow>,
+) -> QueryResult<MergeStatement> {
+    let set = columns
+        .iter()
+        .zip(defaults)
+        .filter(|c| !*c.1)
+        .map(|c| c.0)
+        .collect::<Vec<_>>();
+    if let Some(k) = key.iter().find(|k| !set.contains(k)) {
+        return Err(merge_error(format!("The key column {} is not set", k)));
+    }
+
+    let mut source = String::new();
+    let mut metadata = Vec::new();
+    let mut binds = Vec::new();
+    for (i, (values, row_binds)) in rows.into_iter().enumerate() {
+        source.push_str(if i == 0 {
+            "SELECT "
+        } else {
+            " UNION ALL SELECT "
+        });
+        let mut row_binds = row_binds.into_iter();
+        let values = values.iter().zip(columns).zip(defaults);
+        for (j, ((expression, column), _)) in values.filter(|v| !*v.1).enumerate() {
+            if j > 0 {
+                source.push_str(", ");
+            }
+            // binds are numbered by their position in the whole statement
+            for fragment in expression {
+                match *fragment {
+                    SqlFragment::Sql(ref sql, _) | SqlFragment::Identifier(ref sql) => {
+                        source.push_str(sql)
+                    }
+                    SqlFragment::Bind => {
+                        let (tpe, value) = row_binds
+                            .next()
+                            .ok_or_else(|| merge_error("Missing bind value".into()))?;
+                        metadata.push(tpe);
+                        binds.push(value);
+                        source.push_str(&format!(":{}", binds.len()));
+                    }
+                }
+            }
+            if i == 0 {
+                source.push_str(" AS ");
+                source.push_str(column);
+            }
+        }
+        source.push_str(" FROM dual");
+    }
+
+    let on = key
+        .iter()
+        .map(|k| format!("t.{0} = s.{0}", k))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let update = set
+        .iter()
+        .filter(|c| !key.contains(c))
+        .map(|c| format!("t.{0} = s.{0}", c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let values = columns
+        .iter()
+        .zip(defaults)
+        .map(|(c, &is_default)| {
+            if is_default {
+                "DEFAULT".to_owned()
+            } else {
+                format!("s.{}", c)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut sql = format!("{} t USING ({}) s ON ({})", merge, source, on);
+    if !update.is_empty() {
+        sql.push_str(" WHEN MATCHED THEN UPDATE SET ");
+        sql.push_str(&update);
+    }
+    sql.push_str(&format!(
+        " WHEN NOT MATCHED THEN INSERT ({}) VALUES ({})",
+        columns.join(", "),
+        values
+    ));
+    Ok((sql, metadata, binds))
+}
+
+// splits the fragments of `("A", "B") VALUES (:1, DEFAULT)` as pushed by
+// diesel's `ValuesClause` into the columns and the fragments of each value,
+// `None` for other clauses like `DEFAULT VALUES`
+fn split_values_clause(fragments: &[SqlFragment]) -> Option<(Vec<String>, Vec<&[SqlFragment]>)> {
+    let (first, rest) = fragments.split_first()?;
+    let (last, rest) = rest.split_last()?;
+    if !first.is_sql("(", 0) || !last.is_sql(")", 1) {
+        return None;
+    }
+    let separator = rest.iter().position(|f| f.is_sql(") VALUES (", 1))?;
+    let mut columns = Vec::new();
+    for fragment in rest[..separator].split(|f| f.is_sql(", ", 1)) {
+        match *fragment {
+            [SqlFragment::Identifier(ref column)] => columns.push(column.clone()),
+            _ => return None,
+        }
+    }
+    // separators of the values are pushed outside of their parentheses
+    let values = rest[separator + 1..]
+        .split(|f| f.is_sql(", ", 1))
+        .collect::<Vec<_>>();
+    if columns.len() == values.len() {
+        Some((columns, values))
+    } else {
+        None
+    }
+}
+
+fn merge_error(message: String) -> Error {
+    Error::DatabaseError(DatabaseErrorKind::__Unknown, Box::new(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_values_clause, upsert_batch};
+    use diesel::dsl::sql;
+    use diesel::prelude::*;
+    use diesel::query_builder::QueryFragment;
+    use diesel::sql_types::Text;
+    use oracle::backend::Oracle;
+    use oracle::query_builder::{OciQueryBuilder, SqlFragment};
+
+    table! {
+        users {
+            id -> Integer,
+            name -> Text,
+            hair_color -> Nullable<Text>,
+        }
+    }
+
+    #[derive(Insertable)]
+    #[table_name = "users"]
+    struct NewUser<'a> {
+        id: i32,
+        name: &'a str,
+        hair_color: Option<&'a str>,
+    }
+
+    fn fragments<V: QueryFragment<Oracle>>(values: V) -> Vec<SqlFragment> {
+        let mut builder = OciQueryBuilder::with_fragments();
+        values.to_sql(&mut builder).unwrap();
+        builder.fragments().to_vec()
+    }
+
+    #[test]
+    fn check_split_values_clause() {
+        let values = fragments(
+            (
+                users::id.eq(1),
+                users::name.eq(sql::<Text>("REPLACE('a, b', ', ', ',')")),
+                None::<diesel::dsl::Eq<users::hair_color, &str>>,
+            )
+                .values(),
+        );
+        let (columns, values) = split_values_clause(&values).unwrap();
+        assert_eq!(columns, vec!["\"ID\"", "\"NAME\"", "\"HAIR_COLOR\""]);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], &[SqlFragment::Bind][..]);
+        assert_eq!(
+            values[1],
+            &[SqlFragment::Sql("REPLACE('a, b', ', ', ',')".into(), 1)][..]
+        );
+        assert!(values[2][0].is_sql("DEFAULT", 1));
+
+        let values = fragments(diesel::insert_into(users::table).default_values());
+        assert_eq!(split_values_clause(&values), None);
+    }
+
+    #[test]
+    fn check_upsert_batch_sql() {
+        let rows = vec![
+            NewUser {
+                id: 1,
+                name: "Sean",
+                hair_color: Some("black"),
+            },
+            NewUser {
+                id: 2,
+                name: "Tess",
+                hair_color: None,
+            },
+            NewUser {
+                id: 3,
+                name: "Jim",
+                hair_color: Some("brown"),
+            },
+        ];
+        let statements = upsert_batch(users::table)
+            .key(users::id)
+            .values(rows)
+            .build()
+            .unwrap();
+        assert_eq!(statements.len(), 2);
+        let (ref sql, ref metadata, ref binds) = statements[0];
+        assert_eq!(
+            sql,
+            "MERGE INTO \"USERS\" t USING (\
+             SELECT :1 AS \"ID\", :2 AS \"NAME\", :3 AS \"HAIR_COLOR\" FROM dual \
+             UNION ALL SELECT :4, :5, :6 FROM dual) s \
+             ON (t.\"ID\" = s.\"ID\") \
+             WHEN MATCHED THEN UPDATE SET t.\"NAME\" = s.\"NAME\", \
+             t.\"HAIR_COLOR\" = s.\"HAIR_COLOR\" \
+             WHEN NOT MATCHED THEN INSERT (\"ID\", \"NAME\", \"HAIR_COLOR\") \
+             VALUES (s.\"ID\", s.\"NAME\", s.\"HAIR_COLOR\")"
+        );
+        assert_eq!(metadata.len(), 6);
+        assert_eq!(binds.len(), 6);
+
+        // the hair color of Tess is neither updated nor inserted as NULL
+        let (ref sql, ref metadata, _) = statements[1];
+        assert_eq!(
+            sql,
+            "MERGE INTO \"USERS\" t USING (\
+             SELECT :1 AS \"ID\", :2 AS \"NAME\" FROM dual) s \
+             ON (t.\"ID\" = s.\"ID\") \
+             WHEN MATCHED THEN UPDATE SET t.\"NAME\" = s.\"NAME\" \
+             WHEN NOT MATCHED THEN INSERT (\"ID\", \"NAME\", \"HAIR_COLOR\") \
+             VALUES (s.\"ID\", s.\"NAME\", DEFAULT)"
+        );
+        assert_eq!(metadata.len(), 2);
+    }
+
+    #[test]
+    fn check_upsert_batch_of_expressions() {
+        let rows = vec![
+            (
+                users::id.eq(1),
+                users::name.eq(sql::<Text>("UPPER('a, b')")),
+            ),
+            (users::id.eq(2), users::name.eq(sql::<Text>("USER"))),
+        ];
+        let statements = upsert_batch(users::table)
+            .key((users::id,))
+            .values(rows)
+            .build()
+            .unwrap();
+        let (ref sql, ref metadata, _) = statements[0];
+        assert_eq!(
+            sql,
+            "MERGE INTO \"USERS\" t USING (\
+             SELECT :1 AS \"ID\", UPPER('a, b') AS \"NAME\" FROM dual \
+             UNION ALL SELECT :2, USER FROM dual) s \
+             ON (t.\"ID\" = s.\"ID\") \
+             WHEN MATCHED THEN UPDATE SET t.\"NAME\" = s.\"NAME\" \
+             WHEN NOT MATCHED THEN INSERT (\"ID\", \"NAME\") \
+             VALUES (s.\"ID\", s.\"NAME\")"
+        );
+        assert_eq!(metadata.len(), 2);
+    }
+
+    #[test]
+    fn check_upsert_batch_of_default_owner() {
+        use oracle::query_builder::with_default_owner;
+
+        let rows = vec![(users::id.eq(1), users::name.eq("Sean"))];
+        let statements = with_default_owner(Some("app"), || {
+            upsert_batch(users::table)
+                .key(users::id)
+                .values(rows)
+                .build()
+                .unwrap()
+        });
+        let sql = &statements[0].0;
+        assert!(sql.starts_with(
+            "MERGE INTO \"APP\".\"USERS\" t USING (SELECT :1 AS \"ID\", :2 AS \"NAME\""
+        ));
+        assert!(sql.contains("ON (t.\"ID\" = s.\"ID\")"));
+    }
+
+    #[test]
+    fn check_upsert_batch_requires_key_column() {
+        #[derive(Insertable)]
+        #[table_name = "users"]
+        struct Rename<'a> {
+            name: &'a str,
+        }
+
+        let ret = upsert_batch(users::table)
+            .key(users::id)
+            .values(vec![Rename { name: "Sean" }])
+            .build();
+        assert!(ret.is_err());
+    }
+}
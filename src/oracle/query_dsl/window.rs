@@ -0,0 +1,318 @@
+use diesel::expression::{AppearsOnTable, Expression, NonAggregate, SelectableExpression};
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, IntoNullable};
+
+use oracle::backend::Oracle;
+
+// diesel 1.4 has no support for window functions, so oracle's analytic
+// functions are provided as expressions of the oracle backend.
+// c.f. https://docs.oracle.com/database/121/SQLRF/functions004.htm
+
+/// A ranking function, c.f. `row_number`
+///
+/// Ranking functions are only valid with an `OVER` clause.
+#[derive(Debug, Clone, Copy)]
+pub struct RankingFunction {
+    name: &'static str,
+}
+
+/// `ROW_NUMBER()`, the number of the row within its partition starting at 1
+pub fn row_number() -> RankingFunction {
+    RankingFunction { name: "ROW_NUMBER" }
+}
+
+/// `RANK()`, the rank of the row within its partition, rows with equal
+/// values have the same rank and leave a gap
+pub fn rank() -> RankingFunction {
+    RankingFunction { name: "RANK" }
+}
+
+/// `DENSE_RANK()`, like `rank` without gaps
+pub fn dense_rank() -> RankingFunction {
+    RankingFunction { name: "DENSE_RANK" }
+}
+
+impl Expression for RankingFunction {
+    type SqlType = BigInt;
+}
+
+impl<QS> AppearsOnTable<QS> for RankingFunction {}
+
+impl<QS> SelectableExpression<QS> for RankingFunction {}
+
+impl QueryId for RankingFunction {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl QueryFragment<Oracle> for RankingFunction {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql(self.name);
+        out.push_sql("()");
+        Ok(())
+    }
+}
+
+/// The value of an expression in a preceding or following row, c.f. `lag`
+///
+/// The value is null if there is no such row in the partition.
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetFunction<E> {
+    name: &'static str,
+    expr: E,
+    offset: u32,
+}
+
+/// `LAG(expr, offset)`, the value of `expr` `offset` rows before the current
+/// one
+pub fn lag<E: Expression>(expr: E, offset: u32) -> OffsetFunction<E> {
+    OffsetFunction {
+        name: "LAG",
+        expr,
+        offset,
+    }
+}
+
+/// `LEAD(expr, offset)`, the value of `expr` `offset` rows after the current
+/// one
+pub fn lead<E: Expression>(expr: E, offset: u32) -> OffsetFunction<E> {
+    OffsetFunction {
+        name: "LEAD",
+        expr,
+        offset,
+    }
+}
+
+impl<E> Expression for OffsetFunction<E>
+where
+    E: Expression,
+    E::SqlType: IntoNullable,
+{
+    type SqlType = <E::SqlType as IntoNullable>::Nullable;
+}
+
+impl<E, QS> AppearsOnTable<QS> for OffsetFunction<E>
+where
+    E: AppearsOnTable<QS>,
+    Self: Expression,
+{
+}
+
+impl<E, QS> SelectableExpression<QS> for OffsetFunction<E>
+where
+    E: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+impl<E> QueryId for OffsetFunction<E> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<E: QueryFragment<Oracle>> QueryFragment<Oracle> for OffsetFunction<E> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql(self.name);
+        out.push_sql("(");
+        self.expr.walk_ast(out.reborrow())?;
+        out.push_sql(&format!(", {})", self.offset));
+        Ok(())
+    }
+}
+
+/// The `PARTITION BY` clause of `Over`
+#[derive(Debug, Clone, Copy)]
+pub struct PartitionBy<T>(T);
+
+/// The `ORDER BY` clause of `Over`
+#[derive(Debug, Clone, Copy)]
+pub struct WindowOrder<T>(T);
+
+/// A clause of `Over` whose expressions appear on the tables `QS`, `()` if
+/// the clause is omitted
+pub trait WindowClause<QS> {}
+
+impl<QS> WindowClause<QS> for () {}
+
+impl<T: AppearsOnTable<QS>, QS> WindowClause<QS> for PartitionBy<T> {}
+
+impl<T: AppearsOnTable<QS>, QS> WindowClause<QS> for WindowOrder<T> {}
+
+impl<T: QueryFragment<Oracle>> QueryFragment<Oracle> for PartitionBy<T> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql("PARTITION BY ");
+        self.0.walk_ast(out.reborrow())
+    }
+}
+
+impl<T: QueryFragment<Oracle>> QueryFragment<Oracle> for WindowOrder<T> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql("ORDER BY ");
+        self.0.walk_ast(out.reborrow())
+    }
+}
+
+/// An analytic function, c.f. `OverDsl`
+#[derive(Debug, Clone, Copy)]
+pub struct Over<E, P = (), O = ()> {
+    expr: E,
+    partition_by: P,
+    order_by: O,
+    partitioned: bool,
+    ordered: bool,
+}
+
+impl<E, P, O> Over<E, P, O> {
+    /// Computes the function separately for the rows with equal values of
+    /// `expr`, a tuple for several expressions
+    pub fn partition_by<T>(self, expr: T) -> Over<E, PartitionBy<T>, O> {
+        Over {
+            expr: self.expr,
+            partition_by: PartitionBy(expr),
+            order_by: self.order_by,
+            partitioned: true,
+            ordered: self.ordered,
+        }
+    }
+
+    /// Orders the rows of each partition, e.g. `(a.desc(), b)`
+    pub fn order_by<T>(self, expr: T) -> Over<E, P, WindowOrder<T>> {
+        Over {
+            expr: self.expr,
+            partition_by: self.partition_by,
+            order_by: WindowOrder(expr),
+            partitioned: self.partitioned,
+            ordered: true,
+        }
+    }
+}
+
+/// Adds an `OVER` clause to an expression, making it an analytic function
+///
+/// ```ignore
+/// use diesel::dsl::sum;
+///
+/// // the position of each employee in their department by salary and the
+/// // total salary of the department
+/// let ranking = employees::table
+///     .select((
+///         employees::name,
+///         row_number()
+///             .over()
+///             .partition_by(employees::department)
+///             .order_by(employees::salary.desc()),
+///         sum(employees::salary).over().partition_by(employees::department),
+///     ))
+///     .load::<(String, i64, Option<i64>)>(&conn)?;
+/// ```
+pub trait OverDsl: Expression + Sized {
+    /// `expr OVER ()`, the function of all rows of the result
+    fn over(self) -> Over<Self> {
+        Over {
+            expr: self,
+            partition_by: (),
+            order_by: (),
+            partitioned: false,
+            ordered: false,
+        }
+    }
+}
+
+impl<T: Expression> OverDsl for T {}
+
+impl<E: Expression, P, O> Expression for Over<E, P, O> {
+    type SqlType = E::SqlType;
+}
+
+impl<E, P, O> NonAggregate for Over<E, P, O> {}
+
+impl<E, P, O, QS> AppearsOnTable<QS> for Over<E, P, O>
+where
+    E: AppearsOnTable<QS>,
+    P: WindowClause<QS>,
+    O: WindowClause<QS>,
+{
+}
+
+impl<E, P, O, QS> SelectableExpression<QS> for Over<E, P, O>
+where
+    E: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+impl<E, P, O> QueryId for Over<E, P, O> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<E, P, O> QueryFragment<Oracle> for Over<E, P, O>
+where
+    E: QueryFragment<Oracle>,
+    P: QueryFragment<Oracle>,
+    O: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        self.expr.walk_ast(out.reborrow())?;
+        out.push_sql(" OVER (");
+        self.partition_by.walk_ast(out.reborrow())?;
+        if self.partitioned && self.ordered {
+            out.push_sql(" ");
+        }
+        self.order_by.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lag, rank, row_number, OverDsl};
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use oracle::backend::Oracle;
+
+    table! {
+        employees (id) {
+            id -> Integer,
+            department -> Text,
+            salary -> BigInt,
+        }
+    }
+
+    #[test]
+    fn check_window_sql() {
+        let query = employees::table.select((
+            employees::id,
+            row_number()
+                .over()
+                .partition_by(employees::department)
+                .order_by(employees::salary.desc()),
+            diesel::dsl::sum(employees::salary)
+                .over()
+                .partition_by(employees::department),
+        ));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT \"EMPLOYEES\".\"ID\", ROW_NUMBER() OVER (PARTITION BY \
+             \"EMPLOYEES\".\"DEPARTMENT\" ORDER BY \"EMPLOYEES\".\"SALARY\" DESC), \
+             sum(\"EMPLOYEES\".\"SALARY\") OVER (PARTITION BY \"EMPLOYEES\".\"DEPARTMENT\") \
+             FROM \"EMPLOYEES\" -- binds: []"
+        );
+
+        let query = employees::table.select((
+            rank().over().order_by(employees::salary),
+            lag(employees::salary, 1).over().order_by(employees::id),
+        ));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "SELECT RANK() OVER (ORDER BY \"EMPLOYEES\".\"SALARY\"), \
+             LAG(\"EMPLOYEES\".\"SALARY\", 1) OVER (ORDER BY \"EMPLOYEES\".\"ID\") \
+             FROM \"EMPLOYEES\" -- binds: []"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use diesel::query_builder::{AsQuery, AstPass, Query, QueryFragment, QueryId};
+use diesel::result::{Error, QueryResult};
+use diesel::RunQueryDsl;
+
+use oracle::backend::Oracle;
+use oracle::query_builder::push_local_name;
+
+// diesel 1.4 has no support for common table expressions, so the `WITH`
+// clause (subquery factoring) is provided as a separate dsl. The queries
+// refer to a common table expression by a table declared with `table!`
+// c.f. https://docs.oracle.com/database/121/SQLRF/statements_10002.htm#BABFAFID
+
+/// A common table expression, c.f. `WithDsl`
+#[derive(Debug, Clone)]
+pub struct Cte<B> {
+    name: &'static str,
+    columns: Vec<&'static str>,
+    recursive: bool,
+    body: B,
+}
+
+/// Creates the common table expression `name AS (body)`
+pub fn cte<B: AsQuery>(name: &'static str, body: B) -> Cte<B::Query> {
+    Cte {
+        name,
+        columns: Vec::new(),
+        recursive: false,
+        body: body.as_query(),
+    }
+}
+
+impl<B> Cte<B> {
+    /// Names the columns of the common table expression
+    /// (`name (column, ...) AS (body)`), oracle requires them for recursive
+    /// ones
+    pub fn columns(self, columns: &[&'static str]) -> Self {
+        Cte {
+            columns: columns.to_vec(),
+            ..self
+        }
+    }
+
+    /// Makes this a recursive common table expression, whose body is
+    /// `body UNION ALL recursive`
+    ///
+    /// The recursive member joins the common table expression itself, oracle
+    /// iterates until it returns no more rows.
+    pub fn union_all<R: AsQuery>(self, recursive: R) -> Cte<UnionAll<B, R::Query>> {
+        Cte {
+            name: self.name,
+            columns: self.columns,
+            recursive: true,
+            body: UnionAll {
+                anchor: self.body,
+                recursive: recursive.as_query(),
+            },
+        }
+    }
+}
+
+/// The body of a recursive common table expression, c.f. `Cte::union_all`
+#[derive(Debug, Clone, Copy)]
+pub struct UnionAll<A, R> {
+    anchor: A,
+    recursive: R,
+}
+
+/// Several common table expressions of a `WITH` clause
+#[derive(Debug, Clone)]
+pub struct CteList<A, B>(A, B);
+
+/// A query with a `WITH` clause, c.f. `WithDsl`
+#[derive(Debug, Clone)]
+pub struct WithClause<Q, C> {
+    query: Q,
+    ctes: C,
+}
+
+impl<Q, C> WithClause<Q, C> {
+    /// Adds another common table expression, which may refer to the
+    /// previous ones
+    pub fn with_cte<B>(self, cte: Cte<B>) -> WithClause<Q, CteList<C, Cte<B>>> {
+        WithClause {
+            query: self.query,
+            ctes: CteList(self.ctes, cte),
+        }
+    }
+}
+
+/// Adds a `WITH` clause to a query
+///
+/// ```ignore
+/// table! {
+///     subordinates (id) {
+///         id -> Integer,
+///         manager_id -> Nullable<Integer>,
+///     }
+/// }
+///
+/// // all direct and indirect subordinates of employee 1
+/// let anchor = employees::table
+///     .select((employees::id, employees::manager_id))
+///     .filter(employees::id.eq(1));
+/// let recursive = employees::table
+///     .inner_join(subordinates::table.on(employees::manager_id.eq(subordinates::id.nullable())))
+///     .select((employees::id, employees::manager_id));
+/// let ids = subordinates::table
+///     .select(subordinates::id)
+///     .with_cte(cte("subordinates", anchor).columns(&["id", "manager_id"]).union_all(recursive))
+///     .load::<i32>(&conn)?;
+/// ```
+pub trait WithDsl: AsQuery + Sized {
+    /// `WITH name AS (body) query`
+    fn with_cte<B>(self, cte: Cte<B>) -> WithClause<Self::Query, Cte<B>> {
+        WithClause {
+            query: self.as_query(),
+            ctes: cte,
+        }
+    }
+}
+
+impl<T: AsQuery> WithDsl for T {}
+
+impl<Q: Query, C> Query for WithClause<Q, C> {
+    type SqlType = Q::SqlType;
+}
+
+impl<Q, C> QueryId for WithClause<Q, C> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q, C> QueryFragment<Oracle> for WithClause<Q, C>
+where
+    Q: QueryFragment<Oracle>,
+    C: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        out.push_sql("WITH ");
+        self.ctes.walk_ast(out.reborrow())?;
+        out.push_sql(" ");
+        self.query.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q, C, Conn> RunQueryDsl<Conn> for WithClause<Q, C> {}
+
+impl<A, B> QueryFragment<Oracle> for CteList<A, B>
+where
+    A: QueryFragment<Oracle>,
+    B: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        self.0.walk_ast(out.reborrow())?;
+        out.push_sql(", ");
+        self.1.walk_ast(out.reborrow())
+    }
+}
+
+impl<B: QueryFragment<Oracle>> QueryFragment<Oracle> for Cte<B> {
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        if self.recursive && self.columns.is_empty() {
+            // ORA-32039: recursive WITH clause must have column alias list
+            return Err(Error::QueryBuilderError(
+                format!(
+                    "The recursive common table expression {} has no columns",
+                    self.name
+                )
+                .into(),
+            ));
+        }
+        // the name refers to the expression in the following sql and, if it
+        // is recursive, in its body
+        if self.recursive {
+            push_local_name(&mut out, self.name);
+        }
+        out.push_identifier(self.name)?;
+        if !self.columns.is_empty() {
+            out.push_sql(" (");
+            for (i, column) in self.columns.iter().enumerate() {
+                if i > 0 {
+                    out.push_sql(", ");
+                }
+                out.push_identifier(column)?;
+            }
+            out.push_sql(")");
+        }
+        out.push_sql(" AS (");
+        self.body.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        if !self.recursive {
+            push_local_name(&mut out, self.name);
+        }
+        Ok(())
+    }
+}
+
+impl<A, R> QueryFragment<Oracle> for UnionAll<A, R>
+where
+    A: QueryFragment<Oracle>,
+    R: QueryFragment<Oracle>,
+{
+    fn walk_ast(&self, mut out: AstPass<Oracle>) -> QueryResult<()> {
+        self.anchor.walk_ast(out.reborrow())?;
+        out.push_sql(" UNION ALL ");
+        self.recursive.walk_ast(out.reborrow())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cte, WithDsl};
+    use diesel::debug_query;
+    use diesel::prelude::*;
+    use diesel::query_builder::QueryFragment;
+    use oracle::backend::Oracle;
+    use oracle::query_builder::OciQueryBuilder;
+
+    table! {
+        employees (id) {
+            id -> Integer,
+            manager_id -> Nullable<Integer>,
+        }
+    }
+
+    table! {
+        subordinates (id) {
+            id -> Integer,
+            manager_id -> Nullable<Integer>,
+        }
+    }
+
+    allow_tables_to_appear_in_same_query!(employees, subordinates);
+
+    #[test]
+    fn check_with_sql() {
+        let managers = employees::table
+            .select((employees::id, employees::manager_id))
+            .filter(employees::manager_id.is_null());
+        let query = subordinates::table
+            .select(subordinates::id)
+            .with_cte(cte("subordinates", managers));
+        assert_eq!(
+            debug_query::<Oracle, _>(&query).to_string(),
+            "WITH \"SUBORDINATES\" AS (SELECT \"EMPLOYEES\".\"ID\", \"EMPLOYEES\".\"MANAGER_ID\" \
+             FROM \"EMPLOYEES\" WHERE \"EMPLOYEES\".\"MANAGER_ID\" IS NULL) \
+             SELECT \"SUBORDINATES\".\"ID\" FROM \"SUBORDINATES\" -- binds: []"
+        );
+    }
+
+    #[test]
+    fn check_recursive_with_sql() {
+        let anchor = employees::table
+            .select((employees::id, employees::manager_id))
+            .filter(employees::id.eq(1));
+        let recursive = employees::table
+            .inner_join(
+                subordinates::table.on(employees::manager_id.eq(subordinates::id.nullable())),
+            )
+            .select((employees::id, employees::manager_id));
+        let query = subordinates::table.select(subordinates::id).with_cte(
+            cte("subordinates", anchor)
+                .columns(&["id", "manager_id"])
+                .union_all(recursive),
+        );
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.starts_with(
+            "WITH \"SUBORDINATES\" (\"ID\", \"MANAGER_ID\") AS (SELECT \"EMPLOYEES\".\"ID\", \
+             \"EMPLOYEES\".\"MANAGER_ID\" FROM \"EMPLOYEES\" WHERE \"EMPLOYEES\".\"ID\" = :1 \
+             UNION ALL SELECT"
+        ));
+        assert!(
+            sql.ends_with(") SELECT \"SUBORDINATES\".\"ID\" FROM \"SUBORDINATES\" -- binds: [1]")
+        );
+
+        // oracle requires the column list for recursive queries
+        let query = subordinates::table.with_cte(
+            cte(
+                "subordinates",
+                employees::table.select((employees::id, employees::manager_id)),
+            )
+            .union_all(employees::table.select((employees::id, employees::manager_id))),
+        );
+        let mut query_builder = OciQueryBuilder::new();
+        assert!(query.to_sql(&mut query_builder).is_err());
+    }
+
+    #[test]
+    fn check_multiple_ctes_sql() {
+        let query = subordinates::table
+            .select(subordinates::id)
+            .with_cte(cte(
+                "employees",
+                employees::table.filter(employees::id.gt(1)),
+            ))
+            .with_cte(cte("subordinates", employees::table));
+        let sql = debug_query::<Oracle, _>(&query).to_string();
+        assert!(sql.starts_with("WITH \"EMPLOYEES\" AS (SELECT"));
+        assert!(sql.contains("> :1), \"SUBORDINATES\" AS (SELECT"));
+    }
+
+    #[test]
+    fn check_with_sql_of_default_owner() {
+        use oracle::query_builder::with_default_owner;
+
+        // only the tables get the owner, not the common table expressions
+        let query = subordinates::table
+            .select(subordinates::id)
+            .with_cte(cte("subordinates", employees::table.select(employees::id)));
+        let sql = with_default_owner(Some("app"), || debug_query::<Oracle, _>(&query).to_string());
+        assert_eq!(
+            sql,
+            "WITH \"SUBORDINATES\" AS (SELECT \"APP\".\"EMPLOYEES\".\"ID\" \
+             FROM \"APP\".\"EMPLOYEES\") \
+             SELECT \"SUBORDINATES\".\"ID\" FROM \"SUBORDINATES\" -- binds: []"
+        );
+
+        // the body of a non recursive expression refers to the table
+        let query = employees::table.select(employees::id).with_cte(cte(
+            "employees",
+            employees::table.filter(employees::id.gt(1)),
+        ));
+        let sql = with_default_owner(Some("app"), || debug_query::<Oracle, _>(&query).to_string());
+        assert!(sql.contains("FROM \"APP\".\"EMPLOYEES\" WHERE"));
+        assert!(sql.ends_with(") SELECT \"EMPLOYEES\".\"ID\" FROM \"EMPLOYEES\" -- binds: [1]"));
+    }
+}
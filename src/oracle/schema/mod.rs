@@ -0,0 +1,285 @@
+//! A structured model of an oracle schema
+//!
+//! The model is built from the `ALL_*` data dictionary views for the current
+//! schema of a connection, c.f. `OciConnection::snapshot_schema`. With the
+//! `serde` feature enabled all types can be serialized, which allows to store
+//! a snapshot and compare it later on to detect schema drift.
+
+use diesel::dsl::sql;
+use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use diesel::RunQueryDsl;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::connection::OciConnection;
+
+mod print;
+
+pub use self::print::PrintOptions;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemaSnapshot {
+    pub owner: String,
+    pub tables: Vec<Table>,
+    pub sequences: Vec<Sequence>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub constraints: Vec<Constraint>,
+    pub indexes: Vec<Index>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Column {
+    pub name: String,
+    /// The oracle type name, e.g. `NUMBER` or `VARCHAR2`
+    pub data_type: String,
+    /// The length of the column in bytes
+    pub data_length: i32,
+    pub precision: Option<i32>,
+    pub scale: Option<i32>,
+    pub nullable: bool,
+    /// Invisible or system generated columns, which are not returned by
+    /// `SELECT *`
+    pub hidden: bool,
+    /// Columns computed from an expression, which cannot be inserted or
+    /// updated
+    pub virtual_column: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConstraintType {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Constraint {
+    pub name: String,
+    pub constraint_type: ConstraintType,
+    pub columns: Vec<String>,
+    /// The referenced table for foreign keys
+    pub referenced_table: Option<String>,
+    /// The referenced columns for foreign keys, in the order of `columns`
+    pub referenced_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Index {
+    pub name: String,
+    pub unique: bool,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sequence {
+    pub name: String,
+    // min and max value are NUMBER(28), so they don't fit into an i64
+    pub min_value: String,
+    pub max_value: String,
+    pub increment_by: i64,
+    pub cycle: bool,
+}
+
+// Numbers are casted to NUMBER(10)/NUMBER(19) so that they are fetched as
+// integers, 1 byte flags are decoded to something longer than a single char
+
+const TABLES_QUERY: &str = "SELECT table_name FROM all_tables WHERE owner = ";
+
+// ALL_TAB_COLS (other than ALL_TAB_COLUMNS) includes hidden columns
+const COLUMNS_QUERY: &str = "SELECT table_name, column_name, data_type, \
+     CAST(data_length AS NUMBER(10)), \
+     CAST(data_precision AS NUMBER(10)), \
+     CAST(data_scale AS NUMBER(10)), \
+     CAST(DECODE(nullable, 'Y', 1, 0) AS NUMBER(10)), \
+     CAST(DECODE(hidden_column, 'YES', 1, 0) AS NUMBER(10)), \
+     CAST(DECODE(virtual_column, 'YES', 1, 0) AS NUMBER(10)) \
+     FROM all_tab_cols WHERE owner = ";
+
+const CONSTRAINTS_QUERY: &str = "SELECT c.table_name, c.constraint_name, \
+     DECODE(c.constraint_type, 'P', 'PRIMARY KEY', 'U', 'UNIQUE', 'R', 'FOREIGN KEY'), \
+     cc.column_name, r.table_name, rc.column_name \
+     FROM all_constraints c \
+     JOIN all_cons_columns cc \
+     ON cc.owner = c.owner AND cc.constraint_name = c.constraint_name \
+     LEFT JOIN all_constraints r \
+     ON r.owner = c.r_owner AND r.constraint_name = c.r_constraint_name \
+     LEFT JOIN all_cons_columns rc \
+     ON rc.owner = r.owner AND rc.constraint_name = r.constraint_name \
+     AND rc.position = cc.position \
+     WHERE c.constraint_type IN ('P', 'U', 'R') AND c.owner = ";
+
+const INDEXES_QUERY: &str = "SELECT i.table_name, i.index_name, \
+     CAST(DECODE(i.uniqueness, 'UNIQUE', 1, 0) AS NUMBER(10)), ic.column_name \
+     FROM all_indexes i \
+     JOIN all_ind_columns ic \
+     ON ic.index_owner = i.owner AND ic.index_name = i.index_name \
+     WHERE i.table_owner = ";
+
+const SEQUENCES_QUERY: &str = "SELECT sequence_name, \
+     TO_CHAR(min_value), TO_CHAR(max_value), \
+     CAST(increment_by AS NUMBER(19)), \
+     CAST(DECODE(cycle_flag, 'Y', 1, 0) AS NUMBER(10)) \
+     FROM all_sequences WHERE sequence_owner = ";
+
+impl OciConnection {
+    /// Takes a snapshot of the tables, columns, constraints, indexes and
+    /// sequences of the current schema
+    ///
+    /// Only primary key, unique and foreign key constraints are included.
+    pub fn snapshot_schema(&self) -> QueryResult<SchemaSnapshot> {
+        let owner = self.current_schema()?;
+
+        let mut tables = sql::<Text>(TABLES_QUERY)
+            .bind::<Text, _>(&owner)
+            .sql(" ORDER BY table_name")
+            .load::<String>(self)?
+            .into_iter()
+            .map(|name| Table {
+                name,
+                columns: Vec::new(),
+                constraints: Vec::new(),
+                indexes: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        let columns = sql::<(
+            Text,
+            Text,
+            Text,
+            Integer,
+            Nullable<Integer>,
+            Nullable<Integer>,
+            Integer,
+            Integer,
+            Integer,
+        )>(COLUMNS_QUERY)
+        .bind::<Text, _>(&owner)
+        .sql(" ORDER BY table_name, internal_column_id")
+        .load::<(
+            String,
+            String,
+            String,
+            i32,
+            Option<i32>,
+            Option<i32>,
+            i32,
+            i32,
+            i32,
+        )>(self)?;
+        for (table, name, data_type, data_length, precision, scale, nullable, hidden, virt) in
+            columns
+        {
+            if let Some(t) = find_table(&mut tables, &table) {
+                t.columns.push(Column {
+                    name,
+                    data_type,
+                    data_length,
+                    precision,
+                    scale,
+                    nullable: nullable != 0,
+                    hidden: hidden != 0,
+                    virtual_column: virt != 0,
+                });
+            }
+        }
+
+        let constraints =
+            sql::<(Text, Text, Text, Text, Nullable<Text>, Nullable<Text>)>(CONSTRAINTS_QUERY)
+                .bind::<Text, _>(&owner)
+                .sql(" ORDER BY c.table_name, c.constraint_name, cc.position")
+                .load::<(
+                    String,
+                    String,
+                    String,
+                    String,
+                    Option<String>,
+                    Option<String>,
+                )>(self)?;
+        for (table, name, tpe, column, referenced_table, referenced_column) in constraints {
+            let t = match find_table(&mut tables, &table) {
+                Some(t) => t,
+                None => continue,
+            };
+            if t.constraints.last().map(|c| c.name != name).unwrap_or(true) {
+                let constraint_type = match &*tpe {
+                    "PRIMARY KEY" => ConstraintType::PrimaryKey,
+                    "UNIQUE" => ConstraintType::Unique,
+                    _ => ConstraintType::ForeignKey,
+                };
+                t.constraints.push(Constraint {
+                    name,
+                    constraint_type,
+                    columns: Vec::new(),
+                    referenced_table,
+                    referenced_columns: Vec::new(),
+                });
+            }
+            let c = t.constraints.last_mut().expect("Pushed above");
+            c.columns.push(column);
+            c.referenced_columns.extend(referenced_column);
+        }
+
+        let indexes = sql::<(Text, Text, Integer, Text)>(INDEXES_QUERY)
+            .bind::<Text, _>(&owner)
+            .sql(" ORDER BY i.table_name, i.index_name, ic.column_position")
+            .load::<(String, String, i32, String)>(self)?;
+        for (table, name, unique, column) in indexes {
+            let t = match find_table(&mut tables, &table) {
+                Some(t) => t,
+                None => continue,
+            };
+            if t.indexes.last().map(|i| i.name != name).unwrap_or(true) {
+                t.indexes.push(Index {
+                    name,
+                    unique: unique != 0,
+                    columns: Vec::new(),
+                });
+            }
+            t.indexes
+                .last_mut()
+                .expect("Pushed above")
+                .columns
+                .push(column);
+        }
+
+        let sequences = sql::<(Text, Text, Text, BigInt, Integer)>(SEQUENCES_QUERY)
+            .bind::<Text, _>(&owner)
+            .sql(" ORDER BY sequence_name")
+            .load::<(String, String, String, i64, i32)>(self)?
+            .into_iter()
+            .map(
+                |(name, min_value, max_value, increment_by, cycle)| Sequence {
+                    name,
+                    min_value,
+                    max_value,
+                    increment_by,
+                    cycle: cycle != 0,
+                },
+            )
+            .collect();
+
+        Ok(SchemaSnapshot {
+            owner,
+            tables,
+            sequences,
+        })
+    }
+}
+
+fn find_table<'a>(tables: &'a mut [Table], name: &str) -> Option<&'a mut Table> {
+    tables.iter_mut().find(|t| t.name == name)
+}
@@ -0,0 +1,451 @@
+use std::fmt::Write;
+
+use diesel::result::QueryResult;
+
+use super::super::connection::{integer_type, FetchType, OciConnection};
+use super::{Column, ConstraintType, SchemaSnapshot, Table};
+
+// diesel-cli (1.x) only knows about its builtin backends, so we provide the
+// equivalent of `diesel print-schema` here
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Controls which columns `SchemaSnapshot::to_table_macros_with` includes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// Include invisible and system generated columns, `false` by default
+    pub include_hidden: bool,
+    /// Include virtual columns, `true` by default
+    ///
+    /// Virtual columns can be selected, but structs deriving `Insertable`
+    /// or `AsChangeset` must not contain them.
+    pub include_virtual: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            include_hidden: false,
+            include_virtual: true,
+        }
+    }
+}
+
+impl PrintOptions {
+    fn includes(&self, column: &Column) -> bool {
+        (self.include_hidden || !column.hidden) && (self.include_virtual || !column.virtual_column)
+    }
+}
+
+impl Column {
+    /// The diesel sql type used for this column in a `table!` definition,
+    /// without the `Nullable` wrapper
+    ///
+    /// Returns `None` for types diesel-oci does not support.
+    pub fn diesel_type(&self) -> Option<&'static str> {
+        let tpe = match &*self.data_type {
+            // the same types the columns are fetched as (c.f.
+            // `default_fetch_type`), `INTEGER` is a `NUMBER(*,0)` without a
+            // precision
+            "NUMBER" if self.scale == Some(0) => {
+                match integer_type(self.precision.unwrap_or(0) as i16) {
+                    FetchType::SmallInt => "SmallInt",
+                    FetchType::Integer => "Integer",
+                    FetchType::LargeInt => "LargeInt",
+                    _ => "BigInt",
+                }
+            }
+            "NUMBER" => "Double",
+            "FLOAT" | "BINARY_DOUBLE" => "Double",
+            "BINARY_FLOAT" => "Float",
+            "VARCHAR2" | "NVARCHAR2" | "CHAR" | "NCHAR" | "CLOB" | "NCLOB" | "LONG" => "Text",
+            "RAW" | "LONG RAW" | "BLOB" => "Binary",
+            // an oracle DATE contains a time part
+            "DATE" => "Timestamp",
+            t if t.starts_with("TIMESTAMP") => "Timestamp",
+            _ => return None,
+        };
+        Some(tpe)
+    }
+}
+
+impl Table {
+    /// The columns of the primary key of this table
+    pub fn primary_key(&self) -> Option<&[String]> {
+        self.constraints
+            .iter()
+            .find(|c| c.constraint_type == ConstraintType::PrimaryKey)
+            .map(|c| &*c.columns)
+    }
+}
+
+impl SchemaSnapshot {
+    /// Renders the `table!`, `joinable!` and
+    /// `allow_tables_to_appear_in_same_query!` definitions for this schema
+    ///
+    /// Tables without a primary key are skipped, as they cannot be used
+    /// with diesel's `table!` macro.
+    pub fn to_table_macros(&self) -> String {
+        self.to_table_macros_with(&PrintOptions::default())
+    }
+
+    /// Same as `to_table_macros`, with control over hidden and virtual
+    /// columns
+    pub fn to_table_macros_with(&self, options: &PrintOptions) -> String {
+        let mut out = String::new();
+        let mut printed = Vec::new();
+
+        for table in &self.tables {
+            let primary_key = match table.primary_key() {
+                Some(pk) => pk,
+                None => {
+                    writeln!(
+                        out,
+                        "// `{}` has no primary key and cannot be represented by `table!`\n",
+                        table.name
+                    )
+                    .expect("Writing to a string does not fail");
+                    continue;
+                }
+            };
+            print_table(&mut out, table, primary_key, options);
+            printed.push(table);
+        }
+
+        let mut joinables = Vec::new();
+        for table in &printed {
+            for fk in table
+                .constraints
+                .iter()
+                .filter(|c| c.constraint_type == ConstraintType::ForeignKey && c.columns.len() == 1)
+            {
+                let referenced = match fk.referenced_table {
+                    Some(ref r) => r,
+                    None => continue,
+                };
+                // joinable! requires the foreign key to point to the primary key
+                let points_to_pk = printed.iter().any(|t| {
+                    t.name == *referenced && t.primary_key() == Some(&fk.referenced_columns[..])
+                });
+                if points_to_pk {
+                    joinables.push(format!(
+                        "joinable!({} -> {} ({}));",
+                        rust_name(&table.name),
+                        rust_name(referenced),
+                        rust_name(&fk.columns[0])
+                    ));
+                }
+            }
+        }
+        joinables.sort();
+        joinables.dedup();
+        for joinable in &joinables {
+            writeln!(out, "{}", joinable).expect("Writing to a string does not fail");
+        }
+        if !joinables.is_empty() {
+            out.push('\n');
+        }
+
+        if printed.len() > 1 {
+            out.push_str("allow_tables_to_appear_in_same_query!(\n");
+            for table in &printed {
+                writeln!(out, "    {},", rust_name(&table.name))
+                    .expect("Writing to a string does not fail");
+            }
+            out.push_str(");\n");
+        }
+
+        out
+    }
+}
+
+fn print_table(out: &mut String, table: &Table, primary_key: &[String], options: &PrintOptions) {
+    let pk = primary_key
+        .iter()
+        .map(|c| rust_name(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    out.push_str("table! {\n");
+    // `table!` only imports diesel's types by default
+    let columns = table.columns.iter().filter(|c| options.includes(c));
+    if columns.clone().any(|c| c.diesel_type() == Some("LargeInt")) {
+        out.push_str("    use diesel::sql_types::*;\n");
+        out.push_str("    use diesel_oci::oracle::sql_types::LargeInt;\n\n");
+    }
+    if needs_sql_name(&table.name) {
+        writeln!(out, "    #[sql_name = \"{}\"]", table.name)
+            .expect("Writing to a string does not fail");
+    }
+    writeln!(out, "    {} ({}) {{", rust_name(&table.name), pk)
+        .expect("Writing to a string does not fail");
+    for column in columns {
+        let tpe = match column.diesel_type() {
+            Some(tpe) => tpe,
+            None => {
+                writeln!(
+                    out,
+                    "        // `{}` has the unsupported type {}",
+                    column.name, column.data_type
+                )
+                .expect("Writing to a string does not fail");
+                continue;
+            }
+        };
+        if column.virtual_column {
+            out.push_str("        /// Virtual column, cannot be inserted or updated\n");
+        }
+        if needs_sql_name(&column.name) {
+            writeln!(out, "        #[sql_name = \"{}\"]", column.name)
+                .expect("Writing to a string does not fail");
+        }
+        if column.nullable {
+            writeln!(
+                out,
+                "        {} -> Nullable<{}>,",
+                rust_name(&column.name),
+                tpe
+            )
+        } else {
+            writeln!(out, "        {} -> {},", rust_name(&column.name), tpe)
+        }
+        .expect("Writing to a string does not fail");
+    }
+    out.push_str("    }\n}\n\n");
+}
+
+fn rust_name(name: &str) -> String {
+    let lower = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if RUST_KEYWORDS.contains(&&*lower) || lower.starts_with(|c: char| c.is_numeric()) {
+        format!("{}_", lower)
+    } else {
+        lower
+    }
+}
+
+fn needs_sql_name(name: &str) -> bool {
+    // diesel-oci quotes and upper cases identifiers, so the lower cased name
+    // can be used as long as it is a valid (non keyword) rust identifier
+    rust_name(name).to_uppercase() != name
+}
+
+impl OciConnection {
+    /// Generates diesel's schema definitions (as `diesel print-schema`
+    /// does for the builtin backends) for the current schema
+    pub fn print_schema(&self) -> QueryResult<String> {
+        self.snapshot_schema().map(|s| s.to_table_macros())
+    }
+
+    /// Same as `print_schema`, with control over hidden and virtual columns
+    pub fn print_schema_with(&self, options: &PrintOptions) -> QueryResult<String> {
+        self.snapshot_schema()
+            .map(|s| s.to_table_macros_with(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Column, Constraint, ConstraintType, PrintOptions, SchemaSnapshot, Table};
+
+    fn column(name: &str, data_type: &str, precision: Option<i32>, nullable: bool) -> Column {
+        Column {
+            name: name.into(),
+            data_type: data_type.into(),
+            data_length: 22,
+            precision,
+            scale: precision.map(|_| 0),
+            nullable,
+            hidden: false,
+            virtual_column: false,
+        }
+    }
+
+    fn constraint(
+        name: &str,
+        constraint_type: ConstraintType,
+        column: &str,
+        referenced: Option<(&str, &str)>,
+    ) -> Constraint {
+        Constraint {
+            name: name.into(),
+            constraint_type,
+            columns: vec![column.into()],
+            referenced_table: referenced.map(|r| r.0.into()),
+            referenced_columns: referenced.map(|r| vec![r.1.into()]).unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn check_print_schema() {
+        let snapshot = SchemaSnapshot {
+            owner: "DIESEL".into(),
+            tables: vec![
+                Table {
+                    name: "LOGS".into(),
+                    columns: vec![column("MSG", "VARCHAR2", None, true)],
+                    constraints: vec![],
+                    indexes: vec![],
+                },
+                Table {
+                    name: "POSTS".into(),
+                    columns: vec![
+                        column("ID", "NUMBER", Some(18), false),
+                        column("USER_ID", "NUMBER", Some(9), false),
+                        column("TYPE", "VARCHAR2", None, true),
+                        column("DOC", "XMLTYPE", None, true),
+                    ],
+                    constraints: vec![
+                        constraint("POSTS_PK", ConstraintType::PrimaryKey, "ID", None),
+                        constraint(
+                            "POSTS_FK",
+                            ConstraintType::ForeignKey,
+                            "USER_ID",
+                            Some(("USERS", "ID")),
+                        ),
+                    ],
+                    indexes: vec![],
+                },
+                Table {
+                    name: "USERS".into(),
+                    columns: vec![
+                        column("ID", "NUMBER", Some(10), false),
+                        column("CREATED", "DATE", None, false),
+                    ],
+                    constraints: vec![constraint(
+                        "USERS_PK",
+                        ConstraintType::PrimaryKey,
+                        "ID",
+                        None,
+                    )],
+                    indexes: vec![],
+                },
+            ],
+            sequences: vec![],
+        };
+
+        let expected = "\
+// `LOGS` has no primary key and cannot be represented by `table!`
+
+table! {
+    posts (id) {
+        id -> BigInt,
+        user_id -> Integer,
+        #[sql_name = \"TYPE\"]
+        type_ -> Nullable<Text>,
+        // `DOC` has the unsupported type XMLTYPE
+    }
+}
+
+table! {
+    users (id) {
+        id -> BigInt,
+        created -> Timestamp,
+    }
+}
+
+joinable!(posts -> users (user_id));
+
+allow_tables_to_appear_in_same_query!(
+    posts,
+    users,
+);
+";
+        assert_eq!(snapshot.to_table_macros(), expected);
+    }
+
+    #[test]
+    fn check_print_hidden_and_virtual_columns() {
+        let mut hidden = column("SYS_NC00003$", "NUMBER", None, true);
+        hidden.hidden = true;
+        let mut total = column("TOTAL", "NUMBER", Some(9), true);
+        total.virtual_column = true;
+        let snapshot = SchemaSnapshot {
+            owner: "DIESEL".into(),
+            tables: vec![Table {
+                name: "ORDERS".into(),
+                columns: vec![column("ID", "NUMBER", Some(9), false), hidden, total],
+                constraints: vec![constraint(
+                    "ORDERS_PK",
+                    ConstraintType::PrimaryKey,
+                    "ID",
+                    None,
+                )],
+                indexes: vec![],
+            }],
+            sequences: vec![],
+        };
+
+        let expected = "\
+table! {
+    orders (id) {
+        id -> Integer,
+        /// Virtual column, cannot be inserted or updated
+        total -> Nullable<Integer>,
+    }
+}
+
+";
+        assert_eq!(snapshot.to_table_macros(), expected);
+
+        let options = PrintOptions {
+            include_hidden: true,
+            include_virtual: false,
+        };
+        let printed = snapshot.to_table_macros_with(&options);
+        assert!(printed.contains("sys_nc00003_ -> Nullable<Double>"));
+        assert!(!printed.contains("total"));
+    }
+
+    #[test]
+    fn check_print_number_types() {
+        let mut integer = column("QUANTITY", "NUMBER", None, false);
+        integer.scale = Some(0);
+        let mut price = column("PRICE", "NUMBER", Some(10), true);
+        price.scale = Some(2);
+        let snapshot = SchemaSnapshot {
+            owner: "DIESEL".into(),
+            tables: vec![Table {
+                name: "ITEMS".into(),
+                columns: vec![column("ID", "NUMBER", Some(38), false), integer, price],
+                constraints: vec![constraint(
+                    "ITEMS_PK",
+                    ConstraintType::PrimaryKey,
+                    "ID",
+                    None,
+                )],
+                indexes: vec![],
+            }],
+            sequences: vec![],
+        };
+
+        let expected = "\
+table! {
+    use diesel::sql_types::*;
+    use diesel_oci::oracle::sql_types::LargeInt;
+
+    items (id) {
+        id -> LargeInt,
+        quantity -> BigInt,
+        price -> Nullable<Double>,
+    }
+}
+
+";
+        assert_eq!(snapshot.to_table_macros(), expected);
+    }
+}
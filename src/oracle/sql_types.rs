@@ -0,0 +1,51 @@
+//! Oracle specific sql types
+
+/// The national character types `NCHAR`, `NVARCHAR2` and `NCLOB`
+///
+/// Values of these columns are read correctly as `Text` as well, but values
+/// bound as `Text` are converted to the database character set first, which
+/// loses all characters the database character set cannot represent.
+///
+/// Bind values with this type to keep all characters, e.g.
+///
+/// ```ignore
+/// diesel::sql_query("INSERT INTO users (id, name) VALUES (:1, :2)")
+///     .bind::<Integer, _>(1)
+///     .bind::<NVarchar, _>("Ærøskøbing")
+///     .execute(&conn)?;
+/// ```
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+pub struct NVarchar;
+
+/// The type `TIMESTAMP WITH TIME ZONE`, e.g. of `systimestamp()`, whose
+/// values are read as `chrono::DateTime<FixedOffset>` or `DateTime<Utc>`
+///
+/// Oracle converts these values implicitly when they are compared with
+/// `DATE` or `TIMESTAMP` columns, so `systimestamp()` and
+/// `current_timestamp()` can be used with `Timestamp` columns as well.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+pub struct TimestampTz;
+
+/// The type `TIMESTAMP WITH LOCAL TIME ZONE`, whose values are read as
+/// `chrono::DateTime<Utc>`
+///
+/// Oracle returns these values in the time zone of the session, which is
+/// converted to UTC. Reading them as `Timestamp` gives the time in the
+/// session time zone, set it when connecting to make this deterministic,
+/// e.g. with `OciConnectionBuilder::session_timezone("UTC")`.
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+pub struct TimestampLtz;
+
+/// Integral numbers with up to 38 digits, i.e. `NUMBER(38)` or `INTEGER`
+/// columns, which exceed the range of `BigInt`
+///
+/// Values are read as `i128` or `u64`. Columns with more than 19 digits are
+/// fetched with the full precision of `NUMBER` (c.f. `FetchType::LargeInt`),
+/// smaller numbers can be read as well.
+///
+/// ```ignore
+/// let id = diesel::dsl::sql::<LargeInt>("SELECT id FROM large_keys")
+///     .get_result::<i128>(&conn)?;
+/// ```
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+pub struct LargeInt;
@@ -2,20 +2,32 @@ extern crate chrono;
 use std::error::Error;
 use std::io::Write;
 
+use byteorder::ByteOrder;
+use diesel::backend::Backend;
 use diesel::deserialize::FromSql;
 use diesel::serialize::{IsNull, Output, ToSql};
 use diesel::sql_types::*;
 
 use oracle::backend::Oracle;
+use oracle::sql_types::{TimestampLtz, TimestampTz};
 
-use self::chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use self::chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
 
 use super::super::connection::OracleValue;
 
+// values of `DATE` columns and the local time of timestamps (including
+// their nanoseconds), c.f. `FetchType::TimestampTz`
 impl FromSql<Timestamp, Oracle> for NaiveDateTime {
     fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<Error + Send + Sync>> {
         let bytes = not_none!(bytes);
         let bytes = &bytes.bytes;
+        let nanos = if bytes.len() == 13 {
+            <Oracle as Backend>::ByteOrder::read_u32(&bytes[7..11])
+        } else {
+            0
+        };
         let sec = u32::from(bytes[6]) - 1;
         let min = u32::from(bytes[5]) - 1;
         let hr = u32::from(bytes[4]) - 1;
@@ -32,7 +44,7 @@ impl FromSql<Timestamp, Oracle> for NaiveDateTime {
             //         result::DatabaseErrorKind::__Unknown,
             //         Box::new(String::from("could not parse timestamp"))))));
 
-            Ok(d.and_hms_opt(hr, min, sec).unwrap())
+            Ok(d.and_hms_nano_opt(hr, min, sec, nanos).unwrap())
         } else if century < 100 && year < 100 {
             // TODO: error handling
             let d = NaiveDate::from_ymd_opt(century * -100 + year, month, day).unwrap();
@@ -40,13 +52,49 @@ impl FromSql<Timestamp, Oracle> for NaiveDateTime {
             //                                               timestamp"
             //                                                  .to_owned()))));
 
-            Ok(d.and_hms_opt(hr, min, sec).unwrap())
+            Ok(d.and_hms_nano_opt(hr, min, sec, nanos).unwrap())
         } else {
             unreachable!()
         }
     }
 }
 
+// values in the format of `FetchType::TimestampTz`, i.e. the local time
+// followed by the nanoseconds and the offset to UTC
+fn read_timestamp_tz(
+    value: &OracleValue,
+) -> Result<DateTime<FixedOffset>, Box<Error + Send + Sync>> {
+    if value.bytes.len() != 13 {
+        return Err("timestamps with time zone must be fetched with their time zone".into());
+    }
+    let local = <NaiveDateTime as FromSql<Timestamp, Oracle>>::from_sql(Some(value))?;
+    let bytes = &value.bytes;
+    let offset = i32::from(bytes[11] as i8) * 3600 + i32::from(bytes[12] as i8) * 60;
+    let offset = FixedOffset::east_opt(offset).ok_or("invalid time zone offset")?;
+    offset
+        .from_local_datetime(&local)
+        .single()
+        .ok_or_else(|| "invalid timestamp".into())
+}
+
+impl FromSql<TimestampTz, Oracle> for DateTime<FixedOffset> {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<Error + Send + Sync>> {
+        read_timestamp_tz(not_none!(bytes))
+    }
+}
+
+impl FromSql<TimestampTz, Oracle> for DateTime<Utc> {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<Error + Send + Sync>> {
+        read_timestamp_tz(not_none!(bytes)).map(|t| t.with_timezone(&Utc))
+    }
+}
+
+impl FromSql<TimestampLtz, Oracle> for DateTime<Utc> {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<Error + Send + Sync>> {
+        read_timestamp_tz(not_none!(bytes)).map(|t| t.with_timezone(&Utc))
+    }
+}
+
 impl ToSql<Timestamp, Oracle> for NaiveDateTime {
     fn to_sql<W: Write>(
         &self,
@@ -127,3 +175,89 @@ impl ToSql<Date, Oracle> for NaiveDate {
             .map(|_| IsNull::No)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+    use diesel::deserialize::FromSql;
+    use diesel::sql_types::Timestamp;
+    use oracle::backend::Oracle;
+    use oracle::connection::OracleValue;
+    use oracle::sql_types::{TimestampLtz, TimestampTz};
+
+    fn read_utc(bytes: &[u8]) -> Option<DateTime<Utc>> {
+        <DateTime<Utc> as FromSql<TimestampLtz, Oracle>>::from_sql(Some(OracleValue::new(bytes)))
+            .ok()
+    }
+
+    #[test]
+    fn check_timestamp_ltz() {
+        // 2020-03-14 15:09:00.5 +02:30
+        let mut bytes = vec![120, 120, 3, 14, 16, 10, 1];
+        bytes.extend_from_slice(&500_000_000u32.to_ne_bytes());
+        bytes.extend_from_slice(&[2, 30]);
+        let expected = NaiveDate::from_ymd_opt(2020, 3, 14)
+            .and_then(|d| d.and_hms_milli_opt(12, 39, 0, 500))
+            .unwrap();
+        assert_eq!(read_utc(&bytes).unwrap().naive_utc(), expected);
+
+        // the minutes of negative offsets are negative as well
+        bytes[11] = -4i8 as u8;
+        bytes[12] = -30i8 as u8;
+        assert_eq!(
+            read_utc(&bytes).unwrap().naive_utc(),
+            expected + Duration::hours(7)
+        );
+
+        // values fetched without their time zone
+        assert_eq!(read_utc(&bytes[..7]), None);
+    }
+
+    #[test]
+    fn check_timestamp_fractions() {
+        let mut bytes = vec![120, 120, 3, 14, 16, 10, 1];
+        let read = |bytes: &[u8]| {
+            <NaiveDateTime as FromSql<Timestamp, Oracle>>::from_sql(Some(OracleValue::new(bytes)))
+                .unwrap()
+        };
+        let expected = NaiveDate::from_ymd_opt(2020, 3, 14)
+            .and_then(|d| d.and_hms_opt(15, 9, 0))
+            .unwrap();
+        assert_eq!(read(&bytes), expected);
+
+        // the local time of timestamps with their nanoseconds
+        bytes.extend_from_slice(&123_456_789u32.to_ne_bytes());
+        bytes.extend_from_slice(&[2, 0]);
+        assert_eq!(read(&bytes), expected + Duration::nanoseconds(123_456_789));
+    }
+
+    #[test]
+    fn check_timestamp_tz() {
+        // 2020-03-14 15:09:00.5 -04:30
+        let mut bytes = vec![120, 120, 3, 14, 16, 10, 1];
+        bytes.extend_from_slice(&500_000_000u32.to_ne_bytes());
+        bytes.extend_from_slice(&[-4i8 as u8, -30i8 as u8]);
+        let value = OracleValue::new(&bytes);
+
+        let ret =
+            <DateTime<FixedOffset> as FromSql<TimestampTz, Oracle>>::from_sql(Some(value)).unwrap();
+        assert_eq!(
+            ret.offset(),
+            &FixedOffset::west_opt(4 * 3600 + 30 * 60).unwrap()
+        );
+        assert_eq!(
+            ret.naive_local(),
+            NaiveDate::from_ymd_opt(2020, 3, 14)
+                .and_then(|d| d.and_hms_milli_opt(15, 9, 0, 500))
+                .unwrap()
+        );
+
+        let ret = <DateTime<Utc> as FromSql<TimestampTz, Oracle>>::from_sql(Some(value)).unwrap();
+        assert_eq!(
+            ret.naive_utc(),
+            NaiveDate::from_ymd_opt(2020, 3, 14)
+                .and_then(|d| d.and_hms_milli_opt(19, 39, 0, 500))
+                .unwrap()
+        );
+    }
+}
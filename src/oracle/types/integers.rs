@@ -1,6 +1,7 @@
 use std::error::Error;
 
-use diesel::deserialize::FromSql;
+use diesel::deserialize::{FromSql, FromSqlRow, Queryable};
+use diesel::row::Row;
 use diesel::sql_types::*;
 
 use oracle::backend::Oracle;
@@ -9,66 +10,141 @@ use super::super::connection::OracleValue;
 
 use byteorder::ReadBytesExt;
 use diesel::backend::*;
+use diesel::serialize::{IsNull, Output, ToSql};
+use oracle::sql_types::LargeInt;
+use std::convert::TryFrom;
+use std::io::Write;
 
 pub type FromSqlResult<T> = Result<T, ErrorType>;
 pub type ErrorType = Box<Error + Send + Sync>;
 
+// The size an integer is fetched with depends on the precision of the
+// column (c.f. `default_fetch_type`), e.g. a `NUMBER(10)` column is fetched
+// as 8 byte integer but may be declared as `Integer` by an existing `table!`
+// definition. Values of any size are therefore accepted as long as they fit.
+
 impl FromSql<BigInt, Oracle> for i64 {
     fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
         let bytes = not_none!(bytes);
-        let mut bytes = &bytes.bytes;
-        debug_assert!(
-            bytes.len() <= 8,
-            "Received more than 8 bytes decoding i64. \
-             Was an expression of a different type misidentified as BigInteger?"
-        );
-        debug_assert!(
-            bytes.len() >= 8,
-            "Received fewer than 8 bytes decoding i64. \
-             Was an Integer expression misidentified as BigInteger?"
-        );
-        bytes
-            .read_i64::<<Oracle as Backend>::ByteOrder>()
-            .map_err(|e| Box::new(e) as Box<Error + Send + Sync>)
+        i64::try_from(read_integer(&bytes.bytes)?).map_err(|_| out_of_range("i64"))
     }
 }
 
 impl FromSql<Integer, Oracle> for i32 {
     fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
         let bytes = not_none!(bytes);
-        let mut bytes = &bytes.bytes;
-        debug_assert!(
-            bytes.len() <= 4,
-            "Received more than 4 bytes decoding i32. \
-             Was an expression of a different type misidentified as Integer?"
-        );
-        debug_assert!(
-            bytes.len() >= 4,
-            "Received fewer than 4 bytes decoding i32. \
-             Was an SmallInt expression misidentified as Integer?"
-        );
-        bytes
-            .read_i32::<<Oracle as Backend>::ByteOrder>()
-            .map_err(|e| Box::new(e) as Box<Error + Send + Sync>)
+        i32::try_from(read_integer(&bytes.bytes)?).map_err(|_| out_of_range("i32"))
     }
 }
 
 impl FromSql<SmallInt, Oracle> for i16 {
     fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
         let bytes = not_none!(bytes);
-        let mut bytes = &bytes.bytes;
-        debug_assert!(
-            bytes.len() <= 2,
-            "Received more than 2 bytes decoding i16. \
-             Was an expression of a different type misidentified as SmallInteger?"
-        );
-        debug_assert!(
-            bytes.len() >= 2,
-            "Received fewer than 2 bytes decoding i16. \
-             Was an expression of a different type misidentified as SmallInteger?"
+        i16::try_from(read_integer(&bytes.bytes)?).map_err(|_| out_of_range("i16"))
+    }
+}
+
+// an integer of any size fetched by diesel-oci, numbers with more than 19
+// digits are fetched as 16 byte integers
+pub(super) fn read_integer(mut bytes: &[u8]) -> FromSqlResult<i128> {
+    let value = match bytes.len() {
+        2 => i128::from(bytes.read_i16::<<Oracle as Backend>::ByteOrder>()?),
+        4 => i128::from(bytes.read_i32::<<Oracle as Backend>::ByteOrder>()?),
+        8 => i128::from(bytes.read_i64::<<Oracle as Backend>::ByteOrder>()?),
+        16 => bytes.read_i128::<<Oracle as Backend>::ByteOrder>()?,
+        len => return Err(format!("Cannot decode an integer of {} bytes", len).into()),
+    };
+    Ok(value)
+}
+
+fn out_of_range(tpe: &str) -> ErrorType {
+    format!("The number does not fit into an {}", tpe).into()
+}
+
+impl FromSql<LargeInt, Oracle> for i128 {
+    fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
+        let bytes = not_none!(bytes);
+        read_integer(&bytes.bytes)
+    }
+}
+
+impl FromSql<LargeInt, Oracle> for u64 {
+    fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
+        let bytes = not_none!(bytes);
+        u64::try_from(read_integer(&bytes.bytes)?).map_err(|_| out_of_range("u64"))
+    }
+}
+
+// diesel implements these for all integer types but `i128`
+
+impl FromSqlRow<LargeInt, Oracle> for i128 {
+    fn build_from_row<R: Row<Oracle>>(row: &mut R) -> FromSqlResult<Self> {
+        FromSql::<LargeInt, Oracle>::from_sql(row.take())
+    }
+}
+
+impl Queryable<LargeInt, Oracle> for i128 {
+    type Row = Self;
+
+    fn build(row: Self) -> Self {
+        row
+    }
+}
+
+// bound as text, which oracle converts to a NUMBER without losing digits
+impl ToSql<LargeInt, Oracle> for i128 {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Oracle>) -> Result<IsNull, ErrorType> {
+        write!(out, "{}", self)
+            .map(|_| IsNull::No)
+            .map_err(|e| Box::new(e) as ErrorType)
+    }
+}
+
+impl ToSql<LargeInt, Oracle> for u64 {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Oracle>) -> Result<IsNull, ErrorType> {
+        ToSql::<LargeInt, Oracle>::to_sql(&i128::from(*self), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_integer;
+    use diesel::deserialize::FromSql;
+    use diesel::sql_types::{Bool, Integer, SmallInt};
+    use oracle::backend::Oracle;
+    use oracle::connection::OracleValue;
+
+    #[test]
+    fn check_read_integer() {
+        assert_eq!(read_integer(&(-5i16).to_ne_bytes()).unwrap(), -5);
+        assert_eq!(read_integer(&7i32.to_ne_bytes()).unwrap(), 7);
+        assert_eq!(
+            read_integer(&i64::MIN.to_ne_bytes()).unwrap(),
+            i128::from(i64::MIN)
         );
-        bytes
-            .read_i16::<<Oracle as Backend>::ByteOrder>()
-            .map_err(|e| Box::new(e) as Box<Error + Send + Sync>)
+        let large = 10i128.pow(38) - 1;
+        assert_eq!(read_integer(&large.to_ne_bytes()).unwrap(), large);
+        assert!(read_integer(&[0; 3]).is_err());
+    }
+
+    #[test]
+    fn check_integers_of_other_sizes() {
+        let value = 99_999i64.to_ne_bytes();
+        let value = Some(OracleValue::new(&value));
+        let ret = <i32 as FromSql<Integer, Oracle>>::from_sql(value);
+        assert_eq!(ret.unwrap(), 99_999);
+        assert!(<i16 as FromSql<SmallInt, Oracle>>::from_sql(value).is_err());
+    }
+
+    #[test]
+    fn check_bool() {
+        let from_sql = |bytes: &[u8]| {
+            <bool as FromSql<Bool, Oracle>>::from_sql(Some(OracleValue::new(bytes))).unwrap()
+        };
+        assert!(from_sql(&1i16.to_ne_bytes()));
+        assert!(!from_sql(&0i16.to_ne_bytes()));
+        assert!(from_sql(&1i64.to_ne_bytes()));
+        assert!(from_sql(&1f64.to_ne_bytes()));
+        assert!(!from_sql(&0f64.to_ne_bytes()));
     }
 }
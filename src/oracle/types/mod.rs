@@ -1,5 +1,6 @@
 use super::backend::*;
 use super::connection::OracleValue;
+use super::sql_types::{LargeInt, NVarchar, TimestampLtz, TimestampTz};
 use byteorder::WriteBytesExt;
 use diesel::backend::*;
 use diesel::deserialize::FromSql;
@@ -44,6 +45,9 @@ pub enum OCIDataType {
     String = ffi::SQLT_STR,
     AnsiChar = ffi::SQLT_AFC,
     InternDate = ffi::SQLT_DAT,
+    /// `SQLT_CHR` bound with the national character set, the value only
+    /// distinguishes this from `Char`
+    NationalChar = 0x1000 | ffi::SQLT_CHR,
 }
 
 impl OCIDataType {
@@ -87,8 +91,40 @@ impl OCIDataType {
             Int => ffi::SQLT_INT,
             Float | BFloat | IBFloat => ffi::SQLT_BDOUBLE, // this should be SQLT_BFLOAT, but diesel comes with a float here
             BDouble | IBDouble => ffi::SQLT_BDOUBLE,
-            Char | String => ffi::SQLT_CHR,
-            _ => 0u32,
+            Char | String | NationalChar => ffi::SQLT_CHR,
+            Date => ffi::SQLT_DATE,
+            Time => ffi::SQLT_TIME,
+            Timestamp => ffi::SQLT_TIMESTAMP,
+            TimestampWithTz => ffi::SQLT_TIMESTAMP_TZ,
+            TimestampWithLocalTz => ffi::SQLT_TIMESTAMP_LTZ,
+            IntervalYearToMonth => ffi::SQLT_INTERVAL_YM,
+            IntervalDayToSecond => ffi::SQLT_INTERVAL_DS,
+            Clob => ffi::SQLT_CLOB,
+            Blob => ffi::SQLT_BLOB,
+            Uint => ffi::SQLT_UIN,
+            PackedDecimalNumber => ffi::SQLT_PDN,
+            Binary => ffi::SQLT_BIN,
+            Numeric => ffi::SQLT_NUM,
+            NamedObject => ffi::SQLT_NTY,
+            Ref => ffi::SQLT_REF,
+            OCIString => ffi::SQLT_VST,
+            NumericWithLength => ffi::SQLT_VNU,
+            AnsiChar => ffi::SQLT_AFC,
+            InternDate => ffi::SQLT_DAT,
+        }
+    }
+
+    /// The size of the buffer bound for a `NULL` value of this type
+    ///
+    /// Oracle needs a valid buffer length even if the null indicator is set,
+    /// otherwise the bind is rejected for fixed size types like `SQLT_INT`.
+    pub fn null_buffer_size(self) -> usize {
+        use self::OCIDataType::*;
+        match self {
+            Int | Uint | Float | BFloat | IBFloat | BDouble | IBDouble => 8,
+            InternDate => 7,
+            Numeric | NumericWithLength => 22,
+            _ => 1,
         }
     }
 }
@@ -108,6 +144,12 @@ impl HasSqlType<SmallInt> for Oracle {
     }
 }
 
+impl HasSqlType<LargeInt> for Oracle {
+    fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
+        OCIDataType::Char
+    }
+}
+
 impl HasSqlType<Integer> for Oracle {
     fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
         OCIDataType::Int
@@ -144,6 +186,12 @@ impl HasSqlType<VarChar> for Oracle {
     }
 }
 
+impl HasSqlType<NVarchar> for Oracle {
+    fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
+        OCIDataType::NationalChar
+    }
+}
+
 impl HasSqlType<Binary> for Oracle {
     fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
         OCIDataType::Binary
@@ -168,15 +216,30 @@ impl HasSqlType<Timestamp> for Oracle {
     }
 }
 
+impl HasSqlType<TimestampTz> for Oracle {
+    fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
+        OCIDataType::TimestampWithTz
+    }
+}
+
+impl HasSqlType<TimestampLtz> for Oracle {
+    fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
+        OCIDataType::TimestampWithLocalTz
+    }
+}
+
 impl HasSqlType<Bool> for Oracle {
     fn metadata(_: &Self::MetadataLookup) -> OCIDataType {
         OCIDataType::Int
     }
 }
 
+// boolean columns (e.g. `NUMBER(1)`) are fetched as integers, a `NUMBER`
+// without precision as double, whose zero has no bits set either
 impl FromSql<Bool, Oracle> for bool {
     fn from_sql(bytes: Option<&OracleValue>) -> FromSqlResult<Self> {
-        FromSql::<Double, Oracle>::from_sql(bytes).map(|v: f64| v != 0.0)
+        let bytes = not_none!(bytes);
+        integers::read_integer(&bytes.bytes).map(|v| v != 0)
     }
 }
 
@@ -194,3 +257,5 @@ mod chrono_date_time;
 mod decimal;
 mod integers;
 mod primitives;
+
+pub(crate) use self::primitives::str_from_bytes;
@@ -2,24 +2,63 @@ use std::error::Error;
 
 use diesel::deserialize::FromSql;
 use diesel::result::Error as DieselError;
+use diesel::serialize::{IsNull, Output, ToSql};
 use diesel::sql_types::*;
 use oracle::backend::Oracle;
-use std::ffi::CStr;
+use oracle::sql_types::NVarchar;
+use std::io::Write;
+use std::str;
 
 use super::super::connection::OracleValue;
 
-impl FromSql<Text, Oracle> for String {
-    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<Error + Send + Sync>> {
+/// The string of a value terminated by a null byte, borrowed from the define
+/// buffer of its column
+pub(crate) fn str_from_bytes(bytes: &[u8]) -> Result<&str, Box<dyn Error + Send + Sync>> {
+    let pos = bytes.iter().position(|&b| b == 0).ok_or_else(|| {
+        Box::new(DieselError::DeserializationError(
+            "Expected at least one null byte".into(),
+        )) as Box<dyn Error + Send + Sync>
+    })?;
+    Ok(str::from_utf8(&bytes[..pos])?)
+}
+
+/// Used by the `FromSql<Text, Oracle>` impl of `String`
+impl FromSql<Text, Oracle> for *const str {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let bytes = not_none!(bytes);
-        let pos = bytes
-            .bytes
-            .iter()
-            .position(|&b| b == 0)
-            .ok_or(Box::new(DieselError::DeserializationError(
-                "Expected at least one null byte".into(),
-            )) as Box<Error + Send + Sync>)?;
-        Ok(CStr::from_bytes_with_nul(&bytes.bytes[..=pos])?
-            .to_str()?
-            .to_owned())
+        Ok(str_from_bytes(&bytes.bytes)? as *const str)
+    }
+}
+
+/// Used by the `FromSql<NVarchar, Oracle>` impl of `String`
+impl FromSql<NVarchar, Oracle> for *const str {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        FromSql::<Text, Oracle>::from_sql(bytes)
+    }
+}
+
+/// Used by the `FromSql<Binary, Oracle>` impl of `Vec<u8>`
+impl FromSql<Binary, Oracle> for *const [u8] {
+    fn from_sql(bytes: Option<&OracleValue>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let bytes = not_none!(bytes);
+        Ok(&bytes.bytes as *const [u8])
+    }
+}
+
+impl ToSql<NVarchar, Oracle> for str {
+    fn to_sql<W: Write>(
+        &self,
+        out: &mut Output<W, Oracle>,
+    ) -> Result<IsNull, Box<dyn Error + Send + Sync>> {
+        ToSql::<Text, Oracle>::to_sql(self, out)
+    }
+}
+
+impl ToSql<NVarchar, Oracle> for String {
+    fn to_sql<W: Write>(
+        &self,
+        out: &mut Output<W, Oracle>,
+    ) -> Result<IsNull, Box<dyn Error + Send + Sync>> {
+        ToSql::<Text, Oracle>::to_sql(self, out)
     }
 }
@@ -7,36 +7,6 @@ use diesel::Connection;
 use diesel::RunQueryDsl;
 use std::env;
 
-#[cfg(ka)]
-use diesel::migration::MigrationConnection;
-
-#[cfg(ka)]
-impl MigrationConnection for OciConnection {
-    #[cfg(ka)]
-    const CREATE_MIGRATIONS_FUNCTION: &'static str =
-        "create or replace procedure create_if_not_exists(input_sql varchar2) \
-         as \
-         begin \
-         execute immediate input_sql; \
-         exception \
-         when others then \
-         if sqlcode = -955 then \
-         NULL; \
-         else \
-         raise; \
-         end if; \
-         end; \n ";
-
-    const CREATE_MIGRATIONS_TABLE: &'static str = "
-    declare \
-    begin \
-    create_if_not_exists('CREATE TABLE \"__DIESEL_SCHEMA_MIGRATIONS\" (\
-         \"VERSION\" VARCHAR2(50) PRIMARY KEY NOT NULL,\
-         \"RUN_ON\" TIMESTAMP with time zone DEFAULT sysdate not null\
-         )'); \
-        end; \n";
-}
-
 #[allow(dead_code)]
 fn connection() -> OciConnection {
     let database_url = database_url_from_env("OCI_DATABASE_URL");
@@ -113,6 +83,29 @@ table! {
     }
 }
 
+table! {
+    jobs (id) {
+        id -> Integer,
+        state -> Text,
+    }
+}
+
+table! {
+    flags (id) {
+        id -> Integer,
+        active -> Bool,
+        archived -> Nullable<Bool>,
+    }
+}
+
+table! {
+    sessions (id) {
+        id -> Integer,
+        expires_at -> Timestamp,
+        closed_at -> Nullable<Timestamp>,
+    }
+}
+
 fn create_test_table(conn: &OciConnection) -> usize {
     let ret = conn.execute(CREATE_TEST_TABLE);
     assert_result!(ret);
@@ -272,6 +265,28 @@ fn test_diesel_migration() {
     assert_eq!(pending_migrations.len(), 0);
 }
 
+#[test]
+fn diesel_migrations_setup() {
+    use diesel_migrations::{self, MigrationConnection};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_DIESEL_TABLE);
+
+    // the bookkeeping table is created only once
+    assert_result!(diesel_migrations::setup_database(&conn));
+    assert_result!(diesel_migrations::setup_database(&conn));
+
+    assert_result!(conn.insert_new_migration("20151219180527"));
+    let ret = conn.previously_run_migration_versions();
+    assert_result!(ret);
+    assert!(ret.unwrap().contains("20151219180527"));
+    let ret = conn.latest_run_migration_version();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), Some("20151219180527".to_owned()));
+
+    let _ = conn.execute(DROP_DIESEL_TABLE);
+}
+
 #[cfg(this_test_doesnt_work)]
 #[test]
 fn test_multi_insert() {
@@ -511,3 +526,2397 @@ fn gst_compat() {
         }
     }
 }
+
+#[test]
+fn bind_typed_null_to_overloaded_function() {
+    const CREATE_OVERLOAD_PACKAGE: &str = "CREATE OR REPLACE PACKAGE overload_test AS \
+         FUNCTION which(x NUMBER) RETURN VARCHAR2; \
+         FUNCTION which(x VARCHAR2) RETURN VARCHAR2; \
+         END overload_test;";
+    const CREATE_OVERLOAD_PACKAGE_BODY: &str = "CREATE OR REPLACE PACKAGE BODY overload_test AS \
+         FUNCTION which(x NUMBER) RETURN VARCHAR2 IS BEGIN RETURN 'number'; END; \
+         FUNCTION which(x VARCHAR2) RETURN VARCHAR2 IS BEGIN RETURN 'varchar'; END; \
+         END overload_test;";
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let ret = conn.execute(CREATE_OVERLOAD_PACKAGE);
+    assert_result!(ret);
+    let ret = conn.execute(CREATE_OVERLOAD_PACKAGE_BODY);
+    assert_result!(ret);
+
+    use diesel::dsl::sql;
+    use diesel::sql_types::{BigInt, Nullable, Text};
+
+    let ret = sql::<Text>("SELECT overload_test.which(")
+        .bind::<Nullable<BigInt>, _>(None::<i64>)
+        .sql(") FROM dual")
+        .get_result::<String>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "number");
+
+    let ret = sql::<Text>("SELECT overload_test.which(")
+        .bind::<Nullable<Text>, _>(None::<String>)
+        .sql(") FROM dual")
+        .get_result::<String>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "varchar");
+
+    let ret = conn.execute("DROP PACKAGE overload_test");
+    assert_result!(ret);
+}
+
+#[test]
+fn snapshot_schema() {
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+
+    let ret = conn.snapshot_schema();
+    assert_result!(ret);
+    let snapshot = ret.unwrap();
+    let table = snapshot
+        .tables
+        .iter()
+        .find(|t| t.name == "TEST")
+        .expect("Test table is part of the snapshot");
+    let columns = table
+        .columns
+        .iter()
+        .map(|c| (&*c.name, &*c.data_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        columns,
+        vec![
+            ("ID", "NUMBER"),
+            ("TST_CHR", "VARCHAR2"),
+            ("TST_NUM", "NUMBER"),
+        ]
+    );
+    assert_eq!(table.columns[0].precision, Some(38));
+    assert!(table.columns[0].nullable);
+
+    let _u = drop_test_table(&conn);
+}
+
+#[derive(QueryableByName, Debug, PartialEq)]
+#[table_name = "test"]
+struct NamedTest {
+    id: Option<i64>,
+    #[column_name = "TST_CHR"]
+    tst_chr: Option<String>,
+}
+
+#[test]
+fn sql_query_by_name() {
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+
+    let ret = conn.execute("INSERT INTO test (TST_CHR) VALUES ('named')");
+    assert_result!(ret);
+
+    let ret = ::diesel::sql_query("SELECT id, tst_chr FROM test").load::<NamedTest>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        vec![NamedTest {
+            id: None,
+            tst_chr: Some("named".into()),
+        }]
+    );
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn execute_plsql_with_out_binds() {
+    use diesel::sql_types::{Integer, Text};
+    use oracle::connection::PlSqlBind;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let mut binds = [
+        PlSqlBind::input::<Integer, _>(&21).unwrap(),
+        PlSqlBind::output::<Integer>(4),
+        PlSqlBind::in_out::<Text, _>("diesel", 20).unwrap(),
+    ];
+    let ret = conn.execute_plsql(
+        "BEGIN :2 := :1 * 2; :3 := UPPER(:3) || '-OCI'; END;",
+        &mut binds,
+    );
+    assert_result!(ret);
+    assert_eq!(binds[1].get::<Integer, i32>().unwrap(), 42);
+    assert_eq!(binds[2].get::<Text, String>().unwrap(), "DIESEL-OCI");
+}
+
+#[test]
+fn load_from_plsql_block_is_empty() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let ret = sql::<Integer>("BEGIN NULL; END;").load::<i32>(&conn);
+    assert_result!(ret);
+    assert!(ret.unwrap().is_empty());
+}
+
+#[test]
+fn call_procedure_with_out_params() {
+    use diesel::sql_types::{BigInt, Integer, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let ret = conn.execute(
+        "CREATE OR REPLACE PROCEDURE call_test(a IN NUMBER, b IN NUMBER, \
+         sum OUT NUMBER, label IN OUT VARCHAR2) AS \
+         BEGIN sum := a + b; label := label || '=' || sum; END;",
+    );
+    assert_result!(ret);
+
+    let ret = conn
+        .call("call_test")
+        .param::<Integer, _>(&20)
+        .param::<Integer, _>(&22)
+        .out_param::<BigInt>()
+        .in_out_param::<Text, _>("sum")
+        .execute();
+    assert_result!(ret);
+    let result = ret.unwrap();
+    assert_eq!(result.get::<BigInt, i64>(2).unwrap(), 42);
+    assert_eq!(result.get::<Text, String>(3).unwrap(), "sum=42");
+
+    let ret = conn.execute("DROP PROCEDURE call_test");
+    assert_result!(ret);
+}
+
+#[test]
+fn test_transaction_rolls_back() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+    use oracle::connection::TestTransaction;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    create_test_table(&conn);
+
+    ::test_transaction(&conn, || {
+        conn.execute("INSERT INTO test (ID, TST_CHR) VALUES (1, 'rolled back')")
+    });
+    let count =
+        sql::<BigInt>("SELECT CAST(COUNT(*) AS NUMBER(19)) FROM test").get_result::<i64>(&conn);
+    assert_result!(count);
+    assert_eq!(count.unwrap(), 0);
+
+    {
+        let transaction = TestTransaction::begin(&conn).unwrap();
+        assert!(!transaction.was_implicitly_committed());
+        let ret = conn.execute("CREATE TABLE test_ddl_commit (ID NUMBER(10))");
+        assert_result!(ret);
+        assert!(transaction.was_implicitly_committed());
+    }
+    let ret = conn.execute("DROP TABLE test_ddl_commit");
+    assert_result!(ret);
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn ref_cursor_from_procedure() {
+    use diesel::sql_types::{Integer, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let ret = conn.execute(
+        "CREATE OR REPLACE PROCEDURE ref_cursor_test(n IN NUMBER, rows OUT SYS_REFCURSOR) AS \
+         BEGIN OPEN rows FOR \
+         SELECT CAST(LEVEL AS NUMBER(10)), 'row ' || LEVEL FROM dual CONNECT BY LEVEL <= n \
+         ORDER BY 1; END;",
+    );
+    assert_result!(ret);
+
+    let ret = conn
+        .call("ref_cursor_test")
+        .param::<Integer, _>(&3)
+        .out_cursor()
+        .execute();
+    assert_result!(ret);
+    let mut result = ret.unwrap();
+    let rows = result
+        .take_cursor(1)
+        .unwrap()
+        .load::<(Integer, Text), (i32, String)>();
+    assert_result!(rows);
+    assert_eq!(
+        rows.unwrap(),
+        vec![
+            (1, "row 1".to_owned()),
+            (2, "row 2".to_owned()),
+            (3, "row 3".to_owned()),
+        ]
+    );
+
+    let ret = conn.execute("DROP PROCEDURE ref_cursor_test");
+    assert_result!(ret);
+}
+
+#[test]
+fn capture_dbms_output() {
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    assert_result!(conn.enable_dbms_output(None));
+    let ret = conn.execute(
+        "BEGIN DBMS_OUTPUT.PUT_LINE('first'); DBMS_OUTPUT.PUT_LINE(NULL); \
+         DBMS_OUTPUT.PUT_LINE('third'); END;",
+    );
+    assert_result!(ret);
+
+    let ret = conn.get_dbms_output();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec!["first", "", "third"]);
+
+    // the buffer is drained
+    let ret = conn.get_dbms_output();
+    assert_result!(ret);
+    assert!(ret.unwrap().is_empty());
+
+    // lines are fetched in batches
+    let ret = conn
+        .execute("BEGIN FOR i IN 1 .. 250 LOOP DBMS_OUTPUT.PUT_LINE('line ' || i); END LOOP; END;");
+    assert_result!(ret);
+    let ret = conn.get_dbms_output();
+    assert_result!(ret);
+    let lines = ret.unwrap();
+    assert_eq!(lines.len(), 250);
+    assert_eq!(lines[0], "line 1");
+    assert_eq!(lines[249], "line 250");
+
+    assert_result!(conn.disable_dbms_output());
+}
+
+#[test]
+fn duplicate_connection() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Text;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let ret = conn.duplicate();
+    assert_result!(ret);
+    let duplicate = ret.unwrap();
+
+    let session_user = "SELECT SYS_CONTEXT('USERENV', 'SESSION_USER') FROM dual";
+    let user = sql::<Text>(session_user).get_result::<String>(&conn);
+    assert_result!(user);
+    let duplicate_user = sql::<Text>(session_user).get_result::<String>(&duplicate);
+    assert_result!(duplicate_user);
+    assert_eq!(user.unwrap(), duplicate_user.unwrap());
+}
+
+#[test]
+fn session_timezone() {
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .session_timezone("UTC")
+        .establish()
+        .unwrap();
+    assert_result!(conn.db_timezone());
+    let ret = conn.session_timezone();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "UTC");
+
+    assert_result!(conn.set_session_timezone("+05:30"));
+    let ret = conn.session_timezone();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "+05:30");
+
+    #[cfg(feature = "chrono-time")]
+    {
+        let ret = conn.session_utc_offset();
+        assert_result!(ret);
+        assert_eq!(ret.unwrap().local_minus_utc(), 19800);
+    }
+}
+
+#[test]
+fn current_schema() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Integer, Text};
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let user = sql::<Text>("SELECT USER FROM dual").get_result::<String>(&conn);
+    assert_result!(user);
+    let user = user.unwrap();
+    let ret = conn.current_schema();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), user);
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .current_schema("SYS")
+        .establish()
+        .unwrap();
+    let ret = conn.current_schema();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "SYS");
+    let ret = sql::<Integer>("SELECT COUNT(*) FROM dual").get_result::<i32>(&conn);
+    assert_result!(ret);
+
+    assert_result!(conn.reset_current_schema());
+    let ret = conn.current_schema();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), user);
+    assert!(conn.set_current_schema("x; DROP TABLE test").is_err());
+}
+
+#[test]
+fn default_owner_qualifies_tables() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Text;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    create_test_table(&conn);
+    let user = sql::<Text>("SELECT USER FROM dual").get_result::<String>(&conn);
+    assert_result!(user);
+    let user = user.unwrap();
+
+    // the session resolves unqualified names in SYS, the queries of diesel
+    // use the tables of the user
+    let owned = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .current_schema("SYS")
+        .default_owner(&user)
+        .establish()
+        .unwrap();
+    assert_eq!(owned.default_owner(), Some(&*user));
+    let ret = diesel::insert_into(test::table)
+        .values((test::id.eq(1), test::TST_CHR.eq("owned")))
+        .execute(&owned);
+    assert_result!(ret);
+    let ret = test::table
+        .select(test::TST_CHR)
+        .filter(test::id.eq(1))
+        .get_result::<Option<String>>(&owned);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), Some("owned".to_owned()));
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn upsert_batch_merges_rows() {
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use oracle::query_dsl::upsert_batch;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    create_test_table(&conn);
+
+    let ret = conn.execute("INSERT INTO test (ID, TST_CHR) VALUES (1, 'old')");
+    assert_result!(ret);
+
+    let rows = vec![
+        (test::id.eq(1), test::TST_CHR.eq("updated")),
+        (test::id.eq(2), test::TST_CHR.eq("inserted")),
+    ];
+    let ret = upsert_batch(test::table)
+        .key(test::id)
+        .values(rows)
+        .execute(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 2);
+
+    let ret = test::table
+        .select((test::id, test::TST_CHR))
+        .order(test::id)
+        .load::<(Option<i64>, Option<String>)>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        vec![
+            (Some(1), Some("updated".to_owned())),
+            (Some(2), Some("inserted".to_owned())),
+        ]
+    );
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn upsert_batch_keeps_default_columns() {
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use oracle::query_dsl::upsert_batch;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    drop_table(&conn, "FLAGS");
+    let ret = conn.execute(
+        "CREATE TABLE flags (id NUMBER(10) PRIMARY KEY, active NUMBER(1) NOT NULL, \
+         archived NUMBER(1) DEFAULT 0)",
+    );
+    assert_result!(ret);
+    let ret = conn.execute("INSERT INTO flags (id, active, archived) VALUES (1, 0, 1)");
+    assert_result!(ret);
+
+    // rows leaving `archived` to its default neither update nor insert it
+    let rows = vec![
+        (flags::id.eq(1), flags::active.eq(true), None),
+        (flags::id.eq(2), flags::active.eq(false), None),
+        (
+            flags::id.eq(3),
+            flags::active.eq(true),
+            Some(flags::archived.eq(Some(true))),
+        ),
+    ];
+    let ret = upsert_batch(flags::table)
+        .key(flags::id)
+        .values(rows)
+        .execute(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 3);
+
+    let ret = flags::table
+        .order(flags::id)
+        .load::<(i32, bool, Option<bool>)>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        vec![
+            (1, true, Some(true)),
+            (2, false, Some(false)),
+            (3, true, Some(true)),
+        ]
+    );
+
+    drop_table(&conn, "FLAGS");
+}
+
+#[test]
+fn plan_statistics_of_last_query() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(conn.set_gather_plan_statistics(true));
+
+    let ret = sql::<BigInt>("SELECT CAST(level AS NUMBER(19)) FROM dual CONNECT BY level <= 5")
+        .load::<i64>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap().len(), 5);
+
+    let ret = conn.last_plan_statistics();
+    assert_result!(ret);
+    let steps = ret.unwrap();
+    assert!(!steps.is_empty());
+    assert_eq!(steps[0].actual_rows, Some(5));
+
+    assert_result!(conn.set_gather_plan_statistics(false));
+}
+
+#[test]
+fn session_info_attributes() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Nullable, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(conn.set_client_identifier("user@example.com"));
+    assert_result!(conn.set_module("diesel-oci"));
+    assert_result!(conn.set_action("tests"));
+    assert_result!(conn.set_client_info("session_info_attributes"));
+
+    let ret = sql::<(
+        Nullable<Text>,
+        Nullable<Text>,
+        Nullable<Text>,
+        Nullable<Text>,
+    )>(
+        "SELECT SYS_CONTEXT('USERENV', 'CLIENT_IDENTIFIER'), \
+         SYS_CONTEXT('USERENV', 'MODULE'), \
+         SYS_CONTEXT('USERENV', 'ACTION'), \
+         SYS_CONTEXT('USERENV', 'CLIENT_INFO') FROM dual",
+    )
+    .get_result::<(
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    )>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        (
+            Some("user@example.com".to_owned()),
+            Some("diesel-oci".to_owned()),
+            Some("tests".to_owned()),
+            Some("session_info_attributes".to_owned()),
+        )
+    );
+}
+
+#[test]
+fn statement_timeout_cancels_long_running_statements() {
+    use std::time::Duration;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_eq!(conn.statement_timeout(), None);
+
+    let ret = conn.with_statement_timeout(Duration::from_millis(500), || {
+        conn.execute("BEGIN DBMS_SESSION.SLEEP(5); END;")
+    });
+    match ret {
+        Err(Error::DatabaseError(_, ref info)) => assert!(info.message().contains("ORA-03156")),
+        ref other => panic!("Expected a timeout, got {:?}", other),
+    }
+    assert_eq!(conn.statement_timeout(), None);
+
+    // the connection is still usable
+    assert_result!(conn.execute("SELECT 1 FROM dual"));
+
+    // the result of the statement is kept
+    let ret = conn.with_statement_timeout(Duration::from_secs(5), || {
+        conn.execute("SELECT 1 FROM dual")
+    });
+    assert_result!(ret);
+    assert_eq!(conn.statement_timeout(), None);
+
+    // oci takes the timeout in milliseconds as u32
+    let ret = conn.set_statement_timeout(Duration::from_secs(u64::from(u32::MAX)));
+    assert!(ret.is_err());
+    assert_eq!(conn.statement_timeout(), None);
+}
+
+#[test]
+fn execute_script_from_reader() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+
+    let script = "-- setup\n\
+                  CREATE TABLE test (ID NUMBER(38), TST_CHR VARCHAR(50), TST_NUM NUMBER(38));\n\
+                  BEGIN\n\
+                  \x20 INSERT INTO test (ID) VALUES (1);\n\
+                  \x20 INSERT INTO test (ID) VALUES (2);\n\
+                  END;\n\
+                  /\n\
+                  INSERT INTO test (ID) VALUES (3);\n";
+    let mut lines = Vec::new();
+    let ret = conn.execute_script_from_reader(script.as_bytes(), |s| lines.push(s.line));
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 3);
+    assert_eq!(lines, vec![2, 3, 8]);
+
+    let count =
+        sql::<BigInt>("SELECT CAST(COUNT(*) AS NUMBER(19)) FROM test").get_result::<i64>(&conn);
+    assert_result!(count);
+    assert_eq!(count.unwrap(), 3);
+
+    let script = "INSERT INTO test (ID) VALUES (4);\n\nINSERT INTO no_such_table VALUES (5);\n";
+    match conn.execute_script_from_reader(script.as_bytes(), |_| {}) {
+        Err(Error::DatabaseError(_, ref info)) => {
+            assert!(info.message().contains("ORA-00942"));
+            assert!(info.message().contains("line 3"));
+        }
+        ref other => panic!("Expected an error, got {:?}", other),
+    }
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn batch_execute_splits_statements() {
+    use diesel::connection::SimpleConnection;
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+
+    let script = "CREATE TABLE test (ID NUMBER(38), TST_CHR VARCHAR(50), TST_NUM NUMBER(38));\n\
+                  CREATE OR REPLACE TRIGGER test_num BEFORE INSERT ON test FOR EACH ROW\n\
+                  BEGIN\n\
+                  \x20 :new.TST_NUM := :new.ID * 10;\n\
+                  END;\n\
+                  /\n\
+                  INSERT INTO test (ID) VALUES (1); INSERT INTO test (ID) VALUES (2);";
+    assert_result!(conn.batch_execute(script));
+
+    let sum =
+        sql::<BigInt>("SELECT CAST(SUM(TST_NUM) AS NUMBER(19)) FROM test").get_result::<i64>(&conn);
+    assert_result!(sum);
+    assert_eq!(sum.unwrap(), 30);
+
+    // a single statement may be terminated by `;`
+    assert_result!(conn.batch_execute("INSERT INTO test (ID) VALUES (3);"));
+    match conn.batch_execute("DELETE FROM test;\nINSERT INTO no_such_table VALUES (5);") {
+        Err(Error::DatabaseError(_, ref info)) => {
+            assert!(info.message().contains("ORA-00942"));
+            assert!(info.message().contains("line 2"));
+        }
+        ref other => panic!("Expected an error, got {:?}", other),
+    }
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn run_script_with_package() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let script = "CREATE OR REPLACE PACKAGE diesel_test_pkg AS\n\
+                  \x20 FUNCTION twice(n NUMBER) RETURN NUMBER;\n\
+                  END diesel_test_pkg;\n\
+                  /\n\
+                  CREATE OR REPLACE PACKAGE BODY diesel_test_pkg AS\n\
+                  \x20 FUNCTION twice(n NUMBER) RETURN NUMBER IS\n\
+                  \x20 BEGIN\n\
+                  \x20   RETURN n * 2;\n\
+                  \x20 END;\n\
+                  END diesel_test_pkg;\n\
+                  /\n\
+                  SELECT 1 FROM dual;\n";
+    let ret = conn.run_script(script);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 3);
+
+    let ret = sql::<BigInt>("SELECT CAST(diesel_test_pkg.twice(21) AS NUMBER(19)) FROM dual")
+        .get_result::<i64>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 42);
+
+    assert_result!(conn.run_script("DROP PACKAGE diesel_test_pkg;"));
+}
+
+#[test]
+fn prepared_statement_is_executed_repeatedly() {
+    use diesel::result::QueryResult;
+    use diesel::sql_types::{BigInt, Nullable, Text};
+    use oracle::connection::BindValue;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+
+    {
+        let stmt = conn.prepare("INSERT INTO test (ID, TST_CHR) VALUES (:1, :2)");
+        assert_result!(stmt);
+        let mut stmt = stmt.unwrap();
+        for &(id, chr) in &[(1, "one"), (2, "two"), (3, "three")] {
+            let ret = stmt.execute(vec![BindValue::Int(id), chr.into()]);
+            assert_result!(ret);
+            assert_eq!(ret.unwrap().affected_rows, 1);
+        }
+    }
+
+    let stmt = conn.prepare("SELECT ID, TST_CHR FROM test WHERE ID >= :1 ORDER BY ID");
+    assert_result!(stmt);
+    let mut stmt = stmt.unwrap();
+    for &(min, expected) in &[(2, 2), (3, 1), (4, 0)] {
+        let rows = stmt
+            .query::<(Nullable<BigInt>, Nullable<Text>), (Option<i64>, Option<String>), _>(vec![
+                BindValue::Int(min),
+            ]);
+        assert_result!(rows);
+        let rows = rows.unwrap().collect::<QueryResult<Vec<_>>>();
+        assert_result!(rows);
+        let rows = rows.unwrap();
+        assert_eq!(rows.len(), expected);
+        if let Some(row) = rows.first() {
+            assert_eq!(row.0, Some(min));
+        }
+    }
+    drop(stmt);
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn cursors_on_other_threads_report_their_own_errors() {
+    use diesel::sql_types::{Integer, Text};
+    use std::thread;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(conn.execute(
+        "CREATE OR REPLACE PROCEDURE thread_cursor_test(n IN NUMBER, rows OUT SYS_REFCURSOR) AS \
+         BEGIN OPEN rows FOR \
+         SELECT CAST(LEVEL AS NUMBER(10)), 'row ' || LEVEL FROM dual CONNECT BY LEVEL <= n; END;",
+    ));
+    let mut result = conn
+        .call("thread_cursor_test")
+        .param::<Integer, _>(&10_000)
+        .out_cursor()
+        .execute()
+        .unwrap();
+    let cursor = result.take_cursor(1).unwrap();
+
+    // the errors of the connection must not be reported by the cursor and
+    // vice versa
+    let rows = thread::spawn(move || cursor.load::<(Integer, Text), (i32, String)>());
+    for _ in 0..100 {
+        match conn.execute("SELECT * FROM thread_cursor_missing_table") {
+            Err(Error::DatabaseError(_, ref info)) => {
+                assert!(info.message().contains("ORA-00942"), "{}", info.message())
+            }
+            ref other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+    let rows = rows.join().unwrap();
+    assert_result!(rows);
+    assert_eq!(rows.unwrap().len(), 10_000);
+
+    assert_result!(conn.execute("DROP PROCEDURE thread_cursor_test"));
+}
+
+#[test]
+fn connections_share_the_environment() {
+    use oracle::connection::OciConnectionBuilder;
+
+    let first = OciConnection::establish(DB_URL).unwrap();
+    let second = OciConnection::establish(DB_URL).unwrap();
+    assert!(first.raw().env.is_shared_with(&second.raw().env));
+
+    let isolated = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .shared_environment(false)
+        .establish()
+        .unwrap();
+    assert!(!first.raw().env.is_shared_with(&isolated.raw().env));
+    assert_result!(isolated.execute("SELECT 1 FROM dual"));
+
+    // the environment outlives the connections using it
+    drop(first);
+    assert_result!(second.execute("SELECT 1 FROM dual"));
+
+    // and is created again once all of them were dropped
+    drop(second);
+    let third = OciConnection::establish(DB_URL).unwrap();
+    let fourth = OciConnection::establish(DB_URL).unwrap();
+    assert!(third.raw().env.is_shared_with(&fourth.raw().env));
+    assert_result!(third.execute("SELECT 1 FROM dual"));
+}
+
+// The following tests exercise the teardown of connections, run them with
+// valgrind and the address sanitizer to detect invalid frees, c.f.
+// `ci/teardown_checks.sh`
+#[test]
+fn connections_are_dropped_repeatedly() {
+    use self::test::columns::{id, TST_CHR};
+    use self::test::dsl::test;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use oracle::connection::BindValue;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+    let ret = conn.execute("INSERT INTO test (id, TST_CHR) VALUES (1, 'one')");
+    assert_result!(ret);
+
+    for _ in 0..20 {
+        let other = OciConnection::establish(DB_URL).unwrap();
+        let ret = test
+            .select(TST_CHR)
+            .filter(id.eq(1))
+            .load::<Option<String>>(&other);
+        assert_result!(ret);
+        let stmt = other.prepare("SELECT 1 FROM dual WHERE 1 = :1");
+        assert_result!(stmt);
+        let ret = stmt.unwrap().execute(vec![BindValue::Int(1)]);
+        assert_result!(ret);
+        drop(other);
+    }
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn lazy_cursor_outlives_connection() {
+    use self::test::columns::id;
+    use self::test::dsl::test;
+    use diesel::QueryDsl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+    let ret = conn.execute("INSERT INTO test (id) SELECT LEVEL FROM dual CONNECT BY LEVEL <= 3");
+    assert_result!(ret);
+
+    let other = OciConnection::establish(DB_URL).unwrap();
+    let cursor = other.load_lazy::<_, Option<i64>>(test.select(id).order(id));
+    assert_result!(cursor);
+    let cursor = cursor.unwrap();
+    // the cursor keeps the session alive until it is dropped
+    drop(other);
+    let ids = cursor.collect::<Result<Vec<_>, _>>();
+    assert_result!(ids);
+    assert_eq!(ids.unwrap(), vec![Some(1), Some(2), Some(3)]);
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn failed_connections_free_their_handles() {
+    use oracle::connection::OciConnectionBuilder;
+
+    for _ in 0..20 {
+        let ret = OciConnectionBuilder::from_url(DB_URL)
+            .unwrap()
+            .password("wrong password")
+            .establish();
+        assert!(ret.is_err());
+    }
+    // the shared environment is still usable
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(conn.execute("SELECT 1 FROM dual"));
+}
+
+#[test]
+fn cancel_running_statement() {
+    use std::thread;
+    use std::time::Duration;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let token = conn.cancel_token();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        token.cancel()
+    });
+
+    let ret = conn.execute("BEGIN DBMS_SESSION.SLEEP(10); END;");
+    match ret {
+        Err(Error::DatabaseError(_, ref info)) => assert!(info.message().contains("ORA-01013")),
+        ref other => panic!("Expected a cancelled statement, got {:?}", other),
+    }
+    let cancelled = canceller.join().unwrap();
+    assert_result!(cancelled);
+    assert!(cancelled.unwrap());
+
+    // the connection is still usable
+    assert_result!(conn.execute("SELECT 1 FROM dual"));
+
+    let token = conn.cancel_token();
+    drop(conn);
+    let ret = token.cancel();
+    assert_result!(ret);
+    assert!(!ret.unwrap());
+}
+
+#[test]
+fn execute_non_blocking() {
+    use std::task::Poll;
+    use std::thread;
+    use std::time::Duration;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let mut stmt = conn
+        .execute_non_blocking("BEGIN DBMS_SESSION.SLEEP(1); END;")
+        .unwrap();
+    let mut pending = 0;
+    let ret = loop {
+        match stmt.poll_execute() {
+            Poll::Ready(ret) => break ret,
+            Poll::Pending => {
+                pending += 1;
+                // other statements are refused meanwhile
+                assert!(conn.execute("SELECT 1 FROM dual").is_err());
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    };
+    assert_result!(ret);
+    assert!(pending > 0);
+    drop(stmt);
+
+    let mut stmt = conn
+        .execute_non_blocking("SELECT 'a' FROM dual UNION ALL SELECT 'b' FROM dual")
+        .unwrap();
+    let mut rows = Vec::new();
+    loop {
+        match stmt.poll_next_row() {
+            Poll::Ready(Ok(Some(row))) => rows.push(row[0].map(|v| v.to_vec())),
+            Poll::Ready(Ok(None)) => break,
+            Poll::Ready(Err(e)) => panic!("{:?}", e),
+            Poll::Pending => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+    assert_eq!(rows, vec![Some(b"a".to_vec()), Some(b"b".to_vec())]);
+
+    // dropping an unfinished statement aborts it
+    let mut stmt = conn
+        .execute_non_blocking("BEGIN DBMS_SESSION.SLEEP(10); END;")
+        .unwrap();
+    let _ = stmt.poll_execute();
+    drop(stmt);
+    assert_result!(conn.execute("SELECT 1 FROM dual"));
+}
+
+#[test]
+fn ping_and_reconnect() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Nullable, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(conn.ping());
+    assert_result!(conn.execute("ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY'"));
+    assert_result!(conn.set_module("diesel-oci"));
+    let session = "SELECT SYS_CONTEXT('USERENV', 'SID'), SYS_CONTEXT('USERENV', 'MODULE'), \
+                   (SELECT value FROM nls_session_parameters \
+                   WHERE parameter = 'NLS_DATE_FORMAT') FROM dual";
+    let before = sql::<(Nullable<Text>, Nullable<Text>, Nullable<Text>)>(session)
+        .get_result::<(Option<String>, Option<String>, Option<String>)>(&conn)
+        .unwrap();
+
+    assert_result!(conn.reconnect());
+    let after = sql::<(Nullable<Text>, Nullable<Text>, Nullable<Text>)>(session)
+        .get_result::<(Option<String>, Option<String>, Option<String>)>(&conn)
+        .unwrap();
+    assert_ne!(before.0, after.0);
+    assert_eq!(after.1, Some("diesel-oci".to_owned()));
+    assert_eq!(after.2, Some("YYYY".to_owned()));
+
+    conn.transaction::<_, Error, _>(|| {
+        assert!(conn.reconnect().is_err());
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn only_sessions_without_pending_work_are_reconnected() {
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    create_test_table(&conn);
+    assert!(!conn.raw().has_pending_work());
+
+    assert_result!(conn.execute("INSERT INTO test (ID) VALUES (1)"));
+    assert!(conn.raw().has_pending_work());
+    conn.transaction::<_, Error, _>(|| Ok(())).unwrap();
+    assert!(!conn.raw().has_pending_work());
+
+    conn.set_autocommit(true);
+    assert_result!(conn.execute("INSERT INTO test (ID) VALUES (2)"));
+    assert!(!conn.raw().has_pending_work());
+    conn.set_autocommit(false);
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn identifier_quoting_per_connection() {
+    use diesel::QueryDsl;
+    use oracle::connection::OciConnectionBuilder;
+    use oracle::IdentifierQuoting;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    clean_test(&conn);
+    create_test_table(&conn);
+
+    // `test` is written without quotes and folded to TEST by oracle
+    let never = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .identifier_quoting(IdentifierQuoting::Never)
+        .establish()
+        .unwrap();
+    assert_eq!(never.identifier_quoting(), IdentifierQuoting::Never);
+    assert_eq!(conn.identifier_quoting(), IdentifierQuoting::Uppercase);
+    let ret = test::table.select(test::id).load::<Option<i64>>(&never);
+    assert_result!(ret);
+
+    // there is no table "test" in lowercase
+    let always = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .identifier_quoting(IdentifierQuoting::Always)
+        .establish()
+        .unwrap();
+    assert!(test::table
+        .select(test::id)
+        .load::<Option<i64>>(&always)
+        .is_err());
+
+    drop_test_table(&conn);
+}
+
+#[test]
+fn nls_parameters_of_new_sessions() {
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .nls_territory("GERMANY")
+        .nls_date_format("YYYY-MM-DD")
+        .establish()
+        .unwrap();
+    let ret = conn.nls_parameter("nls_date_format");
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), Some("YYYY-MM-DD".to_owned()));
+    // the separators of the territory
+    assert_eq!(
+        conn.nls_parameter("NLS_NUMERIC_CHARACTERS").unwrap(),
+        Some(",.".to_owned())
+    );
+    assert_eq!(conn.nls_parameter("NLS_UNKNOWN").unwrap(), None);
+
+    assert_result!(conn.set_nls_parameter("NLS_NUMERIC_CHARACTERS", ".,"));
+    assert_eq!(
+        conn.nls_parameter("NLS_NUMERIC_CHARACTERS").unwrap(),
+        Some(".,".to_owned())
+    );
+}
+
+#[test]
+fn characters_outside_of_the_bmp() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Text;
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let ret = sql::<Text>("SELECT ")
+        .bind::<Text, _>("smile 😀")
+        .sql(" FROM dual")
+        .get_result::<String>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "smile 😀");
+
+    let ret = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .charset("NO_SUCH_CHARSET")
+        .establish();
+    assert!(ret.is_err());
+    let ret = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .charset("WE8ISO8859P1")
+        .establish();
+    assert!(ret.is_err());
+}
+
+#[test]
+fn national_character_columns() {
+    use diesel::sql_types::{Integer, Text};
+    use oracle::sql_types::NVarchar;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE nchar_test");
+    let ret = conn.execute("CREATE TABLE nchar_test (id NUMBER(10), name NVARCHAR2(50))");
+    assert_result!(ret);
+
+    let ret = diesel::sql_query("INSERT INTO nchar_test (id, name) VALUES (:1, :2)")
+        .bind::<Integer, _>(1)
+        .bind::<NVarchar, _>("Ærøskøbing ☃")
+        .execute(&conn);
+    assert_result!(ret);
+
+    let ret = diesel::dsl::sql::<NVarchar>("SELECT name FROM nchar_test WHERE id = 1")
+        .get_result::<String>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), "Ærøskøbing ☃");
+    let ret = diesel::dsl::sql::<Text>("SELECT name FROM nchar_test WHERE id = 1")
+        .get_result::<String>(&conn);
+    assert_eq!(ret.unwrap(), "Ærøskøbing ☃");
+
+    let _ = conn.execute("DROP TABLE nchar_test");
+}
+
+#[test]
+fn long_columns() {
+    use diesel::sql_types::{Binary, Integer, Nullable, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE long_test");
+    let _ = conn.execute("DROP TABLE long_raw_test");
+    assert_result!(conn.execute("CREATE TABLE long_test (id NUMBER(10), text LONG)"));
+    assert_result!(conn.execute("CREATE TABLE long_raw_test (id NUMBER(10), data LONG RAW)"));
+
+    // larger than a single piece
+    let text = "0123456789".repeat(10_000);
+    let ret = diesel::sql_query("INSERT INTO long_test (id, text) VALUES (1, :1)")
+        .bind::<Text, _>(&text)
+        .execute(&conn);
+    assert_result!(ret);
+    assert_result!(conn.execute("INSERT INTO long_test (id, text) VALUES (2, NULL)"));
+    assert_result!(
+        conn.execute("INSERT INTO long_raw_test (id, data) VALUES (1, HEXTORAW('00FF10'))")
+    );
+
+    let ret =
+        diesel::dsl::sql::<(Integer, Nullable<Text>)>("SELECT id, text FROM long_test ORDER BY id")
+            .load::<(i32, Option<String>)>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![(1, Some(text)), (2, None)]);
+
+    let ret =
+        diesel::dsl::sql::<Binary>("SELECT data FROM long_raw_test").get_result::<Vec<u8>>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![0x00, 0xFF, 0x10]);
+
+    let _ = conn.execute("DROP TABLE long_test");
+    let _ = conn.execute("DROP TABLE long_raw_test");
+}
+
+#[test]
+fn scrollable_cursor() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+    use oracle::connection::ScrollPosition;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query = sql::<Integer>("SELECT CAST(level AS NUMBER(10)) FROM dual CONNECT BY level <= 5");
+    let ret = conn.with_scrollable_cursor(query, |cursor| {
+        let mut rows = vec![cursor.seek(ScrollPosition::Last)?];
+        rows.push(cursor.seek(ScrollPosition::First)?);
+        rows.push(cursor.next().transpose()?);
+        rows.push(cursor.seek(ScrollPosition::Absolute(4))?);
+        assert_eq!(cursor.row_number()?, 4);
+        rows.push(cursor.seek(ScrollPosition::Relative(-3))?);
+        rows.push(cursor.seek(ScrollPosition::Absolute(6))?);
+        Ok(rows)
+    });
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        vec![Some(5), Some(1), Some(2), Some(4), Some(1), None]
+    );
+
+    // cached statements are executed without a scrollable cursor again
+    let query = sql::<Integer>("SELECT CAST(level AS NUMBER(10)) FROM dual CONNECT BY level <= 5");
+    let ret = query.load::<i32>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap().len(), 5);
+}
+
+#[test]
+fn oci_statement_cache() {
+    use oracle::connection::OciConnectionBuilder;
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .statement_cache_size(10)
+        .establish()
+        .unwrap();
+    let _ = conn.execute("DROP TABLE stmt_cache_test");
+    // DDL statements are prepared again instead of being taken from the cache
+    for _ in 0..2 {
+        assert_result!(conn.execute("CREATE TABLE stmt_cache_test (id NUMBER(10))"));
+        assert_result!(conn.execute("INSERT INTO stmt_cache_test (id) VALUES (1)"));
+        assert_result!(conn.execute("DROP TABLE stmt_cache_test"));
+    }
+}
+
+#[test]
+fn load_with_statement_options() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+    use oracle::connection::StatementOptions;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query =
+        sql::<Integer>("SELECT CAST(level AS NUMBER(10)) FROM dual CONNECT BY level <= 100");
+    let options = StatementOptions::new()
+        .prefetch_rows(50)
+        .prefetch_memory(1024);
+    let ret = conn.load_with_options::<_, i32>(query, &options);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (1..=100).collect::<Vec<_>>());
+}
+
+#[test]
+fn for_each_row_borrows_values() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Nullable, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query = sql::<Nullable<Text>>(
+        "SELECT CASE WHEN level = 2 THEN NULL ELSE 'row ' || level END FROM dual CONNECT BY level <= 3",
+    );
+    let mut values = Vec::new();
+    let ret = conn.for_each_row(&query, |row| {
+        values.push(row.get_str(0)?.map(str::to_owned));
+        assert!(row.get_bytes(1).is_none());
+        Ok(())
+    });
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 3);
+    assert_eq!(
+        values,
+        vec![Some("row 1".to_owned()), None, Some("row 3".to_owned())]
+    );
+}
+
+#[test]
+fn query_logger_redacts_binds() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Integer, Text};
+    use std::sync::{Arc, Mutex};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let logged = Arc::new(Mutex::new(Vec::new()));
+    let recorded = logged.clone();
+    conn.set_query_logger(false, move |q| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((q.sql.to_owned(), q.binds.map(str::to_owned), q.rows));
+    });
+    let query = sql::<Integer>("SELECT CAST(LENGTH(")
+        .bind::<Text, _>("secret")
+        .sql(") AS NUMBER(10)) FROM dual");
+    let ret = query.get_result::<i32>(&conn);
+    assert_result!(ret);
+    conn.clear_query_logger();
+    let _ = sql::<Integer>("SELECT 1 FROM dual").execute(&conn);
+
+    assert_eq!(
+        *logged.lock().unwrap(),
+        vec![(
+            "SELECT CAST(LENGTH(:1) AS NUMBER(10)) FROM dual".to_owned(),
+            None,
+            Some(1)
+        )]
+    );
+}
+
+#[test]
+fn metrics_sink_records_statement_stats() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+    use oracle::connection::{MetricsSink, StatementStats};
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Mutex<Vec<StatementStats>>);
+
+    impl MetricsSink for Recorder {
+        fn record(&self, stats: &StatementStats) {
+            self.0.lock().unwrap().push(stats.clone());
+        }
+    }
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+    conn.set_metrics_sink(recorder.clone());
+    let query = sql::<Integer>("SELECT CAST(level AS NUMBER(10)) FROM dual CONNECT BY level <= 3");
+    let ret = query.load::<i32>(&conn);
+    assert_result!(ret);
+    conn.clear_metrics_sink();
+
+    let stats = recorder.0.lock().unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].rows_fetched, 3);
+    // the last call finds no more rows
+    assert_eq!(stats[0].fetch_calls, 4);
+}
+
+#[test]
+fn metrics_sink_records_implicit_conversions() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Bool, Double};
+    use diesel::{ExpressionMethods, QueryDsl};
+    use oracle::connection::{ImplicitConversion, MetricsSink, StatementStats};
+    use oracle::OCIDataType;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Mutex<Vec<StatementStats>>);
+
+    impl MetricsSink for Recorder {
+        fn record(&self, stats: &StatementStats) {
+            self.0.lock().unwrap().push(stats.clone());
+        }
+    }
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+    let ret = diesel::insert_into(test::table)
+        .values(test::id.eq(10))
+        .execute(&conn);
+    assert_result!(ret);
+
+    let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+    conn.set_metrics_sink(recorder.clone());
+    // `ID` is a NUMBER(38), so the integer is bound as NUMBER
+    let ret = test::table
+        .select(test::id)
+        .filter(test::id.eq(10))
+        .load::<Option<i64>>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![Some(10)]);
+    let ret = test::table
+        .select(test::id)
+        .filter(sql::<Bool>("\"TEST\".\"TST_NUM\" = ").bind::<Double, _>(1.5))
+        .load::<Option<i64>>(&conn);
+    assert_result!(ret);
+    conn.clear_metrics_sink();
+
+    let stats = recorder.0.lock().unwrap();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].implicit_conversions, vec![]);
+    assert_eq!(
+        stats[1].implicit_conversions,
+        vec![ImplicitConversion {
+            position: 1,
+            bind_type: OCIDataType::BDouble,
+            column: "\"TEST\".\"TST_NUM\"".to_owned(),
+            column_type: Some(OCIDataType::Numeric),
+        }]
+    );
+
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn sequence_nextval_and_currval() {
+    use diesel::{ExpressionMethods, NullableExpressionMethods, QueryDsl};
+    use oracle::query_dsl::sequence;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    let _ = conn.execute("DROP SEQUENCE test_ids");
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+    assert_result!(conn.execute("CREATE SEQUENCE test_ids START WITH 10"));
+
+    let ids = sequence("test_ids");
+    let ret = diesel::insert_into(test::table)
+        .values(test::id.eq(ids.nextval().nullable()))
+        .execute(&conn);
+    assert_result!(ret);
+    let ret = test::table.select(ids.currval()).first::<i64>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 10);
+    let ret = test::table.select(test::id).first::<Option<i64>>(&conn);
+    assert_eq!(ret.unwrap(), Some(10));
+
+    let _ = conn.execute("DROP SEQUENCE test_ids");
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn truncate_and_drop_table_if_exists() {
+    use diesel::QueryDsl;
+    use oracle::ddl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    assert_result!(ddl::drop_table_if_exists(test::table).execute(&conn));
+    // a missing table is not an error
+    assert_result!(ddl::drop_table_if_exists(test::table).execute(&conn));
+
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+    assert_result!(conn.execute("INSERT INTO test (id) VALUES (1)"));
+    assert_result!(ddl::truncate(test::table).execute(&conn));
+    let ret = test::table.count().get_result::<i64>(&conn);
+    assert_eq!(ret.unwrap(), 0);
+
+    assert_result!(ddl::drop_table_if_exists(test::table).execute(&conn));
+    assert!(conn.execute("SELECT * FROM test").is_err());
+}
+
+#[test]
+fn two_phase_commit_of_global_transaction() {
+    use diesel::QueryDsl;
+    use oracle::connection::Xid;
+    use std::time::Duration;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+
+    let xid = Xid::new(0x1234, b"diesel-oci", b"branch-1").unwrap();
+    assert_result!(conn.start_global_transaction(&xid, Duration::from_secs(60)));
+    assert_result!(conn.execute("INSERT INTO test (id) VALUES (1)"));
+    assert_result!(conn.detach_global_transaction());
+
+    // the branch is not visible before it is committed
+    let other = OciConnection::establish(DB_URL).unwrap();
+    assert_eq!(test::table.count().get_result::<i64>(&other).unwrap(), 0);
+
+    let ret = other.prepare_global_transaction(&xid);
+    assert_result!(ret);
+    assert!(ret.unwrap());
+    assert_result!(other.commit_global_transaction(&xid, false));
+    assert_eq!(test::table.count().get_result::<i64>(&conn).unwrap(), 1);
+
+    // a read-only branch is completed by prepare
+    let xid = Xid::new(0x1234, b"diesel-oci", b"branch-2").unwrap();
+    assert_result!(conn.start_global_transaction(&xid, Duration::from_secs(60)));
+    assert_result!(test::table.count().get_result::<i64>(&conn));
+    assert_result!(conn.detach_global_transaction());
+    assert!(!conn.prepare_global_transaction(&xid).unwrap());
+
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn read_only_and_serializable_transactions() {
+    use oracle::connection::TransactionBehavior;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+
+    let ret = conn.transaction_with_behavior(TransactionBehavior::ReadOnly, || {
+        conn.execute("INSERT INTO test (id) VALUES (1)")
+    });
+    assert!(ret.is_err());
+
+    let ret = conn.transaction_with_behavior(TransactionBehavior::Serializable, || {
+        conn.execute("INSERT INTO test (id) VALUES (1)")
+    });
+    assert_eq!(ret.unwrap(), 1);
+
+    // oracle only allows `SET TRANSACTION` at the start of a transaction
+    let ret = conn.transaction::<_, Error, _>(|| {
+        conn.transaction_with_behavior(TransactionBehavior::ReadOnly, || Ok(()))
+    });
+    match ret {
+        Err(Error::AlreadyInTransaction) => {}
+        ret => panic!("unexpected result {:?}", ret),
+    }
+
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn autocommit_commits_statements_outside_of_transactions() {
+    use diesel::QueryDsl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let other = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+    let count = || test::table.count().get_result::<i64>(&other).unwrap();
+
+    assert!(!conn.autocommit());
+    assert_result!(conn.execute("INSERT INTO test (id) VALUES (1)"));
+    assert_eq!(count(), 0);
+    assert_result!(conn.execute("ROLLBACK"));
+
+    conn.set_autocommit(true);
+    assert_result!(conn.execute("INSERT INTO test (id) VALUES (1)"));
+    assert_eq!(count(), 1);
+
+    // statements of transactions are still committed together
+    let _ = conn.transaction::<(), _, _>(|| {
+        conn.execute("INSERT INTO test (id) VALUES (2)")?;
+        assert_eq!(count(), 1);
+        Err(Error::RollbackTransaction)
+    });
+    assert_eq!(count(), 1);
+
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn direct_path_load() {
+    use diesel::{ExpressionMethods, QueryDsl};
+    use oracle::connection::{DirectPathColumn, DirectPathTable};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute(DROP_TEST_TABLE);
+    assert_result!(conn.execute(CREATE_TEST_TABLE));
+
+    let table = DirectPathTable::new("test")
+        .column(DirectPathColumn::new("id", 20))
+        .column(DirectPathColumn::new("tst_chr", 50));
+    let mut loader = conn.direct_path_loader(&table).unwrap();
+    for i in 0..10_000 {
+        let id = i.to_string();
+        let chr = if i % 2 == 0 { Some("even") } else { None };
+        loader.append_row(&[Some(&id), chr]).unwrap();
+    }
+    assert!(loader.append_row(&[Some("1")]).is_err());
+    assert_eq!(loader.finish().unwrap(), 10_000);
+
+    let count = test::table.count().get_result::<i64>(&conn).unwrap();
+    assert_eq!(count, 10_000);
+    let nulls = test::table
+        .filter(test::TST_CHR.is_null())
+        .count()
+        .get_result::<i64>(&conn)
+        .unwrap();
+    assert_eq!(nulls, 5_000);
+
+    // rows of an aborted load are discarded
+    let mut loader = conn.direct_path_loader(&table).unwrap();
+    loader.append_row(&[Some("10000"), None]).unwrap();
+    drop(loader);
+    let count = test::table.count().get_result::<i64>(&conn).unwrap();
+    assert_eq!(count, 10_000);
+
+    let _ = conn.execute(DROP_TEST_TABLE);
+}
+
+#[test]
+fn direct_path_load_exceeding_a_stream() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+    use oracle::connection::{DirectPathColumn, DirectPathTable};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE direct_path_wide");
+    assert_result!(
+        conn.execute("CREATE TABLE direct_path_wide (id NUMBER(10), payload VARCHAR2(4000))")
+    );
+
+    // the rows of a column array don't fit into a single stream, which is
+    // then loaded in several parts
+    let table = DirectPathTable::new("direct_path_wide")
+        .column(DirectPathColumn::new("id", 10))
+        .column(DirectPathColumn::new("payload", 4000));
+    let payload = "x".repeat(4000);
+    let mut loader = conn.direct_path_loader(&table).unwrap();
+    for i in 0..1_000 {
+        loader
+            .append_row(&[Some(&i.to_string()), Some(&payload)])
+            .unwrap();
+    }
+    assert_eq!(loader.finish().unwrap(), 1_000);
+
+    let (count, ids, length) = sql::<(BigInt, BigInt, BigInt)>(
+        "SELECT COUNT(*), COUNT(DISTINCT id), SUM(LENGTH(payload)) FROM direct_path_wide",
+    )
+    .get_result::<(i64, i64, i64)>(&conn)
+    .unwrap();
+    assert_eq!(count, 1_000);
+    assert_eq!(ids, 1_000);
+    assert_eq!(length, 4_000_000);
+
+    let _ = conn.execute("DROP TABLE direct_path_wide");
+}
+
+#[test]
+fn lob_streaming() {
+    use diesel::sql_types::{Binary, Integer, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE lob_test");
+    assert_result!(conn.execute("CREATE TABLE lob_test (id NUMBER(10), doc CLOB, data BLOB)"));
+
+    // values larger than a VARCHAR2 or RAW are bound as temporary LOBs
+    let text = "äbc".repeat(20_000);
+    let data = vec![0xA5; 100_000];
+    let ret = diesel::sql_query("INSERT INTO lob_test (id, doc, data) VALUES (1, :1, :2)")
+        .bind::<Text, _>(&text)
+        .bind::<Binary, _>(&data)
+        .execute(&conn);
+    assert_result!(ret);
+    let ret = diesel::dsl::sql::<(Integer, Integer)>(
+        "SELECT DBMS_LOB.GETLENGTH(doc), DBMS_LOB.GETLENGTH(data) FROM lob_test",
+    )
+    .get_result::<(i32, i32)>(&conn);
+    assert_eq!(ret.unwrap(), (60_000, 100_000));
+
+    let mut doc = conn
+        .select_lob("SELECT doc FROM lob_test WHERE id = 1 FOR UPDATE")
+        .unwrap()
+        .unwrap();
+    assert_eq!(doc.len().unwrap(), 60_000);
+    doc.trim(3).unwrap();
+    doc.append("dë".as_bytes()).unwrap();
+    doc.write_chunk(0, b"x").unwrap();
+    assert_eq!(doc.len().unwrap(), 5);
+    let ret = diesel::dsl::sql::<Text>("SELECT DBMS_LOB.SUBSTR(doc, 10, 1) FROM lob_test")
+        .get_result::<String>(&conn);
+    assert_eq!(ret.unwrap(), "xbcdë");
+
+    let mut data = conn
+        .select_lob("SELECT data FROM lob_test WHERE id = 1 FOR UPDATE")
+        .unwrap()
+        .unwrap();
+    data.trim(0).unwrap();
+    assert!(data.is_empty().unwrap());
+
+    assert!(conn
+        .select_lob("SELECT doc FROM lob_test WHERE id = 2")
+        .unwrap()
+        .is_none());
+    assert!(conn.select_lob("SELECT id FROM lob_test").is_err());
+
+    let _ = conn.execute("DROP TABLE lob_test");
+}
+
+#[test]
+fn temporary_lob_binds() {
+    use diesel::sql_types::Integer;
+    use oracle::connection::{LobType, PlSqlBind};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let mut doc = conn.create_temporary_lob(LobType::Clob).unwrap();
+    assert!(doc.is_temporary());
+    doc.append("äbc".repeat(50_000).as_bytes()).unwrap();
+    let mut binds = [PlSqlBind::lob(doc), PlSqlBind::output::<Integer>(4)];
+    assert_result!(conn.execute_plsql("BEGIN :2 := DBMS_LOB.GETLENGTH(:1); END;", &mut binds));
+    assert_eq!(binds[1].get::<Integer, i32>().unwrap(), 150_000);
+
+    let mut data = conn.create_temporary_lob(LobType::Blob).unwrap();
+    data.append(&[1, 2, 3]).unwrap();
+    let ret = conn
+        .call("DBMS_LOB.TRIM")
+        .lob_param(data)
+        .param::<Integer, _>(&1)
+        .execute();
+    assert_result!(ret);
+}
+
+#[test]
+fn multi_byte_strings_fill_columns() {
+    use diesel::sql_types::Text;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE utf8_test");
+    assert_result!(conn.execute(
+        "CREATE TABLE utf8_test (chars VARCHAR2(5 CHAR), bytes VARCHAR2(12 BYTE), fixed CHAR(3 CHAR))"
+    ));
+
+    // columns filled with characters of 2 and 3 bytes
+    let ret = diesel::sql_query("INSERT INTO utf8_test VALUES (:1, :2, :3)")
+        .bind::<Text, _>("äöü€ß")
+        .bind::<Text, _>("€€€€")
+        .bind::<Text, _>("äö")
+        .execute(&conn);
+    assert_result!(ret);
+
+    let ret = diesel::dsl::sql::<(Text, Text, Text)>("SELECT chars, bytes, fixed FROM utf8_test")
+        .get_result::<(String, String, String)>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        ("äöü€ß".to_owned(), "€€€€".to_owned(), "äö ".to_owned())
+    );
+
+    let _ = conn.execute("DROP TABLE utf8_test");
+}
+
+#[test]
+fn row_values_by_column_name() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Integer, Nullable, Text};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query = sql::<(Integer, Nullable<Text>)>(
+        "SELECT CAST(42 AS NUMBER(10)) AS id, 'Sean' AS \"Name\" FROM dual",
+    );
+    let ret = conn.for_each_row(&query, |row| {
+        assert_eq!(row.column_names(), vec!["ID", "Name"]);
+        assert_eq!(row.column_name(1), Some("Name"));
+        assert_eq!(row.column_name(2), None);
+        assert_eq!(row.get_by_name::<Integer, i32>("id")?, 42);
+        assert_eq!(
+            row.get_by_name::<Nullable<Text>, Option<String>>("Name")?,
+            Some("Sean".to_owned())
+        );
+        assert!(row.get_by_name::<Integer, i32>("missing").is_err());
+        Ok(())
+    });
+    assert_eq!(ret.unwrap(), 1);
+}
+
+#[test]
+fn query_dynamic_rows() {
+    use oracle::connection::DynamicValue;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let ret = conn.query_dynamic(
+        "SELECT CAST(42 AS NUMBER(10)) AS id, CAST(1.5 AS NUMBER(5, 2)) AS amount, \
+         'abc' AS name, CAST(NULL AS VARCHAR2(10)) AS missing, \
+         HEXTORAW('0102') AS bytes, DATE '2020-03-14' AS day FROM dual",
+    );
+    assert_result!(ret);
+    let rows = ret.unwrap();
+    assert_eq!(rows.len(), 1);
+    let row = &rows[0];
+    let names = row
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["ID", "AMOUNT", "NAME", "MISSING", "BYTES", "DAY"]
+    );
+    assert_eq!(row[0].1, DynamicValue::Number(42));
+    assert_eq!(row[1].1, DynamicValue::Float(1.5));
+    assert_eq!(row[2].1, DynamicValue::String("abc".to_owned()));
+    assert_eq!(row[3].1, DynamicValue::Null);
+    assert_eq!(row[4].1, DynamicValue::Raw(vec![1, 2]));
+    #[cfg(feature = "chrono-time")]
+    assert_eq!(
+        row[5].1,
+        DynamicValue::Date(
+            ::chrono::NaiveDate::from_ymd_opt(2020, 3, 14)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn owned_cursor_outlives_call() {
+    use diesel::result::QueryResult;
+    use diesel::sql_types::{Integer, Text};
+    use oracle::connection::OwnedCursor;
+
+    fn numbers(
+        conn: &OciConnection,
+        n: i32,
+    ) -> QueryResult<OwnedCursor<(Integer, Text), (i32, String)>> {
+        conn.execute(
+            "CREATE OR REPLACE PROCEDURE owned_cursor_test(n IN NUMBER, rows OUT SYS_REFCURSOR) AS \
+             BEGIN OPEN rows FOR \
+             SELECT CAST(LEVEL AS NUMBER(10)), 'row ' || LEVEL FROM dual CONNECT BY LEVEL <= n \
+             ORDER BY 1; END;",
+        )?;
+        let mut result = conn
+            .call("owned_cursor_test")
+            .param::<Integer, _>(&n)
+            .out_cursor()
+            .execute()?;
+        result
+            .take_cursor(1)
+            .expect("the procedure returns a cursor")
+            .into_cursor()
+    }
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let ret = numbers(&conn, 2);
+    assert_result!(ret);
+    let rows = ret.unwrap().collect::<QueryResult<Vec<_>>>();
+    assert_result!(rows);
+    assert_eq!(
+        rows.unwrap(),
+        vec![(1, "row 1".to_owned()), (2, "row 2".to_owned())]
+    );
+}
+
+#[test]
+fn load_lazy_fetches_while_iterating() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query = sql::<Integer>(
+        "SELECT CAST(LEVEL AS NUMBER(10)) FROM dual CONNECT BY LEVEL <= 10000 ORDER BY 1",
+    );
+    let ret = conn.load_lazy::<_, i32>(query);
+    assert_result!(ret);
+    let mut count = 0;
+    for (i, row) in ret.unwrap().enumerate() {
+        assert_eq!(row.unwrap(), i as i32 + 1);
+        count += 1;
+    }
+    assert_eq!(count, 10000);
+
+    let ret = conn.load_lazy::<_, i32>(sql::<Integer>("SELECT 1 FROM dual WHERE 1 = 0"));
+    assert_result!(ret);
+    assert_eq!(ret.unwrap().count(), 0);
+}
+
+#[test]
+fn numbers_fetched_by_precision() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{BigInt, Integer, SmallInt, Text};
+    use oracle::connection::{ColumnInfo, FetchType, TypeMap};
+    use std::sync::Arc;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let query = "SELECT CAST(123 AS NUMBER(3)), CAST(1234567 AS NUMBER(7)), \
+                 CAST(123456789012 AS NUMBER(12)) FROM dual";
+    let ret = sql::<(SmallInt, Integer, BigInt)>(query).get_result::<(i16, i32, i64)>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (123, 1_234_567, 123_456_789_012));
+
+    struct LargeNumbersAsText;
+
+    impl TypeMap for LargeNumbersAsText {
+        fn fetch_type(&self, column: &ColumnInfo) -> Option<FetchType> {
+            if column.precision > 10 {
+                Some(FetchType::Text)
+            } else {
+                None
+            }
+        }
+    }
+
+    conn.set_type_map(Arc::new(LargeNumbersAsText));
+    let ret = sql::<(SmallInt, Integer, Text)>(query).get_result::<(i16, i32, String)>(&conn);
+    conn.clear_type_map();
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (123, 1_234_567, "123456789012".to_owned()));
+}
+
+#[test]
+fn numbers_are_converted_with_overflow_checks() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::{BigInt, Double, Integer, SmallInt};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let ret = sql::<(BigInt, BigInt, Double)>(
+        "SELECT CAST(9223372036854775807 AS NUMBER(38)), CAST(-42 AS INTEGER), \
+         CAST(2.5 AS NUMBER(10, 2)) FROM dual",
+    )
+    .get_result::<(i64, i64, f64)>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (i64::MAX, -42, 2.5));
+
+    let ret = sql::<BigInt>("SELECT CAST(1e20 AS NUMBER(38)) FROM dual").get_result::<i64>(&conn);
+    match ret {
+        Err(Error::DeserializationError(_)) => {}
+        r => panic!("expected a DeserializationError, got {:?}", r),
+    }
+
+    // NUMBER(5) is fetched as Integer, which holds all its values
+    let ret = sql::<Integer>("SELECT CAST(99999 AS NUMBER(5)) FROM dual").get_result::<i32>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 99_999);
+
+    // values are checked against the type they are read as
+    let ret = sql::<SmallInt>("SELECT CAST(99999 AS NUMBER(5)) FROM dual").get_result::<i16>(&conn);
+    match ret {
+        Err(Error::DeserializationError(_)) => {}
+        r => panic!("expected a DeserializationError, got {:?}", r),
+    }
+    let ret =
+        sql::<Integer>("SELECT CAST(9999999999 AS NUMBER(10)) FROM dual").get_result::<i32>(&conn);
+    match ret {
+        Err(Error::DeserializationError(_)) => {}
+        r => panic!("expected a DeserializationError, got {:?}", r),
+    }
+    let ret =
+        sql::<BigInt>("SELECT CAST(9999999999 AS NUMBER(10)) FROM dual").get_result::<i64>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 9_999_999_999);
+}
+
+#[test]
+fn large_integers() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+    use oracle::sql_types::LargeInt;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let _ = conn.execute("DROP TABLE large_keys");
+    assert_result!(conn.execute("CREATE TABLE large_keys (id NUMBER(38), small NUMBER(38))"));
+
+    let large = 10i128.pow(37) + 7;
+    let ret = diesel::sql_query("INSERT INTO large_keys VALUES (:1, :2)")
+        .bind::<LargeInt, _>(-large)
+        .bind::<LargeInt, _>(u64::MAX)
+        .execute(&conn);
+    assert_result!(ret);
+
+    let ret = sql::<(LargeInt, LargeInt)>("SELECT id, small FROM large_keys")
+        .get_result::<(i128, u64)>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (-large, u64::MAX));
+
+    // fetched with all digits, so values exceeding an i64 are reported
+    let ret = sql::<BigInt>("SELECT id FROM large_keys").get_result::<i64>(&conn);
+    assert!(ret.is_err());
+    let ret = sql::<BigInt>("SELECT CAST(-42 AS NUMBER(38)) FROM dual").get_result::<i64>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), -42);
+
+    let _ = conn.execute("DROP TABLE large_keys");
+}
+
+#[cfg(feature = "chrono-time")]
+#[test]
+fn timestamp_with_local_time_zone() {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+    use diesel::dsl::sql;
+    use diesel::sql_types::Timestamp;
+    use oracle::connection::OciConnectionBuilder;
+    use oracle::sql_types::TimestampLtz;
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .session_timezone("+02:00")
+        .establish()
+        .unwrap();
+    let value = "CAST(TIMESTAMP '2020-03-14 15:09:00.25 +00:00' \
+                 AS TIMESTAMP(3) WITH LOCAL TIME ZONE) FROM dual";
+    let utc = NaiveDate::from_ymd_opt(2020, 3, 14)
+        .and_then(|d| d.and_hms_milli_opt(15, 9, 0, 250))
+        .unwrap();
+
+    let ret = sql::<TimestampLtz>(&format!("SELECT {}", value)).get_result::<DateTime<Utc>>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap().naive_utc(), utc);
+
+    // read as Timestamp in the session time zone
+    let ret = sql::<Timestamp>(&format!("SELECT {}", value)).get_result::<NaiveDateTime>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        NaiveDate::from_ymd_opt(2020, 3, 14)
+            .and_then(|d| d.and_hms_milli_opt(17, 9, 0, 250))
+            .unwrap()
+    );
+
+    // timestamps without a time zone keep their fractional seconds as well
+    let ret = sql::<Timestamp>("SELECT TIMESTAMP '2020-03-14 15:09:00.123456789' FROM dual")
+        .get_result::<NaiveDateTime>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        NaiveDate::from_ymd_opt(2020, 3, 14)
+            .and_then(|d| d.and_hms_nano_opt(15, 9, 0, 123_456_789))
+            .unwrap()
+    );
+}
+
+#[cfg(feature = "chrono-time")]
+#[test]
+fn timestamp_with_time_zone_functions() {
+    use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+    use diesel::{ExpressionMethods, QueryDsl};
+    use oracle::connection::OciConnectionBuilder;
+    use oracle::query_dsl::{current_timestamp, systimestamp};
+
+    let conn = OciConnectionBuilder::from_url(DB_URL)
+        .unwrap()
+        .session_timezone("+02:00")
+        .establish()
+        .unwrap();
+    drop_table(&conn, "SESSIONS");
+    let ret = conn.execute(
+        "CREATE TABLE sessions (\
+         id NUMBER(9) PRIMARY KEY, \
+         expires_at TIMESTAMP NOT NULL, \
+         closed_at TIMESTAMP)",
+    );
+    assert_result!(ret);
+
+    let ret = diesel::select(current_timestamp()).get_result::<DateTime<FixedOffset>>(&conn);
+    assert_result!(ret);
+    let now = ret.unwrap();
+    assert_eq!(now.offset(), &FixedOffset::east_opt(2 * 3600).unwrap());
+    let ret = diesel::select(systimestamp()).get_result::<DateTime<Utc>>(&conn);
+    assert_result!(ret);
+    assert!((ret.unwrap() - now.with_timezone(&Utc)).num_seconds().abs() < 60);
+
+    // inserted and compared like timestamps of the session time zone
+    let ret = diesel::insert_into(sessions::table)
+        .values((
+            sessions::id.eq(1),
+            sessions::expires_at.eq(current_timestamp()),
+        ))
+        .execute(&conn);
+    assert_result!(ret);
+    let ret = conn.execute(
+        "INSERT INTO sessions (id, expires_at) \
+         VALUES (2, CAST(SYSTIMESTAMP AT TIME ZONE '+02:00' AS TIMESTAMP) + 1)",
+    );
+    assert_result!(ret);
+
+    let ret = diesel::update(sessions::table.filter(sessions::expires_at.le(current_timestamp())))
+        .set(sessions::closed_at.eq(systimestamp()))
+        .execute(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 1);
+
+    let ret = sessions::table
+        .filter(sessions::closed_at.is_not_null())
+        .select(sessions::id)
+        .load::<i32>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![1]);
+    let ret = sessions::table
+        .filter(sessions::expires_at.gt(systimestamp()))
+        .select(sessions::expires_at)
+        .load::<NaiveDateTime>(&conn);
+    assert_result!(ret);
+    let expires_at = ret.unwrap();
+    assert_eq!(expires_at.len(), 1);
+    assert!(
+        (expires_at[0] - now.naive_local() - Duration::days(1))
+            .num_seconds()
+            .abs()
+            < 60
+    );
+
+    drop_table(&conn, "SESSIONS");
+}
+
+#[test]
+fn execute_plsql_with_bind_values() {
+    use diesel::sql_types::Text;
+    use oracle::connection::{BindValue, PlSqlBind};
+    use oracle::OCIDataType;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    let mut binds = [
+        PlSqlBind::value(BindValue::Int(41)),
+        PlSqlBind::value("oci".into()),
+        PlSqlBind::value(BindValue::Null(OCIDataType::InternDate)),
+        PlSqlBind::output::<Text>(40),
+    ];
+    let ret = conn.execute_plsql(
+        "BEGIN :4 := (:1 + 1) || '-' || :2 || '-' || NVL(TO_CHAR(:3, 'YYYY'), 'none'); END;",
+        &mut binds,
+    );
+    assert_result!(ret);
+    assert_eq!(binds[3].get::<Text, String>().unwrap(), "42-oci-none");
+}
+
+#[test]
+fn cached_statement_is_executed_with_new_binds() {
+    use self::test::columns::{id, TST_CHR};
+    use self::test::dsl::test;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+    let ret = conn.execute("INSERT INTO test (id, TST_CHR) VALUES (1, 'one')");
+    assert_result!(ret);
+    let ret = conn.execute("INSERT INTO test (id, TST_CHR) VALUES (2, 'two')");
+    assert_result!(ret);
+
+    // the same query is prepared once and bound again for each execution
+    for &(key, expected) in &[(1, "one"), (2, "two"), (1, "one")] {
+        let ret = test
+            .select(TST_CHR)
+            .filter(id.eq(key))
+            .load::<Option<String>>(&conn);
+        assert_result!(ret);
+        assert_eq!(ret.unwrap(), vec![Some(expected.to_owned())]);
+    }
+
+    let _u = drop_test_table(&conn);
+}
+
+#[cfg(feature = "debug-binds")]
+#[test]
+fn sql_with_inlined_binds() {
+    use self::test::columns::{id, TST_CHR};
+    use self::test::dsl::test;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+    let ret = conn.execute("INSERT INTO test (id, TST_CHR) VALUES (1, 'it''s')");
+    assert_result!(ret);
+
+    let query = test
+        .select((id, TST_CHR))
+        .filter(id.eq(1))
+        .filter(TST_CHR.eq("it's"));
+    let sql = conn.sql_with_binds(&query);
+    assert_result!(sql);
+    let sql = sql.unwrap();
+    assert_eq!(
+        sql,
+        "SELECT \"TEST\".\"ID\", \"TEST\".\"TST_CHR\" FROM \"TEST\" \
+         WHERE \"TEST\".\"ID\" = 1 AND \"TEST\".\"TST_CHR\" = 'it''s'"
+    );
+    // the inlined sql returns the same rows as the query
+    let ret = ::diesel::sql_query(sql).load::<NamedTest>(&conn);
+    assert_result!(ret);
+    assert_eq!(
+        ret.unwrap(),
+        vec![NamedTest {
+            id: Some(1),
+            tst_chr: Some("it's".to_owned()),
+        }]
+    );
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn describe_query_without_executing() {
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+    let ret = conn.execute("INSERT INTO test (id) VALUES (1)");
+    assert_result!(ret);
+
+    // the division by zero fails when the query is executed
+    let ret = conn.describe("SELECT id, TST_CHR AS name, 1 / 0 AS broken FROM test WHERE id = :1");
+    assert_result!(ret);
+    let columns = ret.unwrap();
+    let names = columns.iter().map(|c| &*c.name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["ID", "NAME", "BROKEN"]);
+    assert_eq!(columns[1].data_type(), Some(::oracle::OCIDataType::Char));
+
+    assert!(conn.describe("SELECT missing FROM test").is_err());
+    let ret = conn.describe("DELETE FROM test");
+    assert_result!(ret);
+    assert!(ret.unwrap().is_empty());
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn execute_returning_generated_values() {
+    use diesel::sql_types::{BigInt, Text};
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use oracle::connection::Returning;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+
+    clean_test(&conn);
+    let _u = create_test_table(&conn);
+
+    let insert =
+        diesel::insert_into(test::table).values((test::id.eq(7), test::TST_CHR.eq("seven")));
+    let ret = conn.execute_returning::<(BigInt, Text), (i64, String), _>(
+        &insert,
+        &[
+            Returning::column::<BigInt>("id * 6", 8),
+            Returning::column::<Text>("UPPER(TST_CHR)", 20),
+        ],
+    );
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), (42, "SEVEN".to_owned()));
+
+    let update = diesel::update(test::table.filter(test::id.eq(8))).set(test::TST_CHR.eq("eight"));
+    let ret =
+        conn.execute_returning::<BigInt, i64, _>(&update, &[Returning::column::<BigInt>("id", 8)]);
+    match ret {
+        Err(Error::NotFound) => {}
+        r => panic!("expected NotFound, got {:?}", r),
+    }
+
+    let _u = drop_test_table(&conn);
+}
+
+#[test]
+fn server_version_is_detected() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Text;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let version = conn.server_version();
+    assert!(version.major >= 11);
+
+    let ret =
+        sql::<Text>("SELECT version FROM product_component_version WHERE product LIKE 'Oracle%'")
+            .get_result::<String>(&conn);
+    assert_result!(ret);
+    assert!(ret.unwrap().starts_with(&format!("{}.", version.major)));
+}
+
+#[test]
+fn async_connection_runs_queries() {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+    use oracle::connection::{block_on, AsyncOciConnection, OciConnectionBuilder};
+
+    let establish = || {
+        let config = OciConnectionBuilder::from_url(DB_URL).unwrap();
+        let ret = block_on(AsyncOciConnection::establish(config));
+        assert_result!(ret);
+        ret.unwrap()
+    };
+
+    let conn = establish();
+    let ret = block_on(
+        conn.interact(|conn| sql::<Integer>("SELECT 42 FROM dual").get_result::<i32>(conn)),
+    );
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), 42);
+
+    // pending calls finish before the connection is closed
+    let pending =
+        conn.interact(|conn| sql::<Integer>("SELECT 7 FROM dual").get_result::<i32>(conn));
+    let ret = block_on(conn.close());
+    assert_result!(ret);
+    assert_eq!(block_on(pending).unwrap(), 7);
+
+    // even if the connection is dropped without waiting
+    let conn = establish();
+    let pending =
+        conn.interact(|conn| sql::<Integer>("SELECT 8 FROM dual").get_result::<i32>(conn));
+    drop(conn);
+    assert_eq!(block_on(pending).unwrap(), 8);
+}
+
+#[test]
+#[cfg(feature = "tower")]
+fn deadline_limits_the_call_timeout() {
+    use oracle::connection::{block_on, with_deadline, AsyncOciConnection, OciConnectionBuilder};
+    use std::future::poll_fn;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::Poll;
+    use std::time::{Duration, Instant};
+
+    let assert_timed_out = |ret: Result<usize, Error>| match ret {
+        Err(Error::DatabaseError(_, ref info)) => assert!(info.message().contains("ORA-03156")),
+        ref other => panic!("Expected a timeout, got {:?}", other),
+    };
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    let deadline = Instant::now() + Duration::from_millis(500);
+    let ret = block_on(with_deadline(
+        deadline,
+        poll_fn(|_| Poll::Ready(conn.execute("BEGIN DBMS_SESSION.SLEEP(5); END;"))),
+    ));
+    assert_timed_out(ret);
+    // the previous timeout is restored
+    assert_eq!(conn.statement_timeout(), None);
+    assert_result!(conn.execute("SELECT 1 FROM dual"));
+
+    // a passed deadline fails without executing the statement
+    let ret = block_on(with_deadline(
+        Instant::now(),
+        poll_fn(|_| Poll::Ready(conn.execute("SELECT 1 FROM dual"))),
+    ));
+    assert!(ret.is_err());
+
+    // statements of an async connection use the deadline current when
+    // `interact` is called
+    let config = OciConnectionBuilder::from_url(DB_URL).unwrap();
+    let conn = block_on(AsyncOciConnection::establish(config)).unwrap();
+    let deadline = Instant::now() + Duration::from_millis(500);
+    let mut pending = None;
+    let ret = block_on(with_deadline(
+        deadline,
+        poll_fn(|cx| {
+            let interact = pending.get_or_insert_with(|| {
+                conn.interact(|conn| conn.execute("BEGIN DBMS_SESSION.SLEEP(5); END;"))
+            });
+            Pin::new(interact).poll(cx)
+        }),
+    ));
+    assert_timed_out(ret);
+    let ret = block_on(conn.interact(|conn| Ok(conn.statement_timeout())));
+    assert_eq!(ret.unwrap(), None);
+}
+
+#[test]
+fn select_for_update_skips_or_waits_for_locked_rows() {
+    use diesel::{ExpressionMethods, QueryDsl};
+    use oracle::query_dsl::ForUpdateDsl;
+    use std::time::{Duration, Instant};
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    drop_table(&conn, "JOBS");
+    let ret =
+        conn.execute("CREATE TABLE jobs (id NUMBER(9) PRIMARY KEY, state VARCHAR2(20) NOT NULL)");
+    assert_result!(ret);
+    let ret = conn.execute(
+        "INSERT INTO jobs (id, state) SELECT level, 'new' FROM dual CONNECT BY level <= 2",
+    );
+    assert_result!(ret);
+
+    let other = OciConnection::establish(DB_URL).unwrap();
+    let ret = conn.transaction::<_, Error, _>(|| {
+        let locked = jobs::table
+            .filter(jobs::id.eq(1))
+            .select(jobs::id)
+            .select_for_update()
+            .load::<i32>(&conn)?;
+        assert_eq!(locked, vec![1]);
+
+        let ret = jobs::table
+            .filter(jobs::state.eq("new"))
+            .select(jobs::id)
+            .select_for_update()
+            .skip_locked()
+            .load::<i32>(&other);
+        assert_result!(ret);
+        assert_eq!(ret.unwrap(), vec![2]);
+
+        let start = Instant::now();
+        let ret = jobs::table
+            .filter(jobs::id.eq(1))
+            .select(jobs::id)
+            .select_for_update()
+            .wait(1)
+            .load::<i32>(&other);
+        match ret {
+            Err(Error::DatabaseError(_, ref info)) => {
+                assert!(info.message().contains("ORA-30006"), "{}", info.message())
+            }
+            ref other => panic!("Expected an error, got {:?}", other),
+        }
+        assert!(start.elapsed() >= Duration::from_millis(900));
+
+        let ret = jobs::table
+            .filter(jobs::id.eq(1))
+            .select(jobs::id)
+            .select_for_update()
+            .no_wait()
+            .load::<i32>(&other);
+        match ret {
+            Err(Error::DatabaseError(_, ref info)) => {
+                assert!(info.message().contains("ORA-00054"), "{}", info.message())
+            }
+            ref other => panic!("Expected an error, got {:?}", other),
+        }
+        Ok(())
+    });
+    assert_result!(ret);
+
+    drop(other);
+    drop_table(&conn, "JOBS");
+}
+
+#[test]
+fn bool_columns_round_trip() {
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+
+    let conn = OciConnection::establish(DB_URL).unwrap();
+    drop_table(&conn, "FLAGS");
+    let ret = conn.execute(
+        "CREATE TABLE flags (id NUMBER(10) NOT NULL, active NUMBER(1) NOT NULL, \
+         archived NUMBER(1))",
+    );
+    assert_result!(ret);
+
+    let ret = diesel::insert_into(flags::table)
+        .values((
+            flags::id.eq(1),
+            flags::active.eq(true),
+            flags::archived.eq(Some(false)),
+        ))
+        .execute(&conn);
+    assert_result!(ret);
+    let ret = diesel::insert_into(flags::table)
+        .values((
+            flags::id.eq(2),
+            flags::active.eq(false),
+            flags::archived.eq(None::<bool>),
+        ))
+        .execute(&conn);
+    assert_result!(ret);
+
+    let ret = flags::table
+        .order(flags::id)
+        .load::<(i32, bool, Option<bool>)>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![(1, true, Some(false)), (2, false, None)]);
+
+    let ret = flags::table
+        .select(flags::id)
+        .filter(flags::active.eq(true))
+        .load::<i32>(&conn);
+    assert_result!(ret);
+    assert_eq!(ret.unwrap(), vec![1]);
+
+    drop_table(&conn, "FLAGS");
+}